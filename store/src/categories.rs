@@ -0,0 +1,237 @@
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use super::{core, errors::CustomErrors, format::{self, Cabecalho, CATEGORIES_MAGIC, CATEGORIES_VERSION, HEADER_LENGTH}, search, Categoria, Produto, ProdutoExibido};
+
+// Categorias criadas com todo estoque novo. Os registros anteriores às categorias
+// cadastradas são convertidos para estes ids.
+pub const ELETRONICO: u64 = 1;
+pub const ROUPA: u64 = 2;
+pub const ALIMENTO: u64 = 3;
+pub const GERAL: u64 = 4;
+
+pub fn built_in() -> Vec<Categoria> {
+    vec![
+        Categoria::new(ELETRONICO, "Eletrônico".to_string(), None),
+        Categoria::new(ROUPA, "Roupa".to_string(), None),
+        Categoria::new(ALIMENTO, "Alimento".to_string(), None),
+        Categoria::new(GERAL, "Geral".to_string(), None)
+    ]
+}
+
+// Procura a categoria pelo id ou pelo nome, sem diferenciar maiúsculas, minúsculas ou acentos.
+pub fn find<'a>(categorias: &'a [Categoria], text: &str) -> Option<&'a Categoria> {
+    let text = text.trim();
+
+    categorias.iter().find(|categoria| text.parse() == Ok(categoria.id))
+        .or_else(|| categorias.iter().find(|categoria| search::normalize(&categoria.nome) == search::normalize(text)))
+}
+
+// A categoria e todas as que estão abaixo dela, em qualquer nível.
+pub fn with_descendants(categorias: &[Categoria], id: u64) -> Vec<u64> {
+    let mut ids = vec![id];
+    let mut position = 0;
+
+    while position < ids.len() {
+        let pai = ids[position];
+        ids.extend(categorias.iter().filter(|categoria| categoria.pai == Some(pai)).map(|categoria| categoria.id));
+        position += 1;
+    }
+
+    ids
+}
+
+// Nome da categoria precedido pelos nomes das categorias acima dela, como em "Alimento > Bebidas".
+pub fn path(categorias: &[Categoria], id: u64) -> String {
+    let mut names = Vec::new();
+    let mut current = Some(id);
+
+    while let Some(categoria) = current.and_then(|id| categorias.iter().find(|categoria| categoria.id == id)) {
+        // Evita repetir a mesma categoria se o arquivo tiver um ciclo.
+        if names.len() > categorias.len() {
+            break;
+        }
+
+        names.push(categoria.nome.as_str());
+        current = categoria.pai;
+    }
+
+    match names.is_empty() {
+        true => format!("ID {id} (removida)"),
+        false => names.into_iter().rev().collect::<Vec<_>>().join(" > ")
+    }
+}
+
+pub fn describe(products: Vec<Produto>, categorias: &[Categoria]) -> Vec<ProdutoExibido> {
+    products.into_iter()
+        .map(|product| {
+            let nome = path(categorias, product.categoria);
+            ProdutoExibido::new(product, nome)
+        })
+        .collect()
+}
+
+pub fn serialize_record(category: &Categoria) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(category)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+// Cada categoria com a posição e o tamanho do seu registro.
+type Registro = (Categoria, u64, u64);
+
+#[derive(Debug)]
+pub struct ArquivoCategorias {
+    pub dados: File
+}
+
+impl ArquivoCategorias {
+    pub fn new(dados: File) -> Self {
+        ArquivoCategorias { dados }
+    }
+
+    // Grava o cabeçalho e as categorias iniciais em um arquivo vazio.
+    pub fn initialize(&mut self) -> Result<(), Box<dyn Error>> {
+        format::write_header(&mut self.dados, &Cabecalho::new(CATEGORIES_MAGIC, CATEGORIES_VERSION))?;
+
+        for category in built_in() {
+            self.insert(category)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, CATEGORIES_MAGIC)
+    }
+
+    fn records(&mut self) -> Result<Vec<Registro>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut categories = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let size: u64 = bincode::deserialize(&buf[offset..])?;
+            let end = offset + 8 + size as usize;
+
+            let record = buf.get(offset + 8..end).ok_or(CustomErrors::FormatMismatch)?;
+            categories.push((bincode::deserialize(record)?, HEADER_LENGTH + offset as u64, end as u64 - offset as u64));
+
+            offset = end;
+        }
+
+        Ok(categories)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Categoria>, Box<dyn Error>> {
+        let mut categories: Vec<Categoria> = self.records()?.into_iter().map(|(category, _, _)| category).collect();
+        categories.sort_by_key(|category| category.id);
+
+        Ok(categories)
+    }
+
+    pub fn insert(&mut self, mut category: Categoria) -> Result<u64, Box<dyn Error>> {
+        let mut header = self.header()?;
+        category.id = header.ultimo_id + 1;
+
+        self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(&category)?)?;
+
+        header.quantidade += 1;
+        header.ultimo_id = category.id;
+        format::write_header(&mut self.dados, &header)?;
+        self.dados.sync_all()?;
+
+        Ok(category.id)
+    }
+
+    fn record(&mut self, id: u64) -> Result<Registro, Box<dyn Error>> {
+        let record = self.records()?.into_iter()
+            .find(|(category, _, _)| category.id == id)
+            .ok_or(CustomErrors::CategoryNotFound)?;
+
+        Ok(record)
+    }
+
+    pub fn update(&mut self, category: &Categoria) -> Result<(), Box<dyn Error>> {
+        let (_, position, length) = self.record(category.id)?;
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(category)?)?;
+        self.dados.sync_all()?;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        let (_, position, length) = self.record(id)?;
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        let mut header = self.header()?;
+        header.quantidade -= 1;
+        format::write_header(&mut self.dados, &header)?;
+        self.dados.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::{get_test_categories, test_path};
+
+    #[test]
+    fn test_category_file() {
+        let path = &test_path("test_categories.bin");
+        let mut categories = get_test_categories(path);
+
+        assert_eq!(categories.list().unwrap(), built_in());
+
+        let id = categories.insert(Categoria::new(0, "Bebidas".to_string(), Some(ALIMENTO))).unwrap();
+        assert_eq!(id, 5);
+
+        categories.update(&Categoria::new(ROUPA, "Vestuário".to_string(), None)).unwrap();
+        categories.remove(ELETRONICO).unwrap();
+
+        let names: Vec<String> = categories.list().unwrap().iter().map(|category| category.nome.clone()).collect();
+        assert_eq!(names, vec!["Vestuário", "Alimento", "Geral", "Bebidas"]);
+
+        // Os ids não são reaproveitados depois de uma remoção.
+        assert_eq!(categories.insert(Categoria::new(0, "Limpeza".to_string(), None)).unwrap(), 6);
+        assert!(categories.remove(ELETRONICO).is_err());
+
+        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let mut categorias = built_in();
+        categorias.push(Categoria::new(5, "Bebidas".to_string(), Some(ALIMENTO)));
+        categorias.push(Categoria::new(6, "Refrigerantes".to_string(), Some(5)));
+        categorias.push(Categoria::new(7, "Limpeza".to_string(), None));
+
+        assert_eq!(with_descendants(&categorias, ALIMENTO), vec![ALIMENTO, 5, 6]);
+        assert_eq!(with_descendants(&categorias, 7), vec![7]);
+
+        assert_eq!(path(&categorias, 6), "Alimento > Bebidas > Refrigerantes");
+        assert_eq!(path(&categorias, GERAL), "Geral");
+        assert_eq!(path(&categorias, 9), "ID 9 (removida)");
+
+        assert_eq!(find(&categorias, "eletronico").map(|categoria| categoria.id), Some(ELETRONICO));
+        assert_eq!(find(&categorias, " REFRIGERANTES ").map(|categoria| categoria.id), Some(6));
+        assert_eq!(find(&categorias, "7").map(|categoria| categoria.id), Some(7));
+        assert!(find(&categorias, "brinquedo").is_none());
+    }
+}
@@ -0,0 +1,1530 @@
+use std::{collections::HashMap, error::Error, fs, io::BufRead, path::Path};
+
+use store::{categories, core, csv, errors::{CustomErrors, StoreError}, money::Dinheiro, orders::{self, ArquivoPedidos}, promotions::Cesta, receipt::{self, Loja}, reports, repository::{ProductRepository, SaleRepository, SaleTransaction, StockTransaction}, search::FiltroProdutos, sessions::ArquivoCaixas, validation, Caixa, Categoria, Produto, Promocao, Regra, Tributacao, Venda};
+
+use crate::{output::{self, Saida}, prompts, screens};
+
+const DATE_FORMAT: &str = "%d/%m/%Y";
+const DEFAULT_OPERATOR: &str = "linha de comando";
+const TAX_OPTIONS: [&str; 5] = ["ncm", "cfop", "icms", "pis", "cofins"];
+
+struct Argumentos<'a> {
+    posicionais: Vec<&'a str>,
+    opcoes: HashMap<&'a str, Vec<&'a str>>
+}
+
+impl<'a> Argumentos<'a> {
+    fn parse(args: &'a [String]) -> Result<Self, CustomErrors> {
+        let mut arguments = Argumentos {
+            posicionais: Vec::new(),
+            opcoes: HashMap::new()
+        };
+
+        let mut iter = args.iter().peekable();
+
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some("") => return Err(CustomErrors::InvalidCommand),
+                Some(name) => {
+                    let values = arguments.opcoes.entry(name).or_default();
+
+                    if let Some(value) = iter.next_if(|value| !value.starts_with("--")) {
+                        values.push(value);
+                    }
+                },
+                None => arguments.posicionais.push(arg)
+            }
+        }
+
+        Ok(arguments)
+    }
+
+    fn allow(&self, names: &[&str]) -> Result<(), CustomErrors> {
+        match self.opcoes.keys().all(|name| names.contains(name)) {
+            true => Ok(()),
+            false => Err(CustomErrors::InvalidCommand)
+        }
+    }
+
+    fn optional(&self, name: &str) -> Result<Option<&'a str>, CustomErrors> {
+        match self.opcoes.get(name).map(Vec::as_slice) {
+            None => Ok(None),
+            Some([value]) => Ok(Some(value)),
+            Some(_) => Err(CustomErrors::InvalidCommand)
+        }
+    }
+
+    fn required(&self, name: &str) -> Result<&'a str, CustomErrors> {
+        self.optional(name)?.ok_or(CustomErrors::InvalidCommand)
+    }
+
+    fn all(&self, name: &str) -> &[&'a str] {
+        self.opcoes.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, CustomErrors> {
+        match self.opcoes.get(name).map(Vec::as_slice) {
+            None => Ok(false),
+            Some([]) => Ok(true),
+            Some(_) => Err(CustomErrors::InvalidCommand)
+        }
+    }
+}
+
+// Sem --operator, os movimentos de estoque ficam em nome do operador do caixa aberto.
+fn operator(arguments: &Argumentos, sessions_file: &mut ArquivoCaixas) -> Result<String, Box<dyn Error>> {
+    if let Some(name) = arguments.optional("operator")? {
+        return Ok(validation::validate_name(name)?);
+    }
+
+    Ok(sessions_file.current()?.map_or_else(|| DEFAULT_OPERATOR.to_string(), |session| session.operador().to_string()))
+}
+
+fn parse_items(arguments: &Argumentos) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    let mut items = Vec::new();
+
+    for item in arguments.all("item") {
+        items.push(validation::validate_sale(&item.replace(':', " "))?);
+    }
+
+    match items.is_empty() {
+        true => Err(Box::new(CustomErrors::InvalidCommand)),
+        false => Ok(items)
+    }
+}
+
+// Na venda, o produto pode ser informado pelo código de barras no lugar do id.
+fn parse_scanned_items(arguments: &Argumentos) -> Result<Vec<validation::ItemLido>, Box<dyn Error>> {
+    let mut items = Vec::new();
+
+    for item in arguments.all("item") {
+        items.push(validation::validate_scan(&item.replace(':', " "))?);
+    }
+
+    match items.is_empty() {
+        true => Err(Box::new(CustomErrors::InvalidCommand)),
+        false => Ok(items)
+    }
+}
+
+// Descontos do vendedor por produto (--discount ID:DESCONTO) e no total da venda.
+fn parse_basket(arguments: &Argumentos, items: Vec<(u64, u64)>) -> Result<Cesta, Box<dyn Error>> {
+    let mut cesta = Cesta::new(items);
+
+    for discount in arguments.all("discount") {
+        cesta.descontos.push(validation::validate_line_discount(&discount.replace(':', " "))?);
+    }
+
+    cesta.desconto = arguments.optional("sale-discount")?.map(validation::parse_discount).transpose()?;
+
+    Ok(cesta)
+}
+
+// Leve e pague em um produto ou porcentagem de desconto em uma categoria, nunca os dois.
+fn parse_promotion<P: ProductRepository>(arguments: &Argumentos, products: &mut P) -> Result<Promocao, Box<dyn Error>> {
+    let regra = match (arguments.optional("product")?, arguments.optional("category")?) {
+        (Some(product), None) => Regra::LevePague {
+            produto: validation::validate_int(product)?,
+            leve: validation::validate_int(arguments.required("buy")?)?,
+            pague: validation::validate_int(arguments.required("pay")?)?
+        },
+        (None, Some(category)) => Regra::Categoria {
+            categoria: category_id(products, category)?,
+            percentual: validation::validate_int(arguments.required("percent")?)?
+        },
+        _ => return Err(Box::new(CustomErrors::InvalidCommand))
+    };
+
+    let inicio = arguments.optional("from")?.map(parse_date).transpose()?;
+    let fim = arguments.optional("to")?.map(parse_date).transpose()?;
+
+    Ok(Promocao::new(0, arguments.required("name")?.to_string(), regra, inicio, fim))
+}
+
+fn parse_costs(arguments: &Argumentos) -> Result<Vec<(u64, Dinheiro)>, Box<dyn Error>> {
+    let mut costs = Vec::new();
+
+    for cost in arguments.all("cost") {
+        let (id, value) = cost.split_once(':').ok_or(CustomErrors::InvalidCommand)?;
+        costs.push((validation::validate_int(id)?, validation::validate_money(value)?));
+    }
+
+    Ok(costs)
+}
+
+// Categoria indicada pelo nome ou pelo id em um comando de categoria.
+fn category_id<P: ProductRepository>(products: &mut P, text: &str) -> Result<u64, Box<dyn Error>> {
+    match categories::find(&products.categories()?, text) {
+        Some(categoria) => Ok(categoria.id()),
+        None => Err(Box::new(CustomErrors::CategoryNotFound))
+    }
+}
+
+fn parse_parameters(arguments: &Argumentos) -> Result<orders::Parametros, Box<dyn Error>> {
+    let default = orders::Parametros::default();
+
+    Ok(orders::Parametros {
+        dias: arguments.optional("days")?.map(validation::validate_int).transpose()?.unwrap_or(default.dias),
+        prazo: arguments.optional("lead-time")?.map(validation::validate_int).transpose()?.unwrap_or(default.prazo),
+        cobertura: arguments.optional("coverage")?.map(validation::validate_int).transpose()?.unwrap_or(default.cobertura)
+    })
+}
+
+// A busca por uma categoria também encontra os produtos das suas subcategorias.
+fn parse_filter(arguments: &Argumentos, categorias: &[Categoria]) -> Result<FiltroProdutos, Box<dyn Error>> {
+    let categoria = arguments.optional("category")?.map(|category| validation::validate_category(category, categorias)).transpose()?;
+
+    Ok(FiltroProdutos {
+        nome: arguments.optional("name")?.map(String::from),
+        categorias: categoria.map(|id| categories::with_descendants(categorias, id)).unwrap_or_default(),
+        valor_minimo: arguments.optional("min-price")?.map(validation::validate_money).transpose()?,
+        valor_maximo: arguments.optional("max-price")?.map(validation::validate_money).transpose()?,
+        estoque_minimo: arguments.optional("min-stock")?.map(validation::validate_int).transpose()?,
+        estoque_maximo: arguments.optional("max-stock")?.map(validation::validate_int).transpose()?,
+        abaixo_restoque: arguments.flag("low-stock")?
+    })
+}
+
+// --json é um atalho para --format json.
+fn output_format(arguments: &Argumentos) -> Result<Saida, CustomErrors> {
+    match (arguments.flag("json")?, arguments.optional("format")?) {
+        (false, None) => Ok(Saida::Texto),
+        (true, None) => Ok(Saida::Json),
+        (false, Some(format)) => output::parse_output(format),
+        (true, Some(_)) => Err(CustomErrors::InvalidCommand)
+    }
+}
+
+fn check_format(arguments: &Argumentos) -> Result<(), CustomErrors> {
+    match arguments.optional("format")?.unwrap_or("csv") {
+        "csv" => Ok(()),
+        _ => Err(CustomErrors::InvalidCommand)
+    }
+}
+
+// Os campos informados substituem os dados fiscais atuais do produto; NCM e CFOP continuam obrigatórios.
+fn parse_taxes(arguments: &Argumentos, current: Option<Tributacao>) -> Result<Option<Tributacao>, Box<dyn Error>> {
+    if TAX_OPTIONS.iter().all(|name| arguments.all(name).is_empty()) {
+        return Ok(current);
+    }
+
+    let current = current.unwrap_or_default();
+
+    let tributacao = Tributacao::new(
+        arguments.optional("ncm")?.map(validation::validate_ncm).transpose()?.unwrap_or_else(|| current.ncm().to_string()),
+        arguments.optional("cfop")?.map(validation::validate_cfop).transpose()?.unwrap_or_else(|| current.cfop().to_string()),
+        arguments.optional("icms")?.map(validation::parse_rate).transpose()?.unwrap_or(current.icms()),
+        arguments.optional("pis")?.map(validation::parse_rate).transpose()?.unwrap_or(current.pis()),
+        arguments.optional("cofins")?.map(validation::parse_rate).transpose()?.unwrap_or(current.cofins())
+    );
+
+    validation::check_taxes(&tributacao)?;
+    Ok(Some(tributacao))
+}
+
+// Com --printer o cupom vai direto para o arquivo da impressora em vez da tela.
+fn emit_receipt(sale: &Venda, loja: &Loja, printer: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match printer {
+        Some(printer) => receipt::send(sale, loja, Path::new(printer)),
+        None => print_receipt(sale, loja)
+    }
+}
+
+fn parse_date(string: &str) -> Result<chrono::NaiveDate, Box<dyn Error>> {
+    Ok(chrono::NaiveDate::parse_from_str(string, DATE_FORMAT)?)
+}
+
+// Com a impressora configurada em loja.conf, o cupom também é enviado para ela.
+pub fn print_receipt(sale: &Venda, loja: &Loja) -> Result<(), Box<dyn Error>> {
+    println!("{}", receipt::render_text(sale, loja)?);
+
+    match &loja.impressora {
+        Some(printer) => receipt::send(sale, loja, printer),
+        None => Ok(())
+    }
+}
+
+pub fn list_products<P: ProductRepository>(products: &mut P, saida: Saida) -> Result<(), Box<dyn Error>> {
+    let list = products.list()?;
+    output::print_records("Produtos no estoque", &core::describe_products(products, list)?, saida)
+}
+
+pub fn show_product_search<P: ProductRepository>(products: &mut P, filtro: &FiltroProdutos, saida: Saida) -> Result<(), Box<dyn Error>> {
+    let found = core::search_products(products, filtro)?;
+    output::print_records("Produtos encontrados", &core::describe_products(products, found)?, saida)
+}
+
+pub fn products_needing_restock<P: ProductRepository, S: SaleRepository>(products: &mut P, sales: &mut S, parametros: &orders::Parametros) -> Result<(), Box<dyn Error>> {
+    let suggestions = core::restock_suggestions(products, sales, parametros)?;
+
+    println!("\nProdutos com necessidade de restoque (vendas dos últimos {} dias, prazo de entrega de {} dias, cobertura de {} dias):", parametros.dias, parametros.prazo, parametros.cobertura);
+
+    for (supplier, suggestions) in orders::by_supplier(suggestions) {
+        println!("\nFornecedor: {}", supplier.as_deref().unwrap_or("não informado"));
+
+        for suggestion in suggestions.iter() {
+            println!("  {suggestion}");
+        }
+    }
+
+    println!();
+
+    Ok(())
+}
+
+pub fn generate_purchase_orders<P: ProductRepository, S: SaleRepository>(products: &mut P, sales: &mut S, orders_file: &mut ArquivoPedidos, parametros: &orders::Parametros) -> Result<(), Box<dyn Error>> {
+    let created = core::create_purchase_orders(products, sales, orders_file, parametros)?;
+
+    if created.is_empty() {
+        println!("\nNenhum produto precisa de restoque.\n");
+    }
+
+    for order in created.iter() {
+        println!("\n{order}\n");
+    }
+
+    Ok(())
+}
+
+pub fn list_orders(orders_file: &mut ArquivoPedidos, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Pedidos de compra", &orders_file.list()?, saida)
+}
+
+pub fn list_categories<P: ProductRepository>(products: &mut P, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Categorias cadastradas", &products.categories()?, saida)
+}
+
+pub fn list_promotions<P: ProductRepository>(products: &mut P, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Promoções cadastradas", &products.promotions()?, saida)
+}
+
+pub fn product_history<P: ProductRepository>(products: &mut P, id: u64, saida: Saida) -> Result<(), Box<dyn Error>> {
+    let movements = products.movements(id)?;
+
+    if saida != Saida::Texto {
+        return output::print_records(&format!("Movimentos de estoque do produto {id}"), &movements, saida);
+    }
+
+    println!("\nMovimentos de estoque do produto {id}:\n");
+
+    for movement in movements.iter() {
+        println!("{movement}");
+    }
+
+    let balance: i64 = movements.iter().map(|movement| movement.quantidade()).sum();
+    println!("\nSaldo pelos movimentos: {balance}\n");
+
+    Ok(())
+}
+
+pub fn audit_stock<P: ProductRepository>(products: &mut P, fix: bool) -> Result<(), Box<dyn Error>> {
+    let divergences = core::stock_divergences(products)?;
+
+    if divergences.is_empty() {
+        println!("\nO estoque de todos os produtos confere com o histórico de movimentos.\n");
+        return Ok(());
+    }
+
+    println!("\nProdutos com divergência entre o estoque gravado e o histórico de movimentos:\n");
+
+    for (product, balance) in divergences.iter() {
+        println!("{} (ID: {}) - Gravado: {} - Pelos movimentos: {}", product.nome(), product.id(), product.quantidade_estoque(), balance);
+    }
+
+    if fix {
+        core::fix_divergences(products, divergences)?;
+
+        println!("\nO estoque dos produtos foi corrigido a partir do histórico de movimentos.");
+    }
+
+    println!();
+
+    Ok(())
+}
+
+pub fn search_sales_by_date<S: SaleRepository>(sales: &mut S, date: chrono::NaiveDate, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Vendas realizadas na data especificada", &sales.by_date(date)?, saida)
+}
+
+pub fn search_product_sales<S: SaleRepository>(sales: &mut S, id: u64, saida: Saida) -> Result<(), Box<dyn Error>> {
+    let sales = sales.by_product(id)?;
+    output::print_records("Vendas do produto especificado", &sales, saida)?;
+
+    if saida != Saida::Texto {
+        return Ok(());
+    }
+
+    let (units_sold, total_value) = core::product_sales_totals(&sales, id)?;
+    println!("Unidades vendidas: {units_sold}\nValor total vendido: {total_value}\n");
+
+    Ok(())
+}
+
+pub fn show_sales_report<S: SaleRepository>(sales: &mut S, agrupamento: reports::Agrupamento, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>, saida: Saida) -> Result<(), Box<dyn Error>> {
+    let report = reports::sales_report(sales, agrupamento, from, to)?;
+
+    output::print_record(&report, saida)
+}
+
+pub fn list_sales<S: SaleRepository>(sales: &mut S, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Vendas realizadas", &sales.list()?, saida)
+}
+
+pub fn finish_session<S: SaleRepository>(sessions: &mut ArquivoCaixas, sales: &mut S, counted: Dinheiro) -> Result<(), Box<dyn Error>> {
+    let closed = core::end_session(sessions, sales, counted)?;
+
+    show_session(sales, &closed)
+}
+
+pub fn show_session<S: SaleRepository>(sales: &mut S, session: &Caixa) -> Result<(), Box<dyn Error>> {
+    let (_, report) = core::session_summary(sales, session)?;
+    println!("\n{session}\n\n{report}\n");
+
+    Ok(())
+}
+
+pub fn list_sessions(sessions: &mut ArquivoCaixas, saida: Saida) -> Result<(), Box<dyn Error>> {
+    output::print_records("Caixas registrados", &sessions.list()?, saida)
+}
+
+fn show_import(kind: &str, ids: &[u64], dry_run: bool) {
+    match (ids.first(), ids.last(), dry_run) {
+        (Some(first), Some(last), true) => println!("\n{} {kind} válidos. Seriam cadastrados com os números {first} a {last}.\n", ids.len()),
+        (Some(first), Some(last), false) => println!("\n{} {kind} importados com os números {first} a {last}.\n", ids.len()),
+        _ => println!("\nNenhum registro para importar.\n")
+    }
+}
+
+pub fn exit_code(error: &(dyn Error + 'static)) -> i32 {
+    if let Some(custom) = error.downcast_ref::<CustomErrors>() {
+        return custom.exit_code();
+    }
+
+    if let Some(StoreError::Import(_)) = error.downcast_ref::<StoreError>() {
+        return CustomErrors::InvalidImport.exit_code();
+    }
+
+    match error.is::<std::num::ParseIntError>() || error.is::<chrono::ParseError>() {
+        true => 3,
+        false => 1
+    }
+}
+
+pub fn run<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(args: &[String], products_file: &mut P, sales_file: &mut S, sessions_file: &mut ArquivoCaixas, orders_file: &mut ArquivoPedidos, journal_file: &mut T, loja: &Loja) -> Result<(), Box<dyn Error>> {
+    let arguments = Argumentos::parse(args)?;
+
+    match arguments.posicionais.as_slice() {
+        ["product", "add"] => {
+            arguments.allow(&["name", "stock", "price", "restock", "date", "category", "barcode", "operator", "ncm", "cfop", "icms", "pis", "cofins"])?;
+
+            let today = chrono::Local::now().date_naive().format(DATE_FORMAT).to_string();
+
+            let mut product = validation::validate_product(vec![
+                arguments.required("name")?,
+                arguments.required("stock")?,
+                arguments.required("price")?,
+                arguments.required("restock")?,
+                arguments.optional("date")?.unwrap_or(&today),
+                arguments.required("category")?
+            ], &products_file.categories()?)?;
+
+            product.set_codigo_barras(arguments.optional("barcode")?.map(validation::validate_barcode).transpose()?);
+            product.set_tributacao(parse_taxes(&arguments, None)?);
+
+            let id = core::insert_product(products_file, journal_file, product, &operator(&arguments, sessions_file)?)?;
+            println!("\nProduto adicionado com sucesso com o id {id}.\n");
+
+            Ok(())
+        },
+        ["product", "get", id] => {
+            arguments.allow(&["json", "format"])?;
+
+            let product = core::search_product_id(products_file, validation::validate_int(id)?)?;
+            output::print_record(&core::describe_product(products_file, product)?, output_format(&arguments)?)
+        },
+        ["product", "get"] => {
+            arguments.allow(&["barcode", "json", "format"])?;
+
+            let product = core::search_product_barcode(products_file, &validation::validate_barcode(arguments.required("barcode")?)?)?;
+            output::print_record(&core::describe_product(products_file, product)?, output_format(&arguments)?)
+        },
+        ["product", "list"] => {
+            arguments.allow(&["json", "format"])?;
+            list_products(products_file, output_format(&arguments)?)
+        },
+        ["product", "search"] => {
+            arguments.allow(&["name", "category", "min-price", "max-price", "min-stock", "max-stock", "low-stock", "json", "format"])?;
+            let filtro = parse_filter(&arguments, &products_file.categories()?)?;
+            show_product_search(products_file, &filtro, output_format(&arguments)?)
+        },
+        ["product", "restock"] => {
+            arguments.allow(&["days", "lead-time", "coverage"])?;
+            products_needing_restock(products_file, sales_file, &parse_parameters(&arguments)?)
+        },
+        ["product", "update", id] => {
+            arguments.allow(&["name", "stock", "price", "restock", "date", "category", "barcode", "operator", "ncm", "cfop", "icms", "pis", "cofins"])?;
+
+            let current = core::search_product_id(products_file, validation::validate_int(id)?)?;
+            let categorias = products_file.categories()?;
+
+            let mut product = Produto::new(
+                arguments.optional("name")?.map(validation::validate_name).transpose()?.unwrap_or_else(|| current.nome().to_string()),
+                current.id(),
+                arguments.optional("stock")?.map(validation::validate_int).transpose()?.unwrap_or(current.quantidade_estoque()),
+                arguments.optional("price")?.map(validation::validate_money).transpose()?.unwrap_or(current.valor()),
+                arguments.optional("restock")?.map(validation::validate_int).transpose()?.unwrap_or(current.quantidade_restoque()),
+                arguments.optional("date")?.map(parse_date).transpose()?.unwrap_or(current.data_restoque()),
+                arguments.optional("category")?.map(|category| validation::validate_category(category, &categorias)).transpose()?.unwrap_or(current.categoria())
+            );
+
+            // Sem código de barras ou dados fiscais informados, a edição mantém os atuais.
+            product.set_codigo_barras(arguments.optional("barcode")?.map(validation::validate_barcode).transpose()?);
+            product.set_tributacao(parse_taxes(&arguments, current.tributacao().cloned())?);
+
+            core::replace_product(products_file, journal_file, current.id(), product, &operator(&arguments, sessions_file)?)
+        },
+        ["category", "list"] => {
+            arguments.allow(&["json", "format"])?;
+            list_categories(products_file, output_format(&arguments)?)
+        },
+        ["category", "add"] => {
+            arguments.allow(&["name", "parent"])?;
+
+            let parent = arguments.optional("parent")?.map(|parent| category_id(products_file, parent)).transpose()?;
+            let category = core::create_category(products_file, journal_file, arguments.required("name")?, parent)?;
+
+            println!("\nCategoria cadastrada com sucesso com o id {}.\n", category.id());
+
+            Ok(())
+        },
+        ["category", "rename", category] => {
+            arguments.allow(&["name"])?;
+
+            let id = category_id(products_file, category)?;
+            core::rename_category(products_file, journal_file, id, arguments.required("name")?)?;
+
+            println!("\nCategoria renomeada com sucesso.\n");
+
+            Ok(())
+        },
+        ["category", "merge", category] => {
+            arguments.allow(&["into"])?;
+
+            let from = category_id(products_file, category)?;
+            let into = category_id(products_file, arguments.required("into")?)?;
+            let moved = core::merge_categories(products_file, journal_file, from, into)?;
+
+            println!("\nCategorias unidas com sucesso. {moved} produtos foram movidos.\n");
+
+            Ok(())
+        },
+        ["category", "remove", category] => {
+            arguments.allow(&[])?;
+
+            let id = category_id(products_file, category)?;
+            core::delete_category(products_file, journal_file, id)?;
+
+            println!("\nCategoria removida com sucesso.\n");
+
+            Ok(())
+        },
+        ["promotion", "list"] => {
+            arguments.allow(&["json", "format"])?;
+            list_promotions(products_file, output_format(&arguments)?)
+        },
+        ["promotion", "add"] => {
+            arguments.allow(&["name", "product", "buy", "pay", "category", "percent", "from", "to"])?;
+
+            let promotion = parse_promotion(&arguments, products_file)?;
+            let promotion = core::create_promotion(products_file, journal_file, promotion)?;
+
+            println!("\nPromoção cadastrada com sucesso com o id {}.\n", promotion.id());
+
+            Ok(())
+        },
+        ["promotion", "remove", id] => {
+            arguments.allow(&[])?;
+
+            core::delete_promotion(products_file, journal_file, validation::validate_int(id)?)?;
+            println!("\nPromoção removida com sucesso.\n");
+
+            Ok(())
+        },
+        ["product", "remove", id] => {
+            arguments.allow(&["operator"])?;
+            core::delete_product(products_file, journal_file, validation::validate_int(id)?, &operator(&arguments, sessions_file)?)
+        },
+        ["product", "receive"] => {
+            arguments.allow(&["supplier", "item", "operator"])?;
+
+            let supplier = validation::validate_name(arguments.required("supplier")?)?;
+            let mut items = Vec::new();
+
+            for item in arguments.all("item") {
+                items.push(validation::validate_receipt(&item.replace(':', " "))?);
+            }
+
+            if items.is_empty() {
+                return Err(Box::new(CustomErrors::InvalidCommand));
+            }
+
+            core::receive_stock(products_file, journal_file, &supplier, &items, &operator(&arguments, sessions_file)?)?;
+            println!("\nRecebimento registrado com sucesso.\n");
+
+            Ok(())
+        },
+        ["product", "adjust", id] => {
+            arguments.allow(&["type", "quantity", "reason", "operator"])?;
+
+            let tipo = validation::parse_movement_type(arguments.optional("type")?.unwrap_or("ajuste"))?;
+            let quantity = validation::parse_adjustment(tipo, arguments.required("quantity")?)?;
+            let reason = arguments.required("reason")?;
+
+            let product = core::adjust_stock(products_file, journal_file, validation::validate_int(id)?, tipo, quantity, &operator(&arguments, sessions_file)?, reason)?;
+            println!("\nMovimento registrado. Estoque atual: {}.\n", product.quantidade_estoque());
+
+            Ok(())
+        },
+        ["product", "history", id] => {
+            arguments.allow(&["json", "format"])?;
+
+            let product = core::search_product_id(products_file, validation::validate_int(id)?)?;
+            product_history(products_file, product.id(), output_format(&arguments)?)
+        },
+        ["product", "audit"] => {
+            arguments.allow(&["fix"])?;
+            audit_stock(products_file, arguments.flag("fix")?)
+        },
+        ["order", "create"] => {
+            arguments.allow(&["days", "lead-time", "coverage"])?;
+            generate_purchase_orders(products_file, sales_file, orders_file, &parse_parameters(&arguments)?)
+        },
+        ["order", "get", id] => {
+            arguments.allow(&["json", "format"])?;
+
+            let order = orders_file.search(validation::validate_int(id)?)?;
+            output::print_record(&order, output_format(&arguments)?)
+        },
+        ["order", "list"] => {
+            arguments.allow(&["json", "format"])?;
+            list_orders(orders_file, output_format(&arguments)?)
+        },
+        ["order", "receive", id] => {
+            arguments.allow(&["supplier", "cost", "operator"])?;
+
+            let supplier = arguments.optional("supplier")?.map(validation::validate_name).transpose()?;
+            let costs = parse_costs(&arguments)?;
+
+            let order = core::complete_order(products_file, orders_file, journal_file, validation::validate_int(id)?, supplier, &costs, &operator(&arguments, sessions_file)?)?;
+            println!("\nPedido {} recebido. O estoque dos produtos foi atualizado.\n", order.id());
+
+            Ok(())
+        },
+        ["export", "products"] => {
+            arguments.allow(&["format"])?;
+            check_format(&arguments)?;
+
+            print!("{}", csv::export_products(&products_file.list()?, &products_file.categories()?));
+
+            Ok(())
+        },
+        ["export", "sales"] => {
+            arguments.allow(&["format"])?;
+            check_format(&arguments)?;
+
+            print!("{}", csv::export_sales(&sales_file.list()?));
+
+            Ok(())
+        },
+        ["import", "products", path] => {
+            arguments.allow(&["format", "dry-run", "operator"])?;
+            check_format(&arguments)?;
+
+            let text = fs::read_to_string(path)?;
+            let dry_run = arguments.flag("dry-run")?;
+            let ids = core::import_products(products_file, journal_file, &text, dry_run, &operator(&arguments, sessions_file)?)?;
+            show_import("produtos", &ids, dry_run);
+
+            Ok(())
+        },
+        ["import", "sales", path] => {
+            arguments.allow(&["format", "dry-run"])?;
+            check_format(&arguments)?;
+
+            let text = fs::read_to_string(path)?;
+            let dry_run = arguments.flag("dry-run")?;
+            let ids = core::import_sales(products_file, sales_file, journal_file, &text, dry_run)?;
+            show_import("vendas", &ids, dry_run);
+
+            Ok(())
+        },
+        ["sale", "register"] => {
+            arguments.allow(&["seller", "item", "payment", "discount", "sale-discount", "receipt", "printer"])?;
+
+            let seller = arguments.required("seller")?.to_string();
+            let payment_method = validation::parse_payment_method(arguments.required("payment")?)?;
+
+            let items = core::resolve_items(products_file, &parse_scanned_items(&arguments)?)?;
+            let cesta = parse_basket(&arguments, items)?;
+            let session = sessions_file.current()?.map(|session| session.id());
+            let sale = core::complete_sale(products_file, sales_file, journal_file, seller, session, &cesta, payment_method)?;
+            println!("\nVenda registrada com sucesso com o código {}.\n", sale.codigo());
+
+            let printer = arguments.optional("printer")?;
+
+            match arguments.flag("receipt")? || printer.is_some() {
+                true => emit_receipt(&sale, loja, printer),
+                false => Ok(())
+            }
+        },
+        ["sale", "get", code] => {
+            arguments.allow(&["json", "format"])?;
+
+            let sale = core::search_sale_code(sales_file, validation::validate_int(code)?)?;
+            output::print_record(&sale, output_format(&arguments)?)
+        },
+        ["sale", "receipt", code] => {
+            arguments.allow(&["printer"])?;
+
+            let sale = core::search_sale_code(sales_file, validation::validate_int(code)?)?;
+            emit_receipt(&sale, loja, arguments.optional("printer")?)
+        },
+        ["sale", "update", code] => {
+            arguments.allow(&["date", "payment", "item", "operator"])?;
+
+            let code = validation::validate_int(code)?;
+
+            if !arguments.all("item").is_empty() {
+                core::change_sale_items(products_file, sales_file, journal_file, code, &parse_items(&arguments)?, &operator(&arguments, sessions_file)?)?;
+            }
+
+            let sale = core::search_sale_code(sales_file, code)?;
+
+            let date = match arguments.optional("date")? {
+                Some(date) => parse_date(date)?,
+                None => sale.data()
+            };
+
+            let payment_method = match arguments.optional("payment")? {
+                Some(payment) => validation::parse_payment_method(payment)?,
+                None => sale.metodo_pagamento()
+            };
+
+            core::edit_sale(sales_file, code, date, payment_method)
+        },
+        ["sale", "cancel", code] => {
+            arguments.allow(&["reason", "operator"])?;
+
+            let reason = arguments.required("reason")?.to_string();
+            core::void_sale(products_file, sales_file, journal_file, validation::validate_int(code)?, reason, &operator(&arguments, sessions_file)?)?;
+
+            println!("\nVenda cancelada. O estoque dos produtos foi restaurado.\n");
+
+            Ok(())
+        },
+        ["sale", "return", code] => {
+            arguments.allow(&["item", "reason", "operator"])?;
+
+            let reason = arguments.required("reason")?.to_string();
+            let items = parse_items(&arguments)?;
+
+            let sale = core::return_items(products_file, sales_file, journal_file, validation::validate_int(code)?, &items, reason, &operator(&arguments, sessions_file)?)?;
+            println!("\nDevolução registrada. Situação da venda: {}.\n", sale.situacao());
+
+            Ok(())
+        },
+        ["sales", "list"] => {
+            arguments.allow(&["date", "product", "json", "format"])?;
+
+            let saida = output_format(&arguments)?;
+
+            match (arguments.optional("date")?, arguments.optional("product")?) {
+                (None, None) => list_sales(sales_file, saida),
+                (Some(date), None) => search_sales_by_date(sales_file, parse_date(date)?, saida),
+                (None, Some(id)) => search_product_sales(sales_file, validation::validate_int(id)?, saida),
+                (Some(_), Some(_)) => Err(Box::new(CustomErrors::InvalidCommand))
+            }
+        },
+        ["sales", "report"] => {
+            arguments.allow(&["by", "from", "to", "json", "format"])?;
+
+            let agrupamento = reports::parse_grouping(arguments.optional("by")?.unwrap_or("dia"))?;
+            let from = arguments.optional("from")?.map(parse_date).transpose()?;
+            let to = arguments.optional("to")?.map(parse_date).transpose()?;
+
+            show_sales_report(sales_file, agrupamento, from, to, output_format(&arguments)?)
+        },
+        ["session", "open"] => {
+            arguments.allow(&["operator", "float"])?;
+
+            let operator = validation::validate_name(arguments.required("operator")?)?;
+            let float = validation::validate_money(arguments.optional("float")?.unwrap_or("0"))?;
+
+            let session = core::start_session(sessions_file, operator, float)?;
+            println!("\nCaixa {} aberto com sucesso.\n", session.id());
+
+            Ok(())
+        },
+        ["session", "close"] => {
+            arguments.allow(&["counted"])?;
+            finish_session(sessions_file, sales_file, validation::validate_money(arguments.required("counted")?)?)
+        },
+        ["session", "get", id] => {
+            arguments.allow(&["json", "format"])?;
+
+            let session = sessions_file.search(validation::validate_int(id)?)?;
+
+            match output_format(&arguments)? {
+                Saida::Texto => show_session(sales_file, &session),
+                saida => output::print_record(&session, saida)
+            }
+        },
+        ["session", "list"] => {
+            arguments.allow(&["json", "format"])?;
+            list_sessions(sessions_file, output_format(&arguments)?)
+        },
+        _ => Err(Box::new(CustomErrors::InvalidCommand))
+    }
+}
+
+pub fn add_product<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let categorias = products.categories()?;
+    let product = prompts::get_product_info(reader, &categorias)?;
+    let id = core::insert_product(products, journal_file, product, operator)?;
+
+    println!("\nProduto adicionado com sucesso com o id {id}.\n");
+
+    Ok(())
+}
+
+pub fn register_sale<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>, R: BufRead>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, seller: String, session: Option<u64>, loja: &Loja, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    screens::add_sale_screen();
+    let products = core::resolve_items(products_file, &prompts::get_sale_items(reader)?)?;
+
+    if products.is_empty() {
+        println!("\nNenhum produto inserido.\n");
+        return Ok(());
+    }
+
+    let mut cesta = Cesta::new(products);
+    cesta.descontos = prompts::get_line_discounts(reader)?;
+    cesta.desconto = prompts::get_sale_discount(reader)?;
+
+    let payment_method = prompts::validate_payment_method(reader)?;
+    let sale = core::complete_sale(products_file, sales_file, journal_file, seller, session, &cesta, payment_method)?;
+
+    println!("\nVenda registrada com sucesso com o código {}.\n", sale.codigo());
+
+    print_receipt(&sale, loja)
+}
+
+pub fn show_sale_receipt<S: SaleRepository, R: BufRead>(sales: &mut S, loja: &Loja, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let code = prompts::validate_search("code", reader)?;
+    let sale = core::search_sale_code(sales, code)?;
+
+    print_receipt(&sale, loja)
+}
+
+pub fn find_products<P: ProductRepository, R: BufRead>(products: &mut P, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let categorias = products.categories()?;
+    let filtro = prompts::get_product_filter(reader, &categorias)?;
+
+    show_product_search(products, &filtro, Saida::Texto)
+}
+
+pub fn receive_order<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, orders_file: &mut ArquivoPedidos, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    println!("\nDigite o número do pedido de compra (ou sair para cancelar a operação):");
+
+    let id = loop {
+        match validation::validate_int(&prompts::validate_string(reader)?) {
+            Ok(id) => break id,
+            Err(error) => eprintln!("\nUm erro ocorreu ao tentar converter o número: {error}\nCertifique-se de que um valor válido foi inserido.\n")
+        }
+    };
+
+    let order = orders_file.search(id)?;
+    println!("\nPedido encontrado:\n\n{order}\n");
+
+    if order.recebimento().is_some() {
+        return Err(Box::new(CustomErrors::OrderReceived));
+    }
+
+    let supplier = match order.fornecedor() {
+        Some(_) => None,
+        None => Some(prompts::get_supplier(reader)?)
+    };
+
+    let mut costs = Vec::new();
+
+    for item in order.itens().iter().filter(|item| item.custo_unitario().is_none()) {
+        let message = format!("Digite o custo unitário de {} (ID: {}) (ou 'sair' para cancelar):", item.nome(), item.id());
+        costs.push((item.id(), prompts::validate_amount(&message, reader)?));
+    }
+
+    let order = core::complete_order(products, orders_file, journal_file, id, supplier, &costs, operator)?;
+
+    println!("\nPedido {} recebido. O estoque dos produtos foi atualizado.\n", order.id());
+
+    Ok(())
+}
+
+pub fn update_product<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let id = prompts::validate_search("id", reader)?;
+    let product = core::search_product_id(products, id)?;
+
+    println!("\nProduto encontrado:\n\n{}\n", core::describe_product(products, product)?);
+    let updated_product = prompts::get_product_info(reader, &products.categories()?)?;
+
+    core::replace_product(products, journal_file, id, updated_product, operator)
+}
+
+pub fn update_product_taxes<P: ProductRepository, R: BufRead>(products: &mut P, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let id = prompts::validate_search("id", reader)?;
+    let product = core::search_product_id(products, id)?;
+
+    println!("\nProduto encontrado:\n\n{}\n", core::describe_product(products, product)?);
+    core::set_product_taxes(products, id, Some(prompts::get_tax_info(reader)?))?;
+
+    println!("\nDados fiscais atualizados com sucesso.\n");
+    Ok(())
+}
+
+pub fn remove_product<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let id = prompts::validate_search("id", reader)?;
+
+    core::delete_product(products, journal_file, id, operator)
+}
+
+pub fn add_category<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let (nome, pai) = prompts::get_category_info(reader, &products.categories()?)?;
+    let category = core::create_category(products, journal_file, &nome, pai)?;
+
+    println!("\nCategoria cadastrada com sucesso com o id {}.\n", category.id());
+
+    Ok(())
+}
+
+pub fn edit_category_name<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let categorias = products.categories()?;
+    let id = prompts::get_category("Digite o nome ou o id da categoria que deseja renomear (ou 'sair' para cancelar):", reader, &categorias)?;
+
+    println!("\nDigite o novo nome da categoria (ou 'sair' para cancelar):");
+    let nome = prompts::validate_string(reader)?;
+
+    core::rename_category(products, journal_file, id, &nome)?;
+    println!("\nCategoria renomeada com sucesso.\n");
+
+    Ok(())
+}
+
+pub fn join_categories<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let categorias = products.categories()?;
+    let from = prompts::get_category("Digite o nome ou o id da categoria que deixará de existir (ou 'sair' para cancelar):", reader, &categorias)?;
+    let into = prompts::get_category("Digite o nome ou o id da categoria que receberá os produtos (ou 'sair' para cancelar):", reader, &categorias)?;
+
+    let moved = core::merge_categories(products, journal_file, from, into)?;
+    println!("\nCategorias unidas com sucesso. {moved} produtos foram movidos.\n");
+
+    Ok(())
+}
+
+pub fn remove_category<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let categorias = products.categories()?;
+    let id = prompts::get_category("Digite o nome ou o id da categoria que deseja remover (ou 'sair' para cancelar):", reader, &categorias)?;
+
+    core::delete_category(products, journal_file, id)?;
+    println!("\nCategoria removida com sucesso.\n");
+
+    Ok(())
+}
+
+pub fn add_promotion<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let promotion = prompts::get_promotion_info(reader, &products.categories()?)?;
+    let promotion = core::create_promotion(products, journal_file, promotion)?;
+
+    println!("\nPromoção cadastrada com sucesso com o id {}.\n", promotion.id());
+
+    Ok(())
+}
+
+pub fn remove_promotion<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    println!("\nDigite o id da promoção que deseja remover (ou 'sair' para cancelar):");
+    let id = prompts::validate_string(reader)?.parse::<u64>().map_err(|_| CustomErrors::PromotionNotFound)?;
+
+    core::delete_promotion(products, journal_file, id)?;
+    println!("\nPromoção removida com sucesso. As vendas já registradas mantêm os descontos.\n");
+
+    Ok(())
+}
+
+pub fn receive_products<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let supplier = prompts::get_supplier(reader)?;
+
+    screens::receive_stock_screen();
+    let items = prompts::get_receipt_items(reader)?;
+
+    if items.is_empty() {
+        println!("\nNenhum produto inserido.\n");
+        return Ok(());
+    }
+
+    for product in core::receive_stock(products, journal_file, &supplier, &items, operator)? {
+        println!("\n{} (ID: {}) - Estoque atual: {}", product.nome(), product.id(), product.quantidade_estoque());
+    }
+
+    println!("\nRecebimento registrado com sucesso.\n");
+
+    Ok(())
+}
+
+pub fn register_adjustment<P: ProductRepository, T: StockTransaction<P>, R: BufRead>(products: &mut P, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let id = prompts::validate_search("id", reader)?;
+    let product = core::search_product_id(products, id)?;
+
+    println!("\nProduto encontrado:\n\n{}\n", core::describe_product(products, product)?);
+
+    let (tipo, quantity, reason) = prompts::get_adjustment_info(reader)?;
+    let product = core::adjust_stock(products, journal_file, id, tipo, quantity, operator, &reason)?;
+
+    println!("\nMovimento registrado. Estoque atual: {}.\n", product.quantidade_estoque());
+
+    Ok(())
+}
+
+pub fn report_sales<S: SaleRepository, R: BufRead>(sales: &mut S, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let (agrupamento, from, to) = prompts::get_report_info(reader)?;
+
+    show_sales_report(sales, agrupamento, from, to, Saida::Texto)
+}
+
+pub fn update_sale<S: SaleRepository, R: BufRead>(sales: &mut S, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let code = prompts::validate_search("code", reader)?;
+    let sale = core::search_sale_code(sales, code)?;
+
+    println!("\nVenda encontrada:\n\n{sale}\n");
+
+    let (date, payment_method) = prompts::get_sale_info(reader)?;
+
+    core::edit_sale(sales, code, date, payment_method)
+}
+
+pub fn update_sale_items<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>, R: BufRead>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let code = prompts::validate_search("code", reader)?;
+    let sale = core::search_sale_code(sales_file, code)?;
+
+    println!("\nVenda encontrada:\n\n{sale}\n");
+
+    screens::edit_sale_items_screen();
+    let items = prompts::get_items(reader)?;
+
+    if items.is_empty() {
+        println!("\nNenhum produto inserido.\n");
+        return Ok(());
+    }
+
+    let sale = core::change_sale_items(products_file, sales_file, journal_file, code, &items, operator)?;
+
+    println!("\nVenda atualizada:\n\n{sale}\n");
+
+    Ok(())
+}
+
+pub fn cancel_sale<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>, R: BufRead>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let code = prompts::validate_search("code", reader)?;
+    let sale = core::search_sale_code(sales_file, code)?;
+
+    println!("\nVenda encontrada:\n\n{sale}\n");
+
+    let reason = prompts::get_reason(reader)?;
+    let sale = core::void_sale(products_file, sales_file, journal_file, code, reason, operator)?;
+
+    println!("\nVenda {} cancelada. O estoque dos produtos foi restaurado.\n", sale.codigo());
+
+    Ok(())
+}
+
+pub fn return_sale_items<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>, R: BufRead>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, operator: &str, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let code = prompts::validate_search("code", reader)?;
+    let sale = core::search_sale_code(sales_file, code)?;
+
+    println!("\nVenda encontrada:\n\n{sale}\n");
+
+    screens::return_items_screen();
+    let items = prompts::get_items(reader)?;
+
+    if items.is_empty() {
+        println!("\nNenhum produto inserido.\n");
+        return Ok(());
+    }
+
+    let reason = prompts::get_reason(reader)?;
+    let sale = core::return_items(products_file, sales_file, journal_file, code, &items, reason, operator)?;
+
+    println!("\nDevolução registrada na venda {}. Situação: {}.\n", sale.codigo(), sale.situacao());
+
+    Ok(())
+}
+
+pub fn open_session<R: BufRead>(sessions: &mut ArquivoCaixas, reader: &mut R) -> Result<Caixa, Box<dyn Error>> {
+    if let Some(session) = sessions.current()? {
+        println!("\nCaixa em aberto:\n\n{session}\n");
+        return Err(Box::new(CustomErrors::SessionAlreadyOpen));
+    }
+
+    let (operator, float) = prompts::get_session_info(reader)?;
+    let session = core::start_session(sessions, operator, float)?;
+
+    println!("\nCaixa {} aberto com sucesso.\n", session.id());
+
+    Ok(session)
+}
+
+pub fn close_session<S: SaleRepository, R: BufRead>(sessions: &mut ArquivoCaixas, sales: &mut S, reader: &mut R) -> Result<(), Box<dyn Error>> {
+    let session = sessions.current()?.ok_or(CustomErrors::SessionNotFound)?;
+
+    println!("\nCaixa em aberto:\n\n{session}\n");
+
+    let counted = prompts::validate_amount("Digite o valor em dinheiro contado no caixa (ou 'sair' para cancelar):", reader)?;
+
+    finish_session(sessions, sales, counted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Cursor, path::PathBuf};
+
+    use super::*;
+    use store::{categories, memory::{MemoriaProdutos, MemoriaVendas, SemDiario}, validation::Arquivos, MetodoPagamento, Situacao};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    // Cada teste usa um estoque novo em um diretório próprio, removido no fim do teste.
+    fn test_store(name: &str) -> (PathBuf, Arquivos) {
+        let dir = env::temp_dir().join("store-tests").join(name);
+        let _ = fs::remove_dir_all(&dir);
+
+        validation::create_store(&dir).expect("Erro ao criar o estoque de teste.");
+        let (files, _) = validation::get_files(&dir).expect("Erro ao abrir o estoque de teste.");
+
+        (dir, files)
+    }
+
+    fn memory_products() -> MemoriaProdutos {
+        let mut products = MemoriaProdutos::new();
+
+        products.insert(Produto::new("Teste1".to_string(), 1, 10, Dinheiro::from_centavos(5000), 5, chrono::NaiveDate::default(), categories::GERAL)).unwrap();
+        products.insert(Produto::new("Teste2".to_string(), 2, 15, Dinheiro::from_centavos(4000), 25, chrono::NaiveDate::default(), categories::ALIMENTO)).unwrap();
+        products.insert(Produto::new("Teste3".to_string(), 3, 20, Dinheiro::from_centavos(6000), 10, chrono::NaiveDate::default(), categories::ELETRONICO)).unwrap();
+        core::record_opening_balances(&mut products).unwrap();
+
+        products
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        let input = args("sale register --seller Ana --item 2:10 --item 3 --payment pix --dry");
+        let arguments = Argumentos::parse(&input).unwrap();
+
+        assert_eq!(arguments.posicionais, vec!["sale", "register"]);
+        assert_eq!(arguments.required("seller").unwrap(), "Ana");
+        assert_eq!(arguments.all("item"), &["2:10", "3"]);
+        assert!(arguments.all("dry").is_empty());
+        assert!(arguments.required("item").is_err());
+        assert!(arguments.optional("date").unwrap().is_none());
+        assert!(arguments.allow(&["seller", "item", "payment"]).is_err());
+    }
+
+    #[test]
+    fn test_run_commands() {
+        let (dir, (mut products_file, mut sales_file, mut sessions_file, mut orders_file, mut journal_file)) = test_store("test_cli_run");
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("product add --name Camisa --stock 10 --price 49,90 --restock 2 --category roupa").is_ok());
+        assert!(run_line("product add --name Arroz --stock 5 --price 20 --restock 1 --date 10/08/2023 --category alimento").is_ok());
+        assert!(run_line("product update 2 --price 22.50 --barcode 7891234567895").is_ok());
+        assert!(run_line("session open --operator Ana --float 100").is_ok());
+        assert!(run_line("sale register --seller Ana --item 1:3 --item 7891234567895 --payment pix").is_ok());
+        assert!(run_line("sale update 1 --payment dinheiro").is_ok());
+        assert!(run_line("sale update 2 --item 1:1").is_err());
+        assert!(run_line("sales list --date 10/08/2023").is_ok());
+        assert!(run_line("sales report --by vendedor --from 01/01/2020").is_ok());
+        assert!(run_line("sales report --by pagamento --format jsonl").is_ok());
+
+        let error = run_line("sales report --by ano").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        let error = run_line("sales report --from 02/01/2024 --to 01/01/2024").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("sale register --seller Ana --item 2:10 --payment pix").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 5);
+
+        let error = run_line("product get 9").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        assert!(run_line("product get --barcode 7891234567895 --json").is_ok());
+
+        let error = run_line("product get --barcode 4006381333931").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        let error = run_line("product add --name Feijao --stock 1 --price 8 --restock 1 --category alimento --barcode 7891234567895").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 5);
+
+        let error = run_line("product update 1 --barcode 7891234567896").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("product add --name Camisa --stock dez --price 1 --restock 2 --category roupa").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("product remove 1 --force").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        let error = run_line("unknown").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        let error = run_line("session open --operator Bia").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 5);
+
+        let product = core::search_product_id(&mut products_file, 1).expect("Erro na busca pelo produto.");
+        assert_eq!(product.quantidade_estoque(), 7);
+
+        let product = core::search_product_id(&mut products_file, 2).expect("Erro na busca pelo produto.");
+        assert_eq!(product.valor(), Dinheiro::from_centavos(2250));
+        assert_eq!(product.quantidade_estoque(), 4);
+
+        let sale = core::search_sale_code(&mut sales_file, 1).expect("Erro na busca pela venda.");
+        assert_eq!(sale.valor(), Dinheiro::from_centavos(17220));
+        assert!(matches!(sale.metodo_pagamento(), MetodoPagamento::Dinheiro));
+        assert_eq!(sale.caixa(), Some(1));
+
+        assert!(run(&args("session close --counted 272,20"), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default()).is_ok());
+
+        let session = sessions_file.search(1).unwrap();
+        let closing = session.fechamento().unwrap();
+        assert_eq!((closing.esperado(), closing.contado()), (Dinheiro::from_centavos(27220), Dinheiro::from_centavos(27220)));
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("sale return 1 --item 1:2 --reason defeito").is_ok());
+
+        let error = run_line("sale return 1 --item 1:2 --reason defeito").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        assert!(run_line("sale cancel 1 --reason desistencia").is_ok());
+
+        let error = run_line("sale cancel 1 --reason desistencia").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 5);
+
+        let sale = core::search_sale_code(&mut sales_file, 1).expect("Erro na busca pela venda.");
+        assert_eq!(sale.situacao(), Situacao::Cancelada);
+        assert_eq!(sale.devolucoes().len(), 2);
+
+        let product = core::search_product_id(&mut products_file, 1).expect("Erro na busca pelo produto.");
+        assert_eq!(product.quantidade_estoque(), 10);
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("product adjust 1 --type perda --quantity 2 --reason avaria --operator Carla").is_ok());
+        assert!(run_line("product history 1").is_ok());
+        assert!(run_line("product receive --supplier Malharia --item 1:4:25,00 --operator Carla").is_ok());
+        assert!(run_line("product receive --supplier Malharia --item 1:4").is_err());
+        assert!(run_line("product audit").is_ok());
+
+        let error = run_line("product adjust 1 --quantity 0 --reason contagem").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("product audit --fix agora").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        let movements = products_file.movimentos.by_product(1).unwrap();
+        assert_eq!(movements.last().unwrap().operador(), "Carla");
+        assert!(core::stock_divergences(&mut products_file).unwrap().is_empty());
+        assert_eq!(products_file.movimentos.balances().unwrap()[&1], 12);
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("product restock --days 7 --lead-time 2").is_ok());
+        assert!(run_line("product adjust 2 --type perda --quantity 4 --reason validade").is_ok());
+        assert!(run_line("order create").is_ok());
+        assert!(run_line("order list").is_ok());
+        assert!(run_line("order get 1").is_ok());
+
+        let error = run_line("order receive 1").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        assert!(run_line("order receive 1 --supplier Cerealista --cost 2:15,00 --operator Carla").is_ok());
+
+        let error = run_line("order receive 1 --supplier Cerealista").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 5);
+
+        let error = run_line("order get 9").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        let csv_path = dir.join("importacao.csv");
+        fs::write(&csv_path, "nome,estoque,valor,restoque,data,categoria\nBola,4,\"15,00\",1,10/08/2023,geral\n").unwrap();
+
+        let import = format!("import products {} --dry-run", csv_path.display());
+        assert!(run_line(&import).is_ok());
+        assert!(run_line(&import.replace(" --dry-run", "")).is_ok());
+        assert!(run_line("export products").is_ok());
+        assert!(run_line("product list --json").is_ok());
+        assert!(run_line("product search --name bola --category geral --max-price 20 --json").is_ok());
+        assert!(run_line("product search --min-stock 1 --low-stock").is_ok());
+
+        let error = run_line("product search --min-price 10 --max-price 5").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("product search --category bebida").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+        assert!(run_line("product get 1 --format jsonl").is_ok());
+        assert!(run_line("sales list --product 1 --format json").is_ok());
+        assert!(run_line("order list --format jsonl").is_ok());
+        assert!(run_line("session list --json").is_ok());
+
+        let error = run_line("product list --json --format jsonl").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        assert!(run_line("export sales --format csv").is_ok());
+
+        let error = run_line("export sales --format xml").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        fs::write(&csv_path, "codigo,data,vendedor,pagamento,produto,quantidade\n1,10/08/2023,Ana,pix,9,1\n").unwrap();
+
+        let error = run_line(&format!("import sales {}", csv_path.display())).unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        fs::remove_file(&csv_path).expect("Erro ao tentar excluir o arquivo.");
+
+        let product = core::search_product_id(&mut products_file, 3).expect("Erro na busca pelo produto.");
+        assert_eq!(product.nome(), "Bola");
+
+        let product = core::search_product_id(&mut products_file, 2).expect("Erro na busca pelo produto.");
+        assert_eq!(product.fornecedor(), Some("Cerealista"));
+        assert!(product.quantidade_estoque() > 0);
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+
+    #[test]
+    fn test_run_promotions() {
+        let (dir, (mut products_file, mut sales_file, mut sessions_file, mut orders_file, mut journal_file)) = test_store("test_cli_promotions");
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("product add --name Camisa --stock 10 --price 10 --restock 2 --category roupa").is_ok());
+        assert!(run_line("promotion add --name Leve3 --product 1 --buy 3 --pay 2").is_ok());
+        assert!(run_line("promotion add --name Roupas --category roupa --percent 10 --from 01/01/2020").is_ok());
+        assert!(run_line("promotion list --json").is_ok());
+
+        let error = run_line("promotion add --name Errada --product 1 --buy 2 --pay 2").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("promotion add --name Errada --product 1 --category roupa --percent 10").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 2);
+
+        let error = run_line("promotion add --name Errada --product 9 --buy 3 --pay 2").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        let error = run_line("sale register --seller Ana --item 1:3 --discount 2:10% --payment pix").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        assert!(run_line("sale register --seller Ana --item 1:3 --discount 1:5,00 --sale-discount 10% --payment pix").is_ok());
+
+        assert!(run_line("promotion remove 2").is_ok());
+
+        let error = run_line("promotion remove 2").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        // Leve 3, pague 2 vence os 10% da categoria; depois vêm os R$ 5,00 na linha e os 10% na venda.
+        let sale = core::search_sale_code(&mut sales_file, 1).expect("Erro na busca pela venda.");
+        assert_eq!(sale.produtos()[0].desconto(), Dinheiro::from_centavos(1500));
+        assert_eq!(sale.desconto(), Dinheiro::from_centavos(150));
+        assert_eq!(sale.valor(), Dinheiro::from_centavos(1350));
+        assert_eq!(sale.promocoes()[0].promocao(), 1);
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+
+    #[test]
+    fn test_run_taxes() {
+        let (dir, (mut products_file, mut sales_file, mut sessions_file, mut orders_file, mut journal_file)) = test_store("test_cli_taxes");
+        let printer = dir.join("impressora.bin");
+
+
+        let mut run_line = |line: &str| run(&args(line), &mut products_file, &mut sales_file, &mut sessions_file, &mut orders_file, &mut journal_file, &Loja::default());
+
+        assert!(run_line("product add --name Arroz --stock 10 --price 20 --restock 2 --category alimento --ncm 1006.30.21 --cfop 5102 --icms 18").is_ok());
+        assert!(run_line("product add --name Sacola --stock 10 --price 1 --restock 2 --category geral").is_ok());
+
+        let error = run_line("product add --name Feijao --stock 10 --price 8 --restock 2 --category alimento --ncm 0713").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        let error = run_line("product update 2 --icms 18").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 3);
+
+        assert!(run_line("product update 1 --pis 1,65 --cofins 7,6").is_ok());
+        assert!(run_line("sale register --seller Ana --item 1:2 --item 2 --payment pix --receipt").is_ok());
+        assert!(run_line(&format!("sale receipt 1 --printer {}", printer.display())).is_ok());
+
+        let error = run_line("sale receipt 9").unwrap_err();
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        // A venda guarda os dados fiscais do produto no momento da venda.
+        let sale = core::search_sale_code(&mut sales_file, 1).expect("Erro na busca pela venda.");
+        let tributacao = sale.produtos()[0].tributacao().expect("A venda deveria ter os dados fiscais.");
+        assert_eq!((tributacao.ncm(), tributacao.cofins().to_string().as_str()), ("10063021", "7,60%"));
+        assert!(sale.produtos()[1].tributacao().is_none());
+
+        assert!(fs::read(&printer).unwrap().starts_with(&[0x1b, b'@']));
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+
+    #[test]
+    fn test_add_product() {
+        let (dir, (mut products, _, _, _, mut journal_file)) = test_store("test_cli_add_product");
+
+        let input = b"Camisa 10 50 5 10/8/2023 Geral";
+        let mut cursor = Cursor::new(input);
+
+        assert!(add_product(&mut products, &mut journal_file, "Ana", &mut cursor).is_ok());
+
+        let product = core::search_product_id(&mut products, 1).expect("Erro na busca pelo produto.");
+
+        assert_eq!(product.nome(), "Camisa");
+        assert_eq!(product.quantidade_estoque(), 10);
+        assert_eq!(products.balances().unwrap()[&1], 10);
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+
+    #[test]
+    fn test_register_sale() {
+        let mut products = memory_products();
+        let mut sales = MemoriaVendas::new();
+
+        let input = "1 2\n2\n1 3\nconcluir\n2 10%\nconcluir\n5,00\npix";
+        let mut cursor = Cursor::new(input);
+
+        assert!(register_sale(&mut products, &mut sales, &mut SemDiario, "Teste".to_string(), None, &Loja::default(), &mut cursor).is_ok());
+
+        let sale = core::search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.");
+
+        assert_eq!(sale.produtos().len(), 2);
+        assert_eq!(sale.produtos()[0].id(), 1);
+        assert_eq!(sale.produtos()[0].quantidade(), 5);
+        assert_eq!(sale.produtos()[0].valor_total(), Dinheiro::from_centavos(25000));
+        assert_eq!(sale.produtos()[1].quantidade(), 1);
+        assert_eq!(sale.produtos()[1].desconto(), Dinheiro::from_centavos(400));
+        assert_eq!(sale.desconto(), Dinheiro::from_centavos(500));
+        assert_eq!(sale.valor(), Dinheiro::from_centavos(28100));
+        assert_eq!(core::search_product_id(&mut products, 1).unwrap().quantidade_estoque(), 5);
+
+        // O produto também pode ser lido pelo código de barras.
+        let mut product = core::search_product_id(&mut products, 2).unwrap();
+        product.set_codigo_barras(Some("7891234567895".to_string()));
+        core::replace_product(&mut products, &mut SemDiario, 2, product, "Ana").unwrap();
+
+        let mut cursor = Cursor::new("7891234567895\n3 2\n7891234567895 4\nconcluir\nconcluir\n\npix\n");
+        register_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Loja::default(), &mut cursor).unwrap();
+
+        let sale = sales.get(2).unwrap();
+        assert_eq!(sale.produtos().iter().map(|item| (item.id(), item.quantidade())).collect::<Vec<_>>(), vec![(2, 5), (3, 2)]);
+
+        let mut cursor = Cursor::new("4006381333931\nconcluir\nconcluir\n\npix\n");
+        let error = register_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Loja::default(), &mut cursor).err().unwrap();
+        assert!(matches!(error.downcast_ref::<CustomErrors>(), Some(CustomErrors::ProductNotFound)));
+
+        assert!(show_sale_receipt(&mut sales, &Loja::default(), &mut Cursor::new("1\n")).is_ok());
+    }
+
+    #[test]
+    fn test_update_products() {
+        let mut products = memory_products();
+
+        let input = "1\nFeijão-carioca-tipo-1-pacote-econômico-de-5-quilos 10 50 5 10/8/2023 Geral".as_bytes();
+        assert!(update_product(&mut products, &mut SemDiario, "Ana", &mut Cursor::new(input)).is_ok());
+        assert_eq!(core::search_product_id(&mut products, 1).unwrap().nome(), "Feijão-carioca-tipo-1-pacote-econômico-de-5-quilos");
+
+        // O NCM inválido é pedido de novo.
+        let input = "2\n1006302 5102 18 0 0\n1006.30.21 5.102 18 0 0\n";
+        assert!(update_product_taxes(&mut products, &mut Cursor::new(input)).is_ok());
+        assert_eq!(core::search_product_id(&mut products, 2).unwrap().tributacao().unwrap().ncm(), "10063021");
+
+        let input = "Distribuidora Norte\n1 5 30,00\n3 2 45\n1 1 32,50\nconcluir\n";
+        assert!(receive_products(&mut products, &mut SemDiario, "Ana", &mut Cursor::new(input)).is_ok());
+        assert_eq!(core::search_product_id(&mut products, 1).unwrap().quantidade_estoque(), 16);
+
+        assert!(remove_product(&mut products, &mut SemDiario, "Ana", &mut Cursor::new(b"1")).is_ok());
+        assert!(core::search_product_id(&mut products, 1).is_err());
+        assert!(core::stock_divergences(&mut products).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_sales() {
+        let mut products = memory_products();
+        let mut sales = MemoriaVendas::new();
+
+        for item in [(2, 1), (3, 1), (1, 2)] {
+            core::complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![item]), MetodoPagamento::Credito).unwrap();
+        }
+
+        assert!(update_sale(&mut sales, &mut Cursor::new(b"2\n1/8/2023\npix")).is_ok());
+        assert_eq!(core::search_sale_code(&mut sales, 2).unwrap().data(), chrono::NaiveDate::from_ymd_opt(2023, 8, 1).unwrap());
+
+        assert!(return_sale_items(&mut products, &mut sales, &mut SemDiario, "Ana", &mut Cursor::new(b"3\n1 1\nconcluir\nDefeito\n")).is_ok());
+        assert!(cancel_sale(&mut products, &mut sales, &mut SemDiario, "Ana", &mut Cursor::new("3\nDesistência do cliente\n")).is_ok());
+
+        let sale = core::search_sale_code(&mut sales, 3).unwrap();
+        assert_eq!(sale.devolucoes().len(), 2);
+        assert_eq!(core::search_product_id(&mut products, 1).unwrap().quantidade_estoque(), 10);
+
+        assert!(list_sales(&mut sales, Saida::JsonLines).is_ok());
+        assert!(search_sales_by_date(&mut sales, chrono::NaiveDate::default(), Saida::Json).is_ok());
+        assert!(search_product_sales(&mut sales, 1, Saida::Texto).is_ok());
+    }
+
+    #[test]
+    fn test_listings() {
+        let mut products = memory_products();
+        let mut sales = MemoriaVendas::new();
+
+        assert!(list_products(&mut products, Saida::Texto).is_ok());
+        assert!(find_products(&mut products, &mut Cursor::new("teste\n\n\n\n\n\nn\n")).is_ok());
+        assert!(products_needing_restock(&mut products, &mut sales, &orders::Parametros::default()).is_ok());
+        assert!(product_history(&mut products, 1, Saida::Texto).is_ok());
+        assert!(audit_stock(&mut products, false).is_ok());
+    }
+
+    #[test]
+    fn test_sessions() {
+        let (dir, (_, _, mut sessions, _, _)) = test_store("test_cli_sessions");
+        let mut products = memory_products();
+        let mut sales = MemoriaVendas::new();
+
+        let session = open_session(&mut sessions, &mut Cursor::new(b"Ana\n50,00\n")).expect("Erro ao abrir o caixa.");
+
+        let error = open_session(&mut sessions, &mut Cursor::new(b"Bia\n0\n")).err().unwrap();
+        assert!(matches!(error.downcast_ref::<CustomErrors>(), Some(CustomErrors::SessionAlreadyOpen)));
+
+        core::complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), Some(session.id()), &Cesta::new(vec![(1, 1)]), MetodoPagamento::Dinheiro).unwrap();
+
+        assert!(close_session(&mut sessions, &mut sales, &mut Cursor::new(b"sair\n")).is_err());
+        assert!(sessions.current().unwrap().is_some());
+
+        assert!(close_session(&mut sessions, &mut sales, &mut Cursor::new(b"99,00\n")).is_ok());
+
+        let session = sessions.search(session.id()).unwrap();
+        let closing = session.fechamento().unwrap();
+
+        assert_eq!(closing.esperado(), Dinheiro::from_centavos(10000));
+        assert_eq!(closing.contado(), Dinheiro::from_centavos(9900));
+        assert!(list_sessions(&mut sessions, Saida::Texto).is_ok());
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+}
@@ -0,0 +1,126 @@
+use std::{env, error::Error, fs, path::{Path, PathBuf}};
+
+use super::errors::CustomErrors;
+
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+pub const DATA_DIR_VARIABLE: &str = "STORE_DATA_DIR";
+pub const CONFIG_VARIABLE: &str = "STORE_CONFIG";
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_VARIABLE) {
+        return Some(PathBuf::from(path));
+    }
+
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("store").join("config"))
+}
+
+// Linhas 'chave = valor' de um arquivo de configuração; linhas vazias e comentários com '#' são ignorados.
+pub fn read_settings(path: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut settings = Vec::new();
+
+    for line in fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => settings.push((key.trim().to_string(), value.trim().to_string())),
+            None => return Err(Box::new(CustomErrors::InvalidConfig))
+        }
+    }
+
+    Ok(settings)
+}
+
+pub fn read_config(path: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    Ok(read_settings(path)?.into_iter()
+        .filter(|(key, _)| key == "data_dir")
+        .map(|(_, value)| PathBuf::from(value))
+        .next_back())
+}
+
+fn take_flag(args: &mut Vec<String>) -> Result<Option<String>, CustomErrors> {
+    let Some(index) = args.iter().position(|arg| arg == DATA_DIR_FLAG || arg.starts_with("--data-dir=")) else {
+        return Ok(None);
+    };
+
+    let arg = args.remove(index);
+
+    if let Some(value) = arg.strip_prefix("--data-dir=") {
+        return Ok(Some(value.to_string()));
+    }
+
+    match index < args.len() && !args[index].starts_with("--") {
+        true => Ok(Some(args.remove(index))),
+        false => Err(CustomErrors::InvalidCommand)
+    }
+}
+
+pub fn data_dir(args: &mut Vec<String>) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(dir) = take_flag(args)? {
+        return Ok(PathBuf::from(dir));
+    }
+
+    if let Some(dir) = env::var_os(DATA_DIR_VARIABLE).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    if let Some(dir) = config_path().map(|path| read_config(&path)).transpose()?.flatten() {
+        return Ok(dir);
+    }
+
+    Ok(PathBuf::from("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::test_path;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_data_dir_flag() {
+        let mut input = args("product list --data-dir /tmp/filial");
+
+        assert_eq!(data_dir(&mut input).unwrap(), PathBuf::from("/tmp/filial"));
+        assert_eq!(input, args("product list"));
+
+        let mut input = args("--data-dir=/tmp/centro sales list");
+
+        assert_eq!(data_dir(&mut input).unwrap(), PathBuf::from("/tmp/centro"));
+        assert_eq!(input, args("sales list"));
+
+        let mut input = args("product list --data-dir");
+
+        assert!(data_dir(&mut input).is_err());
+    }
+
+    #[test]
+    fn test_read_config() {
+        let path = test_path("test_read_config.conf");
+
+        fs::write(&path, "# Loja do centro\n\ndata_dir = /var/lib/store/centro\noutra = 1\n").unwrap();
+        assert_eq!(read_config(&path).unwrap(), Some(PathBuf::from("/var/lib/store/centro")));
+
+        fs::write(&path, "data_dir\n").unwrap();
+        assert!(read_config(&path).is_err());
+
+        fs::remove_file(&path).expect("Erro ao tentar excluir o arquivo.");
+
+        assert_eq!(read_config(&path).unwrap(), None);
+    }
+}
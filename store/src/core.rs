@@ -1,659 +1,1667 @@
-use std::{error::Error, fs::File, io::{BufRead, Read, Seek, SeekFrom, Write}};
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
 
-use super::{errors, screens, validation, Produto, Venda};
+use super::{categories, csv, errors, validation, money::Dinheiro, orders::{self, ArquivoPedidos}, promotions::{self, Cesta}, reports, repository::{ProductRepository, SaleRepository, SaleTransaction, StockTransaction}, search::{self, FiltroProdutos}, sessions::ArquivoCaixas, Caixa, Categoria, Devolucao, ItemPedido, ItemVenda, MetodoPagamento, Movimento, Pedido, Produto, ProdutoExibido, Promocao, Recebimento, Regra, Situacao, TipoMovimento, Tributacao, Venda};
 
-const PRODUCT_LENGTH: usize = 102;
-const PRODUCT_LENGTH_U64: u64 = 102;
+pub(crate) fn remove_bytes(file: &mut File, mut position: u64, length: u64) -> Result<(), Box<dyn Error>> {
+    let size = file.seek(SeekFrom::End(0))? - length;
+    let mut buf = vec![0; 100000];
 
-pub fn add_product<R: BufRead>(file: &mut File, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    let mut product = validation::get_product_info(reader)?;
+    loop {
+        file.seek(SeekFrom::Start(position + length))?;
+        let bytes_read = file.read(&mut buf)?;
 
-    if file.seek(SeekFrom::End(0))? == 0 {
-        product.id = 1;
-    } else {
-        let mut buf = vec![0; 8];
+        if bytes_read == 0 {
+            file.set_len(size)?;
+            return Ok(());
+        }
 
-        file.seek(SeekFrom::End(-8))?;
-        file.read_exact(&mut buf)?;
+        file.seek(SeekFrom::Start(position))?;
+        file.write_all(&buf[..bytes_read])?;
+        position += bytes_read as u64;
+    }
+}
 
-        let id: u64 = bincode::deserialize(&buf)?;
-        product.id = id + 1;
+pub fn insert_product<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, product: Produto, operator: &str) -> Result<u64, Box<dyn Error>> {
+    Ok(insert_products(products, journal_file, vec![product], operator)?[0])
+}
 
-        file.seek(SeekFrom::End(-8))?;
+// Os produtos são gravados numa única transação: se um deles falhar, nenhum é cadastrado.
+fn insert_products<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, new_products: Vec<Produto>, operator: &str) -> Result<Vec<u64>, Box<dyn Error>> {
+    for product in new_products.iter() {
+        check_category(products, product.categoria)?;
+        check_barcode(products, product)?;
     }
 
-    let mut serialized = bincode::serialize(&product)?;
-    serialized.resize(PRODUCT_LENGTH, 0);
-    file.write(&serialized)?;
+    journal_file.insert_products(products, new_products, |product| {
+        stock_movements(&[(product.id, product.quantidade_estoque as i64)], TipoMovimento::Inicial, operator, "Cadastro do produto")
+    })
+}
 
-    let id = product.id;
-    let serialized_id = bincode::serialize(&id)?;
-    file.write(&serialized_id)?;
+// Os movimentos vão para a mesma transação que grava a alteração do estoque.
+fn stock_movements(changes: &[(u64, i64)], tipo: TipoMovimento, operator: &str, reason: &str) -> Vec<Movimento> {
+    changes.iter()
+        .filter(|&&(_, quantidade)| quantidade != 0)
+        .map(|&(id, quantidade)| Movimento::new(id, tipo, quantidade, operator.to_string(), reason.to_string()))
+        .collect()
+}
 
-    println!("\nProduto adicionado com sucesso com o id {}.\n", product.id);
+pub fn record_opening_balances<P: ProductRepository>(products: &mut P) -> Result<(), Box<dyn Error>> {
+    let balances: Vec<(u64, i64)> = products.list()?.iter()
+        .map(|product| (product.id, product.quantidade_estoque as i64))
+        .collect();
 
-    Ok(())
+    products.append_movements(stock_movements(&balances, TipoMovimento::Inicial, "sistema", "Saldo existente ao criar o histórico de movimentos"))
 }
 
-pub fn register_sale<R: BufRead>(products_file: &mut File, sales_file: &mut File, seller: String, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    screens::add_sale_screen();
-    let mut products: Vec<(u64, u64)> = Vec::new();
-
-    loop {
-        let mut buf = String::new();
-        reader.read_line(&mut buf)?;
+// O produto precisa estar em uma categoria cadastrada.
+fn check_category<P: ProductRepository>(products: &mut P, id: u64) -> Result<(), Box<dyn Error>> {
+    match products.categories()?.iter().any(|categoria| categoria.id == id) {
+        true => Ok(()),
+        false => Err(Box::new(errors::CustomErrors::NoCategory))
+    }
+}
 
-        match buf.trim().to_lowercase().as_str() {
-            "sair" => return Err(Box::new(errors::CustomErrors::OperationCanceled)),
-            "concluir" => break,
-            _ => ()
-        }
+// Um código de barras só pode pertencer a um produto.
+fn check_barcode<P: ProductRepository>(products: &mut P, product: &Produto) -> Result<(), Box<dyn Error>> {
+    let Some(barcode) = &product.codigo_barras else {
+        return Ok(());
+    };
 
-        products.push(validation::validate_sale(buf.trim())?);
-        println!("\nPróximo produto na venda (ou 'sair'):\n")
+    match products.barcode_id(barcode)? {
+        Some(id) if id != product.id => Err(Box::new(errors::CustomErrors::DuplicateBarcode)),
+        _ => Ok(())
     }
+}
 
-    if products.is_empty() {
-        println!("\nNenhum produto inserido.\n");
-        return Ok(());
+pub fn complete_sale<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, seller: String, session: Option<u64>, cesta: &Cesta, payment_method: MetodoPagamento) -> Result<Venda, Box<dyn Error>> {
+    let mut products: Vec<(u64, u64)> = Vec::new();
+
+    for &(id, amount) in cesta.itens.iter() {
+        match products.iter_mut().find(|(product_id, _)| *product_id == id) {
+            Some((_, total)) => *total += amount,
+            None => products.push((id, amount))
+        }
     }
 
-    let mut value: f64 = 0.0;
+    let mut value = Dinheiro::ZERO;
 
-    let mut sale = Venda::new(seller, 0, value, chrono::Local::now().date_naive(), validation::validate_payment_method(reader)?);
+    let mut sale = Venda::new(seller, 0, value, chrono::Local::now().date_naive(), payment_method);
+    sale.caixa = session;
 
-    for &(id, amount) in products.iter() {
-        let (mut product, position) = search_product_id(products_file, id)?;
+    let mut updated_products: Vec<Produto> = Vec::new();
 
-        value += product.valor * amount as f64;
+    for &(id, amount) in products.iter() {
+        let mut product = search_product_id(products_file, id)?;
 
-        match amount > product.quantidade_estoque {
-            true => return Err(Box::new(errors::CustomErrors::LowStock)),
-            false => product.quantidade_estoque -= amount
+        if amount > product.quantidade_estoque {
+            return Err(Box::new(errors::CustomErrors::LowStock));
         }
 
-        products_file.seek(SeekFrom::Start(position))?;
+        let item = ItemVenda::from_product(&product, amount)?;
 
-        let mut serialized = bincode::serialize(&product)?;
-        serialized.resize(PRODUCT_LENGTH, 0);
-        products_file.write(&serialized)?;
+        value = value.checked_add(item.valor_total)?;
+        product.quantidade_estoque -= amount;
 
-        if !sale.produtos.contains(&product.id) {
-            sale.produtos.push(product.id);
-        }
+        sale.produtos.push(item);
+        updated_products.push(product);
     }
 
     sale.valor = value;
 
-    if sales_file.seek(SeekFrom::End(0))? == 0 {
-        sale.codigo = 1;
-    } else {
-        sales_file.seek(SeekFrom::End(-8))?;
+    promotions::apply_discounts(&mut sale, &updated_products, &products_file.categories()?, &products_file.promotions()?, cesta)?;
+
+    let changes: Vec<(u64, i64)> = products.iter().map(|&(id, amount)| (id, -(amount as i64))).collect();
+
+    journal_file.insert_sale(products_file, sales_file, &updated_products, sale, |sale| {
+        stock_movements(&changes, TipoMovimento::Venda, &sale.vendedor, &format!("Venda {}", sale.codigo))
+    })
+}
+
+// Troca os códigos de barras lidos pelos ids dos produtos.
+pub fn resolve_items<P: ProductRepository>(products: &mut P, items: &[validation::ItemLido]) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    let mut resolved = Vec::with_capacity(items.len());
+
+    for item in items.iter() {
+        match item {
+            validation::ItemLido::Id(id, amount) => resolved.push((*id, *amount)),
+            validation::ItemLido::CodigoBarras(barcode, amount) => resolved.push((search_product_barcode(products, barcode)?.id, *amount))
+        }
+    }
 
-        let mut buf = vec![0; 8];
-        sales_file.read_exact(&mut buf)?;
+    Ok(resolved)
+}
 
-        let code: u64 = bincode::deserialize(&buf)?;
+pub fn search_product_id<P: ProductRepository>(products: &mut P, id: u64) -> Result<Produto, Box<dyn Error>> {
+    products.get(id)
+}
 
-        sale.codigo = code + 1;
-        sales_file.seek(SeekFrom::End(-8))?;
+pub fn search_product_barcode<P: ProductRepository>(products: &mut P, barcode: &str) -> Result<Produto, Box<dyn Error>> {
+    match products.barcode_id(barcode)? {
+        Some(id) => products.get(id),
+        None => Err(Box::new(errors::CustomErrors::ProductNotFound))
     }
+}
+
+// Produtos com o nome da categoria no lugar do id, para exibição.
+pub fn describe_products<P: ProductRepository>(products: &mut P, list: Vec<Produto>) -> Result<Vec<ProdutoExibido>, Box<dyn Error>> {
+    Ok(categories::describe(list, &products.categories()?))
+}
 
-    let serialized = bincode::serialize(&sale)?;
-    let size = serialized.len() as u64;
-    let code = sale.codigo;
-    let serialized_size = bincode::serialize(&size)?;
-    let serialized_code = bincode::serialize(&code)?;
+pub fn describe_product<P: ProductRepository>(products: &mut P, product: Produto) -> Result<ProdutoExibido, Box<dyn Error>> {
+    Ok(describe_products(products, vec![product])?.remove(0))
+}
 
-    sales_file.write(&serialized_size)?;
-    sales_file.write(&serialized)?;
-    sales_file.write(&serialized_code)?;
+pub fn search_products<P: ProductRepository>(products: &mut P, filtro: &FiltroProdutos) -> Result<Vec<Produto>, Box<dyn Error>> {
+    filtro.validate()?;
 
-    Ok(())
+    Ok(search::filter_products(products.list()?, filtro))
 }
 
-pub fn search_product_id(file: &mut File, id: u64) -> Result<(Produto, u64), Box<dyn Error>> {
-    let mut left = 0;
-    let mut right = file.seek(SeekFrom::End(-8))? / PRODUCT_LENGTH_U64;
+pub fn restock_suggestions<P: ProductRepository, S: SaleRepository>(products: &mut P, sales: &mut S, parametros: &orders::Parametros) -> Result<Vec<orders::Sugestao>, Box<dyn Error>> {
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Days::new(parametros.dias);
+
+    Ok(orders::suggest(products.list()?, &sales.by_range(Some(from), Some(today))?, parametros))
+}
 
-    if right != 0 {
-        right -= 1;
+// Um pedido por fornecedor com as quantidades sugeridas para cada produto.
+pub fn create_purchase_orders<P: ProductRepository, S: SaleRepository>(products: &mut P, sales: &mut S, orders_file: &mut ArquivoPedidos, parametros: &orders::Parametros) -> Result<Vec<Pedido>, Box<dyn Error>> {
+    let suggestions = restock_suggestions(products, sales, parametros)?;
+    let today = chrono::Local::now().date_naive();
+    let mut created = Vec::new();
+
+    for (supplier, suggestions) in orders::by_supplier(suggestions) {
+        let items = suggestions.into_iter()
+            .map(|suggestion| ItemPedido {
+                id: suggestion.produto.id,
+                nome: suggestion.produto.nome,
+                quantidade: suggestion.quantidade,
+                custo_unitario: suggestion.produto.custo_unitario
+            })
+            .collect();
+
+        created.push(orders_file.create(supplier, items, today)?);
     }
 
-    let mut buf = vec![0; PRODUCT_LENGTH];
+    Ok(created)
+}
+
+// Fornecedor e custos informados no recebimento substituem os que faltam no pedido.
+pub fn complete_order<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, orders_file: &mut ArquivoPedidos, journal_file: &mut T, id: u64, supplier: Option<String>, costs: &[(u64, Dinheiro)], operator: &str) -> Result<Pedido, Box<dyn Error>> {
+    let order = orders_file.search(id)?;
 
-    while left <= right {
-        let mid= (left + right) / 2;
-        file.seek(SeekFrom::Start(mid * PRODUCT_LENGTH_U64))?;
-        file.read_exact(&mut buf)?;
+    if order.recebimento.is_some() {
+        return Err(Box::new(errors::CustomErrors::OrderReceived));
+    }
 
-        let product: Produto = bincode::deserialize(&buf)?;
+    let supplier = supplier.or(order.fornecedor).ok_or(errors::CustomErrors::IncompleteOrder)?;
+    let mut items = Vec::new();
 
-        if product.id > id {
-            if mid != 0 {
-                right = mid - 1;
-            } else {
-                break;
-            }
-        } else if product.id < id {
-            left = mid + 1;
-        } else {
-            return Ok((product, mid * PRODUCT_LENGTH_U64));
-        }
+    for item in order.itens.iter() {
+        let cost = costs.iter()
+            .find(|(item_id, _)| *item_id == item.id)
+            .map(|&(_, cost)| cost)
+            .or(item.custo_unitario)
+            .ok_or(errors::CustomErrors::IncompleteOrder)?;
+
+        items.push((item.id, item.quantidade, cost));
     }
-    
-    Err(Box::new(errors::CustomErrors::ProductNotFound))
+
+    // O motivo dos movimentos identifica o pedido. Se a gravação do pedido foi interrompida
+    // depois que o estoque foi creditado, receber de novo só marca o pedido como recebido.
+    let reason = format!("Recebimento do pedido {id}");
+    let credited = match order.itens.first() {
+        Some(item) => products.movements(item.id)?.iter().any(|movement| movement.tipo() == TipoMovimento::Restoque && movement.motivo() == reason),
+        None => false
+    };
+
+    if !credited {
+        credit_stock(products, journal_file, &supplier, &items, operator, &reason)?;
+    }
+
+    orders_file.mark_received(id, operator.to_string(), chrono::Local::now().date_naive())
+}
+
+// Os dados fiscais não mexem no estoque, então nenhum movimento é registrado.
+pub fn set_product_taxes<P: ProductRepository>(products: &mut P, id: u64, tributacao: Option<Tributacao>) -> Result<Produto, Box<dyn Error>> {
+    let mut product = search_product_id(products, id)?;
+
+    if let Some(tributacao) = &tributacao {
+        validation::check_taxes(tributacao)?;
+    }
+
+    product.tributacao = tributacao;
+    products.update(&product)?;
+
+    Ok(product)
+}
+
+pub fn replace_product<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64, mut updated_product: Produto, operator: &str) -> Result<(), Box<dyn Error>> {
+    let product = search_product_id(products, id)?;
+    let change = updated_product.quantidade_estoque as i64 - product.quantidade_estoque as i64;
+
+    // Os dados do último recebimento não fazem parte da edição do produto.
+    updated_product.id = id;
+    updated_product.fornecedor = updated_product.fornecedor.or(product.fornecedor);
+    updated_product.custo_unitario = updated_product.custo_unitario.or(product.custo_unitario);
+    updated_product.codigo_barras = updated_product.codigo_barras.or(product.codigo_barras);
+    updated_product.tributacao = updated_product.tributacao.or(product.tributacao);
+
+    check_category(products, updated_product.categoria)?;
+    check_barcode(products, &updated_product)?;
+
+    journal_file.update_products(products, &[updated_product], stock_movements(&[(id, change)], TipoMovimento::Ajuste, operator, "Edição do produto"))
+}
+
+pub fn delete_product<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64, operator: &str) -> Result<(), Box<dyn Error>> {
+    let product = search_product_id(products, id)?;
+
+    journal_file.remove_product(products, id, stock_movements(&[(id, -(product.quantidade_estoque as i64))], TipoMovimento::Ajuste, operator, "Produto removido"))
 }
 
-pub fn list_products(file: &mut File) -> Result<(), Box<dyn Error>> {
-    println!("\nProdutos no estoque:\n");
+pub fn search_category<P: ProductRepository>(products: &mut P, id: u64) -> Result<Categoria, Box<dyn Error>> {
+    let category = products.categories()?.into_iter()
+        .find(|categoria| categoria.id == id)
+        .ok_or(errors::CustomErrors::CategoryNotFound)?;
+
+    Ok(category)
+}
 
-    let mut buf = [0; PRODUCT_LENGTH];
-    let mut product: Produto;
+// Os nomes são comparados sem maiúsculas, minúsculas e acentos, como na busca.
+fn check_category_name(categorias: &[Categoria], nome: &str, id: u64) -> Result<(), Box<dyn Error>> {
+    let duplicate = categorias.iter().any(|categoria| categoria.id != id && search::normalize(&categoria.nome) == search::normalize(nome));
 
-    file.seek(SeekFrom::Start(0))?;
+    match duplicate {
+        true => Err(Box::new(errors::CustomErrors::DuplicateCategory)),
+        false => Ok(())
+    }
+}
 
-    while let Ok(_) = file.read_exact(&mut buf) {
+pub fn create_category<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, nome: &str, pai: Option<u64>) -> Result<Categoria, Box<dyn Error>> {
+    let nome = validation::validate_name(nome)?;
+    let categorias = products.categories()?;
 
-        product = bincode::deserialize(&buf)?;
+    check_category_name(&categorias, &nome, 0)?;
 
-        println!("{product}\n");
+    if let Some(pai) = pai {
+        search_category(products, pai)?;
     }
 
-    Ok(())
+    let mut category = Categoria::new(0, nome, pai);
+    category.id = journal_file.update_catalog(products, &[], |products| products.insert_category(category.clone()))?;
+
+    Ok(category)
+}
+
+pub fn rename_category<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64, nome: &str) -> Result<Categoria, Box<dyn Error>> {
+    let nome = validation::validate_name(nome)?;
+    let mut category = search_category(products, id)?;
+
+    check_category_name(&products.categories()?, &nome, id)?;
+
+    category.nome = nome;
+    journal_file.update_catalog(products, &[], |products| products.update_category(&category))?;
+
+    Ok(category)
 }
 
-pub fn products_needing_restock(file: &mut File) -> Result<(), Box<dyn Error>> {
-    println!("\nProdutos com necessidade de restoque:\n");
+// Os produtos e as subcategorias da categoria removida passam para a categoria de destino,
+// tudo numa só transação para que uma falha não deixe produtos numa categoria que não existe mais.
+pub fn merge_categories<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, from: u64, into: u64) -> Result<usize, Box<dyn Error>> {
+    search_category(products, from)?;
+    search_category(products, into)?;
 
-    let mut buf = [0; PRODUCT_LENGTH];
-    let mut product: Produto;
+    let categorias = products.categories()?;
 
-    file.seek(SeekFrom::Start(0))?;
+    if categories::with_descendants(&categorias, from).contains(&into) {
+        return Err(Box::new(errors::CustomErrors::InvalidParent));
+    }
+
+    let moved: Vec<Produto> = products.list()?.into_iter().filter(|product| product.categoria == from).collect();
+    let ids: Vec<u64> = moved.iter().map(|product| product.id).collect();
 
-    while let Ok(_) = file.read_exact(&mut buf) {
+    journal_file.update_catalog(products, &ids, |products| {
+        for mut product in moved.iter().cloned() {
+            product.categoria = into;
+            products.update(&product)?;
+        }
 
-        product = bincode::deserialize(&buf)?;
+        for mut category in categorias.into_iter().filter(|categoria| categoria.pai == Some(from)) {
+            category.pai = Some(into);
+            products.update_category(&category)?;
+        }
 
-        if product.quantidade_estoque <= product.quantidade_restoque {
-            println!("{product}\n");
+        for mut promotion in products.promotions()? {
+            if let Regra::Categoria { categoria, percentual } = promotion.regra {
+                if categoria == from {
+                    promotion.regra = Regra::Categoria { categoria: into, percentual };
+                    products.update_promotion(&promotion)?;
+                }
+            }
         }
+
+        products.remove_category(from)
+    })?;
+
+    Ok(moved.len())
+}
+
+// Só categorias sem produtos, sem subcategorias e sem promoções podem ser removidas.
+pub fn delete_category<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64) -> Result<(), Box<dyn Error>> {
+    search_category(products, id)?;
+
+    let has_children = products.categories()?.iter().any(|categoria| categoria.pai == Some(id));
+    let has_products = products.list()?.iter().any(|product| product.categoria == id);
+    let has_promotions = products.promotions()?.iter().any(|promocao| matches!(promocao.regra, Regra::Categoria { categoria, .. } if categoria == id));
+
+    if has_children || has_products || has_promotions {
+        return Err(Box::new(errors::CustomErrors::CategoryInUse));
     }
 
-    Ok(())
+    journal_file.update_catalog(products, &[], |products| products.remove_category(id))
 }
 
-pub fn update_product<R: BufRead>(file: &mut File, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    let id = validation::validate_search("id", reader)?;
-    let (product, position) = search_product_id(file, id)?;
+pub fn create_promotion<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, mut promotion: Promocao) -> Result<Promocao, Box<dyn Error>> {
+    promotion.nome = validation::validate_name(&promotion.nome)?;
+    promotions::validate_promotion(&promotion)?;
 
-    println!("\nProduto encontrado:\n\n{product}\n");
-    let mut updated_product = validation::get_product_info(reader)?;
-    updated_product.id = product.id;
+    match promotion.regra {
+        Regra::LevePague { produto, .. } => { search_product_id(products, produto)?; },
+        Regra::Categoria { categoria, .. } => { search_category(products, categoria)?; }
+    }
 
-    let serialized = bincode::serialize(&updated_product)?;
-    file.seek(SeekFrom::Start(position))?;
-    file.write(&serialized)?;
+    promotion.id = journal_file.update_catalog(products, &[], |products| products.insert_promotion(promotion.clone()))?;
 
-    Ok(())
+    Ok(promotion)
 }
 
-pub fn remove_product<R: BufRead>(file: &mut File, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    let id = validation::validate_search("id", reader)?;
-    let (_, mut position) = search_product_id(file, id)?;
+// As vendas já registradas guardam os descontos e o nome da promoção, então não mudam.
+pub fn delete_promotion<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64) -> Result<(), Box<dyn Error>> {
+    journal_file.update_catalog(products, &[], |products| products.remove_promotion(id))
+}
 
-    let size = file.seek(SeekFrom::End(0))? - PRODUCT_LENGTH_U64;
-    file.seek(SeekFrom::Start(position))?;
+pub fn receive_stock<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, supplier: &str, items: &[validation::ItemRecebido], operator: &str) -> Result<Vec<Produto>, Box<dyn Error>> {
+    credit_stock(products, journal_file, supplier, items, operator, "Recebimento de mercadoria")
+}
 
-    loop {
-        let mut buf = vec![0; PRODUCT_LENGTH * 100];
-        file.seek(SeekFrom::Current(PRODUCT_LENGTH_U64 as i64))?;
-        let bytes_read = file.read(&mut buf)?;
-        file.seek(SeekFrom::Start(position))?;
-        file.write(&buf[..bytes_read])?;
-        position = file.seek(SeekFrom::Current(0))?;
+fn credit_stock<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, supplier: &str, items: &[validation::ItemRecebido], operator: &str, reason: &str) -> Result<Vec<Produto>, Box<dyn Error>> {
+    let today = chrono::Local::now().date_naive();
+    let mut received: Vec<Produto> = Vec::new();
+    let mut movements = Vec::new();
 
-        if bytes_read != PRODUCT_LENGTH * 100 {
-            file.set_len(size)?;
-            return Ok(());
+    // Todos os produtos são validados antes de qualquer gravação.
+    for &(id, amount, cost) in items.iter() {
+        let quantity = i64::try_from(amount).map_err(|_| errors::CustomErrors::ValueOverflow)?;
+
+        if quantity == 0 || cost.centavos() < 0 {
+            return Err(Box::new(errors::CustomErrors::InvalidAdjustment));
         }
+
+        let mut product = match received.iter().position(|product| product.id == id) {
+            Some(index) => received.remove(index),
+            None => search_product_id(products, id)?
+        };
+
+        product.quantidade_estoque = product.quantidade_estoque.checked_add(amount).ok_or(errors::CustomErrors::ValueOverflow)?;
+        product.data_restoque = today;
+        product.fornecedor = Some(supplier.to_string());
+        product.custo_unitario = Some(cost);
+
+        let mut movement = Movimento::new(id, TipoMovimento::Restoque, quantity, operator.to_string(), reason.to_string());
+        movement.recebimento = Some(Recebimento { fornecedor: supplier.to_string(), custo_unitario: cost });
+
+        movements.push(movement);
+        received.push(product);
     }
+
+    journal_file.update_products(products, &received, movements)?;
+
+    Ok(received)
 }
 
-pub fn search_sale_code(file: &mut File, code: u64) -> Result<(Venda, u64), Box<dyn Error>> {
-    file.seek(SeekFrom::Start(0))?;
-    let mut size_buf = vec![0; 8];
+pub fn adjust_stock<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, id: u64, tipo: TipoMovimento, quantity: i64, operator: &str, reason: &str) -> Result<Produto, Box<dyn Error>> {
+    if quantity == 0 || (tipo == TipoMovimento::Perda && quantity > 0) {
+        return Err(Box::new(errors::CustomErrors::InvalidAdjustment));
+    }
 
-    while let Ok(_) = file.read_exact(&mut size_buf) {
-        let size: u64 = bincode::deserialize(&size_buf)?;
-        let mut buf = vec![0; size as usize];
+    let mut product = search_product_id(products, id)?;
 
-        if let Ok(_) = file.read_exact(&mut buf) {
-            let sale: Venda = bincode::deserialize(&buf)?;
-            
-            if sale.codigo == code {
-                let position = file.seek(SeekFrom::Current(-((size_buf.len() + buf.len()) as i64)))?;
-                return Ok((sale, position));
-            }
+    product.quantidade_estoque = match quantity < 0 {
+        true => product.quantidade_estoque.checked_sub(quantity.unsigned_abs()).ok_or(errors::CustomErrors::LowStock)?,
+        false => product.quantidade_estoque.checked_add(quantity as u64).ok_or(errors::CustomErrors::ValueOverflow)?
+    };
+
+    journal_file.update_products(products, std::slice::from_ref(&product), stock_movements(&[(id, quantity)], tipo, operator, reason))?;
+
+    Ok(product)
+}
+
+// Produtos cujo estoque gravado difere do recalculado a partir dos movimentos.
+pub fn stock_divergences<P: ProductRepository>(products: &mut P) -> Result<Vec<(Produto, i64)>, Box<dyn Error>> {
+    let balances = products.balances()?;
+
+    Ok(products.list()?.into_iter()
+        .map(|product| {
+            let balance = balances.get(&product.id).copied().unwrap_or(0);
+            (product, balance)
+        })
+        .filter(|(product, balance)| product.quantidade_estoque as i64 != *balance)
+        .collect())
+}
+
+// O estoque gravado passa a ser o saldo recalculado a partir dos movimentos.
+pub fn fix_divergences<P: ProductRepository>(products: &mut P, divergences: Vec<(Produto, i64)>) -> Result<Vec<Produto>, Box<dyn Error>> {
+    let mut fixed = Vec::new();
+
+    for (mut product, balance) in divergences {
+        product.quantidade_estoque = u64::try_from(balance).map_err(|_| errors::CustomErrors::InvalidAdjustment)?;
+        products.update(&product)?;
+
+        fixed.push(product);
+    }
+
+    Ok(fixed)
+}
+
+pub fn search_sale_code<S: SaleRepository>(sales: &mut S, code: u64) -> Result<Venda, Box<dyn Error>> {
+    sales.get(code)
+}
+
+// Unidades e valor líquido vendidos de um produto nas vendas informadas, sem as
+// vendas canceladas e as unidades devolvidas.
+pub fn product_sales_totals(sales: &[Venda], id: u64) -> Result<(u64, Dinheiro), Box<dyn Error>> {
+    let mut units_sold: u64 = 0;
+    let mut total_value = Dinheiro::ZERO;
+
+    for sale in sales.iter().filter(|sale| sale.situacao != Situacao::Cancelada) {
+        for item in sale.produtos.iter().filter(|item| item.id == id) {
+            let kept = item.quantidade.saturating_sub(sale.returned(id));
+
+            units_sold += kept;
+            total_value = total_value.checked_add(promotions::refund_value(sale, item, kept)?)?;
         }
     }
 
-    Err(Box::new(errors::CustomErrors::SaleNotFound))
+    Ok((units_sold, total_value))
 }
 
-pub fn search_sales_by_date(file: &mut File, date: chrono::NaiveDate) -> Result<(), Box<dyn Error>> {
-    file.seek(SeekFrom::Start(0))?;
-    let mut size_buf = vec![0; 8];
+pub fn edit_sale<S: SaleRepository>(sales: &mut S, code: u64, date: chrono::NaiveDate, payment_method: MetodoPagamento) -> Result<(), Box<dyn Error>> {
+    let mut sale = search_sale_code(sales, code)?;
 
-    println!("\nVendas realizadas na data especificada:\n");
+    sale.data = date;
+    sale.metodo_pagamento = payment_method;
 
-    while let Ok(_) = file.read_exact(&mut size_buf) {
-        let size: u64 = bincode::deserialize(&size_buf)?;
-        let mut buf = vec![0; size as usize];
+    sales.rewrite(&sale)
+}
+
+pub fn change_sale_items<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, code: u64, items: &[(u64, u64)], operator: &str) -> Result<Venda, Box<dyn Error>> {
+    let mut sale = search_sale_code(sales_file, code)?;
+
+    if sale.situacao == Situacao::Cancelada {
+        return Err(Box::new(errors::CustomErrors::SaleCancelled));
+    }
+
+    let mut updated_products: Vec<Produto> = Vec::new();
+    let mut changes: Vec<(u64, i64)> = Vec::new();
+
+    for &(id, amount) in items.iter() {
+        if amount < sale.returned(id) {
+            return Err(Box::new(errors::CustomErrors::InvalidReturn));
+        }
+
+        let line = sale.produtos.iter().position(|item| item.id == id);
+        let sold = line.map_or(0, |index| sale.produtos[index].quantidade);
+
+        if amount == sold {
+            continue;
+        }
 
-        if let Ok(_) = file.read_exact(&mut buf) {
-            let sale: Venda = bincode::deserialize(&buf)?;
+        let mut product = match updated_products.iter().position(|product| product.id == id) {
+            Some(index) => updated_products.remove(index),
+            None => search_product_id(products_file, id)?
+        };
 
-            if sale.data == date {
-                println!("\n{sale}\n");
+        // A diferença entre a nova quantidade e a vendida sai do estoque ou volta para ele.
+        if amount > sold {
+            if amount - sold > product.quantidade_estoque {
+                return Err(Box::new(errors::CustomErrors::LowStock));
             }
+
+            product.quantidade_estoque -= amount - sold;
+        } else {
+            product.quantidade_estoque = product.quantidade_estoque.checked_add(sold - amount).ok_or(errors::CustomErrors::ValueOverflow)?;
         }
+
+        match line {
+            Some(index) if amount == 0 => {
+                sale.produtos.remove(index);
+            },
+            Some(index) if sold > 0 => {
+                let item = &sale.produtos[index];
+                sale.produtos[index] = ItemVenda { tributacao: item.tributacao.clone(), ..ItemVenda::new(id, item.nome.clone(), amount, item.valor_unitario)? };
+            },
+            Some(index) => sale.produtos[index] = ItemVenda::from_product(&product, amount)?,
+            None => sale.produtos.push(ItemVenda::from_product(&product, amount)?)
+        }
+
+        changes.push((id, sold as i64 - amount as i64));
+        updated_products.push(product);
     }
 
-    Ok(())
+    if sale.produtos.is_empty() {
+        return Err(Box::new(errors::CustomErrors::EmptySale));
+    }
+
+    // As linhas alteradas perdem o desconto manual e têm as promoções recalculadas.
+    let changed: Vec<u64> = changes.iter().map(|&(id, _)| id).collect();
+    promotions::reprice(&mut sale, &changed, &updated_products, &products_file.categories()?, &products_file.promotions()?)?;
+
+    journal_file.update_sale(products_file, sales_file, &updated_products, &sale, stock_movements(&changes, TipoMovimento::Venda, operator, &format!("Alteração da venda {code}")))?;
+
+    Ok(sale)
 }
 
-pub fn search_product_sales(file: &mut File, id: u64) -> Result<(), Box<dyn Error>> {
-    file.seek(SeekFrom::Start(0))?;
-    let mut size_buf = vec![0; 8];
+pub fn void_sale<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, code: u64, reason: String, operator: &str) -> Result<Venda, Box<dyn Error>> {
+    let sale = search_sale_code(sales_file, code)?;
 
-    println!("\nVendas do produto especificado:\n");
+    let items: Vec<(u64, u64)> = sale.produtos.iter()
+        .map(|item| (item.id, item.quantidade.saturating_sub(sale.returned(item.id))))
+        .filter(|&(_, amount)| amount > 0)
+        .collect();
 
-    while let Ok(_) = file.read_exact(&mut size_buf) {
-        let size: u64 = bincode::deserialize(&size_buf)?;
-        let mut buf = vec![0; size as usize];
+    apply_return(products_file, sales_file, journal_file, sale, &items, reason, operator)
+}
 
-        if let Ok(_) = file.read_exact(&mut buf) {
-            let sale: Venda = bincode::deserialize(&buf)?;
+pub fn return_items<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, code: u64, items: &[(u64, u64)], reason: String, operator: &str) -> Result<Venda, Box<dyn Error>> {
+    let sale = search_sale_code(sales_file, code)?;
 
-            if sale.produtos.contains(&id) {
-                println!("{sale}\n");
-            }
+    apply_return(products_file, sales_file, journal_file, sale, items, reason, operator)
+}
+
+fn apply_return<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products_file: &mut P, sales_file: &mut S, journal_file: &mut T, mut sale: Venda, items: &[(u64, u64)], reason: String, operator: &str) -> Result<Venda, Box<dyn Error>> {
+    if sale.situacao == Situacao::Cancelada {
+        return Err(Box::new(errors::CustomErrors::SaleCancelled));
+    }
+
+    let mut returned: Vec<(u64, u64)> = Vec::new();
+
+    for &(id, amount) in items.iter() {
+        match returned.iter_mut().find(|(item_id, _)| *item_id == id) {
+            Some((_, total)) => *total += amount,
+            None => returned.push((id, amount))
         }
     }
 
-    Ok(())
-}
+    let mut value = Dinheiro::ZERO;
+    let mut updated_products: Vec<Produto> = Vec::new();
+
+    for &(id, amount) in returned.iter() {
+        let item = sale.produtos.iter().find(|item| item.id == id).ok_or(errors::CustomErrors::InvalidReturn)?;
+
+        if amount == 0 || amount > item.quantidade.saturating_sub(sale.returned(id)) {
+            return Err(Box::new(errors::CustomErrors::InvalidReturn));
+        }
 
-pub fn list_sales(file: &mut File) -> Result<(), Box<dyn Error>> {
-    println!("\nVendas realizadas:\n");
+        value = value.checked_add(promotions::refund_value(&sale, item, amount)?)?;
 
-    file.seek(SeekFrom::Start(0))?;
-    let mut size_buf = vec![0; 8];
+        // Produtos já excluídos do estoque não têm para onde voltar.
+        if let Ok(mut product) = search_product_id(products_file, id) {
+            product.quantidade_estoque = product.quantidade_estoque.checked_add(amount).ok_or(errors::CustomErrors::ValueOverflow)?;
+            updated_products.push(product);
+        }
+    }
+
+    let changes: Vec<(u64, i64)> = updated_products.iter()
+        .filter_map(|product| returned.iter().find(|(id, _)| *id == product.id))
+        .map(|&(id, amount)| (id, amount as i64))
+        .collect();
 
-    while let Ok(_) = file.read_exact(&mut size_buf) {
-        let size: u64 = bincode::deserialize(&size_buf)?;
-        let mut buf = vec![0; size as usize];
+    let movement_reason = format!("Venda {}: {}", sale.codigo, reason);
+    let refunded = sale.devolucoes.iter().try_fold(Dinheiro::ZERO, |total, devolucao| total.checked_add(devolucao.valor))?;
 
-        if let Ok(_) = file.read_exact(&mut buf) {
-            let sale: Venda = bincode::deserialize(&buf)?;
-            println!("{sale}\n");
+    sale.devolucoes.push(Devolucao {
+        data: chrono::Local::now().date_naive(),
+        motivo: reason,
+        itens: returned,
+        valor: value
+    });
+
+    let fully_returned = sale.produtos.iter().all(|item| sale.returned(item.id) >= item.quantidade);
+
+    // A última devolução reembolsa o que falta, sem sobras do arredondamento.
+    if fully_returned {
+        if let Some(devolucao) = sale.devolucoes.last_mut() {
+            devolucao.valor = sale.valor.checked_sub(refunded)?;
         }
     }
 
-    Ok(())
+    sale.situacao = match fully_returned {
+        true => Situacao::Cancelada,
+        false => Situacao::DevolucaoParcial
+    };
+
+    journal_file.update_sale(products_file, sales_file, &updated_products, &sale, stock_movements(&changes, TipoMovimento::Devolucao, operator, &movement_reason))?;
+
+    Ok(sale)
 }
 
-pub fn update_sale<R: BufRead>(file: &mut File, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    let code = validation::validate_search("code", reader)?;
-    let (mut sale, position) = search_sale_code(file, code)?;
+pub fn start_session(sessions: &mut ArquivoCaixas, operator: String, float: Dinheiro) -> Result<Caixa, Box<dyn Error>> {
+    sessions.open(operator, float, chrono::Local::now().date_naive())
+}
 
-    println!("\nVenda encontrada:\n\n{sale}\n");
+pub fn session_summary<S: SaleRepository>(sales: &mut S, session: &Caixa) -> Result<(Dinheiro, reports::Relatorio), Box<dyn Error>> {
+    let session_sales = sales.by_session(session.id)?;
+    let mut expected = session.fundo;
 
-    let (date, payment_method) = validation::get_sale_info(reader)?;
-    sale.data = date;
-    sale.metodo_pagamento = payment_method;
+    for sale in session_sales.iter().filter(|sale| matches!(sale.metodo_pagamento, MetodoPagamento::Dinheiro)) {
+        expected = expected.checked_add(sale.valor_liquido()?)?;
+    }
+
+    let end = session.fechamento.as_ref().map(|fechamento| fechamento.data);
+    let report = reports::build_report(&session_sales, reports::Agrupamento::MetodoPagamento, Some(session.abertura), end)?;
 
-    let serialized = bincode::serialize(&sale)?;
-    let size = serialized.len() as u64;
-    let serialized_size = bincode::serialize(&size)?;
+    Ok((expected, report))
+}
 
-    file.seek(SeekFrom::Start(position))?;
-    file.write(&serialized_size)?;
-    file.write(&serialized)?;
+pub fn end_session<S: SaleRepository>(sessions: &mut ArquivoCaixas, sales: &mut S, counted: Dinheiro) -> Result<Caixa, Box<dyn Error>> {
+    let session = sessions.current()?.ok_or(errors::CustomErrors::SessionNotFound)?;
+    let (expected, _) = session_summary(sales, &session)?;
 
-    Ok(())
+    sessions.close(expected, counted, chrono::Local::now().date_naive())
 }
 
-pub fn remove_sale<R: BufRead>(file: &mut File, reader: &mut R) -> Result<(), Box<dyn Error>> {
-    let code = validation::validate_search("code", reader)?;
-    let (sale, mut position) = search_sale_code(file, code)?;
+// As linhas inválidas voltam no erro para que quem chamou possa mostrá-las.
+fn check_import(errors: Vec<csv::ErroLinha>) -> Result<(), Box<dyn Error>> {
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(Box::new(errors::StoreError::Import(errors)))
+    }
+}
 
-    let size = bincode::serialized_size(&sale)?;
-    file.seek(SeekFrom::Start(position))?;
+// Todas as linhas são validadas antes da gravação: com qualquer linha inválida nada é importado.
+pub fn import_products<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, text: &str, dry_run: bool, operator: &str) -> Result<Vec<u64>, Box<dyn Error>> {
+    let (parsed, errors) = csv::parse_products(text, &products.list()?, &products.categories()?)?;
+    check_import(errors)?;
 
-    loop {
-        let mut buf = vec![0; 100000];
-        file.seek(SeekFrom::Current(size as i64 + 8))?;
-        let bytes_read = file.read(&mut buf)?;
-        file.seek(SeekFrom::Start(position))?;
-        file.write(&buf[..bytes_read])?;
-        position = file.seek(SeekFrom::Current(0))?;
+    save_imported_products(products, journal_file, parsed.into_iter().map(|(_, product)| product).collect(), dry_run, operator)
+}
 
-        if bytes_read != 100000 {
-            file.set_len(position)?;
-            return Ok(())
-        }
+// Os ids seguem o último id gravado no cabeçalho de produtos.bin.
+fn save_imported_products<P: ProductRepository, T: StockTransaction<P>>(products: &mut P, journal_file: &mut T, parsed: Vec<Produto>, dry_run: bool, operator: &str) -> Result<Vec<u64>, Box<dyn Error>> {
+    match dry_run {
+        true => {
+            let last_id = products.last_id()?;
+            Ok((last_id + 1..=last_id + parsed.len() as u64).collect())
+        },
+        false => insert_products(products, journal_file, parsed, operator)
+    }
+}
+
+pub fn import_sales<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products: &mut P, sales: &mut S, journal_file: &mut T, text: &str, dry_run: bool) -> Result<Vec<u64>, Box<dyn Error>> {
+    let (parsed, errors) = csv::parse_sales(text, &products.list()?, &sales.list()?)?;
+    check_import(errors)?;
+
+    save_imported_sales(products, sales, journal_file, parsed.into_iter().map(|(_, sale)| sale).collect(), dry_run)
+}
+
+// As vendas importadas entram como histórico: o estoque dos produtos não é alterado.
+fn save_imported_sales<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(products: &mut P, sales: &mut S, journal_file: &mut T, parsed: Vec<Venda>, dry_run: bool) -> Result<Vec<u64>, Box<dyn Error>> {
+    match dry_run {
+        true => Ok(parsed.iter().map(|sale| sale.codigo).collect()),
+        false => journal_file.insert_sales(products, sales, parsed)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::{self, OpenOptions}, io::Cursor};
+    use std::fs::{self, OpenOptions};
 
     use super::*;
+    use crate::{format::HEADER_LENGTH, journal, memory::{MemoriaProdutos, MemoriaVendas, SemDiario}, products, sales::ArquivoVendas};
+    use crate::test_utils::{get_memory_products, get_memory_sales, get_test_file, get_test_orders, get_test_products, get_test_sales, get_test_sessions, read_all, remove_test_orders, remove_test_products, remove_test_sales, remove_test_sessions, set_test_products, set_test_sales, test_path};
+
+    #[test]
+    fn test_insert_product() {
+        let path = &test_path("test_insert_product.bin");
+        let path_journal = &test_path("test_insert_product_journal.bin");
+        let mut products = get_test_products(path);
+        let mut journal_file = get_test_file(path_journal);
+
+        let product = Produto::new("Camisa".to_string(), 0, 10, Dinheiro::from_centavos(5000), 5, chrono::NaiveDate::default(), crate::categories::GERAL);
+
+        assert_eq!(insert_product(&mut products, &mut journal_file, product, "Ana").unwrap(), 1);
 
-    fn get_test_file(path: &str) -> File {
-        let _ = fs::remove_file(path);
+        let (product, position) = products.search(1).expect("Erro na busca pelo produto.");
+        let size = products.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
 
-        OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(path)
-            .expect("Não foi possível criar o arquivo.")
+        assert_eq!(position, HEADER_LENGTH);
+        assert_eq!(size, HEADER_LENGTH + products::serialize_record(&product).unwrap().len() as u64);
+
+        remove_test_products(path);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
-    fn set_products(file: &mut File) {
-        let product1 = Produto::new("Teste1".to_string(), 1, 10, 50.0, 5, chrono::NaiveDate::default(), crate::Categoria::Geral);
-        let product2 = Produto::new("Teste2".to_string(), 2, 15, 40.0, 25, chrono::NaiveDate::default(), crate::Categoria::Alimento);
-        let product3 = Produto::new("Teste3".to_string(), 3, 20, 60.0, 10, chrono::NaiveDate::default(), crate::Categoria::Eletronico);
+    #[test]
+    fn test_register_sale() {
+        let path_products = &test_path("test_register_sale_1.bin");
+        let path_sales = &test_path("test_register_sale_2.bin");
+        let path_journal = &test_path("test_register_sale_3.bin");
+
+        let mut products_file = get_test_products(path_products);
+        let mut sales_file = get_test_sales(path_sales);
+        let mut journal_file = get_test_file(path_journal);
 
-        let mut buf1 = bincode::serialize(&product1).unwrap();
-        buf1.resize(102, 0);
+        set_test_products(&mut products_file);
 
-        let mut buf2 = bincode::serialize(&product2).unwrap();
-        buf2.resize(102, 0);
+        let products_size = products_file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo de produtos.");
+        let sales_size = sales_file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo de vendas.");
 
-        let mut buf3 = bincode::serialize(&product3).unwrap();
-        buf3.resize(102, 0);
+        let result = complete_sale(&mut products_file, &mut sales_file, &mut journal_file, "Teste".to_string(), None, &Cesta::new(vec![(1, 1)]), MetodoPagamento::Debito);
 
-        let id: u64 = 3;
-        let serialized_id = bincode::serialize(&id).unwrap();
+        assert!(result.is_ok());
 
-        file.write(&buf1).unwrap();
-        file.write(&buf2).unwrap();
-        file.write(&buf3).unwrap();
+        assert_eq!(products_size, products_file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo de produtos."));
+        assert!(sales_size < sales_file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo de vendas."));
 
-        file.write(&serialized_id).unwrap();
+        remove_test_products(path_products);
+        remove_test_sales(path_sales);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
-    fn set_sales(file: &mut File) {
-        let sale1 = Venda::new("Venda1".to_string(), 1, 50.0, chrono::NaiveDate::default(), crate::MetodoPagamento::Credito);
-        let sale2 = Venda::new("Venda2".to_string(), 2, 70.0, chrono::NaiveDate::default(), crate::MetodoPagamento::Dinheiro);
-        let mut sale3 = Venda::new("Venda3".to_string(), 3, 90.0, chrono::NaiveDate::default(), crate::MetodoPagamento::Pix);
+    #[test]
+    fn test_register_sale_low_stock() {
+        let path_products = &test_path("test_register_sale_low_stock_1.bin");
+        let path_sales = &test_path("test_register_sale_low_stock_2.bin");
+        let path_journal = &test_path("test_register_sale_low_stock_3.bin");
 
-        sale3.produtos.push(1);
+        let mut products_file = get_test_products(path_products);
+        let mut sales_file = get_test_sales(path_sales);
+        let mut journal_file = get_test_file(path_journal);
 
-        let buf1 = bincode::serialize(&sale1).unwrap();
-        let size1 = buf1.len() as u64;
-        let size_buf1 = bincode::serialize(&size1).unwrap();
+        set_test_products(&mut products_file);
+        set_test_sales(&mut sales_file);
 
-        let buf2 = bincode::serialize(&sale2).unwrap();
-        let size2 = buf2.len() as u64;
-        let size_buf2 = bincode::serialize(&size2).unwrap();
+        let products = read_all(&mut products_file.dados);
+        let sales = read_all(&mut sales_file.dados);
 
-        let buf3 = bincode::serialize(&sale3).unwrap();
-        let size3 = buf3.len() as u64;
-        let size_buf3 = bincode::serialize(&size3).unwrap();
+        let result = complete_sale(&mut products_file, &mut sales_file, &mut journal_file, "Teste".to_string(), None, &Cesta::new(vec![(1, 5), (2, 16)]), MetodoPagamento::Dinheiro);
 
-        let code: u64 = 3;
-        let serialized_code = bincode::serialize(&code).unwrap();
+        assert!(result.is_err());
 
-        file.write(&size_buf1).unwrap();
-        file.write(&buf1).unwrap();
-        file.write(&size_buf2).unwrap();
-        file.write(&buf2).unwrap();
-        file.write(&size_buf3).unwrap();
-        file.write(&buf3).unwrap();
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(sales, read_all(&mut sales_file.dados));
 
-        file.write(&serialized_code).unwrap();
+        remove_test_products(path_products);
+        remove_test_sales(path_sales);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
     #[test]
-    fn test_add_product() {
-        let path = "test_add_product.bin";
-        let mut file = get_test_file(path);
+    fn test_register_sale_write_failure() {
+        let path_products = &test_path("test_register_sale_write_failure_1.bin");
+        let path_sales = &test_path("test_register_sale_write_failure_2.bin");
+        let path_journal = &test_path("test_register_sale_write_failure_3.bin");
+
+        let mut products_file = get_test_products(path_products);
+        let mut sales_file = get_test_sales(path_sales);
+        let mut journal_file = get_test_file(path_journal);
+
+        set_test_products(&mut products_file);
+        set_test_sales(&mut sales_file);
+        record_opening_balances(&mut products_file).unwrap();
+
+        let products = read_all(&mut products_file.dados);
+        let sales = read_all(&mut sales_file.dados);
+        let movements = read_all(&mut products_file.movimentos.dados);
+
+        let path_read_only_index = &test_path("test_register_sale_write_failure_4.idx");
+        let read_only_file = OpenOptions::new().read(true).open(path_sales).expect("Erro no arquivo de vendas.");
+        let mut read_only_sales = ArquivoVendas::open(read_only_file, get_test_file(path_read_only_index)).expect("Erro no arquivo de vendas.");
+
+        let result = complete_sale(&mut products_file, &mut read_only_sales, &mut journal_file, "Teste".to_string(), None, &Cesta::new(vec![(1, 5), (2, 3)]), MetodoPagamento::Dinheiro);
+
+        assert!(result.is_err());
 
-        let input = b"Camisa 10 50 5 10/8/2023 Geral";
-        let mut cursor = Cursor::new(input);
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(sales, read_all(&mut sales_file.dados));
 
-        assert!(add_product(&mut file, &mut cursor).is_ok());
+        journal::rollback(&mut journal_file, &mut products_file, &mut sales_file.dados).expect("Erro ao desfazer a transação.");
 
-        let size = file.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        // Os movimentos da venda foram gravados antes da falha e também são desfeitos.
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(sales, read_all(&mut sales_file.dados));
+        assert_eq!(movements, read_all(&mut products_file.movimentos.dados));
+        assert_eq!(products_file.movements(1).unwrap().len(), 1);
+        assert!(stock_divergences(&mut products_file).unwrap().is_empty());
+        assert_eq!(0, journal_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de transação."));
 
-        assert_eq!(size, PRODUCT_LENGTH_U64 + 8);
+        fs::remove_file(path_read_only_index).expect("Erro ao tentar excluir o índice de vendas.");
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        remove_test_products(path_products);
+        remove_test_sales(path_sales);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
     #[test]
-    fn test_register_sale() {
-        let path_products = "test_register_sale_1.bin";
-        let path_sales = "test_register_sale_2.bin";
+    fn test_register_sale_interrupted() {
+        let path_products = &test_path("test_register_sale_interrupted_1.bin");
+        let path_sales = &test_path("test_register_sale_interrupted_2.bin");
+        let path_journal = &test_path("test_register_sale_interrupted_3.bin");
 
-        let mut products_file = get_test_file(path_products);
-        let mut sales_file = get_test_file(path_sales);
+        let mut products_file = get_test_products(path_products);
+        let mut sales_file = get_test_sales(path_sales);
+        let mut journal_file = get_test_file(path_journal);
 
-        set_products(&mut products_file);
+        set_test_products(&mut products_file);
+        set_test_sales(&mut sales_file);
 
-        let products_size = products_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de produtos.");
-        let sales_size = sales_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de vendas.");
+        let products = read_all(&mut products_file.dados);
+        let sales = read_all(&mut sales_file.dados);
 
-        let input = "1\nconcluir\ndebito";
-        let mut cursor = Cursor::new(input);
+        let (mut product, position) = products_file.search(2).expect("Erro na busca pelo produto.");
+        product.quantidade_estoque = 0;
 
-        let result = register_sale(&mut products_file, &mut sales_file, "Teste".to_string(), &mut cursor);
+        let sale_position = sales_file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo de vendas.");
+        let header = sales_file.header().expect("Erro no arquivo de vendas.");
 
-        assert!(result.is_ok());
+        let mut transaction = journal::Transacao::new(&mut products_file.dados, &mut sales_file.dados, &mut products_file.movimentos.dados).expect("Erro ao criar a transação.");
+        transaction.save_product(&mut products_file.dados, position, products::serialize_record(&product).unwrap().len() as u64).expect("Erro ao criar a transação.");
+        transaction.save_sale(&mut sales_file.dados, 0, HEADER_LENGTH).expect("Erro ao criar a transação.");
+        transaction.save_sale(&mut sales_file.dados, sale_position, 32).expect("Erro ao criar a transação.");
+        journal::begin(&mut journal_file, &transaction).expect("Erro ao iniciar a transação.");
+
+        let mut read_only_sales = OpenOptions::new().read(true).open(path_sales).expect("Erro no arquivo de vendas.");
+
+        let result = journal::write_sale_transaction(&mut products_file.dados, &mut read_only_sales, &[(product, position)], &header, sale_position, &[0; 32]);
 
-        assert_eq!(products_size, products_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de produtos."));
-        assert!(sales_size < sales_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de vendas."));
+        assert!(result.is_err());
+        assert_ne!(products, read_all(&mut products_file.dados));
 
-        fs::remove_file(path_products).expect("Erro ao tentar excluir o arquivo de produtos.");
-        fs::remove_file(path_sales).expect("Erro ao tentar excluir o arquivo de vendas.");
+        let rolled_back = journal::rollback(&mut journal_file, &mut products_file, &mut sales_file.dados).expect("Erro ao desfazer a transação.");
+
+        assert!(rolled_back);
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(sales, read_all(&mut sales_file.dados));
+
+        remove_test_products(path_products);
+        remove_test_sales(path_sales);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
     #[test]
     fn test_search_product_id() {
-        let path = "test_search_product_id.bin";
-        let mut file = get_test_file(path);
+        let path = &test_path("test_search_product_id.bin");
+        let mut products = get_test_products(path);
 
-        set_products(&mut file);
+        set_test_products(&mut products);
 
-        let (found_product1, position1) = search_product_id(&mut file, 1).expect("Erro na busca pelo produto.");
-        let (found_product2, position2) = search_product_id(&mut file, 2).expect("Erro na busca pelo produto.");
-        let (found_product3, position3) = search_product_id(&mut file, 3).expect("Erro na busca pelo produto.");
+        let (found_product1, position1) = products.search(1).expect("Erro na busca pelo produto.");
+        let (found_product2, position2) = products.search(2).expect("Erro na busca pelo produto.");
+        let (found_product3, position3) = products.search(3).expect("Erro na busca pelo produto.");
 
         assert_eq!(found_product1.id, 1);
         assert_eq!(found_product2.id, 2);
         assert_eq!(found_product3.id, 3);
 
-        assert_eq!(position1, 0);
-        assert_eq!(position2, 102);
-        assert_eq!(position3, 204);
+        let length1 = products::serialize_record(&found_product1).unwrap().len() as u64;
+        let length2 = products::serialize_record(&found_product2).unwrap().len() as u64;
+
+        assert_eq!(position1, HEADER_LENGTH);
+        assert_eq!(position2, HEADER_LENGTH + length1);
+        assert_eq!(position3, HEADER_LENGTH + length1 + length2);
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        assert!(search_product_id(&mut products, 4).is_err());
+
+        remove_test_products(path);
     }
 
     #[test]
-    fn test_list_products() {
-        let path = "test_list_products.bin";
-        let mut file = get_test_file(path);
+    fn test_update_product() {
+        let mut products = get_memory_products();
+
+        let product = Produto::new("Feijão-carioca-tipo-1-pacote-econômico-de-5-quilos".to_string(), 0, 10, Dinheiro::from_centavos(5000), 5, chrono::NaiveDate::default(), crate::categories::GERAL);
 
-        set_products(&mut file);
+        let result = replace_product(&mut products, &mut SemDiario, 1, product, "Ana");
 
-        assert!(list_products(&mut file).is_ok());
+        assert!(result.is_ok());
+
+        let product = search_product_id(&mut products, 1).expect("Erro na busca pelo produto.");
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        assert_eq!(product.nome, "Feijão-carioca-tipo-1-pacote-econômico-de-5-quilos");
+        assert_eq!(search_product_id(&mut products, 2).expect("Erro na busca pelo produto.").nome, "Teste2");
     }
 
     #[test]
-    fn test_products_needing_restock() {
-        let path = "test_products_needing_restock.bin";
-        let mut file = get_test_file(path);
+    fn test_remove_product() {
+        let path = &test_path("test_remove_product.bin");
+        let path_journal = &test_path("test_remove_product_journal.bin");
+        let mut products = get_test_products(path);
+        let mut journal_file = get_test_file(path_journal);
 
-        set_products(&mut file);
+        set_test_products(&mut products);
 
-        assert!(products_needing_restock(&mut file).is_ok());
+        let size = products.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        let product = search_product_id(&mut products, 1).expect("Erro na busca pelo produto.");
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        let result = delete_product(&mut products, &mut journal_file, 1, "Ana");
+
+        assert!(result.is_ok());
+
+        let length = products::serialize_record(&product).unwrap().len() as u64;
+
+        assert_eq!(size - length, products.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        assert!(search_product_id(&mut products, 1).is_err());
+        assert_eq!(products.search(3).expect("Erro na busca pelo produto.").1, HEADER_LENGTH + length);
+
+        remove_test_products(path);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
 
     #[test]
-    fn test_update_product() {
-        let path = "test_update_product.bin";
-        let mut file = get_test_file(path);
+    fn test_search_sale_code() {
+        let path = &test_path("test_search_sale_code.bin");
+        let mut file = get_test_sales(path);
+
+        set_test_sales(&mut file);
 
-        set_products(&mut file);
+        let result = file.search(1);
 
-        let size = file.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        assert!(result.is_ok());
 
-        let input = b"1\nCamisa 10 50 5 10/8/2023 Geral";
-        let mut cursor = Cursor::new(input);
+        let sale = result.unwrap();
 
-        let result = update_product(&mut file, &mut cursor);
+        assert_eq!(sale.1, HEADER_LENGTH);
+        assert_eq!(sale.0.codigo, 1);
+        assert_eq!(sale.0.vendedor, "Venda1");
+        assert_eq!(sale.0.valor, Dinheiro::from_centavos(5000));
+
+        remove_test_sales(path)
+    }
+
+    #[test]
+    fn test_search_product_sales() {
+        let mut stored = get_memory_sales();
+
+        let sales = stored.by_product(1).expect("Erro na busca pelas vendas.");
+
+        assert_eq!(product_sales_totals(&sales, 1).unwrap(), (2, Dinheiro::from_centavos(9000)));
+
+        // Vendas canceladas não contam e devolvidas contam só o que ficou, pelo valor líquido
+        let mut cancelled = sales[0].clone();
+        cancelled.situacao = Situacao::Cancelada;
+
+        let mut returned = sales[0].clone();
+        returned.situacao = Situacao::DevolucaoParcial;
+        returned.produtos[0].desconto = Dinheiro::from_centavos(1000);
+        returned.devolucoes.push(Devolucao { data: returned.data, motivo: "Defeito".to_string(), itens: vec![(1, 1)], valor: Dinheiro::from_centavos(4000) });
+
+        let sales = [sales, vec![cancelled, returned]].concat();
+
+        assert_eq!(product_sales_totals(&sales, 1).unwrap(), (3, Dinheiro::from_centavos(13000)));
+    }
+
+    #[test]
+    fn test_update_sale() {
+        let path = &test_path("test_update_sale.bin");
+        let mut file = get_test_sales(path);
+
+        set_test_sales(&mut file);
+
+        let size = file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        let date = chrono::NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+
+        let result = edit_sale(&mut file, 2, date, MetodoPagamento::Pix);
 
         assert!(result.is_ok());
 
-        assert_eq!(size, file.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        assert_eq!(size, file.dados.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        assert_eq!(search_sale_code(&mut file, 2).expect("Erro na busca pela venda.").data, date);
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        remove_test_sales(path);
     }
 
     #[test]
-    fn test_remove_product() {
-        let path = "test_remove_product.bin";
-        let mut file = get_test_file(path);
+    fn test_change_sale_items() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_products(&mut file);
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(1, 2), (2, 1)]), MetodoPagamento::Pix).unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Bia".to_string(), None, &Cesta::new(vec![(3, 1)]), MetodoPagamento::Pix).unwrap();
 
-        let size = file.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        let sale = change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 4), (2, 0), (3, 2)], "Ana").unwrap();
 
-        let input = b"1";
-        let mut cursor = Cursor::new(input);
+        assert_eq!(sale.produtos.len(), 2);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(32000));
 
-        let result = remove_product(&mut file, &mut cursor);
+        let stock = |products: &mut MemoriaProdutos, id| search_product_id(products, id).expect("Erro na busca pelo produto.").quantidade_estoque;
 
-        assert!(result.is_ok());
+        assert_eq!(stock(&mut products, 1), 6);
+        assert_eq!(stock(&mut products, 2), 15);
+        assert_eq!(stock(&mut products, 3), 17);
+        assert_eq!(search_sale_code(&mut sales, 2).expect("Erro na busca pela venda.").vendedor, "Bia");
+
+        assert!(change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 1)], "Ana").is_ok());
+
+        let sale = search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.");
+
+        assert_eq!(sale.valor, Dinheiro::from_centavos(17000));
+        assert_eq!(stock(&mut products, 1), 9);
+
+        let error = change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(3, 100)], "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::LowStock)));
 
-        assert_eq!(size - PRODUCT_LENGTH_U64, file.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        let error = change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 0), (3, 0)], "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::EmptySale)));
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        // As alterações recusadas não mexem na venda nem no estoque.
+        assert_eq!(search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.").valor, Dinheiro::from_centavos(17000));
+        assert_eq!(stock(&mut products, 3), 17);
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
     }
 
     #[test]
-    fn test_search_sale_code() {
-        let path = "test_search_sale_code.bin";
-        let mut file = get_test_file(path);
+    fn test_cancel_sale() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_sales(&mut file);
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(1, 2)]), MetodoPagamento::Pix).unwrap();
 
-        let result = search_sale_code(&mut file, 1);
+        let result = void_sale(&mut products, &mut sales, &mut SemDiario, 1, "Desistência do cliente".to_string(), "Ana");
 
         assert!(result.is_ok());
 
-        let sale = result.unwrap();
+        let sale = search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.");
 
-        assert_eq!(sale.1, 0);
-        assert_eq!(sale.0.codigo, 1);
-        assert_eq!(sale.0.vendedor, "Venda1");
-        assert_eq!(sale.0.valor, 50.0);
+        assert_eq!(sale.situacao, Situacao::Cancelada);
+        assert_eq!(sale.devolucoes[0].motivo, "Desistência do cliente");
+        assert_eq!(sale.devolucoes[0].valor, Dinheiro::from_centavos(10000));
+
+        let product = search_product_id(&mut products, 1).expect("Erro na busca pelo produto.");
+
+        assert_eq!(product.quantidade_estoque, 10);
+        assert_eq!(sales.list().expect("Erro na lista de vendas.").len(), 1);
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.")
+        let error = void_sale(&mut products, &mut sales, &mut SemDiario, 1, "Outro".to_string(), "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::SaleCancelled)));
     }
 
     #[test]
-    fn test_search_sales_by_date() {
-        let path = "test_search_sales_by_date.bin";
-        let mut file = get_test_file(path);
+    fn test_return_items() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_sales(&mut file);
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(1, 3), (2, 1)]), MetodoPagamento::Pix).unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Bia".to_string(), None, &Cesta::new(vec![(2, 2)]), MetodoPagamento::Debito).unwrap();
 
-        let result = search_sales_by_date(&mut file, chrono::NaiveDate::default());
+        assert!(return_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 2)], "Defeito".to_string(), "Ana").is_ok());
 
-        assert!(result.is_ok());
+        let sale = search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.");
+
+        assert_eq!(sale.situacao, Situacao::DevolucaoParcial);
+        assert_eq!(sale.valor_liquido().unwrap(), Dinheiro::from_centavos(9000));
+
+        let sale = search_sale_code(&mut sales, 2).expect("Erro na busca pela venda.");
+        assert_eq!(sale.vendedor, "Bia");
+
+        let result = return_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 2)], "Defeito".to_string(), "Ana");
+        assert!(matches!(result.err().unwrap().downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidReturn)));
+
+        let sale = return_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 1), (2, 1)], "Troca".to_string(), "Ana").unwrap();
+        assert_eq!(sale.situacao, Situacao::Cancelada);
+
+        let product = search_product_id(&mut products, 1).expect("Erro na busca pelo produto.");
+        assert_eq!(product.quantidade_estoque, 10);
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.")
+        let product = search_product_id(&mut products, 2).expect("Erro na busca pelo produto.");
+        assert_eq!(product.quantidade_estoque, 13);
+
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
     }
 
     #[test]
-    fn test_search_product_sales() {
-        let path = "test_search_product_sales.bin";
-        let mut file = get_test_file(path);
+    fn test_sale_promotions() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_sales(&mut file);
+        let leve_pague = Promocao::new(0, "Leve 3, pague 2".to_string(), Regra::LevePague { produto: 1, leve: 3, pague: 2 }, None, None);
+        let alimentos = Promocao::new(0, "Alimentos".to_string(), Regra::Categoria { categoria: crate::categories::ALIMENTO, percentual: 10 }, None, None);
 
-        let result = search_product_sales(&mut file, 1);
+        assert_eq!(create_promotion(&mut products, &mut SemDiario, leve_pague.clone()).unwrap().id, 1);
+        assert_eq!(create_promotion(&mut products, &mut SemDiario, alimentos).unwrap().id, 2);
 
-        assert!(result.is_ok());
+        let error = create_promotion(&mut products, &mut SemDiario, Promocao { regra: Regra::LevePague { produto: 1, leve: 3, pague: 0 }, ..leve_pague.clone() }).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidPromotion)));
+
+        let error = create_promotion(&mut products, &mut SemDiario, Promocao { regra: Regra::LevePague { produto: 9, leve: 3, pague: 2 }, ..leve_pague }).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::ProductNotFound)));
+
+        let mut cesta = Cesta::new(vec![(1, 3), (2, 2)]);
+        cesta.desconto = Some(crate::Desconto::Fixo(Dinheiro::from_centavos(1000)));
+
+        let sale = complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &cesta, MetodoPagamento::Pix).unwrap();
+
+        assert_eq!(sale.produtos.iter().map(|item| item.desconto.centavos()).collect::<Vec<_>>(), vec![5000, 800]);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(16200));
+
+        // A devolução desconta a parte proporcional do desconto sobre o total.
+        let sale = return_items(&mut products, &mut sales, &mut SemDiario, 1, &[(2, 1)], "Defeito".to_string(), "Ana").unwrap();
+        assert_eq!(sale.devolucoes[0].valor, Dinheiro::from_centavos(3391));
+
+        // Com 2 unidades, o leve 3, pague 2 deixa de valer.
+        let sale = change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 2)], "Ana").unwrap();
+        assert_eq!(sale.produtos[0].desconto, Dinheiro::ZERO);
+        assert_eq!(sale.promocoes.iter().map(|aplicada| aplicada.promocao).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(16200));
+
+        let sale = void_sale(&mut products, &mut sales, &mut SemDiario, 1, "Desistência".to_string(), "Ana").unwrap();
+        assert_eq!(sale.devolucoes[1].valor, Dinheiro::from_centavos(12809));
+        assert_eq!(sale.valor_liquido().unwrap(), Dinheiro::ZERO);
+
+        merge_categories(&mut products, &mut SemDiario, crate::categories::ALIMENTO, crate::categories::GERAL).unwrap();
+        assert_eq!(products.promotions().unwrap()[1].regra, Regra::Categoria { categoria: crate::categories::GERAL, percentual: 10 });
+
+        // Uma categoria sem produtos ainda fica presa à promoção que a usa.
+        let bebidas = create_category(&mut products, &mut SemDiario, "Bebidas", None).unwrap();
+        create_promotion(&mut products, &mut SemDiario, Promocao::new(0, "Bebidas".to_string(), Regra::Categoria { categoria: bebidas.id, percentual: 5 }, None, None)).unwrap();
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.")
+        let error = delete_category(&mut products, &mut SemDiario, bebidas.id).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::CategoryInUse)));
+
+        delete_promotion(&mut products, &mut SemDiario, 3).unwrap();
+
+        assert!(delete_category(&mut products, &mut SemDiario, bebidas.id).is_ok());
+        assert_eq!(products.promotions().unwrap().len(), 2);
     }
 
     #[test]
-    fn test_list_sales() {
-        let path = "test_list_sales.bin";
-        let mut file = get_test_file(path);
+    fn test_complete_sale_in_memory() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_sales(&mut file);
+        let sale = complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), Some(4), &Cesta::new(vec![(1, 2), (3, 1), (1, 1)]), MetodoPagamento::Dinheiro).unwrap();
 
-        let result = list_sales(&mut file);
+        assert_eq!(sale.codigo, 1);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(21000));
+        assert_eq!(sales.by_session(4).unwrap().len(), 1);
+        assert_eq!(search_product_id(&mut products, 1).unwrap().quantidade_estoque, 7);
 
-        assert!(result.is_ok());
+        let error = complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(2, 1), (3, 20)]), MetodoPagamento::Pix).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::LowStock)));
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        // A venda recusada não altera o estoque nem ocupa um código.
+        assert_eq!(search_product_id(&mut products, 2).unwrap().quantidade_estoque, 15);
+        assert_eq!(sales.last_code().unwrap(), 1);
+
+        let history = products.movements(1).unwrap();
+        assert_eq!(history.last().unwrap().quantidade, -3);
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
     }
 
     #[test]
-    fn test_update_sale() {
-        let path = "test_update_sale.bin";
-        let mut file = get_test_file(path);
+    fn test_register_sale_by_barcode() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
 
-        set_sales(&mut file);
+        let mut product = search_product_id(&mut products, 2).unwrap();
+        product.codigo_barras = Some("7891234567895".to_string());
+        replace_product(&mut products, &mut SemDiario, 2, product, "Ana").unwrap();
 
-        let input = b"2\n1/8/2023\npix";
-        let mut cursor = Cursor::new(input);
+        // Editar o produto sem informar o código mantém o que já estava gravado.
+        let product = search_product_id(&mut products, 2).unwrap();
+        replace_product(&mut products, &mut SemDiario, 2, Produto { codigo_barras: None, ..product }, "Ana").unwrap();
+        assert_eq!(search_product_barcode(&mut products, "7891234567895").unwrap().id, 2);
 
-        let size = file.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        let items = [validation::ItemLido::CodigoBarras("7891234567895".to_string(), 1), validation::ItemLido::Id(3, 2), validation::ItemLido::CodigoBarras("7891234567895".to_string(), 4)];
+        let items = resolve_items(&mut products, &items).unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(items), MetodoPagamento::Pix).unwrap();
 
-        let result = update_sale(&mut file, &mut cursor);
+        let sale = sales.get(1).unwrap();
+        assert_eq!(sale.produtos.iter().map(|item| (item.id, item.quantidade)).collect::<Vec<_>>(), vec![(2, 5), (3, 2)]);
+        assert_eq!(search_product_id(&mut products, 2).unwrap().quantidade_estoque, 10);
 
-        assert!(result.is_ok());
+        let error = resolve_items(&mut products, &[validation::ItemLido::CodigoBarras("4006381333931".to_string(), 1)]).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::ProductNotFound)));
 
-        assert_eq!(size, file.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        let mut duplicate = Produto::new("Outro".to_string(), 0, 1, Dinheiro::from_centavos(100), 1, chrono::NaiveDate::default(), crate::categories::GERAL);
+        duplicate.codigo_barras = Some("7891234567895".to_string());
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        let error = insert_product(&mut products, &mut SemDiario, duplicate.clone(), "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::DuplicateBarcode)));
+
+        let error = replace_product(&mut products, &mut SemDiario, 1, duplicate, "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::DuplicateBarcode)));
+        assert!(search_product_id(&mut products, 1).unwrap().codigo_barras.is_none());
     }
 
     #[test]
-    fn test_remove_sale() {
-        let path = "test_remove_sale.bin";
-        let mut file = get_test_file(path);
+    fn test_product_taxes() {
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
+        let tributacao = Tributacao::new("10063021".to_string(), "5102".to_string(), crate::money::Aliquota::from_centesimos(1800), crate::money::Aliquota::ZERO, crate::money::Aliquota::ZERO);
 
-        set_sales(&mut file);
+        set_product_taxes(&mut products, 2, Some(tributacao.clone())).unwrap();
+        assert_eq!(search_product_id(&mut products, 2).unwrap().tributacao(), Some(&tributacao));
 
-        let input = b"1";
-        let mut cursor = Cursor::new(input);
+        // Editar o produto não apaga os dados fiscais, e eles não geram movimento de estoque.
+        let product = search_product_id(&mut products, 2).unwrap();
+        replace_product(&mut products, &mut SemDiario, 2, Produto { tributacao: None, ..product }, "Ana").unwrap();
+        assert_eq!(search_product_id(&mut products, 2).unwrap().tributacao(), Some(&tributacao));
 
-        let size = file.seek(SeekFrom::End(0)).expect("Erro no arquivo.");
+        let invalid = Tributacao { cfop: "1102".to_string(), ..tributacao.clone() };
+        let error = set_product_taxes(&mut products, 2, Some(invalid)).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidTax)));
 
-        let result = remove_sale(&mut file, &mut cursor);
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(2, 1), (3, 1)]), MetodoPagamento::Pix).unwrap();
 
-        assert!(result.is_ok());
+        // A venda mantém as alíquotas do momento, mesmo que o produto mude depois.
+        set_product_taxes(&mut products, 2, None).unwrap();
+
+        let sale = sales.get(1).unwrap();
+        assert_eq!(sale.produtos[0].tributacao(), Some(&tributacao));
+        assert!(sale.produtos[1].tributacao().is_none());
+        assert!(crate::receipt::render_text(&sale, &crate::receipt::Loja::default()).unwrap().contains("NCM 10063021"));
+    }
+
+    #[test]
+    fn test_search_products() {
+        let mut products = get_memory_products();
 
-        assert!(size > file.seek(SeekFrom::End(0)).expect("Erro no arquivo."));
+        let ids = |found: Vec<Produto>| found.iter().map(|product| product.id).collect::<Vec<u64>>();
 
-        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        let filtro = FiltroProdutos { nome: Some("teste".to_string()), valor_minimo: Some(Dinheiro::from_centavos(4500)), ..FiltroProdutos::default() };
+        assert_eq!(ids(search_products(&mut products, &filtro).unwrap()), vec![1, 3]);
+
+        let filtro = FiltroProdutos { abaixo_restoque: true, ..FiltroProdutos::default() };
+        assert_eq!(ids(search_products(&mut products, &filtro).unwrap()), vec![2]);
+
+        let filtro = FiltroProdutos { estoque_minimo: Some(20), estoque_maximo: Some(10), ..FiltroProdutos::default() };
+        let error = search_products(&mut products, &filtro).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidRange)));
+    }
+
+    #[test]
+    fn test_category_operations() {
+        let mut products = get_memory_products();
+
+        let bebidas = create_category(&mut products, &mut SemDiario, "Bebidas", Some(categories::ALIMENTO)).unwrap();
+        assert_eq!(bebidas.id, 5);
+
+        let error = create_category(&mut products, &mut SemDiario, " bebidas ", None).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::DuplicateCategory)));
+        let error = create_category(&mut products, &mut SemDiario, "Sucos", Some(9)).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::CategoryNotFound)));
+
+        let sucos = create_category(&mut products, &mut SemDiario, "Sucos", Some(bebidas.id)).unwrap();
+        assert_eq!(rename_category(&mut products, &mut SemDiario, sucos.id, "Sucos naturais").unwrap().nome, "Sucos naturais");
+        let error = rename_category(&mut products, &mut SemDiario, sucos.id, "Geral").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::DuplicateCategory)));
+
+        let mut product = search_product_id(&mut products, 1).unwrap();
+        product.categoria = bebidas.id;
+        replace_product(&mut products, &mut SemDiario, 1, product, "Ana").unwrap();
+
+        let filtro = FiltroProdutos { categorias: categories::with_descendants(&products.categories().unwrap(), categories::ALIMENTO), ..FiltroProdutos::default() };
+        assert_eq!(search_products(&mut products, &filtro).unwrap().len(), 2);
+
+        let error = delete_category(&mut products, &mut SemDiario, bebidas.id).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::CategoryInUse)));
+        let error = merge_categories(&mut products, &mut SemDiario, bebidas.id, sucos.id).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidParent)));
+
+        // Os produtos e as subcategorias passam para a categoria de destino.
+        assert_eq!(merge_categories(&mut products, &mut SemDiario, bebidas.id, categories::GERAL).unwrap(), 1);
+        assert_eq!(search_product_id(&mut products, 1).unwrap().categoria, categories::GERAL);
+        assert_eq!(search_category(&mut products, sucos.id).unwrap().pai, Some(categories::GERAL));
+        assert!(search_category(&mut products, bebidas.id).is_err());
+
+        delete_category(&mut products, &mut SemDiario, sucos.id).unwrap();
+        assert_eq!(products.categories().unwrap(), categories::built_in());
+
+        let mut product = search_product_id(&mut products, 2).unwrap();
+        product.categoria = sucos.id;
+        let error = replace_product(&mut products, &mut SemDiario, 2, product, "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::NoCategory)));
+    }
+
+    #[test]
+    fn test_merge_categories_write_failure() {
+        let path_products = &test_path("test_merge_categories_write_failure_1.bin");
+        let path_journal = &test_path("test_merge_categories_write_failure_2.bin");
+
+        let mut products_file = get_test_products(path_products);
+        let mut journal_file = get_test_file(path_journal);
+
+        set_test_products(&mut products_file);
+        record_opening_balances(&mut products_file).unwrap();
+
+        let bebidas = create_category(&mut products_file, &mut journal_file, "Bebidas", Some(categories::ALIMENTO)).unwrap();
+        create_promotion(&mut products_file, &mut journal_file, Promocao::new(0, "Alimentos".to_string(), Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 }, None, None)).unwrap();
+
+        let products = read_all(&mut products_file.dados);
+        let categorias = read_all(&mut products_file.categorias.dados);
+        let promocoes = read_all(&mut products_file.promocoes.dados);
+
+        // Uma falha depois de mover o produto e a promoção e de remover a categoria desfaz tudo.
+        let result = journal_file.update_catalog(&mut products_file, &[2], |products_file| {
+            let mut product = search_product_id(products_file, 2)?;
+            product.categoria = categories::GERAL;
+            products_file.update(&product)?;
+
+            let mut promotion = products_file.promotions()?.remove(0);
+            promotion.regra = Regra::Categoria { categoria: categories::GERAL, percentual: 10 };
+            products_file.update_promotion(&promotion)?;
+            products_file.remove_category(categories::ALIMENTO)?;
+
+            Err::<(), _>(Box::from("falha"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(categorias, read_all(&mut products_file.categorias.dados));
+        assert_eq!(promocoes, read_all(&mut products_file.promocoes.dados));
+        assert_eq!(0, journal_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de transação."));
+
+        assert_eq!(merge_categories(&mut products_file, &mut journal_file, categories::ALIMENTO, categories::GERAL).unwrap(), 1);
+        assert_eq!(search_product_id(&mut products_file, 2).unwrap().categoria, categories::GERAL);
+        assert_eq!(search_category(&mut products_file, bebidas.id).unwrap().pai, Some(categories::GERAL));
+        assert_eq!(products_file.promotions().unwrap()[0].regra, Regra::Categoria { categoria: categories::GERAL, percentual: 10 });
+
+        remove_test_products(path_products);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_close_session() {
+        let path_sessions = &test_path("test_close_session.bin");
+
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
+        let mut sessions_file = get_test_sessions(path_sessions);
+
+        let session = start_session(&mut sessions_file, "Ana".to_string(), Dinheiro::from_centavos(5000)).expect("Erro ao abrir o caixa.");
+
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), Some(session.id), &Cesta::new(vec![(1, 1)]), MetodoPagamento::Dinheiro).unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), Some(session.id), &Cesta::new(vec![(2, 1)]), MetodoPagamento::Pix).unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(1, 1)]), MetodoPagamento::Dinheiro).unwrap();
+
+        assert_eq!(sales.by_session(session.id).unwrap().len(), 2);
+
+        let (expected, _) = session_summary(&mut sales, &session).unwrap();
+        assert_eq!(expected, Dinheiro::from_centavos(10000));
+
+        assert!(end_session(&mut sessions_file, &mut sales, Dinheiro::from_centavos(9900)).is_ok());
+
+        let closed = sessions_file.search(session.id).unwrap();
+        let closing = closed.fechamento.as_ref().unwrap();
+
+        assert_eq!(closing.esperado, Dinheiro::from_centavos(10000));
+        assert_eq!(closing.contado, Dinheiro::from_centavos(9900));
+        assert!(sessions_file.current().unwrap().is_none());
+
+        remove_test_sessions(path_sessions);
+    }
+
+    #[test]
+    fn test_stock_movements() {
+
+        let mut products = MemoriaProdutos::new();
+        let mut sales = MemoriaVendas::new();
+
+        for nome in ["Arroz", "Feijão"] {
+            let product = Produto::new(nome.to_string(), 0, 10, Dinheiro::from_centavos(1000), 2, chrono::NaiveDate::default(), crate::categories::ALIMENTO);
+            insert_product(&mut products, &mut SemDiario, product, "Ana").unwrap();
+        }
+
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Bia".to_string(), None, &Cesta::new(vec![(1, 3), (2, 1)]), MetodoPagamento::Pix).unwrap();
+        change_sale_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 4)], "Bia").unwrap();
+        return_items(&mut products, &mut sales, &mut SemDiario, 1, &[(1, 2)], "Defeito".to_string(), "Ana").unwrap();
+        adjust_stock(&mut products, &mut SemDiario, 1, TipoMovimento::Perda, -1, "Ana", "Pacote rasgado").unwrap();
+
+        let error = adjust_stock(&mut products, &mut SemDiario, 1, TipoMovimento::Perda, 1, "Ana", "Sinal errado").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidAdjustment)));
+
+        let error = adjust_stock(&mut products, &mut SemDiario, 2, TipoMovimento::Ajuste, -50, "Ana", "Contagem").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::LowStock)));
+
+        let history: Vec<(TipoMovimento, i64, String)> = products.movements(1).unwrap().into_iter()
+            .map(|movement| (movement.tipo, movement.quantidade, movement.operador))
+            .collect();
+
+        assert_eq!(history, vec![
+            (TipoMovimento::Inicial, 10, "Ana".to_string()),
+            (TipoMovimento::Venda, -3, "Bia".to_string()),
+            (TipoMovimento::Venda, -1, "Bia".to_string()),
+            (TipoMovimento::Devolucao, 2, "Ana".to_string()),
+            (TipoMovimento::Perda, -1, "Ana".to_string())
+        ]);
+
+        let product = search_product_id(&mut products, 1).unwrap();
+        assert_eq!(product.quantidade_estoque, 7);
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
+
+        let mut product = search_product_id(&mut products, 2).unwrap();
+        product.quantidade_estoque = 4;
+        replace_product(&mut products, &mut SemDiario, 2, product, "Ana").unwrap();
+
+        assert_eq!(products.balances().unwrap()[&2], 4);
+
+        // Estoque alterado por fora do programa aparece na auditoria.
+        let mut product = search_product_id(&mut products, 1).unwrap();
+        product.quantidade_estoque = 30;
+        products.update(&product).unwrap();
+
+        let divergences = stock_divergences(&mut products).unwrap();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!((divergences[0].0.id, divergences[0].1), (1, 7));
+
+        fix_divergences(&mut products, divergences).unwrap();
+
+        assert_eq!(search_product_id(&mut products, 1).unwrap().quantidade_estoque, 7);
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
+
+        delete_product(&mut products, &mut SemDiario, 2, "Ana").unwrap();
+        assert_eq!(products.balances().unwrap()[&2], 0);
+
+    }
+
+    #[test]
+    fn test_stock_change_write_failure() {
+        let path_products = &test_path("test_stock_change_write_failure_1.bin");
+        let path_journal = &test_path("test_stock_change_write_failure_2.bin");
+
+        let mut products_file = get_test_products(path_products);
+        let mut journal_file = get_test_file(path_journal);
+
+        set_test_products(&mut products_file);
+        record_opening_balances(&mut products_file).unwrap();
+
+        let products = read_all(&mut products_file.dados);
+        let movements = read_all(&mut products_file.movimentos.dados);
+
+        // Com o índice só para leitura, a edição falha depois de gravar o movimento e o produto.
+        let read_only_index = OpenOptions::new().read(true).open(path_products.with_extension("idx")).expect("Erro no índice de produtos.");
+        let index = std::mem::replace(&mut products_file.indice, read_only_index);
+
+        let mut product = search_product_id(&mut products_file, 1).unwrap();
+        product.nome = "Teste1 com um nome mais comprido".to_string();
+        product.quantidade_estoque = 4;
+
+        assert!(replace_product(&mut products_file, &mut journal_file, 1, product, "Ana").is_err());
+
+        products_file.indice = index;
+        products_file.rebuild_index().unwrap();
+
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(movements, read_all(&mut products_file.movimentos.dados));
+        assert_eq!(products_file.movements(1).unwrap().len(), 1);
+        assert_eq!(search_product_id(&mut products_file, 1).unwrap().quantidade_estoque, 10);
+        assert!(stock_divergences(&mut products_file).unwrap().is_empty());
+        assert_eq!(0, journal_file.seek(SeekFrom::End(0)).expect("Erro no arquivo de transação."));
+
+        remove_test_products(path_products);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
+    }
+
+    #[test]
+    fn test_receive_stock() {
+        let mut products = MemoriaProdutos::new();
+
+        set_test_products(&mut products);
+
+        let items = [(1, 5, Dinheiro::from_centavos(3000)), (3, 2, Dinheiro::from_centavos(4500)), (1, 1, Dinheiro::from_centavos(3250))];
+        assert_eq!(receive_stock(&mut products, &mut SemDiario, "Distribuidora Norte", &items, "Ana").unwrap().len(), 2);
+
+        let product = search_product_id(&mut products, 1).unwrap();
+
+        assert_eq!(product.quantidade_estoque, 16);
+        assert_eq!(product.data_restoque, chrono::Local::now().date_naive());
+        assert_eq!(product.fornecedor.as_deref(), Some("Distribuidora Norte"));
+        assert_eq!(product.custo_unitario, Some(Dinheiro::from_centavos(3250)));
+        assert_eq!(search_product_id(&mut products, 3).unwrap().quantidade_estoque, 22);
+
+        let movements = products.movements(1).unwrap();
+
+        assert_eq!(movements.len(), 2);
+        assert_eq!(movements[0].tipo, TipoMovimento::Restoque);
+        assert_eq!(movements[0].recebimento.as_ref().unwrap().custo_unitario, Dinheiro::from_centavos(3000));
+
+        let error = receive_stock(&mut products, &mut SemDiario, "Outro", &[(2, 1, Dinheiro::ZERO), (9, 1, Dinheiro::ZERO)], "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::ProductNotFound)));
+        assert_eq!(search_product_id(&mut products, 2).unwrap().quantidade_estoque, 15);
+
+        // Editar o produto mantém os dados do último recebimento.
+        let product = Produto::new("Teste1".to_string(), 0, 16, Dinheiro::from_centavos(5000), 5, chrono::NaiveDate::default(), crate::categories::GERAL);
+        assert!(replace_product(&mut products, &mut SemDiario, 1, product, "Ana").is_ok());
+        assert_eq!(search_product_id(&mut products, 1).unwrap().fornecedor.as_deref(), Some("Distribuidora Norte"));
+
+    }
+
+    #[test]
+    fn test_purchase_orders() {
+        let path_orders = &test_path("test_purchase_orders.bin");
+
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
+        let mut orders_file = get_test_orders(path_orders);
+
+        receive_stock(&mut products, &mut SemDiario, "Atacadão", &[(1, 10, Dinheiro::from_centavos(3000))], "Ana").unwrap();
+        complete_sale(&mut products, &mut sales, &mut SemDiario, "Ana".to_string(), None, &Cesta::new(vec![(1, 15)]), MetodoPagamento::Pix).unwrap();
+
+        // Produto 1 vendeu 15 em 30 dias: ponto de pedido 5 + 4 e alvo 5 + 19.
+        let parametros = orders::Parametros::default();
+        let created = create_purchase_orders(&mut products, &mut sales, &mut orders_file, &parametros).unwrap();
+
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].fornecedor, None);
+        assert_eq!(created[0].itens.iter().map(|item| item.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(created[1].fornecedor.as_deref(), Some("Atacadão"));
+        assert_eq!((created[1].itens[0].id, created[1].itens[0].quantidade), (1, 19));
+
+        let error = complete_order(&mut products, &mut orders_file, &mut SemDiario, 1, None, &[], "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::IncompleteOrder)));
+
+        // Um recebimento interrompido antes de marcar o pedido já creditou o estoque.
+        credit_stock(&mut products, &mut SemDiario, "Atacadão", &[(1, 19, Dinheiro::from_centavos(3000))], "Bia", "Recebimento do pedido 2").unwrap();
+
+        assert!(complete_order(&mut products, &mut orders_file, &mut SemDiario, 2, None, &[], "Bia").is_ok());
+
+        let product = search_product_id(&mut products, 1).unwrap();
+        assert_eq!(product.quantidade_estoque, 24);
+        assert_eq!(orders_file.search(2).unwrap().recebimento.as_ref().unwrap().operador, "Bia");
+
+        let error = complete_order(&mut products, &mut orders_file, &mut SemDiario, 2, None, &[], "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::OrderReceived)));
+
+        complete_order(&mut products, &mut orders_file, &mut SemDiario, 1, Some("Mercearia".to_string()), &[(2, Dinheiro::from_centavos(2000))], "Ana").unwrap();
+
+        let product = search_product_id(&mut products, 2).unwrap();
+        assert_eq!(product.fornecedor.as_deref(), Some("Mercearia"));
+        assert!(stock_divergences(&mut products).unwrap().is_empty());
+
+        remove_test_orders(path_orders);
+    }
+
+    #[test]
+    fn test_import_products_and_sales() {
+
+        let mut products = get_memory_products();
+        let mut sales = MemoriaVendas::new();
+
+        products.remove(3).unwrap();
+
+        let products_csv = "nome,estoque,valor,restoque,data,categoria,fornecedor\nCamisa,3,\"49,90\",1,10/08/2023,roupa,Malharia\nArroz,10,20,2,10/08/2023,alimento,\n";
+
+        assert_eq!(import_products(&mut products, &mut SemDiario, products_csv, true, "Ana").unwrap(), vec![4, 5]);
+        assert_eq!(products.list().unwrap().len(), 2);
+
+        let error = import_products(&mut products, &mut SemDiario, "nome,estoque,valor,restoque,data,categoria\nCamisa,3,1,1,10/08/2023,roupa\nBola,1,1,1,10/08/2023,brinquedo\n", false, "Ana").err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::StoreError>(), Some(errors::StoreError::Import(_))));
+        assert_eq!(products.list().unwrap().len(), 2);
+
+        assert_eq!(import_products(&mut products, &mut SemDiario, products_csv, false, "Ana").unwrap(), vec![4, 5]);
+
+        let product = search_product_id(&mut products, 4).unwrap();
+        assert_eq!(product.valor, Dinheiro::from_centavos(4990));
+        assert_eq!(product.fornecedor.as_deref(), Some("Malharia"));
+        assert_eq!(products.balances().unwrap()[&5], 10);
+
+        let sales_csv = "codigo,data,vendedor,pagamento,produto,quantidade\n7,10/08/2023,Ana,pix,4,2\n7,10/08/2023,Ana,pix,5,1\n7,10/08/2023,Ana,pix,4,1\n3,11/08/2023,Bia,debito,5,1\n";
+
+        assert_eq!(import_sales(&mut products, &mut sales, &mut SemDiario, sales_csv, true).unwrap(), vec![7, 3]);
+        assert!(sales.list().unwrap().is_empty());
+        assert_eq!(import_sales(&mut products, &mut sales, &mut SemDiario, sales_csv, false).unwrap(), vec![7, 3]);
+        assert_eq!(sales.last_code().unwrap(), 7);
+
+        let sale = search_sale_code(&mut sales, 7).unwrap();
+        assert_eq!(sale.produtos.len(), 2);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(16970));
+
+        let product = search_product_id(&mut products, 4).unwrap();
+        assert_eq!(product.quantidade_estoque, 3);
+
+        // Uma venda importada pode ser cancelada como as outras.
+        let sale = void_sale(&mut products, &mut sales, &mut SemDiario, 7, "Teste".to_string(), "Ana").unwrap();
+        assert_eq!(sale.situacao, Situacao::Cancelada);
+        assert_eq!(search_product_id(&mut products, 4).unwrap().quantidade_estoque, 6);
+
+        // Importar de novo as vendas exportadas não as duplica.
+        let exported = csv::export_sales(&sales.list().unwrap());
+        let error = import_sales(&mut products, &mut sales, &mut SemDiario, &exported, false).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::StoreError>(), Some(errors::StoreError::Import(_))));
+        assert_eq!(sales.list().unwrap().len(), 2);
+
+    }
+
+    #[test]
+    fn test_import_write_failure() {
+        let path_products = &test_path("test_import_write_failure_1.bin");
+        let path_journal = &test_path("test_import_write_failure_2.bin");
+
+        let mut products_file = get_test_products(path_products);
+        let mut journal_file = get_test_file(path_journal);
+
+        set_test_products(&mut products_file);
+        record_opening_balances(&mut products_file).unwrap();
+
+        let products = read_all(&mut products_file.dados);
+        let movements = read_all(&mut products_file.movimentos.dados);
+
+        // Com o índice só para leitura, a gravação falha depois de o primeiro produto ser escrito.
+        let read_only_index = OpenOptions::new().read(true).open(path_products.with_extension("idx")).expect("Erro no índice de produtos.");
+        let index = std::mem::replace(&mut products_file.indice, read_only_index);
+
+        let products_csv = "nome,estoque,valor,restoque,data,categoria\nCamisa,3,1,1,10/08/2023,roupa\nArroz,10,20,2,10/08/2023,alimento\n";
+        assert!(import_products(&mut products_file, &mut journal_file, products_csv, false, "Ana").is_err());
+
+        products_file.indice = index;
+        products_file.rebuild_index().unwrap();
+
+        assert_eq!(products, read_all(&mut products_file.dados));
+        assert_eq!(movements, read_all(&mut products_file.movimentos.dados));
+        assert_eq!(products_file.list().unwrap().len(), 3);
+        assert_eq!(products_file.last_id().unwrap(), 3);
+
+        remove_test_products(path_products);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
+    }
+}
@@ -0,0 +1,567 @@
+use std::{collections::{BTreeMap, BTreeSet}, error::Error};
+
+use super::{errors::CustomErrors, money::Dinheiro, promotions, validation, Categoria, Devolucao, ItemVenda, MetodoPagamento, Produto, Situacao, Tributacao, Venda};
+
+const DATE_FORMAT: &str = "%d/%m/%Y";
+
+pub const PRODUCT_COLUMNS: [&str; 15] = ["id", "nome", "estoque", "valor", "restoque", "data", "categoria", "fornecedor", "custo_unitario", "codigo_barras", "ncm", "cfop", "icms", "pis", "cofins"];
+pub const SALE_COLUMNS: [&str; 13] = ["codigo", "data", "vendedor", "pagamento", "situacao", "caixa", "produto", "nome", "quantidade", "valor_unitario", "desconto", "desconto_venda", "devolvidos"];
+
+// Número da linha no arquivo e a mensagem do erro encontrado nela.
+pub type ErroLinha = (usize, String);
+
+// Registros válidos com o número da sua linha e os erros das linhas restantes.
+pub type Importacao<T> = (Vec<(usize, T)>, Vec<ErroLinha>);
+
+pub fn parse_line(line: &str) -> Result<Vec<String>, CustomErrors> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (false, c) => field.push(c)
+        }
+    }
+
+    if quoted {
+        return Err(CustomErrors::InvalidCsv);
+    }
+
+    fields.push(field);
+
+    Ok(fields)
+}
+
+pub fn write_line(fields: &[String]) -> String {
+    fields.iter()
+        .map(|field| match field.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.clone()
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// A categoria é gravada pelo nome, que é único; sem categoria cadastrada, fica o id.
+fn category_name(categorias: &[Categoria], id: u64) -> String {
+    categorias.iter()
+        .find(|categoria| categoria.id == id)
+        .map(|categoria| categoria.nome.clone())
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn payment_code(metodo: &MetodoPagamento) -> &'static str {
+    match metodo {
+        MetodoPagamento::Credito => "credito",
+        MetodoPagamento::Debito => "debito",
+        MetodoPagamento::Pix => "pix",
+        MetodoPagamento::Dinheiro => "dinheiro"
+    }
+}
+
+// Os valores são gravados sem o prefixo "R$" para que o arquivo possa ser importado de volta.
+fn money(valor: Dinheiro) -> String {
+    valor.to_string().trim_start_matches("R$ ").to_string()
+}
+
+fn tax_fields(tributacao: Option<&Tributacao>) -> [String; 5] {
+    match tributacao {
+        Some(tributacao) => [tributacao.ncm.clone(), tributacao.cfop.clone(), tributacao.icms.to_string(), tributacao.pis.to_string(), tributacao.cofins.to_string()],
+        None => Default::default()
+    }
+}
+
+pub fn export_products(products: &[Produto], categorias: &[Categoria]) -> String {
+    let mut csv = write_line(&PRODUCT_COLUMNS.map(String::from)) + "\n";
+
+    for product in products {
+        let mut fields = vec![
+            product.id.to_string(),
+            product.nome.clone(),
+            product.quantidade_estoque.to_string(),
+            money(product.valor),
+            product.quantidade_restoque.to_string(),
+            product.data_restoque.format(DATE_FORMAT).to_string(),
+            category_name(categorias, product.categoria),
+            product.fornecedor.clone().unwrap_or_default(),
+            product.custo_unitario.map(money).unwrap_or_default(),
+            product.codigo_barras.clone().unwrap_or_default()
+        ];
+        fields.extend(tax_fields(product.tributacao.as_ref()));
+
+        csv += &write_line(&fields);
+        csv += "\n";
+    }
+
+    csv
+}
+
+// Uma linha por produto vendido; as linhas de uma mesma venda repetem os dados da venda.
+pub fn export_sales(sales: &[Venda]) -> String {
+    let mut csv = write_line(&SALE_COLUMNS.map(String::from)) + "\n";
+
+    for sale in sales {
+        for item in sale.produtos.iter() {
+            csv += &write_line(&[
+                sale.codigo.to_string(),
+                sale.data.format(DATE_FORMAT).to_string(),
+                sale.vendedor.clone(),
+                payment_code(&sale.metodo_pagamento).to_string(),
+                sale.situacao.to_string(),
+                sale.caixa.map(|caixa| caixa.to_string()).unwrap_or_default(),
+                item.id.to_string(),
+                item.nome.clone(),
+                item.quantidade.to_string(),
+                money(item.valor_unitario),
+                money(item.desconto),
+                money(sale.desconto),
+                sale.returned(item.id).to_string()
+            ]);
+            csv += "\n";
+        }
+    }
+
+    csv
+}
+
+struct Tabela {
+    colunas: Vec<String>,
+    linhas: Vec<(usize, String)>
+}
+
+impl Tabela {
+    // A primeira linha nomeia as colunas; colunas desconhecidas são ignoradas.
+    fn parse(text: &str, required: &[&str]) -> Result<Self, CustomErrors> {
+        let mut lines = text.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, header) = lines.next().ok_or(CustomErrors::InvalidCsv)?;
+        let colunas: Vec<String> = parse_line(header)?.iter().map(|column| column.trim().to_lowercase()).collect();
+
+        if !required.iter().all(|column| colunas.iter().any(|name| name == column)) {
+            return Err(CustomErrors::InvalidCsv);
+        }
+
+        Ok(Tabela {
+            colunas,
+            linhas: lines.map(|(number, line)| (number, line.to_string())).collect()
+        })
+    }
+
+    fn field<'a>(&self, row: &'a [String], name: &str) -> &'a str {
+        self.colunas.iter()
+            .position(|column| column == name)
+            .and_then(|index| row.get(index))
+            .map_or("", |field| field.trim())
+    }
+
+    fn rows(&self) -> impl Iterator<Item = (usize, Result<Vec<String>, CustomErrors>)> + '_ {
+        self.linhas.iter().map(|(number, line)| {
+            let row = parse_line(line).and_then(|row| match row.len() == self.colunas.len() {
+                true => Ok(row),
+                false => Err(CustomErrors::InvalidCsv)
+            });
+
+            (*number, row)
+        })
+    }
+}
+
+fn optional_money(string: &str) -> Result<Option<Dinheiro>, CustomErrors> {
+    match string.is_empty() {
+        true => Ok(None),
+        false => validation::validate_money(string).map(Some)
+    }
+}
+
+fn parse_product(tabela: &Tabela, row: &[String], categorias: &[Categoria]) -> Result<Produto, Box<dyn Error>> {
+    let mut product = validation::validate_product(vec![
+        tabela.field(row, "nome"),
+        tabela.field(row, "estoque"),
+        tabela.field(row, "valor"),
+        tabela.field(row, "restoque"),
+        tabela.field(row, "data"),
+        tabela.field(row, "categoria")
+    ], categorias)?;
+
+    let fornecedor = tabela.field(row, "fornecedor");
+
+    product.fornecedor = (!fornecedor.is_empty()).then(|| fornecedor.to_string());
+    product.custo_unitario = optional_money(tabela.field(row, "custo_unitario"))?;
+
+    let codigo_barras = tabela.field(row, "codigo_barras");
+    product.codigo_barras = (!codigo_barras.is_empty()).then(|| validation::validate_barcode(codigo_barras)).transpose()?;
+
+    // Sem nenhuma das colunas fiscais preenchida, o produto fica sem dados fiscais.
+    let taxes = ["ncm", "cfop", "icms", "pis", "cofins"].map(|name| tabela.field(row, name));
+    product.tributacao = (!taxes.iter().all(|field| field.is_empty())).then(|| validation::validate_taxes(&taxes)).transpose()?;
+
+    Ok(product)
+}
+
+// Os códigos de barras não podem repetir os dos produtos já cadastrados nem os de outras linhas.
+pub fn parse_products(text: &str, existing: &[Produto], categorias: &[Categoria]) -> Result<Importacao<Produto>, CustomErrors> {
+    let tabela = Tabela::parse(text, &PRODUCT_COLUMNS[1..7])?;
+
+    let mut barcodes: BTreeSet<String> = existing.iter().filter_map(|product| product.codigo_barras.clone()).collect();
+    let mut products = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, row) in tabela.rows() {
+        match row.map_err(Box::from).and_then(|row| parse_product(&tabela, &row, categorias)) {
+            Ok(product) if product.codigo_barras.as_ref().is_some_and(|barcode| !barcodes.insert(barcode.clone())) => {
+                errors.push((number, CustomErrors::DuplicateBarcode.to_string()));
+            },
+            Ok(product) => products.push((number, product)),
+            Err(error) => errors.push((number, error.to_string()))
+        }
+    }
+
+    Ok((products, errors))
+}
+
+// Aceita o texto exportado ("Concluída") e o nome da variante ("Concluida"); vazia, a situação
+// vem das unidades devolvidas.
+fn parse_situation(string: &str) -> Result<Option<Situacao>, CustomErrors> {
+    match string.to_lowercase().as_str() {
+        "" => Ok(None),
+        "concluída" | "concluida" => Ok(Some(Situacao::Concluida)),
+        "devolvida parcialmente" | "devolucaoparcial" => Ok(Some(Situacao::DevolucaoParcial)),
+        "cancelada" => Ok(Some(Situacao::Cancelada)),
+        _ => Err(CustomErrors::InvalidCsv)
+    }
+}
+
+// Venda montada a partir das linhas do arquivo, com as unidades devolvidas de cada produto.
+struct VendaImportada {
+    venda: Venda,
+    situacao: Option<Situacao>,
+    devolvidos: BTreeMap<u64, u64>
+}
+
+fn parse_sale_row(tabela: &Tabela, row: &[String], products: &BTreeMap<u64, &Produto>, existing: &BTreeSet<u64>) -> Result<VendaImportada, Box<dyn Error>> {
+    let codigo = validation::validate_int(tabela.field(row, "codigo"))?;
+
+    if codigo == 0 {
+        return Err(Box::new(CustomErrors::InvalidValue));
+    }
+
+    if existing.contains(&codigo) {
+        return Err(Box::new(CustomErrors::DuplicateSale));
+    }
+
+    let data = chrono::NaiveDate::parse_from_str(tabela.field(row, "data"), DATE_FORMAT)?;
+    let vendedor = validation::validate_name(tabela.field(row, "vendedor"))?;
+    let metodo = validation::parse_payment_method(tabela.field(row, "pagamento"))?;
+
+    let product = products.get(&validation::validate_int(tabela.field(row, "produto"))?).ok_or(CustomErrors::ProductNotFound)?;
+    let quantidade = validation::validate_int(tabela.field(row, "quantidade"))?;
+
+    let valor_unitario = optional_money(tabela.field(row, "valor_unitario"))?.unwrap_or(product.valor);
+
+    // Os descontos entram como valores já calculados; as promoções não são aplicadas de novo.
+    // Os dados fiscais da linha são os do produto no momento da importação.
+    let mut item = ItemVenda::new(product.id, product.nome.clone(), quantidade, valor_unitario)?;
+    item.desconto = optional_money(tabela.field(row, "desconto"))?.unwrap_or(Dinheiro::ZERO);
+    item.tributacao = product.tributacao.clone();
+
+    if item.desconto < Dinheiro::ZERO || item.desconto > item.valor_total {
+        return Err(Box::new(CustomErrors::InvalidDiscount));
+    }
+
+    let devolvidos = match tabela.field(row, "devolvidos") {
+        "" => 0,
+        devolvidos => validation::validate_int(devolvidos)?
+    };
+
+    // O caixa do arquivo pertence a outro estoque, então a venda entra sem caixa.
+    let mut sale = Venda::new(vendedor, codigo, Dinheiro::ZERO, data, metodo);
+    sale.desconto = optional_money(tabela.field(row, "desconto_venda"))?.unwrap_or(Dinheiro::ZERO);
+    sale.produtos.push(item);
+
+    Ok(VendaImportada {
+        devolvidos: BTreeMap::from([(product.id, devolvidos)]),
+        venda: sale,
+        situacao: parse_situation(tabela.field(row, "situacao"))?
+    })
+}
+
+// Linhas repetidas de um produto na mesma venda viram uma linha só, com as quantidades somadas.
+fn merge_item(sale: &mut Venda, item: ItemVenda) -> Result<(), Box<dyn Error>> {
+    let Some(line) = sale.produtos.iter_mut().find(|line| line.id == item.id) else {
+        sale.produtos.push(item);
+        return Ok(());
+    };
+
+    if line.valor_unitario != item.valor_unitario {
+        return Err(format!("O produto {} aparece na venda {} com valores unitários diferentes.", item.id, sale.codigo).into());
+    }
+
+    let desconto = line.desconto.checked_add(item.desconto)?;
+    let quantidade = line.quantidade.checked_add(item.quantidade).ok_or(CustomErrors::ValueOverflow)?;
+
+    *line = ItemVenda { desconto, ..ItemVenda::new(line.id, line.nome.clone(), quantidade, line.valor_unitario)? };
+    line.tributacao = item.tributacao;
+
+    Ok(())
+}
+
+// As unidades devolvidas entram como uma única devolução na data da venda, e a situação
+// informada precisa conferir com elas.
+fn apply_returns(sale: &mut Venda, situacao: Option<Situacao>, returned: &BTreeMap<u64, u64>) -> Result<(), Box<dyn Error>> {
+    let itens: Vec<(u64, u64)> = sale.produtos.iter()
+        .filter_map(|item| returned.get(&item.id).filter(|&&amount| amount > 0).map(|&amount| (item.id, amount)))
+        .collect();
+
+    if sale.produtos.iter().any(|item| returned.get(&item.id).is_some_and(|&amount| amount > item.quantidade)) {
+        return Err(Box::new(CustomErrors::InvalidReturn));
+    }
+
+    let fully_returned = sale.produtos.iter().all(|item| returned.get(&item.id) == Some(&item.quantidade));
+
+    let derived = match (itens.is_empty(), fully_returned) {
+        (true, _) => Situacao::Concluida,
+        (false, true) => Situacao::Cancelada,
+        (false, false) => Situacao::DevolucaoParcial
+    };
+
+    if situacao.is_some_and(|situacao| situacao != derived) {
+        return Err(format!("A situação da venda {} não confere com as unidades devolvidas.", sale.codigo).into());
+    }
+
+    if itens.is_empty() {
+        return Ok(());
+    }
+
+    let valor = match fully_returned {
+        true => sale.valor,
+        false => itens.iter().try_fold(Dinheiro::ZERO, |total, &(id, amount)| {
+            let item = sale.produtos.iter().find(|item| item.id == id).ok_or(CustomErrors::InvalidReturn)?;
+            total.checked_add(promotions::refund_value(sale, item, amount)?)
+        })?
+    };
+
+    sale.devolucoes.push(Devolucao { data: sale.data, motivo: "Importada".to_string(), itens, valor });
+    sale.situacao = derived;
+
+    Ok(())
+}
+
+// As linhas com o mesmo código formam uma venda, que é gravada com esse código. Um código
+// que já pertence a uma venda gravada é recusado, então um arquivo exportado não é
+// importado duas vezes.
+pub fn parse_sales(text: &str, products: &[Produto], existing: &[Venda]) -> Result<Importacao<Venda>, CustomErrors> {
+    let tabela = Tabela::parse(text, &["codigo", "data", "vendedor", "pagamento", "produto", "quantidade"])?;
+    let products: BTreeMap<u64, &Produto> = products.iter().map(|product| (product.id, product)).collect();
+    let existing: BTreeSet<u64> = existing.iter().map(|sale| sale.codigo).collect();
+
+    let mut sales: Vec<(usize, VendaImportada)> = Vec::new();
+    let mut errors = Vec::new();
+
+    for (number, row) in tabela.rows() {
+        let linha = match row.map_err(Box::from).and_then(|row| parse_sale_row(&tabela, &row, &products, &existing)) {
+            Ok(linha) => linha,
+            Err(error) => {
+                errors.push((number, error.to_string()));
+                continue;
+            }
+        };
+
+        let codigo = linha.venda.codigo;
+
+        match sales.iter_mut().find(|(_, importada)| importada.venda.codigo == codigo) {
+            Some((_, importada)) if importada.venda.data != linha.venda.data || importada.venda.vendedor != linha.venda.vendedor || payment_code(&importada.venda.metodo_pagamento) != payment_code(&linha.venda.metodo_pagamento) || importada.venda.desconto != linha.venda.desconto || importada.situacao != linha.situacao => {
+                errors.push((number, format!("A data, o vendedor, o pagamento, a situação e o desconto da venda devem ser iguais em todas as linhas da venda {codigo}.")));
+            },
+            Some((_, importada)) => match linha.venda.produtos.into_iter().try_for_each(|item| merge_item(&mut importada.venda, item)) {
+                Ok(()) => {
+                    for (id, amount) in linha.devolvidos {
+                        *importada.devolvidos.entry(id).or_insert(0) += amount;
+                    }
+                },
+                Err(error) => errors.push((number, error.to_string()))
+            },
+            None => sales.push((number, linha))
+        }
+    }
+
+    let mut parsed = Vec::new();
+
+    for (number, VendaImportada { venda: mut sale, situacao, devolvidos }) in sales {
+        let valor = sale.produtos.iter()
+            .try_fold(Dinheiro::ZERO, |total, item| total.checked_add(item.valor_liquido()?))
+            .and_then(|total| match sale.desconto >= Dinheiro::ZERO && sale.desconto <= total {
+                true => total.checked_sub(sale.desconto),
+                false => Err(CustomErrors::InvalidDiscount)
+            });
+
+        let result = valor.map_err(Box::from).and_then(|valor| {
+            sale.valor = valor;
+            apply_returns(&mut sale, situacao, &devolvidos)
+        });
+
+        match result {
+            Ok(()) => parsed.push((number, sale)),
+            Err(error) => errors.push((number, error.to_string()))
+        }
+    }
+
+    errors.sort_by_key(|(number, _)| *number);
+
+    Ok((parsed, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{categories, money::Aliquota};
+
+    #[test]
+    fn test_parse_and_write_line() {
+        let fields = parse_line("1,\"Arroz, tipo 1\",\"diz \"\"oi\"\"\",,18,50").unwrap();
+
+        assert_eq!(fields, vec!["1", "Arroz, tipo 1", "diz \"oi\"", "", "18", "50"]);
+        assert_eq!(parse_line(&write_line(&fields)).unwrap(), fields);
+        assert!(matches!(parse_line("1,\"aberto"), Err(CustomErrors::InvalidCsv)));
+    }
+
+    #[test]
+    fn test_products_round_trip() {
+        let mut product = Produto::new("Arroz, tipo 1".to_string(), 7, 10, Dinheiro::from_centavos(185050), 2, chrono::NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), categories::ALIMENTO);
+        product.fornecedor = Some("Cerealista".to_string());
+        product.codigo_barras = Some("7891234567895".to_string());
+        product.tributacao = Some(Tributacao::new("10063021".to_string(), "5102".to_string(), Aliquota::from_centesimos(1800), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760)));
+
+        let categorias = categories::built_in();
+
+        let csv = export_products(&[product], &categorias);
+        let (products, errors) = parse_products(&csv, &[], &categorias).unwrap();
+
+        assert!(errors.is_empty());
+        assert!(csv.contains(",Alimento,"));
+        assert_eq!(products[0].1.categoria, categories::ALIMENTO);
+        assert_eq!(products[0].0, 2);
+        assert_eq!(products[0].1.nome, "Arroz, tipo 1");
+        assert_eq!(products[0].1.valor, Dinheiro::from_centavos(185050));
+        assert_eq!(products[0].1.fornecedor.as_deref(), Some("Cerealista"));
+        assert_eq!(products[0].1.custo_unitario, None);
+        assert_eq!(products[0].1.codigo_barras.as_deref(), Some("7891234567895"));
+        assert_eq!(products[0].1.tributacao, Some(Tributacao::new("10063021".to_string(), "5102".to_string(), Aliquota::from_centesimos(1800), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760))));
+
+        // O mesmo arquivo importado de novo repete o código de barras do produto cadastrado.
+        let (_, errors) = parse_products(&csv, &[products[0].1.clone()], &categorias).unwrap();
+        assert_eq!(errors, vec![(2, CustomErrors::DuplicateBarcode.to_string())]);
+
+        let csv = "nome,estoque,valor,restoque,data,categoria\nCamisa,3,49.90,1,10/08/2023,roupa\nBola,dez,5,1,10/08/2023,geral\n\nLapis,1,2,1,10/08/2023,escolar\nCaneta,1\n";
+        let (products, errors) = parse_products(csv, &[], &categorias).unwrap();
+
+        assert_eq!(products.len(), 1);
+        assert_eq!(errors.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![3, 5, 6]);
+        assert!(matches!(parse_products("nome,estoque\nCamisa,3", &[], &categorias), Err(CustomErrors::InvalidCsv)));
+
+        let csv = "nome,estoque,valor,restoque,data,categoria,codigo_barras\nCamisa,3,49.90,1,10/08/2023,roupa,4006381333931\nBola,1,5,1,10/08/2023,geral,4006381333931\nLapis,1,2,1,10/08/2023,geral,4006381333932\n";
+        let (products, errors) = parse_products(csv, &[], &categorias).unwrap();
+
+        assert_eq!(products.len(), 1);
+        assert_eq!(errors.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_parse_sales() {
+        let products = vec![
+            Produto::new("Camisa".to_string(), 1, 10, Dinheiro::from_centavos(4990), 2, chrono::NaiveDate::default(), categories::ROUPA),
+            Produto::new("Arroz".to_string(), 2, 10, Dinheiro::from_centavos(2000), 2, chrono::NaiveDate::default(), categories::ALIMENTO)
+        ];
+
+        let csv = "\
+codigo,data,vendedor,pagamento,situacao,produto,quantidade,valor_unitario,devolvidos
+1,10/08/2023,Ana,pix,,1,2,45,
+1,10/08/2023,Ana,pix,,2,1,,
+2,11/08/2023,Bia,credito,,9,1,,
+3,11/08/2023,Bia,credito,,2,1,,
+3,11/08/2023,Bia,debito,,2,1,,
+4,12/08/2023,Ana,dinheiro,,2,3,,
+4,12/08/2023,Ana,dinheiro,,2,1,,
+5,12/08/2023,Ana,pix,,1,1,40,
+5,12/08/2023,Ana,pix,,1,1,45,
+6,13/08/2023,Bia,pix,Cancelada,2,2,,2
+7,13/08/2023,Bia,pix,Devolvida parcialmente,2,2,,1
+8,13/08/2023,Bia,pix,Concluída,2,2,,1
+A,13/08/2023,Bia,pix,,2,1,,
+";
+
+        let (sales, errors) = parse_sales(csv, &products, &[]).unwrap();
+
+        assert_eq!(sales.iter().map(|(number, sale)| (*number, sale.codigo)).collect::<Vec<_>>(), vec![(2, 1), (5, 3), (7, 4), (9, 5), (11, 6), (12, 7)]);
+        assert_eq!(sales[0].1.produtos.len(), 2);
+        assert_eq!(sales[0].1.valor, Dinheiro::from_centavos(11000));
+        assert_eq!(errors.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![4, 6, 10, 13, 14]);
+
+        // As linhas repetidas de um produto somam as quantidades.
+        assert_eq!(sales[2].1.produtos.len(), 1);
+        assert_eq!(sales[2].1.produtos[0].quantidade, 4);
+        assert_eq!(sales[2].1.valor, Dinheiro::from_centavos(8000));
+
+        assert_eq!(sales[4].1.situacao, Situacao::Cancelada);
+        assert_eq!(sales[4].1.valor_liquido().unwrap(), Dinheiro::ZERO);
+        assert_eq!(sales[5].1.situacao, Situacao::DevolucaoParcial);
+        assert_eq!(sales[5].1.returned(2), 1);
+        assert_eq!(sales[5].1.valor_liquido().unwrap(), Dinheiro::from_centavos(2000));
+
+        let sales: Vec<Venda> = sales.into_iter().map(|(_, sale)| sale).collect();
+
+        let (parsed, errors) = parse_sales(&export_sales(&sales), &products, &[]).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(parsed.iter().map(|(_, sale)| (sale.codigo, sale.situacao)).collect::<Vec<_>>(), sales.iter().map(|sale| (sale.codigo, sale.situacao)).collect::<Vec<_>>());
+        assert_eq!(parsed[0].1.valor, Dinheiro::from_centavos(11000));
+
+        // Importar de novo o arquivo exportado recusa as vendas que já estão gravadas.
+        let (parsed, errors) = parse_sales(&export_sales(&sales), &products, &sales[..2]).unwrap();
+
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(errors.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(errors[0].1, CustomErrors::DuplicateSale.to_string());
+    }
+
+    #[test]
+    fn test_sales_discounts_round_trip() {
+        let products = vec![Produto::new("Camisa".to_string(), 1, 10, Dinheiro::from_centavos(5000), 2, chrono::NaiveDate::default(), categories::ROUPA)];
+
+        let csv = "\
+codigo,data,vendedor,pagamento,produto,quantidade,desconto,desconto_venda
+1,10/08/2023,Ana,pix,1,2,\"10,00\",5
+1,10/08/2023,Ana,pix,1,1,,5
+2,11/08/2023,Bia,pix,1,1,60,
+3,11/08/2023,Bia,pix,1,1,,80
+";
+
+        let (sales, errors) = parse_sales(csv, &products, &[]).unwrap();
+
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].1.produtos[0].desconto, Dinheiro::from_centavos(1000));
+        assert_eq!(sales[0].1.desconto, Dinheiro::from_centavos(500));
+        assert_eq!(sales[0].1.valor, Dinheiro::from_centavos(13500));
+        assert_eq!(errors.iter().map(|(number, _)| *number).collect::<Vec<_>>(), vec![4, 5]);
+
+        let exported = export_sales(&[sales[0].1.clone()]);
+        assert!(exported.lines().nth(1).unwrap().ends_with(",\"10,00\",\"5,00\",0"));
+
+        let (sales, errors) = parse_sales(&exported, &products, &[]).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(sales[0].1.valor, Dinheiro::from_centavos(13500));
+    }
+}
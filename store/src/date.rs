@@ -1,7 +1,7 @@
 use chrono::NaiveDate;
 use serde::{self, Deserialize, Serializer, Deserializer};
 
-const FORMAT: &'static str = "%d/%m/%Y";
+const FORMAT: &str = "%d/%m/%Y";
 
 pub fn serialize<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -22,6 +22,31 @@ where
     Ok(dt)
 }
 
+// Datas opcionais, como o início e o fim de uma promoção.
+pub mod optional {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Serializer, Deserializer};
+
+    pub fn serialize<S>(date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer
+    {
+        match date {
+            Some(date) => serializer.serialize_some(&date.format(super::FORMAT).to_string()),
+            None => serializer.serialize_none()
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| NaiveDate::parse_from_str(&s, super::FORMAT).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde::{Serialize, Deserialize};
@@ -46,6 +71,24 @@ mod tests {
         assert_eq!(date_struct.date, deserialized_date.date);
     }
 
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Periodo {
+        #[serde(with = "super::optional")]
+        inicio: Option<NaiveDate>,
+        #[serde(with = "super::optional")]
+        fim: Option<NaiveDate>
+    }
+
+    #[test]
+    fn test_serialize_deserialize_optional_date() {
+        let periodo = Periodo { inicio: NaiveDate::from_ymd_opt(2024, 5, 20), fim: None };
+
+        let deserialized: Periodo = bincode::deserialize(&bincode::serialize(&periodo).unwrap()).unwrap();
+        assert_eq!(deserialized, periodo);
+
+        assert_eq!(serde_json::to_string(&periodo).unwrap(), r#"{"inicio":"20/05/2024","fim":null}"#);
+    }
+
     #[test]
     fn test_serialize_deserialize_error() {
         let invalid_bytes = vec![0x00, 0xFF, 0x00];
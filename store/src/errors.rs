@@ -2,43 +2,241 @@ use std::fmt;
 
 #[derive(Debug)]
 pub enum CustomErrors {
+    CategoryInUse,
+    CategoryNotFound,
+    DuplicateBarcode,
+    DuplicateCategory,
+    DuplicateSale,
+    EmptyName,
+    EmptySale,
+    FormatMismatch,
+    IncompleteOrder,
+    InvalidAdjustment,
+    InvalidBarcode,
+    InvalidCommand,
+    InvalidConfig,
+    InvalidCsv,
+    InvalidDiscount,
+    InvalidImport,
+    InvalidParent,
+    InvalidPeriod,
+    InvalidPromotion,
+    InvalidRange,
+    InvalidReturn,
+    InvalidTax,
+    InvalidValue,
     LowStock,
-    NameTooLong,
+    NewerFormat,
     NoCategory,
     OperationCanceled,
+    OrderNotFound,
+    OrderReceived,
     ProductNotFound,
+    PromotionNotFound,
+    SaleCancelled,
     SaleNotFound,
-    TooManyArguments
+    SessionAlreadyOpen,
+    SessionNotFound,
+    StoreAlreadyExists,
+    StoreNotFound,
+    TooManyArguments,
+    ValueOverflow
 }
 
 impl fmt::Display for CustomErrors {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            CustomErrors::CategoryInUse => write!(format, "A categoria ainda possui produtos, subcategorias ou promoções. Mova-os ou junte a categoria a outra antes de removê-la."),
+            CustomErrors::CategoryNotFound => write!(format, "A categoria não foi encontrada."),
+            CustomErrors::DuplicateBarcode => write!(format, "Já existe um produto com este código de barras."),
+            CustomErrors::DuplicateCategory => write!(format, "Já existe uma categoria com este nome."),
+            CustomErrors::DuplicateSale => write!(format, "Já existe uma venda gravada com este código. Use outro código para importar uma venda nova."),
+            CustomErrors::EmptyName => write!(format, "O nome do produto não pode ser vazio."),
+            CustomErrors::EmptySale => write!(format, "A venda precisa ter ao menos um produto. Para desfazê-la, cancele a venda."),
+            CustomErrors::FormatMismatch => write!(format, "Os arquivos não estão no formato esperado para esta operação."),
+            CustomErrors::IncompleteOrder => write!(format, "Informe o fornecedor e o custo unitário de todos os produtos do pedido."),
+            CustomErrors::InvalidAdjustment => write!(format, "A quantidade do movimento de estoque é inválida."),
+            CustomErrors::InvalidBarcode => write!(format, "O código de barras informado não é um EAN-13 válido."),
+            CustomErrors::InvalidCommand => write!(format, "Comando inválido. Use 'store help' para ver os comandos disponíveis."),
+            CustomErrors::InvalidConfig => write!(format, "O arquivo de configuração contém uma linha inválida."),
+            CustomErrors::InvalidCsv => write!(format, "O arquivo CSV não está no formato esperado."),
+            CustomErrors::InvalidDiscount => write!(format, "O desconto deve ser uma porcentagem de 1% a 100% ou um valor que não ultrapasse o total."),
+            CustomErrors::InvalidImport => write!(format, "O arquivo contém linhas inválidas. Nenhum registro foi importado."),
+            CustomErrors::InvalidParent => write!(format, "Uma categoria não pode ficar abaixo dela mesma ou de uma de suas subcategorias."),
+            CustomErrors::InvalidPeriod => write!(format, "A data inicial do período é posterior à data final."),
+            CustomErrors::InvalidPromotion => write!(format, "A promoção deve levar mais unidades do que cobra, ou dar de 1% a 100% de desconto."),
+            CustomErrors::InvalidRange => write!(format, "O valor mínimo da busca é maior que o valor máximo."),
+            CustomErrors::InvalidReturn => write!(format, "A quantidade devolvida não corresponde aos produtos vendidos."),
+            CustomErrors::InvalidTax => write!(format, "Os dados fiscais são inválidos: o NCM tem 8 dígitos, o CFOP tem 4 dígitos começando por 5, 6 ou 7 e as alíquotas vão de 0% a 100%."),
+            CustomErrors::InvalidValue => write!(format, "O valor monetário informado é inválido."),
             CustomErrors::LowStock => write!(format, "O estoque do produto não é suficiente para esta compra."),
-            CustomErrors::NameTooLong => write!(format, "O nome do produto deve ter, no máximo, 40 caracteres."),
+            CustomErrors::NewerFormat => write!(format, "Os arquivos de dados foram gravados por uma versão mais recente do programa. Atualize o programa para usá-los."),
             CustomErrors::NoCategory => write!(format, "A categoria especificada não existe."),
             CustomErrors::OperationCanceled => write!(format, "Operação cancelada."),
+            CustomErrors::OrderNotFound => write!(format, "O pedido de compra não foi encontrado."),
+            CustomErrors::OrderReceived => write!(format, "O pedido de compra já foi recebido."),
             CustomErrors::ProductNotFound => write!(format, "O produto não foi encontrado."),
+            CustomErrors::PromotionNotFound => write!(format, "A promoção não foi encontrada."),
+            CustomErrors::SaleCancelled => write!(format, "A venda já foi cancelada."),
             CustomErrors::SaleNotFound => write!(format, "Nenhuma venda encontrada."),
-            CustomErrors::TooManyArguments => write!(format, "Foram fornecidos mais argumentos que o máximo.")
+            CustomErrors::SessionAlreadyOpen => write!(format, "Já existe um caixa aberto. Feche-o antes de abrir outro."),
+            CustomErrors::SessionNotFound => write!(format, "O caixa não foi encontrado."),
+            CustomErrors::StoreAlreadyExists => write!(format, "Já existe um estoque no diretório de dados informado."),
+            CustomErrors::StoreNotFound => write!(format, "Nenhum estoque encontrado no diretório de dados. Use 'store init' para criar um novo."),
+            CustomErrors::TooManyArguments => write!(format, "Foram fornecidos mais argumentos que o máximo."),
+            CustomErrors::ValueOverflow => write!(format, "O valor resultante excede o limite suportado.")
         }
     }
 }
 
 impl std::error::Error for CustomErrors {}
 
+impl CustomErrors {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CustomErrors::InvalidCommand | CustomErrors::InvalidConfig => 2,
+            CustomErrors::EmptyName | CustomErrors::EmptySale | CustomErrors::IncompleteOrder | CustomErrors::InvalidAdjustment | CustomErrors::InvalidBarcode | CustomErrors::InvalidCsv | CustomErrors::InvalidDiscount | CustomErrors::InvalidImport | CustomErrors::InvalidParent | CustomErrors::InvalidPeriod | CustomErrors::InvalidPromotion | CustomErrors::InvalidRange | CustomErrors::InvalidReturn | CustomErrors::InvalidTax | CustomErrors::InvalidValue | CustomErrors::NoCategory | CustomErrors::TooManyArguments => 3,
+            CustomErrors::CategoryNotFound | CustomErrors::OrderNotFound | CustomErrors::ProductNotFound | CustomErrors::PromotionNotFound | CustomErrors::SaleNotFound | CustomErrors::SessionNotFound => 4,
+            CustomErrors::CategoryInUse | CustomErrors::DuplicateBarcode | CustomErrors::DuplicateCategory | CustomErrors::DuplicateSale | CustomErrors::LowStock | CustomErrors::OrderReceived | CustomErrors::SaleCancelled | CustomErrors::SessionAlreadyOpen => 5,
+            CustomErrors::FormatMismatch | CustomErrors::NewerFormat | CustomErrors::ValueOverflow => 6,
+            CustomErrors::StoreAlreadyExists | CustomErrors::StoreNotFound => 7,
+            CustomErrors::OperationCanceled => 130
+        }
+    }
+}
+
+// Erro devolvido pela API de biblioteca. As falhas de leitura e escrita e os erros de
+// operação ficam separados para que quem usa a biblioteca possa tratá-los pelo tipo.
+#[derive(Debug)]
+pub enum StoreError {
+    Operation(CustomErrors),
+    Import(Vec<crate::csv::ErroLinha>),
+    Io(std::io::Error),
+    Other(Box<dyn std::error::Error>)
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Operation(error) => write!(format, "{error}"),
+            StoreError::Import(lines) => {
+                write!(format, "{}", CustomErrors::InvalidImport)?;
+
+                for (line, error) in lines {
+                    write!(format, "\nLinha {line}: {error}")?;
+                }
+
+                Ok(())
+            },
+            StoreError::Io(error) => write!(format, "{error}"),
+            StoreError::Other(error) => write!(format, "{error}")
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<CustomErrors> for StoreError {
+    fn from(error: CustomErrors) -> Self {
+        StoreError::Operation(error)
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for StoreError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        let error = match error.downcast::<CustomErrors>() {
+            Ok(custom) => return StoreError::Operation(*custom),
+            Err(error) => error
+        };
+
+        let error = match error.downcast::<StoreError>() {
+            Ok(store) => return *store,
+            Err(error) => error
+        };
+
+        match error.downcast::<std::io::Error>() {
+            Ok(io) => StoreError::Io(*io),
+            Err(error) => StoreError::Other(error)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::CustomErrors;
+    use super::{CustomErrors, StoreError};
     
     #[test]
     fn test_custom_errors_display() {
+        assert_eq!(format!("{}", CustomErrors::CategoryInUse), "A categoria ainda possui produtos, subcategorias ou promoções. Mova-os ou junte a categoria a outra antes de removê-la.");
+        assert_eq!(format!("{}", CustomErrors::CategoryNotFound), "A categoria não foi encontrada.");
+        assert_eq!(format!("{}", CustomErrors::DuplicateBarcode), "Já existe um produto com este código de barras.");
+        assert_eq!(format!("{}", CustomErrors::DuplicateCategory), "Já existe uma categoria com este nome.");
+        assert_eq!(format!("{}", CustomErrors::EmptyName), "O nome do produto não pode ser vazio.");
+        assert_eq!(format!("{}", CustomErrors::EmptySale), "A venda precisa ter ao menos um produto. Para desfazê-la, cancele a venda.");
+        assert_eq!(format!("{}", CustomErrors::FormatMismatch), "Os arquivos não estão no formato esperado para esta operação.");
+        assert_eq!(format!("{}", CustomErrors::IncompleteOrder), "Informe o fornecedor e o custo unitário de todos os produtos do pedido.");
+        assert_eq!(format!("{}", CustomErrors::InvalidAdjustment), "A quantidade do movimento de estoque é inválida.");
+        assert_eq!(format!("{}", CustomErrors::InvalidBarcode), "O código de barras informado não é um EAN-13 válido.");
+        assert_eq!(format!("{}", CustomErrors::InvalidCommand), "Comando inválido. Use 'store help' para ver os comandos disponíveis.");
+        assert_eq!(format!("{}", CustomErrors::InvalidConfig), "O arquivo de configuração contém uma linha inválida.");
+        assert_eq!(format!("{}", CustomErrors::InvalidCsv), "O arquivo CSV não está no formato esperado.");
+        assert_eq!(format!("{}", CustomErrors::InvalidDiscount), "O desconto deve ser uma porcentagem de 1% a 100% ou um valor que não ultrapasse o total.");
+        assert_eq!(format!("{}", CustomErrors::InvalidImport), "O arquivo contém linhas inválidas. Nenhum registro foi importado.");
+        assert_eq!(format!("{}", CustomErrors::InvalidParent), "Uma categoria não pode ficar abaixo dela mesma ou de uma de suas subcategorias.");
+        assert_eq!(format!("{}", CustomErrors::InvalidPeriod), "A data inicial do período é posterior à data final.");
+        assert_eq!(format!("{}", CustomErrors::InvalidPromotion), "A promoção deve levar mais unidades do que cobra, ou dar de 1% a 100% de desconto.");
+        assert_eq!(format!("{}", CustomErrors::InvalidRange), "O valor mínimo da busca é maior que o valor máximo.");
+        assert_eq!(format!("{}", CustomErrors::InvalidReturn), "A quantidade devolvida não corresponde aos produtos vendidos.");
+        assert_eq!(format!("{}", CustomErrors::InvalidTax), "Os dados fiscais são inválidos: o NCM tem 8 dígitos, o CFOP tem 4 dígitos começando por 5, 6 ou 7 e as alíquotas vão de 0% a 100%.");
+        assert_eq!(format!("{}", CustomErrors::InvalidValue), "O valor monetário informado é inválido.");
         assert_eq!(format!("{}", CustomErrors::LowStock), "O estoque do produto não é suficiente para esta compra.");
-        assert_eq!(format!("{}", CustomErrors::NameTooLong), "O nome do produto deve ter, no máximo, 40 caracteres.");
+        assert_eq!(format!("{}", CustomErrors::NewerFormat), "Os arquivos de dados foram gravados por uma versão mais recente do programa. Atualize o programa para usá-los.");
         assert_eq!(format!("{}", CustomErrors::NoCategory), "A categoria especificada não existe.");
         assert_eq!(format!("{}", CustomErrors::OperationCanceled), "Operação cancelada.");
+        assert_eq!(format!("{}", CustomErrors::OrderNotFound), "O pedido de compra não foi encontrado.");
+        assert_eq!(format!("{}", CustomErrors::OrderReceived), "O pedido de compra já foi recebido.");
         assert_eq!(format!("{}", CustomErrors::ProductNotFound), "O produto não foi encontrado.");
+        assert_eq!(format!("{}", CustomErrors::PromotionNotFound), "A promoção não foi encontrada.");
+        assert_eq!(format!("{}", CustomErrors::SaleCancelled), "A venda já foi cancelada.");
         assert_eq!(format!("{}", CustomErrors::SaleNotFound), "Nenhuma venda encontrada.");
+        assert_eq!(format!("{}", CustomErrors::SessionAlreadyOpen), "Já existe um caixa aberto. Feche-o antes de abrir outro.");
+        assert_eq!(format!("{}", CustomErrors::SessionNotFound), "O caixa não foi encontrado.");
+        assert_eq!(format!("{}", CustomErrors::StoreAlreadyExists), "Já existe um estoque no diretório de dados informado.");
+        assert_eq!(format!("{}", CustomErrors::StoreNotFound), "Nenhum estoque encontrado no diretório de dados. Use 'store init' para criar um novo.");
         assert_eq!(format!("{}", CustomErrors::TooManyArguments), "Foram fornecidos mais argumentos que o máximo.");
+        assert_eq!(format!("{}", CustomErrors::ValueOverflow), "O valor resultante excede o limite suportado.");
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(CustomErrors::InvalidCommand.exit_code(), 2);
+        assert_eq!(CustomErrors::NoCategory.exit_code(), 3);
+        assert_eq!(CustomErrors::ProductNotFound.exit_code(), 4);
+        assert_eq!(CustomErrors::CategoryNotFound.exit_code(), 4);
+        assert_eq!(CustomErrors::PromotionNotFound.exit_code(), 4);
+        assert_eq!(CustomErrors::InvalidDiscount.exit_code(), 3);
+        assert_eq!(CustomErrors::InvalidTax.exit_code(), 3);
+        assert_eq!(CustomErrors::CategoryInUse.exit_code(), 5);
+        assert_eq!(CustomErrors::LowStock.exit_code(), 5);
+        assert_eq!(CustomErrors::SessionAlreadyOpen.exit_code(), 5);
+        assert_eq!(CustomErrors::DuplicateBarcode.exit_code(), 5);
+        assert_eq!(CustomErrors::FormatMismatch.exit_code(), 6);
+        assert_eq!(CustomErrors::NewerFormat.exit_code(), 6);
+        assert_eq!(CustomErrors::StoreNotFound.exit_code(), 7);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_store_error_from_boxed() {
+        let error = StoreError::from(Box::new(CustomErrors::LowStock) as Box<dyn std::error::Error>);
+        assert!(matches!(error, StoreError::Operation(CustomErrors::LowStock)));
+
+        let error = StoreError::from(Box::new(std::io::Error::other("disco cheio")) as Box<dyn std::error::Error>);
+        assert!(matches!(error, StoreError::Io(_)));
+
+        let error = StoreError::from(Box::from("data inválida"));
+        assert!(matches!(error, StoreError::Other(_)));
+
+        let error = StoreError::Import(vec![(2, "O produto não foi encontrado.".to_string())]);
+        assert_eq!(error.to_string(), "O arquivo contém linhas inválidas. Nenhum registro foi importado.\nLinha 2: O produto não foi encontrado.");
+    }
+}
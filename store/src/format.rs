@@ -0,0 +1,95 @@
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use serde::{Serialize, Deserialize};
+
+use super::errors::CustomErrors;
+
+pub const HEADER_LENGTH: u64 = 24;
+
+pub const PRODUCTS_MAGIC: [u8; 4] = *b"STPR";
+pub const SALES_MAGIC: [u8; 4] = *b"STVD";
+pub const SESSIONS_MAGIC: [u8; 4] = *b"STCX";
+pub const MOVEMENTS_MAGIC: [u8; 4] = *b"STMV";
+pub const ORDERS_MAGIC: [u8; 4] = *b"STPC";
+pub const CATEGORIES_MAGIC: [u8; 4] = *b"STCT";
+pub const PROMOTIONS_MAGIC: [u8; 4] = *b"STPM";
+
+pub const PRODUCTS_VERSION: u32 = 8;
+pub const SALES_VERSION: u32 = 8;
+pub const SESSIONS_VERSION: u32 = 1;
+pub const MOVEMENTS_VERSION: u32 = 2;
+pub const ORDERS_VERSION: u32 = 1;
+pub const CATEGORIES_VERSION: u32 = 1;
+pub const PROMOTIONS_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Cabecalho {
+    pub magica: [u8; 4],
+    pub versao: u32,
+    pub quantidade: u64,
+    pub ultimo_id: u64
+}
+
+impl Cabecalho {
+    pub fn new(magica: [u8; 4], versao: u32) -> Self {
+        Cabecalho {
+            magica,
+            versao,
+            quantidade: 0,
+            ultimo_id: 0
+        }
+    }
+}
+
+pub fn parse_header(bytes: &[u8], magica: [u8; 4]) -> Result<Cabecalho, Box<dyn Error>> {
+    let header: Cabecalho = bytes.get(..HEADER_LENGTH as usize)
+        .map(bincode::deserialize)
+        .ok_or(CustomErrors::FormatMismatch)??;
+
+    match header.magica == magica {
+        true => Ok(header),
+        false => Err(Box::new(CustomErrors::FormatMismatch))
+    }
+}
+
+pub fn read_header(file: &mut File, magica: [u8; 4]) -> Result<Cabecalho, Box<dyn Error>> {
+    let mut buf = vec![0; HEADER_LENGTH as usize];
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut buf)?;
+
+    parse_header(&buf, magica)
+}
+
+pub fn write_header(file: &mut File, header: &Cabecalho) -> Result<(), Box<dyn Error>> {
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&bincode::serialize(header)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::{get_test_file, test_path};
+
+    #[test]
+    fn test_header() {
+        let path = &test_path("test_header.bin");
+        let mut file = get_test_file(path);
+
+        let mut header = Cabecalho::new(SALES_MAGIC, SALES_VERSION);
+        header.quantidade = 2;
+        header.ultimo_id = 7;
+
+        write_header(&mut file, &header).unwrap();
+
+        assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), HEADER_LENGTH);
+        assert_eq!(read_header(&mut file, SALES_MAGIC).unwrap(), header);
+        assert!(read_header(&mut file, PRODUCTS_MAGIC).is_err());
+
+        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+    }
+}
@@ -0,0 +1,583 @@
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use serde::{Serialize, Deserialize};
+
+use super::{format::{self, Cabecalho, HEADER_LENGTH}, products::{self, ArquivoProdutos}, repository::{SaleTransaction, StockTransaction}, sales::{self, ArquivoVendas}, Movimento, Produto, Venda};
+
+#[derive(Serialize, Deserialize)]
+pub struct Transacao {
+    tamanho_produtos: u64,
+    // Sem venda na transação, o arquivo de vendas não é alterado.
+    tamanho_vendas: Option<u64>,
+    tamanho_movimentos: u64,
+    produtos: Vec<(u64, Vec<u8>)>,
+    vendas: Vec<(u64, Vec<u8>)>,
+    movimentos: Vec<(u64, Vec<u8>)>,
+    // Só as alterações do catálogo guardam as categorias e as promoções, que são arquivos pequenos, inteiros.
+    categorias: Option<Vec<u8>>,
+    promocoes: Option<Vec<u8>>
+}
+
+impl Transacao {
+    pub fn new(products_file: &mut File, sales_file: &mut File, movements_file: &mut File) -> Result<Self, Box<dyn Error>> {
+        Ok(Transacao {
+            tamanho_vendas: Some(sales_file.seek(SeekFrom::End(0))?),
+            ..Transacao::stock(products_file, movements_file)?
+        })
+    }
+
+    pub fn stock(products_file: &mut File, movements_file: &mut File) -> Result<Self, Box<dyn Error>> {
+        Ok(Transacao {
+            tamanho_produtos: products_file.seek(SeekFrom::End(0))?,
+            tamanho_vendas: None,
+            tamanho_movimentos: movements_file.seek(SeekFrom::End(0))?,
+            produtos: Vec::new(),
+            vendas: Vec::new(),
+            movimentos: Vec::new(),
+            categorias: None,
+            promocoes: None
+        })
+    }
+
+    pub fn save_product(&mut self, file: &mut File, position: u64, length: u64) -> Result<(), Box<dyn Error>> {
+        let bytes = read_original(file, self.tamanho_produtos, position, length)?;
+        self.produtos.push((position, bytes));
+
+        Ok(())
+    }
+
+    pub fn save_sale(&mut self, file: &mut File, position: u64, length: u64) -> Result<(), Box<dyn Error>> {
+        let bytes = read_original(file, self.tamanho_vendas.unwrap_or(0), position, length)?;
+        self.vendas.push((position, bytes));
+
+        Ok(())
+    }
+
+    // Os movimentos só são acrescentados, então basta guardar o cabeçalho.
+    pub fn save_movements(&mut self, file: &mut File) -> Result<(), Box<dyn Error>> {
+        let bytes = read_original(file, self.tamanho_movimentos, 0, HEADER_LENGTH)?;
+        self.movimentos.push((0, bytes));
+
+        Ok(())
+    }
+
+    pub fn save_catalog(&mut self, categories_file: &mut File, promotions_file: &mut File) -> Result<(), Box<dyn Error>> {
+        self.categorias = Some(read_whole(categories_file)?);
+        self.promocoes = Some(read_whole(promotions_file)?);
+
+        Ok(())
+    }
+}
+
+fn read_whole(file: &mut File) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buf = Vec::new();
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn read_original(file: &mut File, size: u64, position: u64, length: u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buf = vec![0; length.min(size.saturating_sub(position)) as usize];
+
+    file.seek(SeekFrom::Start(position))?;
+    file.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn restore(file: &mut File, size: u64, originals: &[(u64, Vec<u8>)]) -> Result<(), Box<dyn Error>> {
+    for (position, bytes) in originals.iter() {
+        file.seek(SeekFrom::Start(*position))?;
+        file.write_all(bytes)?;
+    }
+
+    file.set_len(size)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+pub fn begin<T: Serialize>(journal: &mut File, transaction: &T) -> Result<(), Box<dyn Error>> {
+    let serialized = bincode::serialize(transaction)?;
+
+    journal.set_len(0)?;
+    journal.seek(SeekFrom::Start(0))?;
+    journal.write_all(&serialized)?;
+    journal.sync_all()?;
+
+    Ok(())
+}
+
+pub fn commit(journal: &mut File) -> Result<(), Box<dyn Error>> {
+    journal.set_len(0)?;
+    journal.sync_all()?;
+
+    Ok(())
+}
+
+pub fn rollback(journal: &mut File, products_file: &mut ArquivoProdutos, sales_file: &mut File) -> Result<bool, Box<dyn Error>> {
+    undo(journal, products_file, Some(sales_file))
+}
+
+// Uma transação só de estoque desfaz-se sem o arquivo de vendas.
+fn undo(journal: &mut File, products_file: &mut ArquivoProdutos, sales_file: Option<&mut File>) -> Result<bool, Box<dyn Error>> {
+    let mut buf = Vec::new();
+
+    journal.seek(SeekFrom::Start(0))?;
+    journal.read_to_end(&mut buf)?;
+
+    if buf.is_empty() {
+        return Ok(false);
+    }
+
+    // Um registro incompleto significa que a transação não chegou a alterar os arquivos de dados.
+    let transaction: Transacao = match bincode::deserialize(&buf) {
+        Ok(transaction) => transaction,
+        Err(_) => {
+            commit(journal)?;
+            return Ok(false);
+        }
+    };
+
+    restore(&mut products_file.dados, transaction.tamanho_produtos, &transaction.produtos)?;
+    restore(&mut products_file.movimentos.dados, transaction.tamanho_movimentos, &transaction.movimentos)?;
+
+    if let Some(original) = transaction.categorias {
+        restore(&mut products_file.categorias.dados, original.len() as u64, &[(0, original)])?;
+    }
+
+    if let Some(original) = transaction.promocoes {
+        restore(&mut products_file.promocoes.dados, original.len() as u64, &[(0, original)])?;
+    }
+
+    if let (Some(size), Some(sales_file)) = (transaction.tamanho_vendas, sales_file) {
+        restore(sales_file, size, &transaction.vendas)?;
+    }
+
+    commit(journal)?;
+
+    Ok(true)
+}
+
+// Caixas e pedidos continuam em arquivos mesmo com o SQLite, então cada um tem um diário
+// próprio que guarda o arquivo inteiro, que é pequeno, antes de cada alteração.
+pub fn rewrite_file<T, F: FnOnce(&mut File) -> Result<T, Box<dyn Error>>>(journal: &mut File, file: &mut File, write: F) -> Result<T, Box<dyn Error>> {
+    let original = read_whole(file)?;
+
+    begin(journal, &original)?;
+
+    let result = write(file).and_then(|result| {
+        file.sync_all()?;
+        Ok(result)
+    });
+
+    match result {
+        Ok(result) => {
+            commit(journal)?;
+            Ok(result)
+        },
+        Err(error) => {
+            restore_file(journal, file)?;
+            Err(error)
+        }
+    }
+}
+
+pub fn restore_file(journal: &mut File, file: &mut File) -> Result<bool, Box<dyn Error>> {
+    let mut buf = Vec::new();
+
+    journal.seek(SeekFrom::Start(0))?;
+    journal.read_to_end(&mut buf)?;
+
+    if buf.is_empty() {
+        return Ok(false);
+    }
+
+    let original: Vec<u8> = match bincode::deserialize(&buf) {
+        Ok(original) => original,
+        Err(_) => {
+            commit(journal)?;
+            return Ok(false);
+        }
+    };
+
+    restore(file, original.len() as u64, &[(0, original)])?;
+    commit(journal)?;
+
+    Ok(true)
+}
+
+// Com arquivos, o conteúdo original dos trechos alterados vai para o diário antes da gravação.
+impl StockTransaction<ArquivoProdutos> for File {
+    fn insert_products<F: FnMut(&Produto) -> Vec<Movimento>>(&mut self, products_file: &mut ArquivoProdutos, new_products: Vec<Produto>, mut movements: F) -> Result<Vec<u64>, Box<dyn Error>> {
+        let transaction = stock_transaction(products_file, &[])?;
+
+        write_stock(self, products_file, &transaction, |products_file| {
+            let mut ids = Vec::with_capacity(new_products.len());
+            let mut new_movements = Vec::new();
+
+            for mut product in new_products {
+                product.id = products_file.insert(product.clone())?;
+                new_movements.extend(movements(&product));
+                ids.push(product.id);
+            }
+
+            products_file.movimentos.append(new_movements)?;
+
+            Ok(ids)
+        })
+    }
+
+    fn update_products(&mut self, products_file: &mut ArquivoProdutos, updated_products: &[Produto], movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        let ids: Vec<u64> = updated_products.iter().map(|product| product.id).collect();
+        let transaction = stock_transaction(products_file, &ids)?;
+
+        write_stock(self, products_file, &transaction, |products_file| {
+            products_file.movimentos.append(movements)?;
+
+            for product in updated_products.iter() {
+                products_file.update(product)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn remove_product(&mut self, products_file: &mut ArquivoProdutos, id: u64, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        let transaction = stock_transaction(products_file, &[id])?;
+
+        write_stock(self, products_file, &transaction, |products_file| {
+            products_file.movimentos.append(movements)?;
+            products_file.remove(id)
+        })
+    }
+
+    fn update_catalog<R, F: FnOnce(&mut ArquivoProdutos) -> Result<R, Box<dyn Error>>>(&mut self, products_file: &mut ArquivoProdutos, ids: &[u64], write: F) -> Result<R, Box<dyn Error>> {
+        let mut transaction = stock_transaction(products_file, ids)?;
+        transaction.save_catalog(&mut products_file.categorias.dados, &mut products_file.promocoes.dados)?;
+
+        write_stock(self, products_file, &transaction, |products_file| {
+            let result = write(products_file)?;
+
+            products_file.categorias.dados.sync_all()?;
+            products_file.promocoes.dados.sync_all()?;
+
+            Ok(result)
+        })
+    }
+}
+
+// Um produto editado pode mudar de tamanho e ser movido para o fim, deslocando os seguintes,
+// então o diário guarda o arquivo inteiro a partir do primeiro produto alterado.
+fn stock_transaction(products_file: &mut ArquivoProdutos, ids: &[u64]) -> Result<Transacao, Box<dyn Error>> {
+    let size = products_file.dados.seek(SeekFrom::End(0))?;
+    let mut first = size;
+
+    for &id in ids.iter() {
+        let (_, position) = products_file.search(id)?;
+        first = first.min(position);
+    }
+
+    let mut transaction = Transacao::stock(&mut products_file.dados, &mut products_file.movimentos.dados)?;
+
+    transaction.save_product(&mut products_file.dados, 0, HEADER_LENGTH)?;
+    transaction.save_product(&mut products_file.dados, first, size - first)?;
+    transaction.save_movements(&mut products_file.movimentos.dados)?;
+
+    Ok(transaction)
+}
+
+fn write_stock<T, F: FnOnce(&mut ArquivoProdutos) -> Result<T, Box<dyn Error>>>(journal: &mut File, products_file: &mut ArquivoProdutos, transaction: &Transacao, write: F) -> Result<T, Box<dyn Error>> {
+    begin(journal, transaction)?;
+
+    let result = write(products_file).and_then(|result| {
+        products_file.dados.sync_all()?;
+        products_file.indice.sync_all()?;
+
+        Ok(result)
+    });
+
+    match result {
+        Ok(result) => {
+            commit(journal)?;
+            Ok(result)
+        },
+        Err(error) => {
+            undo(journal, products_file, None)?;
+            products_file.rebuild_index()?;
+
+            Err(error)
+        }
+    }
+}
+
+impl SaleTransaction<ArquivoProdutos, ArquivoVendas> for File {
+    fn insert_sale<F: FnOnce(&Venda) -> Vec<Movimento>>(&mut self, products_file: &mut ArquivoProdutos, sales_file: &mut ArquivoVendas, updated_products: &[Produto], mut sale: Venda, movements: F) -> Result<Venda, Box<dyn Error>> {
+        let products = product_positions(products_file, updated_products)?;
+        let mut header = sales_file.header()?;
+
+        sale.codigo = header.ultimo_id + 1;
+        header.quantidade += 1;
+        header.ultimo_id = sale.codigo;
+
+        let sale_position = sales_file.dados.seek(SeekFrom::End(0))?;
+        let serialized_sale = sales::serialize_record(&sale)?;
+
+        let mut transaction = Transacao::new(&mut products_file.dados, &mut sales_file.dados, &mut products_file.movimentos.dados)?;
+
+        for (product, position) in products.iter() {
+            transaction.save_product(&mut products_file.dados, *position, products::serialize_record(product)?.len() as u64)?;
+        }
+
+        transaction.save_sale(&mut sales_file.dados, 0, HEADER_LENGTH)?;
+        transaction.save_sale(&mut sales_file.dados, sale_position, serialized_sale.len() as u64)?;
+        transaction.save_movements(&mut products_file.movimentos.dados)?;
+
+        sales_file.invalidate()?;
+        begin(self, &transaction)?;
+
+        let result = products_file.movimentos.append(movements(&sale))
+            .and_then(|()| write_sale_transaction(&mut products_file.dados, &mut sales_file.dados, &products, &header, sale_position, &serialized_sale));
+
+        match result {
+            Ok(()) => {
+                commit(self)?;
+                sales_file.add(&sale, sale_position)?;
+
+                Ok(sale)
+            },
+            Err(error) => {
+                rollback(self, products_file, &mut sales_file.dados)?;
+                sales_file.rebuild_index()?;
+
+                Err(error)
+            }
+        }
+    }
+
+    fn update_sale(&mut self, products_file: &mut ArquivoProdutos, sales_file: &mut ArquivoVendas, updated_products: &[Produto], sale: &Venda, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        let products = product_positions(products_file, updated_products)?;
+        let (_, sale_position) = sales_file.search(sale.codigo)?;
+        let sales_size = sales_file.dados.seek(SeekFrom::End(0))?;
+
+        let mut transaction = Transacao::new(&mut products_file.dados, &mut sales_file.dados, &mut products_file.movimentos.dados)?;
+
+        for (product, position) in products.iter() {
+            transaction.save_product(&mut products_file.dados, *position, products::serialize_record(product)?.len() as u64)?;
+        }
+
+        // O registro pode mudar de tamanho e ser movido para o fim, deslocando os seguintes.
+        transaction.save_sale(&mut sales_file.dados, sale_position, sales_size - sale_position)?;
+        transaction.save_movements(&mut products_file.movimentos.dados)?;
+
+        sales_file.invalidate()?;
+        begin(self, &transaction)?;
+
+        let result = products_file.movimentos.append(movements)
+            .and_then(|()| write_sale_changes(&mut products_file.dados, sales_file, &products, sale));
+
+        match result {
+            Ok(()) => commit(self),
+            Err(error) => {
+                rollback(self, products_file, &mut sales_file.dados)?;
+                sales_file.rebuild_index()?;
+
+                Err(error)
+            }
+        }
+    }
+
+    // As vendas são acrescentadas ao fim, então basta guardar o cabeçalho.
+    fn insert_sales(&mut self, products_file: &mut ArquivoProdutos, sales_file: &mut ArquivoVendas, new_sales: Vec<Venda>) -> Result<Vec<u64>, Box<dyn Error>> {
+        let mut transaction = Transacao::new(&mut products_file.dados, &mut sales_file.dados, &mut products_file.movimentos.dados)?;
+        transaction.save_sale(&mut sales_file.dados, 0, HEADER_LENGTH)?;
+
+        sales_file.invalidate()?;
+        begin(self, &transaction)?;
+
+        let result = new_sales.into_iter()
+            .map(|sale| sales_file.import(sale))
+            .collect::<Result<Vec<u64>, Box<dyn Error>>>()
+            .and_then(|codes| {
+                sales_file.dados.sync_all()?;
+                Ok(codes)
+            });
+
+        match result {
+            Ok(codes) => {
+                commit(self)?;
+                Ok(codes)
+            },
+            Err(error) => {
+                rollback(self, products_file, &mut sales_file.dados)?;
+                sales_file.rebuild_index()?;
+
+                Err(error)
+            }
+        }
+    }
+}
+
+// Só o estoque muda, então os registros dos produtos mantêm o tamanho e a posição.
+fn product_positions(products_file: &mut ArquivoProdutos, updated_products: &[Produto]) -> Result<Vec<(Produto, u64)>, Box<dyn Error>> {
+    let mut products = Vec::with_capacity(updated_products.len());
+
+    for product in updated_products.iter() {
+        let (_, position) = products_file.search(product.id)?;
+        products.push((product.clone(), position));
+    }
+
+    Ok(products)
+}
+
+pub(crate) fn write_sale_transaction(products_file: &mut File, sales_file: &mut File, products: &[(Produto, u64)], header: &Cabecalho, sale_position: u64, serialized_sale: &[u8]) -> Result<(), Box<dyn Error>> {
+    for (product, position) in products.iter() {
+        products_file.seek(SeekFrom::Start(*position))?;
+        products_file.write_all(&products::serialize_record(product)?)?;
+    }
+
+    products_file.sync_all()?;
+
+    sales_file.seek(SeekFrom::Start(sale_position))?;
+    sales_file.write_all(serialized_sale)?;
+    format::write_header(sales_file, header)?;
+    sales_file.sync_all()?;
+
+    Ok(())
+}
+
+fn write_sale_changes(products_file: &mut File, sales_file: &mut ArquivoVendas, products: &[(Produto, u64)], sale: &Venda) -> Result<(), Box<dyn Error>> {
+    for (product, position) in products.iter() {
+        products_file.seek(SeekFrom::Start(*position))?;
+        products_file.write_all(&products::serialize_record(product)?)?;
+    }
+
+    products_file.sync_all()?;
+
+    sales_file.rewrite(sale)?;
+    sales_file.dados.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::{categories::ArquivoCategorias, ledger::ArquivoMovimentos, promotions::ArquivoPromocoes, test_utils::{get_test_file, read_all, test_path}};
+
+    // Arquivos de produtos vazios, sem cabeçalho, para os testes gravarem os bytes diretamente.
+    fn raw_products(paths: &[PathBuf]) -> ArquivoProdutos {
+        ArquivoProdutos::new(get_test_file(&paths[0]), get_test_file(&paths[1]), ArquivoMovimentos::new(get_test_file(&paths[2])), ArquivoCategorias::new(get_test_file(&paths[3])), ArquivoPromocoes::new(get_test_file(&paths[4])))
+    }
+
+    fn journal_paths(name: &str) -> Vec<PathBuf> {
+        (1..=7).map(|i| test_path(&format!("test_journal_{name}_{i}.bin"))).collect()
+    }
+
+    #[test]
+    fn test_rollback_restores_files() {
+        let paths = journal_paths("rollback");
+
+        let mut products_file = raw_products(&paths);
+        let mut sales_file = get_test_file(&paths[5]);
+        let mut journal = get_test_file(&paths[6]);
+
+        products_file.dados.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        sales_file.write_all(&[7, 8, 9]).unwrap();
+        products_file.movimentos.dados.write_all(&[1; HEADER_LENGTH as usize]).unwrap();
+        products_file.categorias.dados.write_all(&[3, 3]).unwrap();
+
+        let mut transaction = Transacao::new(&mut products_file.dados, &mut sales_file, &mut products_file.movimentos.dados).unwrap();
+        transaction.save_product(&mut products_file.dados, 2, 2).unwrap();
+        transaction.save_sale(&mut sales_file, 1, 10).unwrap();
+        transaction.save_movements(&mut products_file.movimentos.dados).unwrap();
+        transaction.save_catalog(&mut products_file.categorias.dados, &mut products_file.promocoes.dados).unwrap();
+
+        begin(&mut journal, &transaction).unwrap();
+
+        products_file.dados.seek(SeekFrom::Start(2)).unwrap();
+        products_file.dados.write_all(&[0, 0]).unwrap();
+        sales_file.seek(SeekFrom::Start(1)).unwrap();
+        sales_file.write_all(&[0, 0, 0, 0, 0]).unwrap();
+        products_file.movimentos.dados.seek(SeekFrom::Start(0)).unwrap();
+        products_file.movimentos.dados.write_all(&[2; HEADER_LENGTH as usize + 4]).unwrap();
+        products_file.categorias.dados.set_len(1).unwrap();
+        products_file.promocoes.dados.write_all(&[4]).unwrap();
+
+        assert!(rollback(&mut journal, &mut products_file, &mut sales_file).unwrap());
+
+        assert_eq!(read_all(&mut products_file.dados), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(read_all(&mut sales_file), vec![7, 8, 9]);
+        assert_eq!(read_all(&mut products_file.movimentos.dados), vec![1; HEADER_LENGTH as usize]);
+        assert_eq!(read_all(&mut products_file.categorias.dados), vec![3, 3]);
+        assert!(read_all(&mut products_file.promocoes.dados).is_empty());
+        assert_eq!(journal.seek(SeekFrom::End(0)).unwrap(), 0);
+
+        assert!(!rollback(&mut journal, &mut products_file, &mut sales_file).unwrap());
+
+        for path in paths {
+            fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        }
+    }
+
+    #[test]
+    fn test_rollback_incomplete_journal() {
+        let paths = journal_paths("incomplete");
+
+        let mut products_file = raw_products(&paths);
+        let mut sales_file = get_test_file(&paths[5]);
+        let mut journal = get_test_file(&paths[6]);
+
+        products_file.dados.write_all(&[1, 2, 3]).unwrap();
+        journal.write_all(&[5, 0, 0]).unwrap();
+
+        assert!(!rollback(&mut journal, &mut products_file, &mut sales_file).unwrap());
+
+        assert_eq!(read_all(&mut products_file.dados), vec![1, 2, 3]);
+        assert_eq!(journal.seek(SeekFrom::End(0)).unwrap(), 0);
+
+        for path in paths {
+            fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        }
+    }
+
+    #[test]
+    fn test_rewrite_file() {
+        let paths = [test_path("test_journal_rewrite_1.bin"), test_path("test_journal_rewrite_2.bin")];
+
+        let mut file = get_test_file(&paths[0]);
+        let mut journal = get_test_file(&paths[1]);
+
+        file.write_all(&[1, 2, 3]).unwrap();
+
+        let error = rewrite_file(&mut journal, &mut file, |file| {
+            file.set_len(1)?;
+            file.write_all(&[9, 9, 9, 9])?;
+
+            Err::<(), _>(Box::from("falha"))
+        });
+
+        assert!(error.is_err());
+        assert_eq!(read_all(&mut file), vec![1, 2, 3]);
+        assert_eq!(journal.seek(SeekFrom::End(0)).unwrap(), 0);
+
+        // Uma alteração interrompida é desfeita ao abrir o arquivo.
+        begin(&mut journal, &vec![1u8, 2, 3]).unwrap();
+        file.set_len(0).unwrap();
+
+        assert!(restore_file(&mut journal, &mut file).unwrap());
+        assert_eq!(read_all(&mut file), vec![1, 2, 3]);
+        assert!(!restore_file(&mut journal, &mut file).unwrap());
+
+        rewrite_file(&mut journal, &mut file, |file| Ok(file.write_all(&[4])?)).unwrap();
+        assert_eq!(read_all(&mut file), vec![1, 2, 3, 4]);
+
+        for path in paths {
+            fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{collections::BTreeMap, error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use super::{errors::CustomErrors, format::{self, Cabecalho, HEADER_LENGTH, MOVEMENTS_MAGIC}, Movimento};
+
+pub fn serialize_record(movement: &Movimento) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(movement)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+// Os movimentos só são acrescentados ao fim do arquivo, nunca alterados.
+#[derive(Debug)]
+pub struct ArquivoMovimentos {
+    pub dados: File
+}
+
+impl ArquivoMovimentos {
+    pub fn new(dados: File) -> Self {
+        ArquivoMovimentos { dados }
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, MOVEMENTS_MAGIC)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Movimento>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut movements = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let size: u64 = bincode::deserialize(&buf[offset..])?;
+            let end = offset + 8 + size as usize;
+
+            let record = buf.get(offset + 8..end).ok_or(CustomErrors::FormatMismatch)?;
+            movements.push(bincode::deserialize(record)?);
+
+            offset = end;
+        }
+
+        Ok(movements)
+    }
+
+    pub fn by_product(&mut self, id: u64) -> Result<Vec<Movimento>, Box<dyn Error>> {
+        Ok(self.list()?.into_iter().filter(|movement| movement.produto == id).collect())
+    }
+
+    // Estoque de cada produto recalculado somando todos os movimentos.
+    pub fn balances(&mut self) -> Result<BTreeMap<u64, i64>, Box<dyn Error>> {
+        let mut balances = BTreeMap::new();
+
+        for movement in self.list()? {
+            *balances.entry(movement.produto).or_insert(0) += movement.quantidade;
+        }
+
+        Ok(balances)
+    }
+
+    pub fn append(&mut self, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        if movements.is_empty() {
+            return Ok(());
+        }
+
+        let mut header = self.header()?;
+        let mut buf = Vec::new();
+
+        for mut movement in movements {
+            movement.id = header.ultimo_id + 1;
+            buf.extend(serialize_record(&movement)?);
+
+            header.quantidade += 1;
+            header.ultimo_id = movement.id;
+        }
+
+        self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&buf)?;
+        format::write_header(&mut self.dados, &header)?;
+        self.dados.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{test_utils::{get_test_movements, test_path}, TipoMovimento};
+
+    #[test]
+    fn test_append_and_balances() {
+        let path = &test_path("test_movements.bin");
+        let mut movements = get_test_movements(path);
+
+        movements.append(vec![
+            Movimento::new(1, TipoMovimento::Inicial, 10, "Ana".to_string(), "Cadastro do produto".to_string()),
+            Movimento::new(2, TipoMovimento::Inicial, 5, "Ana".to_string(), "Cadastro do produto".to_string())
+        ]).unwrap();
+
+        movements.append(vec![Movimento::new(1, TipoMovimento::Venda, -3, "Bia".to_string(), "Venda 1".to_string())]).unwrap();
+        movements.append(Vec::new()).unwrap();
+
+        let history = movements.by_product(1).unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].id, 3);
+        assert_eq!(history[1].tipo, TipoMovimento::Venda);
+        assert_eq!(movements.balances().unwrap().into_iter().collect::<Vec<_>>(), vec![(1, 7), (2, 5)]);
+        assert_eq!(movements.header().unwrap().quantidade, 3);
+
+        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+    }
+}
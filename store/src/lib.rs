@@ -1,34 +1,81 @@
 use std::fmt;
 
-use chrono;
 use serde::{Serialize, Deserialize};
 
+use money::{Aliquota, Dinheiro};
+
+pub mod categories;
+pub mod config;
 pub mod core;
+pub mod csv;
 pub mod date;
 pub mod errors;
-pub mod screens;
+pub mod format;
+pub mod journal;
+pub mod ledger;
+pub mod memory;
+pub mod migration;
+pub mod money;
+pub mod orders;
+pub mod products;
+pub mod promotions;
+pub mod receipt;
+pub mod reports;
+pub mod repository;
+pub mod sessions;
+pub mod sales;
+pub mod search;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+pub mod taxes;
+pub mod timestamp;
 pub mod validation;
 
-#[derive(Serialize, Deserialize)]
-enum Categoria {
-    Eletronico,
-    Roupa,
-    Alimento,
-    Geral
+pub use store::Store;
+
+#[cfg(test)]
+mod test_utils;
+
+// Categorias cadastradas pelo usuário; a categoria pai é opcional.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Categoria {
+    id: u64,
+    nome: String,
+    pai: Option<u64>
+}
+
+impl Categoria {
+    pub fn new(id: u64, nome: String, pai: Option<u64>) -> Self {
+        Categoria { id, nome, pai }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn pai(&self) -> Option<u64> {
+        self.pai
+    }
 }
 
 impl std::fmt::Display for Categoria {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Categoria::Alimento => write!(f, "Alimento"),
-            Categoria::Eletronico => write!(f, "Eletrônico"),
-            Categoria::Roupa => write!(f, "Roupa"),
-            Categoria::Geral => write!(f, "Geral")
+        write!(f, "{} (ID: {})", self.nome, self.id)?;
+
+        if let Some(pai) = self.pai {
+            write!(f, " - Subcategoria de: {pai}")?;
         }
+
+        Ok(())
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum MetodoPagamento {
     Credito,
     Debito,
@@ -47,20 +94,66 @@ impl std::fmt::Display for MetodoPagamento {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+// Dados fiscais do produto, copiados para cada linha da venda no momento em que ela é registrada.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Tributacao {
+    ncm: String,
+    cfop: String,
+    icms: Aliquota,
+    pis: Aliquota,
+    cofins: Aliquota
+}
+
+impl Tributacao {
+    pub fn new(ncm: String, cfop: String, icms: Aliquota, pis: Aliquota, cofins: Aliquota) -> Self {
+        Tributacao { ncm, cfop, icms, pis, cofins }
+    }
+
+    pub fn ncm(&self) -> &str {
+        &self.ncm
+    }
+
+    pub fn cfop(&self) -> &str {
+        &self.cfop
+    }
+
+    pub fn icms(&self) -> Aliquota {
+        self.icms
+    }
+
+    pub fn pis(&self) -> Aliquota {
+        self.pis
+    }
+
+    pub fn cofins(&self) -> Aliquota {
+        self.cofins
+    }
+}
+
+impl std::fmt::Display for Tributacao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NCM: {} - CFOP: {} - ICMS: {} - PIS: {} - COFINS: {}", self.ncm, self.cfop, self.icms, self.pis, self.cofins)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Produto {
     nome: String,
     id: u64,
     quantidade_estoque: u64,
-    valor: f64,
+    valor: Dinheiro,
     quantidade_restoque: u64,
-    categoria: Categoria,
+    categoria: u64,
     #[serde(with = "date")]
-    data_restoque: chrono::NaiveDate
+    data_restoque: chrono::NaiveDate,
+    fornecedor: Option<String>,
+    custo_unitario: Option<Dinheiro>,
+    codigo_barras: Option<String>,
+    tributacao: Option<Tributacao>
 }
 
 impl Produto {
-    fn new(nome: String, id: u64, quantidade_estoque: u64, valor: f64, quantidade_restoque: u64, data_restoque: chrono::NaiveDate, categoria: Categoria) -> Self {
+    pub fn new(nome: String, id: u64, quantidade_estoque: u64, valor: Dinheiro, quantidade_restoque: u64, data_restoque: chrono::NaiveDate, categoria: u64) -> Self {
         Produto {
             nome,
             id,
@@ -68,46 +161,838 @@ impl Produto {
             valor,
             quantidade_restoque,
             data_restoque,
-            categoria
+            categoria,
+            fornecedor: None,
+            custo_unitario: None,
+            codigo_barras: None,
+            tributacao: None
+        }
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn quantidade_estoque(&self) -> u64 {
+        self.quantidade_estoque
+    }
+
+    pub fn valor(&self) -> Dinheiro {
+        self.valor
+    }
+
+    pub fn quantidade_restoque(&self) -> u64 {
+        self.quantidade_restoque
+    }
+
+    // Id da categoria do produto.
+    pub fn categoria(&self) -> u64 {
+        self.categoria
+    }
+
+    pub fn data_restoque(&self) -> chrono::NaiveDate {
+        self.data_restoque
+    }
+
+    pub fn fornecedor(&self) -> Option<&str> {
+        self.fornecedor.as_deref()
+    }
+
+    pub fn custo_unitario(&self) -> Option<Dinheiro> {
+        self.custo_unitario
+    }
+
+    pub fn codigo_barras(&self) -> Option<&str> {
+        self.codigo_barras.as_deref()
+    }
+
+    pub fn tributacao(&self) -> Option<&Tributacao> {
+        self.tributacao.as_ref()
+    }
+
+    pub fn set_codigo_barras(&mut self, codigo_barras: Option<String>) {
+        self.codigo_barras = codigo_barras;
+    }
+
+    pub fn set_tributacao(&mut self, tributacao: Option<Tributacao>) {
+        self.tributacao = tributacao;
+    }
+}
+
+impl Produto {
+    fn write_details(&self, f: &mut fmt::Formatter, categoria: &dyn fmt::Display) -> fmt::Result {
+        write!(f, "{}\nID: {}\nEstoque: {}\nPreço: {}\nMínimo para restoque: {}\nData do último restoque: {}\nCategoria: {}",
+                self.nome, self.id, self.quantidade_estoque, self.valor, self.quantidade_restoque, self.data_restoque.format("%d/%m/%Y"), categoria)?;
+
+        if let Some(fornecedor) = &self.fornecedor {
+            write!(f, "\nFornecedor: {fornecedor}")?;
+        }
+
+        if let Some(custo) = self.custo_unitario {
+            write!(f, "\nCusto unitário: {custo}")?;
+        }
+
+        if let Some(codigo) = &self.codigo_barras {
+            write!(f, "\nCódigo de barras: {codigo}")?;
+        }
+
+        if let Some(tributacao) = &self.tributacao {
+            write!(f, "\nDados fiscais: {tributacao}")?;
         }
+
+        Ok(())
     }
 }
 
 impl std::fmt::Display for Produto {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}\nID: {}\nEstoque: {}\nPreço: R${:.2}\nMínimo para restoque: {}\nData do último restoque: {}\nCategoria: {}",
-                self.nome, self.id, self.quantidade_estoque, self.valor, self.quantidade_restoque, self.data_restoque.format("%d/%m/%Y"), self.categoria)
+        self.write_details(f, &self.categoria)
+    }
+}
+
+// Produto acompanhado do nome da sua categoria, para exibição.
+#[derive(Serialize, Clone, Debug)]
+pub struct ProdutoExibido {
+    #[serde(flatten)]
+    produto: Produto,
+    nome_categoria: String
+}
+
+impl ProdutoExibido {
+    pub fn new(produto: Produto, nome_categoria: String) -> Self {
+        ProdutoExibido { produto, nome_categoria }
+    }
+
+    pub fn produto(&self) -> &Produto {
+        &self.produto
+    }
+
+    pub fn nome_categoria(&self) -> &str {
+        &self.nome_categoria
+    }
+}
+
+impl std::fmt::Display for ProdutoExibido {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.produto.write_details(f, &self.nome_categoria)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemVenda {
+    id: u64,
+    nome: String,
+    quantidade: u64,
+    valor_unitario: Dinheiro,
+    valor_total: Dinheiro,
+    desconto: Dinheiro,
+    tributacao: Option<Tributacao>
+}
+
+impl ItemVenda {
+    fn new(id: u64, nome: String, quantidade: u64, valor_unitario: Dinheiro) -> Result<Self, errors::CustomErrors> {
+        Ok(ItemVenda {
+            id,
+            nome,
+            quantidade,
+            valor_unitario,
+            valor_total: valor_unitario.checked_mul(quantidade)?,
+            desconto: Dinheiro::ZERO,
+            tributacao: None
+        })
+    }
+
+    // Linha vendida pelo preço e com os dados fiscais atuais do produto.
+    fn from_product(product: &Produto, quantidade: u64) -> Result<Self, errors::CustomErrors> {
+        Ok(ItemVenda {
+            tributacao: product.tributacao.clone(),
+            ..ItemVenda::new(product.id, product.nome.clone(), quantidade, product.valor)?
+        })
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn quantidade(&self) -> u64 {
+        self.quantidade
+    }
+
+    pub fn valor_unitario(&self) -> Dinheiro {
+        self.valor_unitario
+    }
+
+    pub fn valor_total(&self) -> Dinheiro {
+        self.valor_total
+    }
+
+    // Descontos das promoções e do vendedor na linha, sem o desconto sobre o total da venda.
+    pub fn desconto(&self) -> Dinheiro {
+        self.desconto
+    }
+
+    pub fn valor_liquido(&self) -> Result<Dinheiro, errors::CustomErrors> {
+        self.valor_total.checked_sub(self.desconto)
+    }
+
+    pub fn tributacao(&self) -> Option<&Tributacao> {
+        self.tributacao.as_ref()
+    }
+}
+
+impl std::fmt::Display for ItemVenda {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.quantidade == 0 {
+            return write!(f, "ID: {} (quantidade e valor não registrados)", self.id);
+        }
+
+        write!(f, "{}x {} (ID: {}) - {} cada - Total: {}",
+                self.quantidade, self.nome, self.id, self.valor_unitario, self.valor_total)?;
+
+        if self.desconto > Dinheiro::ZERO {
+            write!(f, " - Desconto: {} - Líquido: {}", self.desconto, self.valor_liquido().map_err(|_| fmt::Error)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Desconto dado pelo vendedor em um produto ou no total da venda.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Desconto {
+    Percentual(u64),
+    Fixo(Dinheiro)
+}
+
+impl std::fmt::Display for Desconto {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Desconto::Percentual(percentual) => write!(f, "{percentual}%"),
+            Desconto::Fixo(valor) => write!(f, "{valor}")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Regra {
+    // Levando `leve` unidades do produto, o cliente paga apenas `pague`.
+    LevePague { produto: u64, leve: u64, pague: u64 },
+    // Porcentagem de desconto nos produtos da categoria e das suas subcategorias.
+    Categoria { categoria: u64, percentual: u64 }
+}
+
+impl std::fmt::Display for Regra {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Regra::LevePague { produto, leve, pague } => write!(f, "Leve {leve}, pague {pague} - Produto: {produto}"),
+            Regra::Categoria { categoria, percentual } => write!(f, "{percentual}% de desconto - Categoria: {categoria}")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Promocao {
+    id: u64,
+    nome: String,
+    regra: Regra,
+    #[serde(with = "date::optional")]
+    inicio: Option<chrono::NaiveDate>,
+    #[serde(with = "date::optional")]
+    fim: Option<chrono::NaiveDate>
+}
+
+impl Promocao {
+    pub fn new(id: u64, nome: String, regra: Regra, inicio: Option<chrono::NaiveDate>, fim: Option<chrono::NaiveDate>) -> Self {
+        Promocao { id, nome, regra, inicio, fim }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn regra(&self) -> Regra {
+        self.regra
+    }
+
+    pub fn inicio(&self) -> Option<chrono::NaiveDate> {
+        self.inicio
+    }
+
+    pub fn fim(&self) -> Option<chrono::NaiveDate> {
+        self.fim
+    }
+
+    // Sem início ou sem fim, a promoção vale desde sempre ou até ser removida.
+    pub fn vigente(&self, data: chrono::NaiveDate) -> bool {
+        self.inicio.is_none_or(|inicio| inicio <= data) && self.fim.is_none_or(|fim| data <= fim)
+    }
+}
+
+impl std::fmt::Display for Promocao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (ID: {})\n{}", self.nome, self.id, self.regra)?;
+
+        match (self.inicio, self.fim) {
+            (Some(inicio), Some(fim)) => write!(f, "\nVigência: de {} a {}", inicio.format("%d/%m/%Y"), fim.format("%d/%m/%Y")),
+            (Some(inicio), None) => write!(f, "\nVigência: a partir de {}", inicio.format("%d/%m/%Y")),
+            (None, Some(fim)) => write!(f, "\nVigência: até {}", fim.format("%d/%m/%Y")),
+            (None, None) => write!(f, "\nVigência: sem prazo")
+        }
+    }
+}
+
+// Desconto que uma promoção deu em um produto da venda.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PromocaoAplicada {
+    promocao: u64,
+    nome: String,
+    produto: u64,
+    valor: Dinheiro
+}
+
+impl PromocaoAplicada {
+    pub fn promocao(&self) -> u64 {
+        self.promocao
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn produto(&self) -> u64 {
+        self.produto
+    }
+
+    pub fn valor(&self) -> Dinheiro {
+        self.valor
+    }
+}
+
+impl std::fmt::Display for PromocaoAplicada {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (ID: {}) - Produto: {} - Desconto: {}", self.nome, self.promocao, self.produto, self.valor)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Situacao {
+    Concluida,
+    DevolucaoParcial,
+    Cancelada
+}
+
+impl std::fmt::Display for Situacao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Situacao::Concluida => write!(f, "Concluída"),
+            Situacao::DevolucaoParcial => write!(f, "Devolvida parcialmente"),
+            Situacao::Cancelada => write!(f, "Cancelada")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Devolucao {
+    #[serde(with = "date")]
+    data: chrono::NaiveDate,
+    motivo: String,
+    itens: Vec<(u64, u64)>,
+    valor: Dinheiro
+}
+
+impl Devolucao {
+    pub fn data(&self) -> chrono::NaiveDate {
+        self.data
+    }
+
+    pub fn motivo(&self) -> &str {
+        &self.motivo
+    }
+
+    // Pares de id do produto e quantidade devolvida.
+    pub fn itens(&self) -> &[(u64, u64)] {
+        &self.itens
+    }
+
+    pub fn valor(&self) -> Dinheiro {
+        self.valor
+    }
+}
+
+impl std::fmt::Display for Devolucao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} - {} - Motivo: {}", self.data.format("%d/%m/%Y"), self.valor, self.motivo)?;
+
+        for (id, quantidade) in self.itens.iter() {
+            write!(f, "\n    {quantidade}x ID: {id}")?;
+        }
+
+        Ok(())
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Venda {
     vendedor: String,
-    produtos: Vec<u64>,
+    produtos: Vec<ItemVenda>,
     codigo: u64,
-    valor: f64,
+    valor: Dinheiro,
     metodo_pagamento: MetodoPagamento,
     #[serde(with = "date")]
-    data: chrono::NaiveDate
+    data: chrono::NaiveDate,
+    caixa: Option<u64>,
+    situacao: Situacao,
+    devolucoes: Vec<Devolucao>,
+    desconto: Dinheiro,
+    promocoes: Vec<PromocaoAplicada>
 }
 
 impl Venda {
-    fn new(vendedor: String, codigo: u64, valor: f64, data: chrono::NaiveDate, metodo_pagamento: MetodoPagamento) -> Self {
+    fn new(vendedor: String, codigo: u64, valor: Dinheiro, data: chrono::NaiveDate, metodo_pagamento: MetodoPagamento) -> Self {
         Venda {
             vendedor,
             produtos: Vec::new(),
             codigo,
             valor,
             data,
-            metodo_pagamento
+            metodo_pagamento,
+            caixa: None,
+            situacao: Situacao::Concluida,
+            devolucoes: Vec::new(),
+            desconto: Dinheiro::ZERO,
+            promocoes: Vec::new()
         }
     }
+
+    fn returned(&self, id: u64) -> u64 {
+        self.devolucoes.iter()
+            .flat_map(|devolucao| devolucao.itens.iter())
+            .filter(|(item_id, _)| *item_id == id)
+            .map(|(_, quantidade)| quantidade)
+            .sum()
+    }
+
+    pub fn valor_liquido(&self) -> Result<Dinheiro, errors::CustomErrors> {
+        self.devolucoes.iter().try_fold(self.valor, |valor, devolucao| valor.checked_sub(devolucao.valor))
+    }
+
+    pub fn vendedor(&self) -> &str {
+        &self.vendedor
+    }
+
+    pub fn produtos(&self) -> &[ItemVenda] {
+        &self.produtos
+    }
+
+    pub fn codigo(&self) -> u64 {
+        self.codigo
+    }
+
+    pub fn valor(&self) -> Dinheiro {
+        self.valor
+    }
+
+    pub fn metodo_pagamento(&self) -> MetodoPagamento {
+        self.metodo_pagamento
+    }
+
+    pub fn data(&self) -> chrono::NaiveDate {
+        self.data
+    }
+
+    pub fn caixa(&self) -> Option<u64> {
+        self.caixa
+    }
+
+    pub fn situacao(&self) -> Situacao {
+        self.situacao
+    }
+
+    pub fn devolucoes(&self) -> &[Devolucao] {
+        &self.devolucoes
+    }
+
+    // Desconto dado pelo vendedor sobre o total, depois dos descontos dos produtos.
+    pub fn desconto(&self) -> Dinheiro {
+        self.desconto
+    }
+
+    pub fn promocoes(&self) -> &[PromocaoAplicada] {
+        &self.promocoes
+    }
+
+    // Valor dos produtos pelo preço de venda, antes de qualquer desconto.
+    pub fn subtotal(&self) -> Result<Dinheiro, errors::CustomErrors> {
+        self.produtos.iter().try_fold(Dinheiro::ZERO, |total, item| total.checked_add(item.valor_total))
+    }
+
+    // Descontos dos produtos, das promoções incluídas, somados ao desconto sobre o total.
+    pub fn descontos(&self) -> Result<Dinheiro, errors::CustomErrors> {
+        self.produtos.iter().try_fold(self.desconto, |total, item| total.checked_add(item.desconto))
+    }
 }
 
 impl std::fmt::Display for Venda {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Data da venda: {}\nVenda realizada por: {}\nCódigo: {}\nValor: R${:.2}\nMétodo de pagamento: {}\nProdutos vendidos:\n{:#?}",
-                self.data.format("%d/%m/%Y"), self.vendedor, self.codigo, self.valor, self.metodo_pagamento, self.produtos)
+        write!(f, "Data da venda: {}\nVenda realizada por: {}\nCódigo: {}", self.data.format("%d/%m/%Y"), self.vendedor, self.codigo)?;
+
+        let descontos = self.descontos().map_err(|_| fmt::Error)?;
+
+        if descontos > Dinheiro::ZERO {
+            write!(f, "\nSubtotal: {}\nDescontos: {}", self.subtotal().map_err(|_| fmt::Error)?, descontos)?;
+        }
+
+        write!(f, "\nValor: {}\nMétodo de pagamento: {}", self.valor, self.metodo_pagamento)?;
+
+        if let Some(caixa) = self.caixa {
+            write!(f, "\nCaixa: {caixa}")?;
+        }
+
+        if self.situacao != Situacao::Concluida {
+            write!(f, "\nSituação: {}", self.situacao)?;
+        }
+
+        write!(f, "\nProdutos vendidos:")?;
+
+        for item in self.produtos.iter() {
+            write!(f, "\n  {item}")?;
+        }
+
+        if self.desconto > Dinheiro::ZERO {
+            write!(f, "\nDesconto na venda: {}", self.desconto)?;
+        }
+
+        if !self.promocoes.is_empty() {
+            write!(f, "\nPromoções aplicadas:")?;
+
+            for promocao in self.promocoes.iter() {
+                write!(f, "\n  {promocao}")?;
+            }
+        }
+
+        if !self.devolucoes.is_empty() {
+            write!(f, "\nDevoluções:")?;
+
+            for devolucao in self.devolucoes.iter() {
+                write!(f, "\n  {devolucao}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Fechamento {
+    #[serde(with = "date")]
+    data: chrono::NaiveDate,
+    esperado: Dinheiro,
+    contado: Dinheiro
+}
+
+impl Fechamento {
+    pub fn data(&self) -> chrono::NaiveDate {
+        self.data
+    }
+
+    pub fn esperado(&self) -> Dinheiro {
+        self.esperado
+    }
+
+    pub fn contado(&self) -> Dinheiro {
+        self.contado
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Caixa {
+    id: u64,
+    operador: String,
+    fundo: Dinheiro,
+    #[serde(with = "date")]
+    abertura: chrono::NaiveDate,
+    fechamento: Option<Fechamento>
+}
+
+impl Caixa {
+    fn new(id: u64, operador: String, fundo: Dinheiro, abertura: chrono::NaiveDate) -> Self {
+        Caixa {
+            id,
+            operador,
+            fundo,
+            abertura,
+            fechamento: None
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn operador(&self) -> &str {
+        &self.operador
+    }
+
+    pub fn fundo(&self) -> Dinheiro {
+        self.fundo
+    }
+
+    pub fn abertura(&self) -> chrono::NaiveDate {
+        self.abertura
+    }
+
+    pub fn fechamento(&self) -> Option<&Fechamento> {
+        self.fechamento.as_ref()
+    }
+}
+
+impl std::fmt::Display for Caixa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Caixa: {}\nOperador: {}\nAbertura: {}\nFundo de troco: {}",
+                self.id, self.operador, self.abertura.format("%d/%m/%Y"), self.fundo)?;
+
+        match &self.fechamento {
+            Some(fechamento) => write!(f, "\nFechamento: {}\nDinheiro esperado: {}\nDinheiro contado: {}\nDiferença: {}",
+                fechamento.data.format("%d/%m/%Y"), fechamento.esperado, fechamento.contado,
+                fechamento.contado.checked_sub(fechamento.esperado).map_err(|_| fmt::Error)?),
+            None => write!(f, "\nFechamento: em aberto")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TipoMovimento {
+    Inicial,
+    Venda,
+    Restoque,
+    Ajuste,
+    Devolucao,
+    Perda
+}
+
+impl std::fmt::Display for TipoMovimento {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TipoMovimento::Inicial => write!(f, "Saldo inicial"),
+            TipoMovimento::Venda => write!(f, "Venda"),
+            TipoMovimento::Restoque => write!(f, "Restoque"),
+            TipoMovimento::Ajuste => write!(f, "Ajuste manual"),
+            TipoMovimento::Devolucao => write!(f, "Devolução"),
+            TipoMovimento::Perda => write!(f, "Perda/avaria")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Recebimento {
+    fornecedor: String,
+    custo_unitario: Dinheiro
+}
+
+impl Recebimento {
+    pub fn fornecedor(&self) -> &str {
+        &self.fornecedor
+    }
+
+    pub fn custo_unitario(&self) -> Dinheiro {
+        self.custo_unitario
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Movimento {
+    id: u64,
+    produto: u64,
+    tipo: TipoMovimento,
+    quantidade: i64,
+    #[serde(with = "timestamp")]
+    data: chrono::NaiveDateTime,
+    operador: String,
+    motivo: String,
+    recebimento: Option<Recebimento>
+}
+
+impl Movimento {
+    fn new(produto: u64, tipo: TipoMovimento, quantidade: i64, operador: String, motivo: String) -> Self {
+        Movimento {
+            id: 0,
+            produto,
+            tipo,
+            quantidade,
+            data: chrono::Local::now().naive_local(),
+            operador,
+            motivo,
+            recebimento: None
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn produto(&self) -> u64 {
+        self.produto
+    }
+
+    pub fn tipo(&self) -> TipoMovimento {
+        self.tipo
+    }
+
+    pub fn quantidade(&self) -> i64 {
+        self.quantidade
+    }
+
+    pub fn data(&self) -> chrono::NaiveDateTime {
+        self.data
+    }
+
+    pub fn operador(&self) -> &str {
+        &self.operador
+    }
+
+    pub fn motivo(&self) -> &str {
+        &self.motivo
+    }
+
+    pub fn recebimento(&self) -> Option<&Recebimento> {
+        self.recebimento.as_ref()
+    }
+}
+
+impl std::fmt::Display for Movimento {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} - {} - {:+} - Operador: {} - Motivo: {}",
+                self.data.format("%d/%m/%Y %H:%M:%S"), self.tipo, self.quantidade, self.operador, self.motivo)?;
+
+        if let Some(recebimento) = &self.recebimento {
+            write!(f, " - Fornecedor: {} - Custo unitário: {}", recebimento.fornecedor, recebimento.custo_unitario)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ItemPedido {
+    id: u64,
+    nome: String,
+    quantidade: u64,
+    custo_unitario: Option<Dinheiro>
+}
+
+impl ItemPedido {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn nome(&self) -> &str {
+        &self.nome
+    }
+
+    pub fn quantidade(&self) -> u64 {
+        self.quantidade
+    }
+
+    pub fn custo_unitario(&self) -> Option<Dinheiro> {
+        self.custo_unitario
+    }
+}
+
+impl std::fmt::Display for ItemPedido {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x {} (ID: {})", self.quantidade, self.nome, self.id)?;
+
+        match self.custo_unitario {
+            Some(custo) => write!(f, " - {} cada - Total: {}", custo, custo.checked_mul(self.quantidade).map_err(|_| fmt::Error)?),
+            None => write!(f, " - custo a definir")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Entrega {
+    #[serde(with = "date")]
+    data: chrono::NaiveDate,
+    operador: String
+}
+
+impl Entrega {
+    pub fn data(&self) -> chrono::NaiveDate {
+        self.data
+    }
+
+    pub fn operador(&self) -> &str {
+        &self.operador
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Pedido {
+    id: u64,
+    fornecedor: Option<String>,
+    #[serde(with = "date")]
+    data: chrono::NaiveDate,
+    itens: Vec<ItemPedido>,
+    recebimento: Option<Entrega>
+}
+
+impl Pedido {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn fornecedor(&self) -> Option<&str> {
+        self.fornecedor.as_deref()
+    }
+
+    pub fn data(&self) -> chrono::NaiveDate {
+        self.data
+    }
+
+    pub fn itens(&self) -> &[ItemPedido] {
+        &self.itens
+    }
+
+    pub fn recebimento(&self) -> Option<&Entrega> {
+        self.recebimento.as_ref()
+    }
+
+    pub fn total(&self) -> Result<Dinheiro, errors::CustomErrors> {
+        self.itens.iter()
+            .filter_map(|item| item.custo_unitario.map(|custo| (custo, item.quantidade)))
+            .try_fold(Dinheiro::ZERO, |total, (custo, quantidade)| total.checked_add(custo.checked_mul(quantidade)?))
+    }
+}
+
+impl std::fmt::Display for Pedido {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Pedido de compra: {}\nFornecedor: {}\nData: {}\nSituação: ",
+                self.id, self.fornecedor.as_deref().unwrap_or("não informado"), self.data.format("%d/%m/%Y"))?;
+
+        match &self.recebimento {
+            Some(entrega) => write!(f, "Recebido em {} por {}", entrega.data.format("%d/%m/%Y"), entrega.operador)?,
+            None => write!(f, "Pendente")?
+        }
+
+        write!(f, "\nProdutos:")?;
+
+        for item in self.itens.iter() {
+            write!(f, "\n  {item}")?;
+        }
+
+        write!(f, "\nTotal estimado: {}", self.total().map_err(|_| fmt::Error)?)
     }
 }
 
@@ -118,10 +1003,8 @@ mod tests {
     
     #[test]
     fn test_categories_display() {
-        assert_eq!(format!("{}", Categoria::Alimento), "Alimento");
-        assert_eq!(format!("{}", Categoria::Eletronico), "Eletrônico");
-        assert_eq!(format!("{}", Categoria::Geral), "Geral");
-        assert_eq!(format!("{}", Categoria::Roupa), "Roupa");
+        assert_eq!(format!("{}", Categoria::new(categories::ALIMENTO, "Alimento".to_string(), None)), "Alimento (ID: 3)");
+        assert_eq!(format!("{}", Categoria::new(5, "Bebidas".to_string(), Some(categories::ALIMENTO))), "Bebidas (ID: 5) - Subcategoria de: 3");
     }
 
     #[test]
@@ -134,41 +1017,143 @@ mod tests {
 
     #[test]
     fn test_create_product() {
-        let product = Produto::new("Smartphone".to_string(), 1, 100, 1500.0, 50, NaiveDate::default(), Categoria::Eletronico);
+        let product = Produto::new("Smartphone".to_string(), 1, 100, Dinheiro::from_centavos(150000), 50, NaiveDate::default(), categories::ELETRONICO);
 
         assert_eq!(product.nome, "Smartphone");
         assert_eq!(product.id, 1);
         assert_eq!(product.quantidade_estoque, 100);
-        assert_eq!(product.valor, 1500.0);
+        assert_eq!(product.valor, Dinheiro::from_centavos(150000));
         assert_eq!(product.quantidade_restoque, 50);
         assert_eq!(product.data_restoque, NaiveDate::default());
     }
 
     #[test]
     fn test_product_display() {
-        let product = Produto::new("Camisa".to_string(), 2, 50, 69.99, 10, NaiveDate::default(), Categoria::Roupa);
+        let product = Produto::new("Camisa".to_string(), 2, 50, Dinheiro::from_centavos(6999), 10, NaiveDate::default(), categories::ROUPA);
 
-        let output = "Camisa\nID: 2\nEstoque: 50\nPreço: R$69.99\nMínimo para restoque: 10\nData do último restoque: 01/01/1970\nCategoria: Roupa";
+        let output = "Camisa\nID: 2\nEstoque: 50\nPreço: R$ 69,99\nMínimo para restoque: 10\nData do último restoque: 01/01/1970\nCategoria: 2";
 
         assert_eq!(format!("{product}"), format!("{output}"));
+        assert_eq!(format!("{}", ProdutoExibido::new(product.clone(), "Roupa".to_string())), output.replace("Categoria: 2", "Categoria: Roupa"));
+
+        let mut product = product;
+        product.fornecedor = Some("Malharia Sul".to_string());
+        product.custo_unitario = Some(Dinheiro::from_centavos(3000));
+
+        assert!(format!("{product}").ends_with("Categoria: 2\nFornecedor: Malharia Sul\nCusto unitário: R$ 30,00"));
+
+        product.codigo_barras = Some("7891234567895".to_string());
+
+        assert!(format!("{product}").ends_with("Custo unitário: R$ 30,00\nCódigo de barras: 7891234567895"));
+
+        product.tributacao = Some(Tributacao::new("61091000".to_string(), "5102".to_string(), Aliquota::from_centesimos(1800), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760)));
+
+        assert!(format!("{product}").ends_with("\nDados fiscais: NCM: 61091000 - CFOP: 5102 - ICMS: 18,00% - PIS: 1,65% - COFINS: 7,60%"));
     }
 
     #[test]
     fn test_create_sale() {
-        let venda = Venda::new("Lucas".to_string(), 2, 8.75, NaiveDate::default(), MetodoPagamento::Pix);
+        let venda = Venda::new("Lucas".to_string(), 2, Dinheiro::from_centavos(875), NaiveDate::default(), MetodoPagamento::Pix);
 
         assert_eq!(venda.vendedor, "Lucas");
         assert_eq!(venda.codigo, 2);
-        assert_eq!(venda.valor, 8.75);
+        assert_eq!(venda.valor, Dinheiro::from_centavos(875));
         assert_eq!(venda.data, NaiveDate::default());
     }
 
     #[test]
     fn test_sale_display() {
-        let venda = Venda::new("Pedro".to_string(), 1, 100.50, NaiveDate::default(), MetodoPagamento::Debito);
+        let venda = Venda::new("Pedro".to_string(), 1, Dinheiro::from_centavos(10050), NaiveDate::default(), MetodoPagamento::Debito);
 
-        let output = "Data da venda: 01/01/1970\nVenda realizada por: Pedro\nCódigo: 1\nValor: R$100.50\nMétodo de pagamento: Cartão de débito\nProdutos vendidos:\n[]";
+        let output = "Data da venda: 01/01/1970\nVenda realizada por: Pedro\nCódigo: 1\nValor: R$ 100,50\nMétodo de pagamento: Cartão de débito\nProdutos vendidos:";
 
         assert_eq!(format!("{venda}"), format!("{output}"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sale_item() {
+        let item = ItemVenda::new(7, "Arroz".to_string(), 3, Dinheiro::from_centavos(450)).unwrap();
+
+        assert_eq!(item.quantidade, 3);
+        assert_eq!(item.valor_total, Dinheiro::from_centavos(1350));
+        assert_eq!(format!("{item}"), "3x Arroz (ID: 7) - R$ 4,50 cada - Total: R$ 13,50");
+        assert!(ItemVenda::new(7, "Arroz".to_string(), u64::MAX, Dinheiro::from_centavos(450)).is_err());
+        assert_eq!(format!("{}", ItemVenda::new(7, String::new(), 0, Dinheiro::ZERO).unwrap()), "ID: 7 (quantidade e valor não registrados)");
+    }
+
+    #[test]
+    fn test_sale_display_with_items() {
+        let mut venda = Venda::new("Ana".to_string(), 4, Dinheiro::from_centavos(1000), NaiveDate::default(), MetodoPagamento::Pix);
+        venda.produtos.push(ItemVenda::new(2, "Leite".to_string(), 2, Dinheiro::from_centavos(500)).unwrap());
+
+        let output = "Data da venda: 01/01/1970\nVenda realizada por: Ana\nCódigo: 4\nValor: R$ 10,00\nMétodo de pagamento: PIX\nProdutos vendidos:\n  2x Leite (ID: 2) - R$ 5,00 cada - Total: R$ 10,00";
+
+        assert_eq!(format!("{venda}"), output);
+    }
+
+    #[test]
+    fn test_cancelled_sale_display() {
+        let mut venda = Venda::new("Ana".to_string(), 5, Dinheiro::from_centavos(1000), NaiveDate::default(), MetodoPagamento::Pix);
+        venda.produtos.push(ItemVenda::new(2, "Leite".to_string(), 2, Dinheiro::from_centavos(500)).unwrap());
+        venda.caixa = Some(1);
+        venda.situacao = Situacao::Cancelada;
+        venda.devolucoes.push(Devolucao { data: NaiveDate::default(), motivo: "Desistência".to_string(), itens: vec![(2, 2)], valor: Dinheiro::from_centavos(1000) });
+
+        let output = "Data da venda: 01/01/1970\nVenda realizada por: Ana\nCódigo: 5\nValor: R$ 10,00\nMétodo de pagamento: PIX\nCaixa: 1\nSituação: Cancelada\nProdutos vendidos:\n  2x Leite (ID: 2) - R$ 5,00 cada - Total: R$ 10,00\nDevoluções:\n  01/01/1970 - R$ 10,00 - Motivo: Desistência\n    2x ID: 2";
+
+        assert_eq!(format!("{venda}"), output);
+        assert_eq!(venda.valor_liquido().unwrap(), Dinheiro::ZERO);
+        assert_eq!(venda.returned(2), 2);
+    }
+
+    #[test]
+    fn test_session_display() {
+        let mut caixa = Caixa::new(3, "Ana".to_string(), Dinheiro::from_centavos(10000), NaiveDate::default());
+
+        assert_eq!(format!("{caixa}"), "Caixa: 3\nOperador: Ana\nAbertura: 01/01/1970\nFundo de troco: R$ 100,00\nFechamento: em aberto");
+
+        caixa.fechamento = Some(Fechamento {
+            data: NaiveDate::default(),
+            esperado: Dinheiro::from_centavos(15000),
+            contado: Dinheiro::from_centavos(14950)
+        });
+
+        assert!(format!("{caixa}").ends_with("Dinheiro esperado: R$ 150,00\nDinheiro contado: R$ 149,50\nDiferença: -R$ 0,50"));
+    }
+
+    #[test]
+    fn test_movement_display() {
+        let mut movimento = Movimento::new(4, TipoMovimento::Perda, -3, "Ana".to_string(), "Embalagem rasgada".to_string());
+        movimento.data = NaiveDate::default().and_hms_opt(9, 5, 0).unwrap();
+
+        assert_eq!(format!("{movimento}"), "01/01/1970 09:05:00 - Perda/avaria - -3 - Operador: Ana - Motivo: Embalagem rasgada");
+
+        movimento.tipo = TipoMovimento::Restoque;
+        movimento.quantidade = 12;
+        movimento.motivo = "Recebimento de mercadoria".to_string();
+        movimento.recebimento = Some(Recebimento { fornecedor: "Atacadão".to_string(), custo_unitario: Dinheiro::from_centavos(350) });
+
+        assert_eq!(format!("{movimento}"), "01/01/1970 09:05:00 - Restoque - +12 - Operador: Ana - Motivo: Recebimento de mercadoria - Fornecedor: Atacadão - Custo unitário: R$ 3,50");
+    }
+
+    #[test]
+    fn test_order_display() {
+        let mut pedido = Pedido {
+            id: 2,
+            fornecedor: Some("Atacadão".to_string()),
+            data: NaiveDate::default(),
+            itens: vec![
+                ItemPedido { id: 1, nome: "Arroz".to_string(), quantidade: 10, custo_unitario: Some(Dinheiro::from_centavos(1850)) },
+                ItemPedido { id: 4, nome: "Sal".to_string(), quantidade: 3, custo_unitario: None }
+            ],
+            recebimento: None
+        };
+
+        let output = "Pedido de compra: 2\nFornecedor: Atacadão\nData: 01/01/1970\nSituação: Pendente\nProdutos:\n  10x Arroz (ID: 1) - R$ 18,50 cada - Total: R$ 185,00\n  3x Sal (ID: 4) - custo a definir\nTotal estimado: R$ 185,00";
+
+        assert_eq!(format!("{pedido}"), output);
+
+        pedido.recebimento = Some(Entrega { data: NaiveDate::default(), operador: "Bia".to_string() });
+        assert!(format!("{pedido}").contains("Situação: Recebido em 01/01/1970 por Bia"));
+    }
+}
@@ -1,40 +1,143 @@
-use std::{io::stdin, process};
+use std::{env, io::stdin, process};
 
-use store::{core, validation};
+#[cfg(feature = "sqlite")]
+use store::sqlite;
+use store::{config, core, errors, orders::{self, ArquivoPedidos}, receipt::{self, Loja}, repository::{ProductRepository, SaleRepository, SaleTransaction}, sessions::ArquivoCaixas, validation};
 
 extern crate store;
 
+mod cli;
+mod output;
+mod prompts;
+mod screens;
+
+use output::Saida;
+
+fn exit_with_error(error: &(dyn std::error::Error + 'static)) -> ! {
+    eprintln!("\nUm erro ocorreu durante a operação: {error}\n");
+    process::exit(cli::exit_code(error));
+}
+
+// Avisos sobre gravações desfeitas e arquivos atualizados ao abrir o estoque.
+fn print_notices(notices: &[String]) {
+    for notice in notices {
+        eprintln!("\n{notice}\n");
+    }
+}
+
 fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
 
-    let (mut products_file, mut sales_file) = validation::get_files();
+    let data_dir = config::data_dir(&mut args).unwrap_or_else(|error| exit_with_error(error.as_ref()));
 
-    println!("\nInsira o nome do caixa que está realizando as vendas (ou 'sair' para encerrar a aplicação):");
-    let mut seller = validation::validate_string(&mut stdin().lock()).unwrap_or_else(|_| {
+    if args == ["help"] {
+        screens::help_screen();
         process::exit(0);
-    });
+    }
+
+    if args == ["init"] {
+        match validation::create_store(&data_dir) {
+            Ok(()) => {
+                println!("\nNovo estoque criado em {}.\n", data_dir.display());
+                process::exit(0);
+            },
+            Err(error) => exit_with_error(error.as_ref())
+        }
+    }
+
+    let loja = receipt::read_store(&data_dir).unwrap_or_else(|error| exit_with_error(error.as_ref()));
+
+    // Com o banco SQLite criado, produtos e vendas são lidos só dele e os seus arquivos nem são abertos;
+    // apenas caixas e pedidos continuam nos arquivos.
+    #[cfg(feature = "sqlite")]
+    if data_dir.join(sqlite::DATABASE_FILE).exists() {
+        if args == ["migrate", "sqlite"] {
+            exit_with_error(&errors::CustomErrors::StoreAlreadyExists);
+        }
+
+        let ((mut sessions, mut orders), notices) = validation::get_shared_files(&data_dir).unwrap_or_else(|error| exit_with_error(error.as_ref()));
+        print_notices(&notices);
+
+        let (mut products, mut sales, mut transaction) = sqlite::open(&data_dir.join(sqlite::DATABASE_FILE)).unwrap_or_else(|error| exit_with_error(error.as_ref()));
+        serve(&args, &mut products, &mut sales, &mut sessions, &mut orders, &mut transaction, &loja);
+    }
+
+    let ((mut products, mut sales, mut sessions, mut orders, mut journal_file), notices) = match validation::get_files(&data_dir) {
+        Ok(files) => files,
+        Err(error) if args.is_empty() && matches!(error.downcast_ref(), Some(errors::CustomErrors::StoreNotFound)) => {
+            println!("\nNenhum estoque encontrado em {}. Deseja criar um novo estoque neste diretório? (s/n)", data_dir.display());
+
+            if !prompts::confirm(&mut stdin().lock()).unwrap_or(false) {
+                process::exit(0);
+            }
+
+            validation::create_store(&data_dir)
+                .and_then(|_| validation::get_files(&data_dir))
+                .unwrap_or_else(|error| exit_with_error(error.as_ref()))
+        },
+        Err(error) => exit_with_error(error.as_ref())
+    };
+
+    print_notices(&notices);
+
+    #[cfg(feature = "sqlite")]
+    if args == ["migrate", "sqlite"] {
+        let path = data_dir.join(sqlite::DATABASE_FILE);
+
+        match sqlite::migrate(&mut products, &mut sales, &path) {
+            Ok((products, sales)) => {
+                println!("\n{products} produtos e {sales} vendas copiados para {}.\n", path.display());
+                process::exit(0);
+            },
+            Err(error) => exit_with_error(error.as_ref())
+        }
+    }
+
+    serve(&args, &mut products, &mut sales, &mut sessions, &mut orders, &mut journal_file, &loja);
+}
+
+fn serve<P: ProductRepository, S: SaleRepository, T: SaleTransaction<P, S>>(args: &[String], products: &mut P, sales: &mut S, sessions: &mut ArquivoCaixas, orders: &mut ArquivoPedidos, journal_file: &mut T, loja: &Loja) -> ! {
+    if !args.is_empty() {
+        match cli::run(args, products, sales, sessions, orders, journal_file, loja) {
+            Ok(()) => process::exit(0),
+            Err(error) => exit_with_error(error.as_ref())
+        }
+    }
+
+    let mut seller = match sessions.current() {
+        Ok(Some(session)) => {
+            println!("\nContinuando no caixa {} aberto por {}.", session.id(), session.operador());
+            session.operador().to_string()
+        },
+        _ => {
+            println!("\nInsira o nome do caixa que está realizando as vendas (ou 'sair' para encerrar a aplicação):");
+            prompts::validate_string(&mut stdin().lock()).unwrap_or_else(|_| {
+                process::exit(0);
+            })
+        }
+    };
 
     loop {
-        let result = match validation::get_option() {
+        let result = match prompts::get_option() {
             0 => process::exit(0),
-            1 => core::add_product(&mut products_file, &mut std::io::stdin().lock()),
-            2 => core::register_sale(&mut products_file, &mut sales_file, seller.clone(), &mut stdin().lock()),
-            3 => match validation::validate_search("id", &mut stdin().lock()) {
-                Ok(id) => match core::search_product_id(&mut products_file, id) {
-                    Ok((product, _)) => {
-                        println!("\n{product}\n");
-                        Ok(())
-                    },
-                    Err(error) => Err(error)
-                },
+            1 => cli::add_product(products, journal_file, &seller, &mut std::io::stdin().lock()),
+            2 => match sessions.current() {
+                Ok(session) => cli::register_sale(products, sales, journal_file, seller.clone(), session.map(|session| session.id()), loja, &mut stdin().lock()),
+                Err(error) => Err(error)
+            },
+            3 => match prompts::validate_search("id", &mut stdin().lock()) {
+                Ok(id) => core::search_product_id(products, id)
+                    .and_then(|product| core::describe_product(products, product))
+                    .map(|product| println!("\n{product}\n")),
                 Err(error) => Err(Box::new(error) as Box<dyn std::error::Error>)
             }
-            4 => core::list_products(&mut products_file),
-            5 => core::products_needing_restock(&mut products_file),
-            6 => core::update_product(&mut products_file, &mut stdin().lock()),
-            7 => core::remove_product(&mut products_file, &mut stdin().lock()),
-            8 => match validation::validate_search("code", &mut stdin().lock()) {
-                Ok(code) => match core::search_sale_code(&mut sales_file, code) {
-                    Ok((sale, _)) => {
+            4 => cli::list_products(products, Saida::Texto),
+            5 => cli::products_needing_restock(products, sales, &orders::Parametros::default()),
+            6 => cli::update_product(products, journal_file, &seller, &mut stdin().lock()),
+            7 => cli::remove_product(products, journal_file, &seller, &mut stdin().lock()),
+            8 => match prompts::validate_search("code", &mut stdin().lock()) {
+                Ok(code) => match core::search_sale_code(sales, code) {
+                    Ok(sale) => {
                         println!("\n{sale}\n");
                         Ok(())
                     },
@@ -44,27 +147,63 @@ fn main() {
             },
             9 => {
                 println!("\nDigite a data da venda que deseja procurar seguindo o formato dd/mm/YYYY (ou digite 'sair' para cancelar):");
-                match validation::validate_date(&mut stdin().lock()) {
-                    Ok(date) => core::search_sales_by_date(&mut sales_file, date),
+                match prompts::validate_date(&mut stdin().lock()) {
+                    Ok(date) => cli::search_sales_by_date(sales, date, Saida::Texto),
                     Err(error) => Err(Box::new(error) as Box<dyn std::error::Error>)
                 }
             },
-            10 => match validation::validate_search("id", &mut stdin().lock()) {
-                Ok(id) => core::search_product_sales(&mut sales_file, id),
+            10 => match prompts::validate_search("id", &mut stdin().lock()) {
+                Ok(id) => cli::search_product_sales(sales, id, Saida::Texto),
                 Err(error) => Err(Box::new(error) as Box<dyn std::error::Error>)
             },
-            11 => core::list_sales(&mut sales_file),
-            12 => core::update_sale(&mut sales_file, &mut stdin().lock()),
-            13 => core::remove_sale(&mut sales_file, &mut stdin().lock()),
-            14 => {
-                println!("\nInsira o nome do caixa que está realizando as vendas (ou 'sair' para cancelar):");
-
-                if let Ok(name) = validation::validate_string(&mut stdin().lock()) {
-                    seller = name;
+            11 => cli::list_sales(sales, Saida::Texto),
+            12 => cli::update_sale(sales, &mut stdin().lock()),
+            13 => cli::cancel_sale(products, sales, journal_file, &seller, &mut stdin().lock()),
+            14 => match cli::open_session(sessions, &mut stdin().lock()) {
+                Ok(session) => {
+                    seller = session.operador().to_string();
+                    Ok(())
+                },
+                Err(error) => Err(error)
+            },
+            15 => cli::report_sales(sales, &mut stdin().lock()),
+            16 => cli::close_session(sessions, sales, &mut stdin().lock()),
+            17 => cli::list_sessions(sessions, Saida::Texto),
+            18 => cli::return_sale_items(products, sales, journal_file, &seller, &mut stdin().lock()),
+            19 => cli::update_sale_items(products, sales, journal_file, &seller, &mut stdin().lock()),
+            20 => cli::register_adjustment(products, journal_file, &seller, &mut stdin().lock()),
+            21 => match prompts::validate_search("id", &mut stdin().lock()) {
+                Ok(id) => match core::search_product_id(products, id) {
+                    Ok(_) => cli::product_history(products, id, Saida::Texto),
+                    Err(error) => Err(error)
+                },
+                Err(error) => Err(Box::new(error) as Box<dyn std::error::Error>)
+            },
+            22 => cli::audit_stock(products, false),
+            23 => cli::receive_products(products, journal_file, &seller, &mut stdin().lock()),
+            24 => cli::generate_purchase_orders(products, sales, orders, &orders::Parametros::default()),
+            25 => cli::list_orders(orders, Saida::Texto),
+            26 => cli::receive_order(products, orders, journal_file, &seller, &mut stdin().lock()),
+            27 => cli::find_products(products, &mut stdin().lock()),
+            28 => {
+                println!("\nLeia ou digite o código de barras do produto (ou digite 'sair' para cancelar):");
+                match prompts::validate_string(&mut stdin().lock()) {
+                    Ok(barcode) => core::search_product_barcode(products, &barcode)
+                        .and_then(|product| core::describe_product(products, product))
+                        .map(|product| println!("\n{product}\n")),
+                    Err(error) => Err(Box::new(error) as Box<dyn std::error::Error>)
                 }
-
-                Ok(())
             },
+            29 => cli::list_categories(products, Saida::Texto),
+            30 => cli::add_category(products, journal_file, &mut stdin().lock()),
+            31 => cli::edit_category_name(products, journal_file, &mut stdin().lock()),
+            32 => cli::join_categories(products, journal_file, &mut stdin().lock()),
+            33 => cli::remove_category(products, journal_file, &mut stdin().lock()),
+            34 => cli::list_promotions(products, Saida::Texto),
+            35 => cli::add_promotion(products, journal_file, &mut stdin().lock()),
+            36 => cli::remove_promotion(products, journal_file, &mut stdin().lock()),
+            37 => cli::show_sale_receipt(sales, loja, &mut stdin().lock()),
+            38 => cli::update_product_taxes(products, &mut stdin().lock()),
             _ => {
                 eprintln!("\nInsira um valor válido de operação.\n");
 
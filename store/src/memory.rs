@@ -0,0 +1,370 @@
+use std::{collections::BTreeMap, error::Error};
+
+use super::{categories, errors, repository::{ProductRepository, SaleRepository, SaleTransaction, StockTransaction}, Categoria, Movimento, Produto, Promocao, Venda};
+
+// Repositórios mantidos só em memória, usados nos testes e por quem não precisa gravar os dados.
+#[derive(Debug)]
+pub struct MemoriaProdutos {
+    produtos: BTreeMap<u64, Produto>,
+    ultimo_id: u64,
+    movimentos: Vec<Movimento>,
+    categorias: BTreeMap<u64, Categoria>,
+    ultima_categoria: u64,
+    promocoes: BTreeMap<u64, Promocao>,
+    ultima_promocao: u64
+}
+
+impl MemoriaProdutos {
+    pub fn new() -> Self {
+        MemoriaProdutos::default()
+    }
+}
+
+// Começa com as categorias iniciais, como um estoque novo.
+impl Default for MemoriaProdutos {
+    fn default() -> Self {
+        let categorias: BTreeMap<u64, Categoria> = categories::built_in().into_iter().map(|category| (category.id, category)).collect();
+
+        MemoriaProdutos {
+            produtos: BTreeMap::new(),
+            ultimo_id: 0,
+            movimentos: Vec::new(),
+            ultima_categoria: categorias.len() as u64,
+            categorias,
+            promocoes: BTreeMap::new(),
+            ultima_promocao: 0
+        }
+    }
+}
+
+impl ProductRepository for MemoriaProdutos {
+    fn last_id(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.ultimo_id)
+    }
+
+    fn get(&mut self, id: u64) -> Result<Produto, Box<dyn Error>> {
+        Ok(self.produtos.get(&id).cloned().ok_or(errors::CustomErrors::ProductNotFound)?)
+    }
+
+    fn list(&mut self) -> Result<Vec<Produto>, Box<dyn Error>> {
+        Ok(self.produtos.values().cloned().collect())
+    }
+
+    fn barcode_id(&mut self, barcode: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self.produtos.values().find(|product| product.codigo_barras.as_deref() == Some(barcode)).map(|product| product.id))
+    }
+
+    fn insert(&mut self, mut product: Produto) -> Result<u64, Box<dyn Error>> {
+        self.ultimo_id += 1;
+        product.id = self.ultimo_id;
+        self.produtos.insert(product.id, product);
+
+        Ok(self.ultimo_id)
+    }
+
+    fn update(&mut self, product: &Produto) -> Result<(), Box<dyn Error>> {
+        let stored = self.produtos.get_mut(&product.id).ok_or(errors::CustomErrors::ProductNotFound)?;
+        *stored = product.clone();
+
+        Ok(())
+    }
+
+    fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        self.produtos.remove(&id).ok_or(errors::CustomErrors::ProductNotFound)?;
+
+        Ok(())
+    }
+
+    fn movements(&mut self, id: u64) -> Result<Vec<Movimento>, Box<dyn Error>> {
+        Ok(self.movimentos.iter().filter(|movement| movement.produto == id).cloned().collect())
+    }
+
+    fn balances(&mut self) -> Result<BTreeMap<u64, i64>, Box<dyn Error>> {
+        let mut balances = BTreeMap::new();
+
+        for movement in self.movimentos.iter() {
+            *balances.entry(movement.produto).or_insert(0) += movement.quantidade;
+        }
+
+        Ok(balances)
+    }
+
+    fn append_movements(&mut self, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        for mut movement in movements {
+            movement.id = self.movimentos.len() as u64 + 1;
+            self.movimentos.push(movement);
+        }
+
+        Ok(())
+    }
+
+    fn categories(&mut self) -> Result<Vec<Categoria>, Box<dyn Error>> {
+        Ok(self.categorias.values().cloned().collect())
+    }
+
+    fn insert_category(&mut self, mut category: Categoria) -> Result<u64, Box<dyn Error>> {
+        self.ultima_categoria += 1;
+        category.id = self.ultima_categoria;
+        self.categorias.insert(category.id, category);
+
+        Ok(self.ultima_categoria)
+    }
+
+    fn update_category(&mut self, category: &Categoria) -> Result<(), Box<dyn Error>> {
+        let stored = self.categorias.get_mut(&category.id).ok_or(errors::CustomErrors::CategoryNotFound)?;
+        *stored = category.clone();
+
+        Ok(())
+    }
+
+    fn remove_category(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        self.categorias.remove(&id).ok_or(errors::CustomErrors::CategoryNotFound)?;
+
+        Ok(())
+    }
+
+    fn promotions(&mut self) -> Result<Vec<Promocao>, Box<dyn Error>> {
+        Ok(self.promocoes.values().cloned().collect())
+    }
+
+    fn insert_promotion(&mut self, mut promotion: Promocao) -> Result<u64, Box<dyn Error>> {
+        self.ultima_promocao += 1;
+        promotion.id = self.ultima_promocao;
+        self.promocoes.insert(promotion.id, promotion);
+
+        Ok(self.ultima_promocao)
+    }
+
+    fn update_promotion(&mut self, promotion: &Promocao) -> Result<(), Box<dyn Error>> {
+        let stored = self.promocoes.get_mut(&promotion.id).ok_or(errors::CustomErrors::PromotionNotFound)?;
+        *stored = promotion.clone();
+
+        Ok(())
+    }
+
+    fn remove_promotion(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        self.promocoes.remove(&id).ok_or(errors::CustomErrors::PromotionNotFound)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct MemoriaVendas {
+    vendas: BTreeMap<u64, Venda>,
+    ultimo_codigo: u64
+}
+
+impl MemoriaVendas {
+    pub fn new() -> Self {
+        MemoriaVendas::default()
+    }
+
+    fn filter<F: Fn(&Venda) -> bool>(&self, predicate: F) -> Vec<Venda> {
+        self.vendas.values().filter(|sale| predicate(sale)).cloned().collect()
+    }
+}
+
+impl SaleRepository for MemoriaVendas {
+    fn last_code(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.ultimo_codigo)
+    }
+
+    fn get(&mut self, code: u64) -> Result<Venda, Box<dyn Error>> {
+        Ok(self.vendas.get(&code).cloned().ok_or(errors::CustomErrors::SaleNotFound)?)
+    }
+
+    fn list(&mut self) -> Result<Vec<Venda>, Box<dyn Error>> {
+        Ok(self.filter(|_| true))
+    }
+
+    fn by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, Box<dyn Error>> {
+        Ok(self.filter(|sale| sale.data == date))
+    }
+
+    fn by_range(&mut self, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let mut sales = self.filter(|sale| from.is_none_or(|from| sale.data >= from) && to.is_none_or(|to| sale.data <= to));
+        sales.sort_by_key(|sale| sale.data);
+
+        Ok(sales)
+    }
+
+    fn by_product(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        Ok(self.filter(|sale| sale.produtos.iter().any(|item| item.id == id)))
+    }
+
+    fn by_session(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        Ok(self.filter(|sale| sale.caixa == Some(id)))
+    }
+
+    fn insert(&mut self, mut sale: Venda) -> Result<u64, Box<dyn Error>> {
+        self.ultimo_codigo += 1;
+        sale.codigo = self.ultimo_codigo;
+        self.vendas.insert(sale.codigo, sale);
+
+        Ok(self.ultimo_codigo)
+    }
+
+    fn import(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        if self.vendas.contains_key(&sale.codigo) {
+            return Err(Box::new(errors::CustomErrors::DuplicateSale));
+        }
+
+        self.ultimo_codigo = self.ultimo_codigo.max(sale.codigo);
+        self.vendas.insert(sale.codigo, sale.clone());
+
+        Ok(sale.codigo)
+    }
+
+    fn rewrite(&mut self, sale: &Venda) -> Result<(), Box<dyn Error>> {
+        let stored = self.vendas.get_mut(&sale.codigo).ok_or(errors::CustomErrors::SaleNotFound)?;
+        *stored = sale.clone();
+
+        Ok(())
+    }
+
+    fn remove(&mut self, code: u64) -> Result<(), Box<dyn Error>> {
+        self.vendas.remove(&code).ok_or(errors::CustomErrors::SaleNotFound)?;
+
+        Ok(())
+    }
+}
+
+// Em memória as gravações não falham no meio, então vendas e alterações de estoque dispensam o diário de transações.
+#[derive(Default, Debug)]
+pub struct SemDiario;
+
+impl StockTransaction<MemoriaProdutos> for SemDiario {
+    fn insert_products<F: FnMut(&Produto) -> Vec<Movimento>>(&mut self, products: &mut MemoriaProdutos, new_products: Vec<Produto>, mut movements: F) -> Result<Vec<u64>, Box<dyn Error>> {
+        let mut ids = Vec::with_capacity(new_products.len());
+
+        for mut product in new_products {
+            product.id = products.insert(product.clone())?;
+            products.append_movements(movements(&product))?;
+            ids.push(product.id);
+        }
+
+        Ok(ids)
+    }
+
+    fn update_products(&mut self, products: &mut MemoriaProdutos, updated_products: &[Produto], movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        for product in updated_products.iter() {
+            products.update(product)?;
+        }
+
+        products.append_movements(movements)
+    }
+
+    fn remove_product(&mut self, products: &mut MemoriaProdutos, id: u64, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        products.remove(id)?;
+        products.append_movements(movements)
+    }
+
+    fn update_catalog<R, F: FnOnce(&mut MemoriaProdutos) -> Result<R, Box<dyn Error>>>(&mut self, products: &mut MemoriaProdutos, _: &[u64], write: F) -> Result<R, Box<dyn Error>> {
+        write(products)
+    }
+}
+
+impl SaleTransaction<MemoriaProdutos, MemoriaVendas> for SemDiario {
+    fn insert_sale<F: FnOnce(&Venda) -> Vec<Movimento>>(&mut self, products: &mut MemoriaProdutos, sales: &mut MemoriaVendas, updated_products: &[Produto], mut sale: Venda, movements: F) -> Result<Venda, Box<dyn Error>> {
+        for product in updated_products.iter() {
+            products.update(product)?;
+        }
+
+        sale.codigo = sales.insert(sale.clone())?;
+        products.append_movements(movements(&sale))?;
+
+        Ok(sale)
+    }
+
+    fn update_sale(&mut self, products: &mut MemoriaProdutos, sales: &mut MemoriaVendas, updated_products: &[Produto], sale: &Venda, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        for product in updated_products.iter() {
+            products.update(product)?;
+        }
+
+        sales.rewrite(sale)?;
+        products.append_movements(movements)
+    }
+
+    fn insert_sales(&mut self, _: &mut MemoriaProdutos, sales: &mut MemoriaVendas, new_sales: Vec<Venda>) -> Result<Vec<u64>, Box<dyn Error>> {
+        new_sales.into_iter().map(|sale| sales.import(sale)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Dinheiro, MetodoPagamento, TipoMovimento};
+
+    fn product(nome: &str, estoque: u64) -> Produto {
+        Produto::new(nome.to_string(), 0, estoque, Dinheiro::from_centavos(1000), 2, chrono::NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), categories::GERAL)
+    }
+
+    fn sale(date: (i32, u32, u32), product_id: u64, session: Option<u64>) -> Venda {
+        let mut sale = Venda::new("Ana".to_string(), 0, Dinheiro::from_centavos(1000), chrono::NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(), MetodoPagamento::Pix);
+        sale.produtos.push(crate::ItemVenda::new(product_id, "Teste".to_string(), 1, Dinheiro::from_centavos(1000)).unwrap());
+        sale.caixa = session;
+
+        sale
+    }
+
+    #[test]
+    fn test_memory_products() {
+        let mut products = MemoriaProdutos::new();
+
+        assert_eq!(products.insert(product("Arroz", 5)).unwrap(), 1);
+        assert_eq!(products.insert(product("Feijão", 3)).unwrap(), 2);
+
+        let mut found = products.get(2).unwrap();
+        assert_eq!(found.nome, "Feijão");
+
+        found.quantidade_estoque = 7;
+        products.update(&found).unwrap();
+        assert_eq!(products.get(2).unwrap().quantidade_estoque, 7);
+
+        products.remove(1).unwrap();
+        assert!(products.get(1).is_err());
+        assert!(products.remove(1).is_err());
+
+        // Os ids não são reaproveitados depois de uma exclusão.
+        assert_eq!(products.insert(product("Açúcar", 1)).unwrap(), 3);
+        assert_eq!(products.list().unwrap().iter().map(|product| product.id).collect::<Vec<u64>>(), vec![2, 3]);
+
+        products.append_movements(vec![
+            Movimento::new(2, TipoMovimento::Inicial, 3, "Ana".to_string(), String::new()),
+            Movimento::new(2, TipoMovimento::Ajuste, 4, "Ana".to_string(), String::new()),
+            Movimento::new(3, TipoMovimento::Inicial, 1, "Ana".to_string(), String::new())
+        ]).unwrap();
+
+        assert_eq!(products.movements(2).unwrap().iter().map(|movement| movement.id).collect::<Vec<u64>>(), vec![1, 2]);
+        assert_eq!(products.balances().unwrap()[&2], 7);
+    }
+
+    #[test]
+    fn test_memory_sales() {
+        let mut sales = MemoriaVendas::new();
+
+        sales.insert(sale((2024, 5, 21), 1, Some(1))).unwrap();
+        sales.insert(sale((2024, 5, 20), 2, None)).unwrap();
+        sales.insert(sale((2024, 5, 22), 1, Some(1))).unwrap();
+
+        let codes = |sales: Vec<Venda>| sales.into_iter().map(|sale| sale.codigo).collect::<Vec<u64>>();
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 5, day);
+
+        assert_eq!(sales.last_code().unwrap(), 3);
+        assert_eq!(codes(sales.by_range(None, None).unwrap()), vec![2, 1, 3]);
+        assert_eq!(codes(sales.by_range(date(21), None).unwrap()), vec![1, 3]);
+        assert_eq!(codes(sales.by_date(date(20).unwrap()).unwrap()), vec![2]);
+        assert_eq!(codes(sales.by_product(1).unwrap()), vec![1, 3]);
+        assert_eq!(codes(sales.by_session(1).unwrap()), vec![1, 3]);
+
+        let mut found = sales.get(2).unwrap();
+        found.vendedor = "Bia".to_string();
+        sales.rewrite(&found).unwrap();
+        assert_eq!(sales.get(2).unwrap().vendedor, "Bia");
+
+        sales.remove(2).unwrap();
+        assert!(sales.get(2).is_err());
+        assert_eq!(codes(sales.list().unwrap()), vec![1, 3]);
+    }
+}
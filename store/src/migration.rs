@@ -0,0 +1,1269 @@
+use std::{error::Error, fs::{self, File}, io::{Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use super::{errors::CustomErrors, format::{self, Cabecalho, PRODUCTS_MAGIC, PRODUCTS_VERSION, SALES_MAGIC, SALES_VERSION, SESSIONS_MAGIC, SESSIONS_VERSION, MOVEMENTS_MAGIC, MOVEMENTS_VERSION, ORDERS_MAGIC, ORDERS_VERSION, CATEGORIES_MAGIC, CATEGORIES_VERSION, PROMOTIONS_MAGIC, PROMOTIONS_VERSION}, categories, money::Dinheiro, Devolucao, ItemVenda, MetodoPagamento, Movimento, Produto, PromocaoAplicada, Situacao, TipoMovimento, Venda};
+
+const PRODUCT_LENGTH: usize = 102;
+
+// Categorias fixas das versões 1 a 6, gravadas pelo índice da variante.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+enum CategoriaFixa {
+    Eletronico,
+    Roupa,
+    Alimento,
+    Geral
+}
+
+impl CategoriaFixa {
+    fn id(self) -> u64 {
+        match self {
+            CategoriaFixa::Eletronico => categories::ELETRONICO,
+            CategoriaFixa::Roupa => categories::ROUPA,
+            CategoriaFixa::Alimento => categories::ALIMENTO,
+            CategoriaFixa::Geral => categories::GERAL
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProdutoF64 {
+    nome: String,
+    id: u64,
+    quantidade_estoque: u64,
+    valor: f64,
+    quantidade_restoque: u64,
+    categoria: CategoriaFixa,
+    #[serde(with = "crate::date")]
+    data_restoque: chrono::NaiveDate
+}
+
+// Registro de produto das versões 1 a 4, anterior ao fornecedor e ao custo.
+#[derive(Serialize, Deserialize)]
+struct ProdutoSemFornecedor {
+    nome: String,
+    id: u64,
+    quantidade_estoque: u64,
+    valor: Dinheiro,
+    quantidade_restoque: u64,
+    categoria: CategoriaFixa,
+    #[serde(with = "crate::date")]
+    data_restoque: chrono::NaiveDate
+}
+
+// Registro de produto da versão 5, anterior ao código de barras.
+#[derive(Serialize, Deserialize)]
+struct ProdutoSemCodigoBarras {
+    nome: String,
+    id: u64,
+    quantidade_estoque: u64,
+    valor: Dinheiro,
+    quantidade_restoque: u64,
+    categoria: CategoriaFixa,
+    #[serde(with = "crate::date")]
+    data_restoque: chrono::NaiveDate,
+    fornecedor: Option<String>,
+    custo_unitario: Option<Dinheiro>
+}
+
+// Registro de produto da versão 6, anterior às categorias cadastradas.
+#[derive(Serialize, Deserialize)]
+struct ProdutoSemCategorias {
+    nome: String,
+    id: u64,
+    quantidade_estoque: u64,
+    valor: Dinheiro,
+    quantidade_restoque: u64,
+    categoria: CategoriaFixa,
+    #[serde(with = "crate::date")]
+    data_restoque: chrono::NaiveDate,
+    fornecedor: Option<String>,
+    custo_unitario: Option<Dinheiro>,
+    codigo_barras: Option<String>
+}
+
+// Registro de produto da versão 7, anterior aos dados fiscais.
+#[derive(Serialize, Deserialize)]
+struct ProdutoSemTributacao {
+    nome: String,
+    id: u64,
+    quantidade_estoque: u64,
+    valor: Dinheiro,
+    quantidade_restoque: u64,
+    categoria: u64,
+    #[serde(with = "crate::date")]
+    data_restoque: chrono::NaiveDate,
+    fornecedor: Option<String>,
+    custo_unitario: Option<Dinheiro>,
+    codigo_barras: Option<String>
+}
+
+// Movimento da versão 1, anterior aos dados do recebimento.
+#[derive(Serialize, Deserialize)]
+struct MovimentoSemRecebimento {
+    id: u64,
+    produto: u64,
+    tipo: TipoMovimento,
+    quantidade: i64,
+    #[serde(with = "crate::timestamp")]
+    data: chrono::NaiveDateTime,
+    operador: String,
+    motivo: String
+}
+
+#[derive(Deserialize)]
+struct ItemVendaF64 {
+    id: u64,
+    nome: String,
+    quantidade: u64,
+    valor_unitario: f64,
+    valor_total: f64
+}
+
+#[derive(Deserialize)]
+struct VendaF64 {
+    vendedor: String,
+    produtos: Vec<ItemVendaF64>,
+    codigo: u64,
+    valor: f64,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate
+}
+
+// Item de venda das versões 2 a 6, anterior aos descontos.
+#[derive(Serialize, Deserialize)]
+struct ItemVendaSemDesconto {
+    id: u64,
+    nome: String,
+    quantidade: u64,
+    valor_unitario: Dinheiro,
+    valor_total: Dinheiro
+}
+
+impl ItemVendaSemDesconto {
+    fn legado(id: u64) -> Self {
+        ItemVendaSemDesconto {
+            id,
+            nome: String::new(),
+            quantidade: 0,
+            valor_unitario: Dinheiro::ZERO,
+            valor_total: Dinheiro::ZERO
+        }
+    }
+}
+
+// Registro de venda das versões 3 e 4, anterior ao campo do caixa.
+#[derive(Serialize, Deserialize)]
+struct VendaSemCaixa {
+    vendedor: String,
+    produtos: Vec<ItemVendaSemDesconto>,
+    codigo: u64,
+    valor: Dinheiro,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate
+}
+
+// Registro de venda da versão 5, anterior à situação e às devoluções.
+#[derive(Serialize, Deserialize)]
+struct VendaComCaixa {
+    vendedor: String,
+    produtos: Vec<ItemVendaSemDesconto>,
+    codigo: u64,
+    valor: Dinheiro,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate,
+    caixa: Option<u64>
+}
+
+// Registro de venda da versão 6, anterior aos descontos e às promoções.
+#[derive(Serialize, Deserialize)]
+struct VendaSemDescontos {
+    vendedor: String,
+    produtos: Vec<ItemVendaSemDesconto>,
+    codigo: u64,
+    valor: Dinheiro,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate,
+    caixa: Option<u64>,
+    situacao: Situacao,
+    devolucoes: Vec<Devolucao>
+}
+
+// Item de venda da versão 7, anterior aos dados fiscais.
+#[derive(Serialize, Deserialize)]
+struct ItemVendaSemTributacao {
+    id: u64,
+    nome: String,
+    quantidade: u64,
+    valor_unitario: Dinheiro,
+    valor_total: Dinheiro,
+    desconto: Dinheiro
+}
+
+// Registro de venda da versão 7, anterior aos dados fiscais dos produtos vendidos.
+#[derive(Serialize, Deserialize)]
+struct VendaSemTributacao {
+    vendedor: String,
+    produtos: Vec<ItemVendaSemTributacao>,
+    codigo: u64,
+    valor: Dinheiro,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate,
+    caixa: Option<u64>,
+    situacao: Situacao,
+    devolucoes: Vec<Devolucao>,
+    desconto: Dinheiro,
+    promocoes: Vec<PromocaoAplicada>
+}
+
+#[derive(Deserialize)]
+struct VendaLegada {
+    vendedor: String,
+    produtos: Vec<u64>,
+    codigo: u64,
+    valor: f64,
+    metodo_pagamento: MetodoPagamento,
+    #[serde(with = "crate::date")]
+    data: chrono::NaiveDate
+}
+
+fn to_money(valor: f64) -> Result<Dinheiro, CustomErrors> {
+    // Valores já migrados, lidos como f64, resultam em números minúsculos ou inválidos.
+    if !valor.is_finite() || valor < 0.0 || (valor > 0.0 && valor < 0.01) {
+        return Err(CustomErrors::FormatMismatch);
+    }
+
+    let centavos = (valor * 100.0).round();
+
+    match centavos < i64::MAX as f64 {
+        true => Ok(Dinheiro::from_centavos(centavos as i64)),
+        false => Err(CustomErrors::ValueOverflow)
+    }
+}
+
+fn convert_product(buf: &[u8]) -> Result<ProdutoSemFornecedor, Box<dyn Error>> {
+    let legacy: ProdutoF64 = bincode::deserialize(buf)?;
+
+    Ok(ProdutoSemFornecedor {
+        nome: legacy.nome,
+        id: legacy.id,
+        quantidade_estoque: legacy.quantidade_estoque,
+        valor: to_money(legacy.valor)?,
+        quantidade_restoque: legacy.quantidade_restoque,
+        categoria: legacy.categoria,
+        data_restoque: legacy.data_restoque
+    })
+}
+
+fn convert_sale(buf: &[u8]) -> Result<VendaSemCaixa, Box<dyn Error>> {
+    let options = bincode::DefaultOptions::new().with_fixint_encoding();
+
+    if let Ok(legacy) = options.deserialize::<VendaF64>(buf) {
+        let mut produtos = Vec::with_capacity(legacy.produtos.len());
+
+        for item in legacy.produtos {
+            produtos.push(ItemVendaSemDesconto {
+                id: item.id,
+                nome: item.nome,
+                quantidade: item.quantidade,
+                valor_unitario: to_money(item.valor_unitario)?,
+                valor_total: to_money(item.valor_total)?
+            });
+        }
+
+        return Ok(VendaSemCaixa {
+            vendedor: legacy.vendedor,
+            produtos,
+            codigo: legacy.codigo,
+            valor: to_money(legacy.valor)?,
+            metodo_pagamento: legacy.metodo_pagamento,
+            data: legacy.data
+        });
+    }
+
+    match options.deserialize::<VendaLegada>(buf) {
+        Ok(legacy) => Ok(VendaSemCaixa {
+            vendedor: legacy.vendedor,
+            produtos: legacy.produtos.into_iter().map(ItemVendaSemDesconto::legado).collect(),
+            codigo: legacy.codigo,
+            valor: to_money(legacy.valor)?,
+            metodo_pagamento: legacy.metodo_pagamento,
+            data: legacy.data
+        }),
+        Err(_) => Err(Box::new(CustomErrors::FormatMismatch))
+    }
+}
+
+fn read_all(file: &mut File) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buf = Vec::new();
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut buf)?;
+
+    Ok(buf)
+}
+
+fn replace_contents(file: &mut File, contents: &[u8]) -> Result<(), Box<dyn Error>> {
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(contents)?;
+    file.set_len(contents.len() as u64)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+type Conversao = fn(&[u8]) -> Result<Vec<u8>, Box<dyn Error>>;
+type Deteccao = fn(&[u8]) -> Result<u32, Box<dyn Error>>;
+type Registros<'a> = (&'a [u8], &'a [u8]);
+
+fn split_trailer(bytes: &[u8]) -> Result<Registros<'_>, Box<dyn Error>> {
+    match bytes.len() {
+        0 => Ok((&[], &[0; 8])),
+        length if length < 8 => Err(Box::new(CustomErrors::FormatMismatch)),
+        length => Ok(bytes.split_at(length - 8))
+    }
+}
+
+fn sized_records(mut records: &[u8]) -> Result<Vec<&[u8]>, Box<dyn Error>> {
+    let mut sales = Vec::new();
+
+    while !records.is_empty() {
+        let size: u64 = bincode::deserialize(records)?;
+        let end = 8 + size as usize;
+
+        if records.len() < end {
+            return Err(Box::new(CustomErrors::FormatMismatch));
+        }
+
+        sales.push(&records[8..end]);
+        records = &records[end..];
+    }
+
+    Ok(sales)
+}
+
+fn with_header(magica: [u8; 4], versao: u32, quantidade: u64, last_id: &[u8], records: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let header = Cabecalho {
+        magica,
+        versao,
+        quantidade,
+        ultimo_id: bincode::deserialize(last_id)?
+    };
+
+    let mut upgraded = bincode::serialize(&header)?;
+    upgraded.extend(records);
+
+    Ok(upgraded)
+}
+
+// Versões 1 a 3 não possuem cabeçalho; terminam com o último id usado.
+fn detect_products(bytes: &[u8]) -> Result<u32, Box<dyn Error>> {
+    let (records, _) = split_trailer(bytes)?;
+
+    let options = bincode::DefaultOptions::new().with_fixint_encoding();
+    let sized = sized_records(records).map(|records| records.iter().all(|record| options.deserialize::<ProdutoSemFornecedor>(record).is_ok()));
+
+    if let Ok(true) = sized {
+        return Ok(3);
+    }
+
+    if records.len() % PRODUCT_LENGTH != 0 {
+        return Err(Box::new(CustomErrors::FormatMismatch));
+    }
+
+    match records.chunks(PRODUCT_LENGTH).all(|record| convert_product(record).is_ok()) {
+        true => Ok(1),
+        false => Ok(2)
+    }
+}
+
+fn products_money(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (records, last_id) = split_trailer(bytes)?;
+    let mut upgraded = Vec::with_capacity(bytes.len());
+
+    for record in records.chunks(PRODUCT_LENGTH) {
+        let mut serialized = bincode::serialize(&convert_product(record)?)?;
+        serialized.resize(PRODUCT_LENGTH, 0);
+
+        upgraded.extend(serialized);
+    }
+
+    upgraded.extend(last_id);
+
+    Ok(upgraded)
+}
+
+fn products_records(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (records, last_id) = split_trailer(bytes)?;
+    let mut upgraded = Vec::with_capacity(bytes.len());
+
+    for record in records.chunks(PRODUCT_LENGTH) {
+        let product: ProdutoSemFornecedor = bincode::deserialize(record)?;
+        let serialized = bincode::serialize(&product)?;
+
+        upgraded.extend(bincode::serialize(&(serialized.len() as u64))?);
+        upgraded.extend(serialized);
+    }
+
+    upgraded.extend(last_id);
+
+    Ok(upgraded)
+}
+
+fn products_header(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (records, last_id) = split_trailer(bytes)?;
+    let count = sized_records(records)?.len() as u64;
+
+    with_header(PRODUCTS_MAGIC, 4, count, last_id, records)
+}
+
+fn detect_sales(bytes: &[u8]) -> Result<u32, Box<dyn Error>> {
+    let (records, _) = split_trailer(bytes)?;
+
+    match sized_records(records)?.into_iter().all(|record| convert_sale(record).is_ok()) {
+        true if !records.is_empty() => Ok(2),
+        _ => Ok(3)
+    }
+}
+
+fn sales_money(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (records, last_code) = split_trailer(bytes)?;
+    let mut upgraded = Vec::with_capacity(bytes.len());
+
+    for record in sized_records(records)? {
+        let serialized = bincode::serialize(&convert_sale(record)?)?;
+
+        upgraded.extend(bincode::serialize(&(serialized.len() as u64))?);
+        upgraded.extend(serialized);
+    }
+
+    upgraded.extend(last_code);
+
+    Ok(upgraded)
+}
+
+fn sales_header(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (records, last_code) = split_trailer(bytes)?;
+    let count = sized_records(records)?.len() as u64;
+
+    with_header(SALES_MAGIC, 4, count, last_code, records)
+}
+
+fn upgrade_records(bytes: &[u8], magica: [u8; 4], versao: u32, convert: Conversao) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut header = format::parse_header(bytes, magica)?;
+    header.versao = versao;
+
+    let mut upgraded = bincode::serialize(&header)?;
+
+    for record in sized_records(&bytes[format::HEADER_LENGTH as usize..])? {
+        let serialized = convert(record)?;
+
+        upgraded.extend(bincode::serialize(&(serialized.len() as u64))?);
+        upgraded.extend(serialized);
+    }
+
+    Ok(upgraded)
+}
+
+fn sales_session(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, SALES_MAGIC, 5, |record| {
+        let old: VendaSemCaixa = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&VendaComCaixa {
+            vendedor: old.vendedor,
+            produtos: old.produtos,
+            codigo: old.codigo,
+            valor: old.valor,
+            metodo_pagamento: old.metodo_pagamento,
+            data: old.data,
+            caixa: None
+        })?)
+    })
+}
+
+fn sales_returns(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, SALES_MAGIC, 6, |record| {
+        let old: VendaComCaixa = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&VendaSemDescontos {
+            vendedor: old.vendedor,
+            produtos: old.produtos,
+            codigo: old.codigo,
+            valor: old.valor,
+            metodo_pagamento: old.metodo_pagamento,
+            data: old.data,
+            caixa: old.caixa,
+            situacao: Situacao::Concluida,
+            devolucoes: Vec::new()
+        })?)
+    })
+}
+
+// As vendas anteriores não tiveram descontos nem promoções.
+fn sales_discounts(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, SALES_MAGIC, 7, |record| {
+        let old: VendaSemDescontos = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&VendaSemTributacao {
+            vendedor: old.vendedor,
+            produtos: old.produtos.into_iter()
+                .map(|item| ItemVendaSemTributacao {
+                    id: item.id,
+                    nome: item.nome,
+                    quantidade: item.quantidade,
+                    valor_unitario: item.valor_unitario,
+                    valor_total: item.valor_total,
+                    desconto: Dinheiro::ZERO
+                })
+                .collect(),
+            codigo: old.codigo,
+            valor: old.valor,
+            metodo_pagamento: old.metodo_pagamento,
+            data: old.data,
+            caixa: old.caixa,
+            situacao: old.situacao,
+            devolucoes: old.devolucoes,
+            desconto: Dinheiro::ZERO,
+            promocoes: Vec::new()
+        })?)
+    })
+}
+
+// As linhas das vendas anteriores ficam sem dados fiscais.
+fn sales_taxes(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, SALES_MAGIC, 8, |record| {
+        let old: VendaSemTributacao = bincode::deserialize(record)?;
+
+        let mut sale = Venda::new(old.vendedor, old.codigo, old.valor, old.data, old.metodo_pagamento);
+        sale.caixa = old.caixa;
+        sale.situacao = old.situacao;
+        sale.devolucoes = old.devolucoes;
+        sale.desconto = old.desconto;
+        sale.promocoes = old.promocoes;
+        sale.produtos = old.produtos.into_iter()
+            .map(|item| ItemVenda {
+                id: item.id,
+                nome: item.nome,
+                quantidade: item.quantidade,
+                valor_unitario: item.valor_unitario,
+                valor_total: item.valor_total,
+                desconto: item.desconto,
+                tributacao: None
+            })
+            .collect();
+
+        Ok(bincode::serialize(&sale)?)
+    })
+}
+
+fn products_supplier(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, PRODUCTS_MAGIC, 5, |record| {
+        let old: ProdutoSemFornecedor = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&ProdutoSemCodigoBarras {
+            nome: old.nome,
+            id: old.id,
+            quantidade_estoque: old.quantidade_estoque,
+            valor: old.valor,
+            quantidade_restoque: old.quantidade_restoque,
+            categoria: old.categoria,
+            data_restoque: old.data_restoque,
+            fornecedor: None,
+            custo_unitario: None
+        })?)
+    })
+}
+
+fn products_barcode(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, PRODUCTS_MAGIC, 6, |record| {
+        let old: ProdutoSemCodigoBarras = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&ProdutoSemCategorias {
+            nome: old.nome,
+            id: old.id,
+            quantidade_estoque: old.quantidade_estoque,
+            valor: old.valor,
+            quantidade_restoque: old.quantidade_restoque,
+            categoria: old.categoria,
+            data_restoque: old.data_restoque,
+            fornecedor: old.fornecedor,
+            custo_unitario: old.custo_unitario,
+            codigo_barras: None
+        })?)
+    })
+}
+
+// As quatro categorias fixas passam a ser as categorias iniciais do arquivo de categorias.
+fn products_categories(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, PRODUCTS_MAGIC, 7, |record| {
+        let old: ProdutoSemCategorias = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&ProdutoSemTributacao {
+            nome: old.nome,
+            id: old.id,
+            quantidade_estoque: old.quantidade_estoque,
+            valor: old.valor,
+            quantidade_restoque: old.quantidade_restoque,
+            categoria: old.categoria.id(),
+            data_restoque: old.data_restoque,
+            fornecedor: old.fornecedor,
+            custo_unitario: old.custo_unitario,
+            codigo_barras: old.codigo_barras
+        })?)
+    })
+}
+
+fn products_taxes(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, PRODUCTS_MAGIC, 8, |record| {
+        let old: ProdutoSemTributacao = bincode::deserialize(record)?;
+
+        let mut product = Produto::new(old.nome, old.id, old.quantidade_estoque, old.valor, old.quantidade_restoque, old.data_restoque, old.categoria);
+        product.fornecedor = old.fornecedor;
+        product.custo_unitario = old.custo_unitario;
+        product.codigo_barras = old.codigo_barras;
+
+        Ok(bincode::serialize(&product)?)
+    })
+}
+
+fn movements_receipt(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    upgrade_records(bytes, MOVEMENTS_MAGIC, 2, |record| {
+        let old: MovimentoSemRecebimento = bincode::deserialize(record)?;
+
+        Ok(bincode::serialize(&Movimento {
+            id: old.id,
+            produto: old.produto,
+            tipo: old.tipo,
+            quantidade: old.quantidade,
+            data: old.data,
+            operador: old.operador,
+            motivo: old.motivo,
+            recebimento: None
+        })?)
+    })
+}
+
+// Arquivos criados já com cabeçalho não têm formato anterior a detectar.
+fn detect_none(_: &[u8]) -> Result<u32, Box<dyn Error>> {
+    Err(Box::new(CustomErrors::FormatMismatch))
+}
+
+struct Atualizacao {
+    versao: u32,
+    aplicar: Conversao
+}
+
+pub struct Formato {
+    magica: [u8; 4],
+    versao: u32,
+    detectar: Deteccao,
+    atualizacoes: &'static [Atualizacao]
+}
+
+pub const PRODUCTS: Formato = Formato {
+    magica: PRODUCTS_MAGIC,
+    versao: PRODUCTS_VERSION,
+    detectar: detect_products,
+    atualizacoes: &[
+        Atualizacao { versao: 1, aplicar: products_money },
+        Atualizacao { versao: 2, aplicar: products_records },
+        Atualizacao { versao: 3, aplicar: products_header },
+        Atualizacao { versao: 4, aplicar: products_supplier },
+        Atualizacao { versao: 5, aplicar: products_barcode },
+        Atualizacao { versao: 6, aplicar: products_categories },
+        Atualizacao { versao: 7, aplicar: products_taxes }
+    ]
+};
+
+pub const SALES: Formato = Formato {
+    magica: SALES_MAGIC,
+    versao: SALES_VERSION,
+    detectar: detect_sales,
+    atualizacoes: &[
+        Atualizacao { versao: 2, aplicar: sales_money },
+        Atualizacao { versao: 3, aplicar: sales_header },
+        Atualizacao { versao: 4, aplicar: sales_session },
+        Atualizacao { versao: 5, aplicar: sales_returns },
+        Atualizacao { versao: 6, aplicar: sales_discounts },
+        Atualizacao { versao: 7, aplicar: sales_taxes }
+    ]
+};
+
+pub const SESSIONS: Formato = Formato {
+    magica: SESSIONS_MAGIC,
+    versao: SESSIONS_VERSION,
+    detectar: detect_none,
+    atualizacoes: &[]
+};
+
+pub const MOVEMENTS: Formato = Formato {
+    magica: MOVEMENTS_MAGIC,
+    versao: MOVEMENTS_VERSION,
+    detectar: detect_none,
+    atualizacoes: &[
+        Atualizacao { versao: 1, aplicar: movements_receipt }
+    ]
+};
+
+pub const ORDERS: Formato = Formato {
+    magica: ORDERS_MAGIC,
+    versao: ORDERS_VERSION,
+    detectar: detect_none,
+    atualizacoes: &[]
+};
+
+pub const CATEGORIES: Formato = Formato {
+    magica: CATEGORIES_MAGIC,
+    versao: CATEGORIES_VERSION,
+    detectar: detect_none,
+    atualizacoes: &[]
+};
+
+pub const PROMOTIONS: Formato = Formato {
+    magica: PROMOTIONS_MAGIC,
+    versao: PROMOTIONS_VERSION,
+    detectar: detect_none,
+    atualizacoes: &[]
+};
+
+pub fn file_version(bytes: &[u8], formato: &Formato) -> Result<u32, Box<dyn Error>> {
+    match bytes.starts_with(&formato.magica) {
+        true => Ok(format::parse_header(bytes, formato.magica)?.versao),
+        false => (formato.detectar)(bytes)
+    }
+}
+
+pub fn upgrade(file: &mut File, path: &Path, formato: &Formato) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let mut bytes = read_all(file)?;
+    let versao = file_version(&bytes, formato)?;
+
+    if versao > formato.versao {
+        return Err(Box::new(CustomErrors::NewerFormat));
+    }
+
+    if versao == formato.versao {
+        return Ok(None);
+    }
+
+    let backup = path.with_extension(format!("v{versao}.bak"));
+    fs::write(&backup, &bytes)?;
+
+    for atualizacao in formato.atualizacoes.iter().filter(|atualizacao| atualizacao.versao >= versao) {
+        bytes = (atualizacao.aplicar)(&bytes)?;
+    }
+
+    replace_contents(file, &bytes)?;
+
+    Ok(Some(backup))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{products::{self, ArquivoProdutos}, sales::ArquivoVendas, test_utils::{get_test_categories, get_test_file, get_test_movements, get_test_promotions, test_path}};
+
+    #[derive(Serialize)]
+    struct LegacyProduct {
+        nome: String,
+        id: u64,
+        quantidade_estoque: u64,
+        valor: f64,
+        quantidade_restoque: u64,
+        categoria: CategoriaFixa,
+        #[serde(with = "crate::date")]
+        data_restoque: chrono::NaiveDate
+    }
+
+    #[derive(Serialize)]
+    struct LegacyItem {
+        id: u64,
+        nome: String,
+        quantidade: u64,
+        valor_unitario: f64,
+        valor_total: f64
+    }
+
+    #[derive(Serialize)]
+    struct LegacySale<T> {
+        vendedor: String,
+        produtos: Vec<T>,
+        codigo: u64,
+        valor: f64,
+        metodo_pagamento: MetodoPagamento,
+        #[serde(with = "crate::date")]
+        data: chrono::NaiveDate
+    }
+
+    fn write_sale<T: Serialize>(file: &mut File, sale: &LegacySale<T>) {
+        let buf = bincode::serialize(sale).unwrap();
+
+        file.write_all(&bincode::serialize(&(buf.len() as u64)).unwrap()).unwrap();
+        file.write_all(&buf).unwrap();
+    }
+
+    fn set_legacy_files(products_file: &mut File, sales_file: &mut File) {
+        for (id, valor) in [(1, 0.1), (2, 1500.35)] {
+            let product = LegacyProduct {
+                nome: format!("Produto{id}"),
+                id,
+                quantidade_estoque: 10,
+                valor,
+                quantidade_restoque: 2,
+                categoria: CategoriaFixa::Geral,
+                data_restoque: chrono::NaiveDate::default()
+            };
+
+            let mut buf = bincode::serialize(&product).unwrap();
+            buf.resize(PRODUCT_LENGTH, 0);
+            products_file.write_all(&buf).unwrap();
+        }
+
+        products_file.write_all(&bincode::serialize(&2_u64).unwrap()).unwrap();
+
+        write_sale(sales_file, &LegacySale {
+            vendedor: "Antigo".to_string(),
+            produtos: vec![1_u64, 2],
+            codigo: 1,
+            valor: 20.3,
+            metodo_pagamento: MetodoPagamento::Dinheiro,
+            data: chrono::NaiveDate::default()
+        });
+
+        write_sale(sales_file, &LegacySale {
+            vendedor: "Novo".to_string(),
+            produtos: vec![LegacyItem { id: 1, nome: "Produto1".to_string(), quantidade: 3, valor_unitario: 0.1, valor_total: 0.30000000000000004 }],
+            codigo: 2,
+            valor: 0.30000000000000004,
+            metodo_pagamento: MetodoPagamento::Pix,
+            data: chrono::NaiveDate::default()
+        });
+
+        sales_file.write_all(&bincode::serialize(&2_u64).unwrap()).unwrap();
+    }
+
+    fn remove_test_files(paths: &[&Path]) {
+        for path in paths {
+            fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+        }
+    }
+
+    #[test]
+    fn test_upgrade_legacy_files() {
+        let path_products = &test_path("test_upgrade_legacy_1.bin");
+        let path_sales = &test_path("test_upgrade_legacy_2.bin");
+        let path_index = &path_products.with_extension("idx");
+        let path_movements = &path_products.with_extension("mov");
+        let path_categories = &path_products.with_extension("cat");
+        let path_promotions = &path_products.with_extension("prm");
+
+        let mut products_file = get_test_file(path_products);
+        let mut sales_file = get_test_file(path_sales);
+
+        set_legacy_files(&mut products_file, &mut sales_file);
+
+        let legacy_products = read_all(&mut products_file).unwrap();
+
+        assert_eq!(file_version(&legacy_products, &PRODUCTS).unwrap(), 1);
+
+        let products_backup = upgrade(&mut products_file, path_products, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+        let sales_backup = upgrade(&mut sales_file, path_sales, &SALES).unwrap().expect("O arquivo de vendas não foi atualizado.");
+
+        assert_eq!(fs::read(&products_backup).unwrap(), legacy_products);
+
+        let mut products = ArquivoProdutos::new(products_file, get_test_file(path_index), get_test_movements(path_movements), get_test_categories(path_categories), get_test_promotions(path_promotions));
+        products.rebuild_index().unwrap();
+
+        let product = crate::core::search_product_id(&mut products, 2).expect("Erro na busca pelo produto.");
+
+        assert_eq!(product.valor, Dinheiro::from_centavos(150035));
+        assert_eq!(products.header().unwrap().quantidade, 2);
+        assert_eq!(products.last_id().unwrap(), 2);
+
+        let path_sales_index = &path_sales.with_extension("idx");
+        let mut sales = ArquivoVendas::open(sales_file, get_test_file(path_sales_index)).unwrap();
+
+        let sale = crate::core::search_sale_code(&mut sales, 1).expect("Erro na busca pela venda.");
+
+        assert_eq!(sale.vendedor, "Antigo");
+        assert_eq!(sale.valor, Dinheiro::from_centavos(2030));
+        assert_eq!(sale.produtos.len(), 2);
+        assert_eq!(sale.produtos[1].id, 2);
+        assert_eq!(sale.produtos[1].quantidade, 0);
+
+        let sale = crate::core::search_sale_code(&mut sales, 2).expect("Erro na busca pela venda.");
+
+        assert_eq!(sale.valor, Dinheiro::from_centavos(30));
+        assert_eq!(sale.produtos[0].quantidade, 3);
+        assert_eq!(sale.produtos[0].valor_unitario, Dinheiro::from_centavos(10));
+        assert_eq!(sale.produtos[0].valor_total, Dinheiro::from_centavos(30));
+
+        let header = sales.header().unwrap();
+
+        assert_eq!((header.versao, header.quantidade, header.ultimo_id), (SALES_VERSION, 2, 2));
+
+        assert!(upgrade(&mut products.dados, path_products, &PRODUCTS).unwrap().is_none());
+        assert!(upgrade(&mut sales.dados, path_sales, &SALES).unwrap().is_none());
+
+        remove_test_files(&[path_products, path_sales, path_index, path_movements, path_categories, path_promotions, path_sales_index, &products_backup, &sales_backup]);
+    }
+
+    #[test]
+    fn test_upgrade_fixed_width_records() {
+        let path = &test_path("test_upgrade_fixed_width.bin");
+        let mut file = get_test_file(path);
+
+        for id in 1..=3 {
+            let product = ProdutoSemFornecedor {
+                nome: format!("Produto{id}"),
+                id,
+                quantidade_estoque: 10,
+                valor: Dinheiro::from_centavos(100),
+                quantidade_restoque: 2,
+                categoria: CategoriaFixa::Geral,
+                data_restoque: chrono::NaiveDate::default()
+            };
+
+            let mut buf = bincode::serialize(&product).unwrap();
+            buf.resize(PRODUCT_LENGTH, 0);
+            file.write_all(&buf).unwrap();
+        }
+
+        file.write_all(&bincode::serialize(&4_u64).unwrap()).unwrap();
+
+        assert_eq!(file_version(&read_all(&mut file).unwrap(), &PRODUCTS).unwrap(), 2);
+
+        let backup = upgrade(&mut file, path, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+        let bytes = read_all(&mut file).unwrap();
+        let header = format::parse_header(&bytes, PRODUCTS_MAGIC).unwrap();
+
+        assert_eq!((header.versao, header.quantidade, header.ultimo_id), (PRODUCTS_VERSION, 3, 4));
+        assert_eq!(products::parse_records(&bytes[format::HEADER_LENGTH as usize..]).unwrap()[2].1.nome, "Produto3");
+
+        remove_test_files(&[path, &backup]);
+    }
+
+    #[test]
+    fn test_upgrade_sales_without_session() {
+        let path = &test_path("test_upgrade_sales_session.bin");
+        let mut file = get_test_file(path);
+
+        let mut header = Cabecalho::new(SALES_MAGIC, 4);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut file, &header).unwrap();
+
+        let old = VendaSemCaixa {
+            vendedor: "Ana".to_string(),
+            produtos: vec![ItemVendaSemDesconto { id: 1, nome: "Produto1".to_string(), quantidade: 2, valor_unitario: Dinheiro::from_centavos(150), valor_total: Dinheiro::from_centavos(300) }],
+            codigo: 1,
+            valor: Dinheiro::from_centavos(300),
+            metodo_pagamento: MetodoPagamento::Pix,
+            data: chrono::NaiveDate::default()
+        };
+
+        let buf = bincode::serialize(&old).unwrap();
+        file.write_all(&bincode::serialize(&(buf.len() as u64)).unwrap()).unwrap();
+        file.write_all(&buf).unwrap();
+
+        let backup = upgrade(&mut file, path, &SALES).unwrap().expect("O arquivo de vendas não foi atualizado.");
+
+        let path_index = &path.with_extension("idx");
+        let mut sales = ArquivoVendas::open(file, get_test_file(path_index)).unwrap();
+        let (sale, _) = sales.search(1).unwrap();
+
+        assert_eq!(sales.header().unwrap().versao, SALES_VERSION);
+        assert_eq!(sale.produtos[0].valor_total, Dinheiro::from_centavos(300));
+        assert_eq!(sale.caixa, None);
+
+        remove_test_files(&[path, path_index, &backup]);
+    }
+
+    #[test]
+    fn test_upgrade_sales_without_discounts() {
+        let path = &test_path("test_upgrade_sales_discounts.bin");
+        let mut file = get_test_file(path);
+
+        let mut header = Cabecalho::new(SALES_MAGIC, 6);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut file, &header).unwrap();
+
+        write_record(&mut file, &VendaSemDescontos {
+            vendedor: "Ana".to_string(),
+            produtos: vec![ItemVendaSemDesconto { id: 1, nome: "Produto1".to_string(), quantidade: 2, valor_unitario: Dinheiro::from_centavos(150), valor_total: Dinheiro::from_centavos(300) }],
+            codigo: 1,
+            valor: Dinheiro::from_centavos(300),
+            metodo_pagamento: MetodoPagamento::Pix,
+            data: chrono::NaiveDate::default(),
+            caixa: Some(2),
+            situacao: Situacao::DevolucaoParcial,
+            devolucoes: vec![Devolucao { data: chrono::NaiveDate::default(), motivo: "Defeito".to_string(), itens: vec![(1, 1)], valor: Dinheiro::from_centavos(150) }]
+        });
+
+        let backup = upgrade(&mut file, path, &SALES).unwrap().expect("O arquivo de vendas não foi atualizado.");
+
+        let path_index = &path.with_extension("idx");
+        let mut sales = ArquivoVendas::open(file, get_test_file(path_index)).unwrap();
+        let (sale, _) = sales.search(1).unwrap();
+
+        assert_eq!(sales.header().unwrap().versao, SALES_VERSION);
+        assert_eq!(sale.caixa, Some(2));
+        assert_eq!(sale.situacao, Situacao::DevolucaoParcial);
+        assert_eq!(sale.valor_liquido().unwrap(), Dinheiro::from_centavos(150));
+        assert_eq!((sale.desconto, sale.produtos[0].desconto), (Dinheiro::ZERO, Dinheiro::ZERO));
+        assert!(sale.promocoes.is_empty());
+
+        remove_test_files(&[path, path_index, &backup]);
+    }
+
+    #[test]
+    fn test_upgrade_without_taxes() {
+        let path_products = &test_path("test_upgrade_taxes_1.bin");
+        let path_sales = &test_path("test_upgrade_taxes_2.bin");
+
+        let mut products_file = get_test_file(path_products);
+        let mut sales_file = get_test_file(path_sales);
+
+        let mut header = Cabecalho::new(PRODUCTS_MAGIC, 7);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut products_file, &header).unwrap();
+        write_record(&mut products_file, &ProdutoSemTributacao {
+            nome: "Arroz".to_string(),
+            id: 1,
+            quantidade_estoque: 10,
+            valor: Dinheiro::from_centavos(2000),
+            quantidade_restoque: 2,
+            categoria: 5,
+            data_restoque: chrono::NaiveDate::default(),
+            fornecedor: None,
+            custo_unitario: None,
+            codigo_barras: Some("7891234567895".to_string())
+        });
+
+        let mut header = Cabecalho::new(SALES_MAGIC, 7);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut sales_file, &header).unwrap();
+        write_record(&mut sales_file, &VendaSemTributacao {
+            vendedor: "Ana".to_string(),
+            produtos: vec![ItemVendaSemTributacao { id: 1, nome: "Arroz".to_string(), quantidade: 3, valor_unitario: Dinheiro::from_centavos(2000), valor_total: Dinheiro::from_centavos(6000), desconto: Dinheiro::from_centavos(600) }],
+            codigo: 1,
+            valor: Dinheiro::from_centavos(5000),
+            metodo_pagamento: MetodoPagamento::Pix,
+            data: chrono::NaiveDate::default(),
+            caixa: None,
+            situacao: Situacao::Concluida,
+            devolucoes: Vec::new(),
+            desconto: Dinheiro::from_centavos(400),
+            promocoes: Vec::new()
+        });
+
+        let products_backup = upgrade(&mut products_file, path_products, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+        let sales_backup = upgrade(&mut sales_file, path_sales, &SALES).unwrap().expect("O arquivo de vendas não foi atualizado.");
+
+        let bytes = read_all(&mut products_file).unwrap();
+        let (_, product) = products::parse_records(&bytes[format::HEADER_LENGTH as usize..]).unwrap().remove(0);
+
+        assert_eq!(format::parse_header(&bytes, PRODUCTS_MAGIC).unwrap().versao, PRODUCTS_VERSION);
+        assert_eq!((product.categoria, product.codigo_barras.as_deref()), (5, Some("7891234567895")));
+        assert!(product.tributacao.is_none());
+
+        let path_index = &path_sales.with_extension("idx");
+        let mut sales = ArquivoVendas::open(sales_file, get_test_file(path_index)).unwrap();
+        let (sale, _) = sales.search(1).unwrap();
+
+        assert_eq!(sales.header().unwrap().versao, SALES_VERSION);
+        assert_eq!((sale.desconto, sale.produtos[0].desconto), (Dinheiro::from_centavos(400), Dinheiro::from_centavos(600)));
+        assert!(sale.produtos[0].tributacao.is_none());
+
+        remove_test_files(&[path_products, path_sales, path_index, &products_backup, &sales_backup]);
+    }
+
+    fn write_record<T: Serialize>(file: &mut File, record: &T) {
+        let buf = bincode::serialize(record).unwrap();
+
+        file.write_all(&bincode::serialize(&(buf.len() as u64)).unwrap()).unwrap();
+        file.write_all(&buf).unwrap();
+    }
+
+    #[test]
+    fn test_upgrade_without_supplier() {
+        let path_products = &test_path("test_upgrade_supplier_1.bin");
+        let path_movements = &test_path("test_upgrade_supplier_2.bin");
+
+        let mut products_file = get_test_file(path_products);
+        let mut movements_file = get_test_file(path_movements);
+
+        let mut header = Cabecalho::new(PRODUCTS_MAGIC, 4);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut products_file, &header).unwrap();
+        write_record(&mut products_file, &ProdutoSemFornecedor {
+            nome: "Arroz".to_string(),
+            id: 1,
+            quantidade_estoque: 8,
+            valor: Dinheiro::from_centavos(2000),
+            quantidade_restoque: 2,
+            categoria: CategoriaFixa::Alimento,
+            data_restoque: chrono::NaiveDate::default()
+        });
+
+        let mut header = Cabecalho::new(MOVEMENTS_MAGIC, 1);
+        header.quantidade = 1;
+        header.ultimo_id = 1;
+
+        format::write_header(&mut movements_file, &header).unwrap();
+        write_record(&mut movements_file, &MovimentoSemRecebimento {
+            id: 1,
+            produto: 1,
+            tipo: TipoMovimento::Inicial,
+            quantidade: 8,
+            data: chrono::NaiveDateTime::default(),
+            operador: "Ana".to_string(),
+            motivo: "Cadastro do produto".to_string()
+        });
+
+        let products_backup = upgrade(&mut products_file, path_products, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+        let movements_backup = upgrade(&mut movements_file, path_movements, &MOVEMENTS).unwrap().expect("O arquivo de movimentos não foi atualizado.");
+
+        let bytes = read_all(&mut products_file).unwrap();
+        let (_, product) = products::parse_records(&bytes[format::HEADER_LENGTH as usize..]).unwrap().remove(0);
+
+        assert_eq!(format::parse_header(&bytes, PRODUCTS_MAGIC).unwrap().versao, PRODUCTS_VERSION);
+        assert_eq!((product.nome.as_str(), product.quantidade_estoque), ("Arroz", 8));
+        assert!(product.fornecedor.is_none());
+
+        let mut movements = crate::ledger::ArquivoMovimentos::new(movements_file);
+        let movement = movements.list().unwrap().remove(0);
+
+        assert_eq!(movements.header().unwrap().versao, MOVEMENTS_VERSION);
+        assert_eq!((movement.operador.as_str(), movement.quantidade), ("Ana", 8));
+        assert!(movement.recebimento.is_none());
+
+        remove_test_files(&[path_products, path_movements, &products_backup, &movements_backup]);
+    }
+
+    #[test]
+    fn test_upgrade_without_barcode() {
+        let path = &test_path("test_upgrade_barcode.bin");
+        let mut file = get_test_file(path);
+
+        let mut header = Cabecalho::new(PRODUCTS_MAGIC, 5);
+        header.quantidade = 1;
+        header.ultimo_id = 3;
+
+        format::write_header(&mut file, &header).unwrap();
+        write_record(&mut file, &ProdutoSemCodigoBarras {
+            nome: "Feijão".to_string(),
+            id: 3,
+            quantidade_estoque: 12,
+            valor: Dinheiro::from_centavos(850),
+            quantidade_restoque: 4,
+            categoria: CategoriaFixa::Alimento,
+            data_restoque: chrono::NaiveDate::default(),
+            fornecedor: Some("Cerealista".to_string()),
+            custo_unitario: Some(Dinheiro::from_centavos(500))
+        });
+
+        let backup = upgrade(&mut file, path, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+
+        let bytes = read_all(&mut file).unwrap();
+        let (_, product) = products::parse_records(&bytes[format::HEADER_LENGTH as usize..]).unwrap().remove(0);
+
+        assert_eq!(format::parse_header(&bytes, PRODUCTS_MAGIC).unwrap().versao, PRODUCTS_VERSION);
+        assert_eq!((product.id, product.fornecedor.as_deref()), (3, Some("Cerealista")));
+        assert_eq!(product.custo_unitario, Some(Dinheiro::from_centavos(500)));
+        assert!(product.codigo_barras.is_none());
+        assert_eq!(product.categoria, categories::ALIMENTO);
+
+        remove_test_files(&[path, &backup]);
+    }
+
+    #[test]
+    fn test_upgrade_fixed_categories() {
+        let path = &test_path("test_upgrade_categories.bin");
+        let mut file = get_test_file(path);
+
+        let fixed = [CategoriaFixa::Eletronico, CategoriaFixa::Roupa, CategoriaFixa::Alimento, CategoriaFixa::Geral];
+
+        let mut header = Cabecalho::new(PRODUCTS_MAGIC, 6);
+        header.quantidade = fixed.len() as u64;
+        header.ultimo_id = fixed.len() as u64;
+
+        format::write_header(&mut file, &header).unwrap();
+
+        for (id, categoria) in (1..).zip(fixed) {
+            write_record(&mut file, &ProdutoSemCategorias {
+                nome: format!("Produto{id}"),
+                id,
+                quantidade_estoque: 1,
+                valor: Dinheiro::from_centavos(100),
+                quantidade_restoque: 0,
+                categoria,
+                data_restoque: chrono::NaiveDate::default(),
+                fornecedor: None,
+                custo_unitario: None,
+                codigo_barras: Some("7891234567895".to_string())
+            });
+        }
+
+        let backup = upgrade(&mut file, path, &PRODUCTS).unwrap().expect("O arquivo de produtos não foi atualizado.");
+
+        let bytes = read_all(&mut file).unwrap();
+        let products: Vec<Produto> = products::parse_records(&bytes[format::HEADER_LENGTH as usize..]).unwrap().into_iter().map(|(_, product)| product).collect();
+        let ids: Vec<u64> = products.iter().map(|product| product.categoria).collect();
+
+        assert_eq!(ids, vec![categories::ELETRONICO, categories::ROUPA, categories::ALIMENTO, categories::GERAL]);
+        assert_eq!(products[0].codigo_barras.as_deref(), Some("7891234567895"));
+
+        remove_test_files(&[path, &backup]);
+    }
+
+    #[test]
+    fn test_upgrade_newer_version() {
+        let path = &test_path("test_upgrade_newer.bin");
+        let mut file = get_test_file(path);
+
+        format::write_header(&mut file, &Cabecalho::new(SALES_MAGIC, SALES_VERSION + 1)).unwrap();
+
+        let result = upgrade(&mut file, path, &SALES);
+
+        assert!(matches!(result.unwrap_err().downcast_ref::<CustomErrors>(), Some(CustomErrors::NewerFormat)));
+        assert_eq!(file.seek(SeekFrom::End(0)).unwrap(), format::HEADER_LENGTH);
+
+        remove_test_files(&[path]);
+    }
+
+    #[test]
+    fn test_upgrade_empty_files() {
+        let path_products = &test_path("test_upgrade_empty_1.bin");
+        let path_sales = &test_path("test_upgrade_empty_2.bin");
+
+        let mut products_file = get_test_file(path_products);
+        let mut sales_file = get_test_file(path_sales);
+
+        let products_backup = upgrade(&mut products_file, path_products, &PRODUCTS).unwrap().unwrap();
+        let sales_backup = upgrade(&mut sales_file, path_sales, &SALES).unwrap().unwrap();
+
+        assert_eq!(format::read_header(&mut products_file, PRODUCTS_MAGIC).unwrap(), Cabecalho::new(PRODUCTS_MAGIC, PRODUCTS_VERSION));
+        assert_eq!(format::read_header(&mut sales_file, SALES_MAGIC).unwrap(), Cabecalho::new(SALES_MAGIC, SALES_VERSION));
+
+        remove_test_files(&[path_products, path_sales, &products_backup, &sales_backup]);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Serialize, Deserialize};
+
+use super::errors::CustomErrors;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dinheiro(i64);
+
+impl Dinheiro {
+    pub const ZERO: Dinheiro = Dinheiro(0);
+
+    pub fn from_centavos(centavos: i64) -> Self {
+        Dinheiro(centavos)
+    }
+
+    pub fn centavos(&self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Dinheiro) -> Result<Dinheiro, CustomErrors> {
+        self.0.checked_add(other.0).map(Dinheiro).ok_or(CustomErrors::ValueOverflow)
+    }
+
+    pub fn checked_sub(self, other: Dinheiro) -> Result<Dinheiro, CustomErrors> {
+        self.0.checked_sub(other.0).map(Dinheiro).ok_or(CustomErrors::ValueOverflow)
+    }
+
+    pub fn checked_mul(self, amount: u64) -> Result<Dinheiro, CustomErrors> {
+        i64::try_from(amount).ok()
+            .and_then(|amount| self.0.checked_mul(amount))
+            .map(Dinheiro)
+            .ok_or(CustomErrors::ValueOverflow)
+    }
+
+    // Parte do valor na razão part/whole, arredondada para o centavo mais próximo.
+    pub fn proportion(self, part: u64, whole: u64) -> Result<Dinheiro, CustomErrors> {
+        if whole == 0 {
+            return Err(CustomErrors::ValueOverflow);
+        }
+
+        let value = (self.0 as i128 * part as i128 * 2 + whole as i128) / (whole as i128 * 2);
+
+        i64::try_from(value).map(Dinheiro).map_err(|_| CustomErrors::ValueOverflow)
+    }
+}
+
+fn parse_digits(digits: &str) -> Result<i64, CustomErrors> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(CustomErrors::InvalidValue);
+    }
+
+    digits.parse().map_err(|_| CustomErrors::ValueOverflow)
+}
+
+impl FromStr for Dinheiro {
+    type Err = CustomErrors;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let string = string.trim();
+        let string = string.strip_prefix("R$").unwrap_or(string).trim();
+
+        let decimal_position = match (string.rfind(','), string.rfind('.')) {
+            (Some(comma), Some(dot)) => Some(comma.max(dot)),
+            (Some(position), None) | (None, Some(position)) => {
+                let separator = &string[position..=position];
+
+                // "1.234" ou "0,500" tanto podem ser milhar quanto decimal
+                match (string.matches(separator).count() == 1, string.len() - position - 1 == 3) {
+                    (true, true) => return Err(CustomErrors::InvalidValue),
+                    (true, false) => Some(position),
+                    (false, _) => None
+                }
+            },
+            (None, None) => None
+        };
+
+        let (integer, fraction) = match decimal_position {
+            Some(position) => (&string[..position], &string[position + 1..]),
+            None => (string, "")
+        };
+
+        let groups: Vec<&str> = integer.split(['.', ',']).collect();
+
+        if groups.len() > 1 && (groups[0].is_empty() || groups[0] == "0" || groups[0].len() > 3 || groups[1..].iter().any(|group| group.len() != 3)) {
+            return Err(CustomErrors::InvalidValue);
+        }
+
+        let reais = parse_digits(&groups.concat())?;
+
+        let centavos = match fraction.len() {
+            0 if decimal_position.is_none() => 0,
+            1 => parse_digits(fraction)? * 10,
+            2 => parse_digits(fraction)?,
+            _ => return Err(CustomErrors::InvalidValue)
+        };
+
+        reais.checked_mul(100)
+            .and_then(|value| value.checked_add(centavos))
+            .map(Dinheiro)
+            .ok_or(CustomErrors::ValueOverflow)
+    }
+}
+
+impl fmt::Display for Dinheiro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let absolute = self.0.unsigned_abs();
+        let reais = (absolute / 100).to_string();
+
+        let mut grouped = String::new();
+
+        for (index, digit) in reais.chars().enumerate() {
+            if index != 0 && (reais.len() - index).is_multiple_of(3) {
+                grouped.push('.');
+            }
+
+            grouped.push(digit);
+        }
+
+        if self.0 < 0 {
+            write!(f, "-")?;
+        }
+
+        write!(f, "R$ {},{:02}", grouped, absolute % 100)
+    }
+}
+
+// Alíquota de imposto em centésimos de ponto percentual: 1800 é 18% e 165 é 1,65%.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Aliquota(u64);
+
+impl Aliquota {
+    pub const ZERO: Aliquota = Aliquota(0);
+
+    pub fn from_centesimos(centesimos: u64) -> Self {
+        Aliquota(centesimos)
+    }
+
+    pub fn centesimos(&self) -> u64 {
+        self.0
+    }
+
+    // Imposto sobre a base, arredondado para o centavo mais próximo.
+    pub fn apply(self, base: Dinheiro) -> Result<Dinheiro, CustomErrors> {
+        base.proportion(self.0, 10_000)
+    }
+}
+
+impl FromStr for Aliquota {
+    type Err = CustomErrors;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let string = string.trim();
+        let string = string.strip_suffix('%').unwrap_or(string).trim();
+
+        let (integer, fraction) = match string.split_once([',', '.']) {
+            Some((integer, fraction)) if !fraction.is_empty() && fraction.len() <= 2 => (integer, fraction),
+            Some(_) => return Err(CustomErrors::InvalidTax),
+            None => (string, "")
+        };
+
+        let integer = parse_digits(integer).map_err(|_| CustomErrors::InvalidTax)?;
+        let fraction = match fraction.len() {
+            0 => 0,
+            length => parse_digits(fraction).map_err(|_| CustomErrors::InvalidTax)? * if length == 1 { 10 } else { 1 }
+        };
+
+        match integer.checked_mul(100).and_then(|value| value.checked_add(fraction)) {
+            Some(value) if value <= 10_000 => Ok(Aliquota(value as u64)),
+            _ => Err(CustomErrors::InvalidTax)
+        }
+    }
+}
+
+impl fmt::Display for Aliquota {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{:02}%", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_money() {
+        assert_eq!("12.50".parse::<Dinheiro>().unwrap(), Dinheiro(1250));
+        assert_eq!("12,50".parse::<Dinheiro>().unwrap(), Dinheiro(1250));
+        assert_eq!("12,5".parse::<Dinheiro>().unwrap(), Dinheiro(1250));
+        assert_eq!("12".parse::<Dinheiro>().unwrap(), Dinheiro(1200));
+        assert_eq!("1.234,50".parse::<Dinheiro>().unwrap(), Dinheiro(123450));
+        assert_eq!("1,234.50".parse::<Dinheiro>().unwrap(), Dinheiro(123450));
+        assert_eq!("1.234.567".parse::<Dinheiro>().unwrap(), Dinheiro(123456700));
+        assert_eq!("R$ 0,99".parse::<Dinheiro>().unwrap(), Dinheiro(99));
+    }
+
+    #[test]
+    fn test_parse_invalid_money() {
+        for input in ["", "NaN", "inf", "-5", "1e3", "1.2.3", "12.", "abc", "1.23,4,5", ".50", "0,500", "0.125", "1,234", "1.234", "0.500.000"] {
+            assert!(input.parse::<Dinheiro>().is_err(), "{input}");
+        }
+
+        assert!(matches!("99999999999999999999".parse::<Dinheiro>(), Err(CustomErrors::ValueOverflow)));
+    }
+
+    #[test]
+    fn test_money_display() {
+        assert_eq!(format!("{}", Dinheiro(123450)), "R$ 1.234,50");
+        assert_eq!(format!("{}", Dinheiro(5)), "R$ 0,05");
+        assert_eq!(format!("{}", Dinheiro(100000000)), "R$ 1.000.000,00");
+        assert_eq!(format!("{}", Dinheiro(-1999)), "-R$ 19,99");
+    }
+
+    #[test]
+    fn test_money_arithmetic() {
+        let a = Dinheiro(10);
+        let b = Dinheiro(20);
+
+        assert_eq!(a.checked_add(b).unwrap(), Dinheiro(30));
+        assert_eq!(a.checked_sub(b).unwrap(), Dinheiro(-10));
+        assert_eq!(b.checked_mul(3).unwrap(), Dinheiro(60));
+
+        assert!(Dinheiro(i64::MAX).checked_add(a).is_err());
+        assert!(Dinheiro(i64::MAX).checked_mul(2).is_err());
+        assert!(a.checked_mul(u64::MAX).is_err());
+
+        assert_eq!(Dinheiro(1999).proportion(10, 100).unwrap(), Dinheiro(200));
+        assert_eq!(Dinheiro(1000).proportion(1, 3).unwrap(), Dinheiro(333));
+        assert_eq!(Dinheiro(1000).proportion(2, 3).unwrap(), Dinheiro(667));
+        assert!(a.proportion(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_rates() {
+        assert_eq!("18".parse::<Aliquota>().unwrap(), Aliquota(1800));
+        assert_eq!("1,65".parse::<Aliquota>().unwrap(), Aliquota(165));
+        assert_eq!("7.6%".parse::<Aliquota>().unwrap(), Aliquota(760));
+        assert_eq!("0".parse::<Aliquota>().unwrap(), Aliquota::ZERO);
+
+        for input in ["", "abc", "-1", "100,01", "1,234", "12,", "1.2.3"] {
+            assert!(matches!(input.parse::<Aliquota>(), Err(CustomErrors::InvalidTax)), "{input}");
+        }
+
+        assert_eq!(format!("{}", Aliquota(165)), "1,65%");
+        assert_eq!(Aliquota(1800).apply(Dinheiro(5400)).unwrap(), Dinheiro(972));
+        assert_eq!(Aliquota(165).apply(Dinheiro(5400)).unwrap(), Dinheiro(89));
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::BTreeMap, error::Error, fmt, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use super::{core, errors::CustomErrors, journal, format::{self, Cabecalho, HEADER_LENGTH, ORDERS_MAGIC}, Entrega, ItemPedido, Pedido, Produto, Situacao, Venda};
+
+pub fn serialize_record(order: &Pedido) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(order)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+// Janela de vendas usada para medir o giro, prazo de entrega do fornecedor
+// e quantos dias de venda o pedido deve cobrir depois de chegar.
+pub struct Parametros {
+    pub dias: u64,
+    pub prazo: u64,
+    pub cobertura: u64
+}
+
+impl Default for Parametros {
+    fn default() -> Self {
+        Parametros {
+            dias: 30,
+            prazo: 7,
+            cobertura: 30
+        }
+    }
+}
+
+pub struct Sugestao {
+    pub produto: Produto,
+    pub vendidos: u64,
+    pub quantidade: u64
+}
+
+impl fmt::Display for Sugestao {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (ID: {}) - Estoque: {} - Mínimo: {} - Vendidos no período: {} - Comprar: {}",
+                self.produto.nome, self.produto.id, self.produto.quantidade_estoque, self.produto.quantidade_restoque, self.vendidos, self.quantidade)
+    }
+}
+
+fn units_sold(sales: &[Venda]) -> BTreeMap<u64, u64> {
+    let mut sold = BTreeMap::new();
+
+    for sale in sales.iter().filter(|sale| sale.situacao != Situacao::Cancelada) {
+        for item in sale.produtos.iter() {
+            *sold.entry(item.id).or_insert(0) += item.quantidade.saturating_sub(sale.returned(item.id));
+        }
+    }
+
+    sold
+}
+
+// Um produto entra na sugestão quando o estoque não cobre o mínimo somado às vendas
+// esperadas durante o prazo de entrega. A quantidade sugerida leva o estoque ao mínimo
+// somado às vendas esperadas no prazo e na cobertura.
+pub fn suggest(products: Vec<Produto>, sales: &[Venda], parametros: &Parametros) -> Vec<Sugestao> {
+    let sold = units_sold(sales);
+    let dias = parametros.dias.max(1);
+
+    products.into_iter()
+        .filter_map(|produto| {
+            let vendidos = sold.get(&produto.id).copied().unwrap_or(0);
+
+            let ponto = produto.quantidade_restoque.saturating_add(vendidos.saturating_mul(parametros.prazo).div_ceil(dias));
+
+            if produto.quantidade_estoque > ponto {
+                return None;
+            }
+
+            let dias_cobertos = parametros.prazo.saturating_add(parametros.cobertura);
+            let alvo = produto.quantidade_restoque.saturating_add(vendidos.saturating_mul(dias_cobertos).div_ceil(dias));
+            let quantidade = alvo.saturating_sub(produto.quantidade_estoque).max(1);
+
+            Some(Sugestao { produto, vendidos, quantidade })
+        })
+        .collect()
+}
+
+// Os produtos são agrupados pelo fornecedor do último recebimento.
+pub fn by_supplier(suggestions: Vec<Sugestao>) -> BTreeMap<Option<String>, Vec<Sugestao>> {
+    let mut groups: BTreeMap<Option<String>, Vec<Sugestao>> = BTreeMap::new();
+
+    for suggestion in suggestions {
+        groups.entry(suggestion.produto.fornecedor.clone()).or_default().push(suggestion);
+    }
+
+    groups
+}
+
+// Cada pedido com a posição e o tamanho do seu registro.
+type Registro = (Pedido, u64, u64);
+
+#[derive(Debug)]
+pub struct ArquivoPedidos {
+    pub dados: File,
+    pub diario: File
+}
+
+impl ArquivoPedidos {
+    pub fn new(dados: File, diario: File) -> Self {
+        ArquivoPedidos { dados, diario }
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, ORDERS_MAGIC)
+    }
+
+    fn records(&mut self) -> Result<Vec<Registro>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut orders = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let size: u64 = bincode::deserialize(&buf[offset..])?;
+            let end = offset + 8 + size as usize;
+
+            let record = buf.get(offset + 8..end).ok_or(CustomErrors::FormatMismatch)?;
+            orders.push((bincode::deserialize(record)?, HEADER_LENGTH + offset as u64, end as u64 - offset as u64));
+
+            offset = end;
+        }
+
+        Ok(orders)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Pedido>, Box<dyn Error>> {
+        let mut orders: Vec<Pedido> = self.records()?.into_iter().map(|(order, _, _)| order).collect();
+        orders.sort_by_key(|order| order.id);
+
+        Ok(orders)
+    }
+
+    pub fn search(&mut self, id: u64) -> Result<Pedido, Box<dyn Error>> {
+        let order = self.list()?.into_iter()
+            .find(|order| order.id == id)
+            .ok_or(CustomErrors::OrderNotFound)?;
+
+        Ok(order)
+    }
+
+    pub fn create(&mut self, fornecedor: Option<String>, itens: Vec<ItemPedido>, data: chrono::NaiveDate) -> Result<Pedido, Box<dyn Error>> {
+        let mut header = self.header()?;
+
+        let order = Pedido {
+            id: header.ultimo_id + 1,
+            fornecedor,
+            data,
+            itens,
+            recebimento: None
+        };
+
+        journal::rewrite_file(&mut self.diario, &mut self.dados, |dados| {
+            dados.seek(SeekFrom::End(0))?;
+            dados.write_all(&serialize_record(&order)?)?;
+
+            header.quantidade += 1;
+            header.ultimo_id = order.id;
+            format::write_header(dados, &header)
+        })?;
+
+        Ok(order)
+    }
+
+    pub fn mark_received(&mut self, id: u64, operador: String, data: chrono::NaiveDate) -> Result<Pedido, Box<dyn Error>> {
+        let (mut order, position, length) = self.records()?.into_iter()
+            .find(|(order, _, _)| order.id == id)
+            .ok_or(CustomErrors::OrderNotFound)?;
+
+        if order.recebimento.is_some() {
+            return Err(Box::new(CustomErrors::OrderReceived));
+        }
+
+        order.recebimento = Some(Entrega { data, operador });
+
+        journal::rewrite_file(&mut self.diario, &mut self.dados, |dados| {
+            core::remove_bytes(dados, position, length)?;
+
+            dados.seek(SeekFrom::End(0))?;
+            dados.write_all(&serialize_record(&order)?)?;
+
+            Ok(())
+        })?;
+
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Dinheiro, test_utils::{get_test_orders, remove_test_orders, test_path}, categories, ItemVenda, MetodoPagamento};
+
+    fn product(id: u64, estoque: u64, restoque: u64, fornecedor: Option<&str>) -> Produto {
+        let mut product = Produto::new(format!("Produto{id}"), id, estoque, Dinheiro::from_centavos(1000), restoque, chrono::NaiveDate::default(), categories::GERAL);
+        product.fornecedor = fornecedor.map(String::from);
+
+        product
+    }
+
+    fn sale(items: &[(u64, u64)]) -> Venda {
+        let mut sale = Venda::new("Ana".to_string(), 1, Dinheiro::ZERO, chrono::NaiveDate::default(), MetodoPagamento::Pix);
+
+        for &(id, quantidade) in items {
+            sale.produtos.push(ItemVenda::new(id, format!("Produto{id}"), quantidade, Dinheiro::from_centavos(1000)).unwrap());
+        }
+
+        sale
+    }
+
+    #[test]
+    fn test_suggest_by_sales_velocity() {
+        let products = vec![
+            product(1, 15, 5, Some("Atacadão")),
+            product(2, 20, 5, Some("Atacadão")),
+            product(3, 4, 5, None),
+            product(4, 50, 5, Some("Distribuidora"))
+        ];
+
+        let mut cancelled = sale(&[(2, 60)]);
+        cancelled.situacao = Situacao::Cancelada;
+
+        let sales = vec![sale(&[(1, 60), (4, 3)]), cancelled];
+        let parametros = Parametros::default();
+
+        let suggestions = suggest(products, &sales, &parametros);
+        let quantities: Vec<(u64, u64)> = suggestions.iter().map(|suggestion| (suggestion.produto.id, suggestion.quantidade)).collect();
+
+        // Produto 1: 2 unidades por dia, ponto de pedido 5 + 14 e alvo 5 + 74.
+        assert_eq!(quantities, vec![(1, 64), (3, 1)]);
+
+        let groups = by_supplier(suggestions);
+
+        assert_eq!(groups.keys().cloned().collect::<Vec<_>>(), vec![None, Some("Atacadão".to_string())]);
+    }
+
+    #[test]
+    fn test_create_and_receive_orders() {
+        let path = &test_path("test_orders.bin");
+        let mut orders = get_test_orders(path);
+
+        let today = chrono::NaiveDate::default();
+        let item = ItemPedido { id: 1, nome: "Arroz".to_string(), quantidade: 10, custo_unitario: Some(Dinheiro::from_centavos(1850)) };
+
+        let first = orders.create(Some("Atacadão".to_string()), vec![item.clone()], today).unwrap();
+        let second = orders.create(None, vec![item], today).unwrap();
+
+        assert_eq!((first.id, second.id), (1, 2));
+
+        orders.mark_received(1, "Ana".to_string(), today).unwrap();
+
+        let error = orders.mark_received(1, "Ana".to_string(), today).err().unwrap();
+        assert!(matches!(error.downcast_ref::<CustomErrors>(), Some(CustomErrors::OrderReceived)));
+
+        let ids: Vec<u64> = orders.list().unwrap().iter().map(|order| order.id).collect();
+
+        assert_eq!(ids, vec![1, 2]);
+        assert!(orders.search(1).unwrap().recebimento.is_some());
+        assert!(orders.search(3).is_err());
+
+        remove_test_orders(path);
+    }
+}
@@ -0,0 +1,82 @@
+use std::{error::Error, fmt::Display};
+
+use serde::Serialize;
+
+use store::errors::CustomErrors;
+
+// Em JSON os registros usam os nomes dos campos gravados nos arquivos, os valores
+// monetários em centavos e as datas no formato dd/mm/YYYY.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Saida {
+    Texto,
+    Json,
+    JsonLines
+}
+
+pub fn parse_output(string: &str) -> Result<Saida, CustomErrors> {
+    match string.trim().to_lowercase().as_str() {
+        "text" => Ok(Saida::Texto),
+        "json" => Ok(Saida::Json),
+        "jsonl" => Ok(Saida::JsonLines),
+        _ => Err(CustomErrors::InvalidCommand)
+    }
+}
+
+pub fn print_record<T: Serialize + Display>(record: &T, saida: Saida) -> Result<(), Box<dyn Error>> {
+    match saida {
+        Saida::Texto => println!("\n{record}\n"),
+        Saida::Json => println!("{}", serde_json::to_string_pretty(record)?),
+        Saida::JsonLines => println!("{}", serde_json::to_string(record)?)
+    }
+
+    Ok(())
+}
+
+// Em texto os registros vêm depois do título; em JSON Lines cada registro ocupa uma linha.
+pub fn print_records<T: Serialize + Display>(title: &str, records: &[T], saida: Saida) -> Result<(), Box<dyn Error>> {
+    match saida {
+        Saida::Texto => {
+            println!("\n{title}:\n");
+
+            for record in records {
+                println!("{record}\n");
+            }
+        },
+        Saida::Json => println!("{}", serde_json::to_string_pretty(records)?),
+        Saida::JsonLines => {
+            for record in records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use store::{categories, money::Dinheiro, Produto, ProdutoExibido};
+
+    #[test]
+    fn test_parse_output() {
+        assert_eq!(parse_output("JSON").unwrap(), Saida::Json);
+        assert_eq!(parse_output("jsonl").unwrap(), Saida::JsonLines);
+        assert_eq!(parse_output("text").unwrap(), Saida::Texto);
+        assert!(matches!(parse_output("xml"), Err(CustomErrors::InvalidCommand)));
+    }
+
+    #[test]
+    fn test_product_json_fields() {
+        let product = Produto::new("Arroz".to_string(), 3, 10, Dinheiro::from_centavos(1850), 2, chrono::NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), categories::ALIMENTO);
+        let json: serde_json::Value = serde_json::to_value(ProdutoExibido::new(product, "Alimento".to_string())).unwrap();
+
+        assert_eq!(json["id"], 3);
+        assert_eq!(json["nome"], "Arroz");
+        assert_eq!(json["valor"], 1850);
+        assert_eq!(json["categoria"], categories::ALIMENTO);
+        assert_eq!(json["nome_categoria"], "Alimento");
+        assert_eq!(json["data_restoque"], "20/05/2024");
+        assert!(json["fornecedor"].is_null());
+    }
+}
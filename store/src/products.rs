@@ -0,0 +1,472 @@
+use std::{collections::BTreeMap, error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use bincode::Options;
+
+use super::{categories::ArquivoCategorias, core, errors, format::{self, Cabecalho, HEADER_LENGTH, PRODUCTS_MAGIC}, ledger::ArquivoMovimentos, promotions::ArquivoPromocoes, repository::ProductRepository, Categoria, Movimento, Produto, Promocao};
+
+const INDEX_ENTRY_LENGTH: u64 = 16;
+
+#[derive(Debug)]
+pub struct ArquivoProdutos {
+    pub dados: File,
+    pub indice: File,
+    pub movimentos: ArquivoMovimentos,
+    pub categorias: ArquivoCategorias,
+    pub promocoes: ArquivoPromocoes,
+    // Código de barras de cada produto e o seu id, montado na primeira busca e mantido a cada gravação.
+    codigos_barras: Option<BTreeMap<String, u64>>
+}
+
+pub fn serialize_record(product: &Produto) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(product)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+pub fn parse_records(records: &[u8]) -> Result<Vec<(u64, Produto)>, Box<dyn Error>> {
+    let options = bincode::DefaultOptions::new().with_fixint_encoding();
+    let mut products = Vec::new();
+    let mut position = 0;
+
+    while position < records.len() {
+        let size = records.get(position..position + 8)
+            .map(|buf| options.deserialize::<u64>(buf))
+            .ok_or(errors::CustomErrors::FormatMismatch)??;
+
+        let end = position + 8 + size as usize;
+        let record = records.get(position + 8..end).ok_or(errors::CustomErrors::FormatMismatch)?;
+
+        products.push((position as u64, options.deserialize(record)?));
+        position = end;
+    }
+
+    Ok(products)
+}
+
+impl ArquivoProdutos {
+    pub fn new(dados: File, indice: File, movimentos: ArquivoMovimentos, categorias: ArquivoCategorias, promocoes: ArquivoPromocoes) -> Self {
+        ArquivoProdutos {
+            dados,
+            indice,
+            movimentos,
+            categorias,
+            promocoes,
+            codigos_barras: None
+        }
+    }
+
+    fn entries(&mut self) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.indice.seek(SeekFrom::Start(0))?;
+        self.indice.read_to_end(&mut buf)?;
+
+        let mut entries = Vec::with_capacity(buf.len() / INDEX_ENTRY_LENGTH as usize);
+
+        for entry in buf.chunks_exact(INDEX_ENTRY_LENGTH as usize) {
+            entries.push(bincode::deserialize(entry)?);
+        }
+
+        Ok(entries)
+    }
+
+    fn write_entries(&mut self, entries: &[(u64, u64)]) -> Result<(), Box<dyn Error>> {
+        let mut buf = Vec::with_capacity(entries.len() * INDEX_ENTRY_LENGTH as usize);
+
+        for entry in entries.iter() {
+            buf.extend(bincode::serialize(entry)?);
+        }
+
+        self.indice.seek(SeekFrom::Start(0))?;
+        self.indice.write_all(&buf)?;
+        self.indice.set_len(buf.len() as u64)?;
+
+        Ok(())
+    }
+
+    fn entry(&mut self, position: u64) -> Result<(u64, u64), Box<dyn Error>> {
+        let mut buf = vec![0; INDEX_ENTRY_LENGTH as usize];
+
+        self.indice.seek(SeekFrom::Start(position * INDEX_ENTRY_LENGTH))?;
+        self.indice.read_exact(&mut buf)?;
+
+        Ok(bincode::deserialize(&buf)?)
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, PRODUCTS_MAGIC)
+    }
+
+    pub fn last_id(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.header()?.ultimo_id)
+    }
+
+    pub fn read_at(&mut self, position: u64) -> Result<(Produto, u64), Box<dyn Error>> {
+        let mut size_buf = vec![0; 8];
+
+        self.dados.seek(SeekFrom::Start(position))?;
+        self.dados.read_exact(&mut size_buf)?;
+
+        let size: u64 = bincode::deserialize(&size_buf)?;
+        let mut buf = vec![0; size as usize];
+        self.dados.read_exact(&mut buf)?;
+
+        Ok((bincode::deserialize(&buf)?, size + 8))
+    }
+
+    pub fn search(&mut self, id: u64) -> Result<(Produto, u64), Box<dyn Error>> {
+        let mut left = 0;
+        let mut right = self.indice.seek(SeekFrom::End(0))? / INDEX_ENTRY_LENGTH;
+
+        while left < right {
+            let mid = (left + right) / 2;
+            let (entry_id, position) = self.entry(mid)?;
+
+            if entry_id > id {
+                right = mid;
+            } else if entry_id < id {
+                left = mid + 1;
+            } else {
+                let (product, _) = self.read_at(position)?;
+                return Ok((product, position));
+            }
+        }
+
+        Err(Box::new(errors::CustomErrors::ProductNotFound))
+    }
+
+    pub fn barcode_id(&mut self, barcode: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        if self.codigos_barras.is_none() {
+            let index = self.list()?.into_iter()
+                .filter_map(|product| Some((product.codigo_barras?, product.id)))
+                .collect();
+
+            self.codigos_barras = Some(index);
+        }
+
+        Ok(self.codigos_barras.as_ref().and_then(|index| index.get(barcode).copied()))
+    }
+
+    fn index_barcode(&mut self, id: u64, barcode: Option<&str>) {
+        if let Some(index) = self.codigos_barras.as_mut() {
+            index.retain(|_, entry| *entry != id);
+
+            if let Some(barcode) = barcode {
+                index.insert(barcode.to_string(), id);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, mut product: Produto) -> Result<u64, Box<dyn Error>> {
+        let mut header = self.header()?;
+        product.id = header.ultimo_id + 1;
+
+        let position = self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(&product)?)?;
+
+        header.quantidade += 1;
+        header.ultimo_id = product.id;
+        format::write_header(&mut self.dados, &header)?;
+
+        self.indice.seek(SeekFrom::End(0))?;
+        self.indice.write_all(&bincode::serialize(&(product.id, position))?)?;
+
+        self.index_barcode(product.id, product.codigo_barras.as_deref());
+
+        Ok(product.id)
+    }
+
+    pub fn update(&mut self, product: &Produto) -> Result<(), Box<dyn Error>> {
+        let (_, position) = self.search(product.id)?;
+        let (_, size) = self.read_at(position)?;
+        let record = serialize_record(product)?;
+
+        self.index_barcode(product.id, product.codigo_barras.as_deref());
+
+        if record.len() as u64 == size {
+            self.dados.seek(SeekFrom::Start(position))?;
+            self.dados.write_all(&record)?;
+
+            return Ok(());
+        }
+
+        self.remove_record(position, size)?;
+
+        let new_position = self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&record)?;
+
+        let mut entries = self.entries()?;
+
+        if let Some(entry) = entries.iter_mut().find(|(id, _)| *id == product.id) {
+            entry.1 = new_position;
+        }
+
+        self.write_entries(&entries)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        let (_, position) = self.search(id)?;
+        let (_, size) = self.read_at(position)?;
+
+        self.remove_record(position, size)?;
+
+        let mut header = self.header()?;
+        header.quantidade -= 1;
+        format::write_header(&mut self.dados, &header)?;
+
+        let mut entries = self.entries()?;
+        entries.retain(|(entry_id, _)| *entry_id != id);
+
+        self.index_barcode(id, None);
+        self.write_entries(&entries)
+    }
+
+    fn remove_record(&mut self, position: u64, length: u64) -> Result<(), Box<dyn Error>> {
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        let mut entries = self.entries()?;
+
+        for entry in entries.iter_mut().filter(|(_, offset)| *offset > position) {
+            entry.1 -= length;
+        }
+
+        self.write_entries(&entries)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Produto>, Box<dyn Error>> {
+        let mut products = Vec::new();
+
+        for (_, position) in self.entries()? {
+            products.push(self.read_at(position)?.0);
+        }
+
+        Ok(products)
+    }
+
+    pub fn rebuild_index(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut entries: Vec<(u64, u64)> = parse_records(&buf)?.into_iter()
+            .map(|(position, product)| (product.id, HEADER_LENGTH + position))
+            .collect();
+
+        entries.sort();
+
+        self.codigos_barras = None;
+        self.write_entries(&entries)
+    }
+
+    pub fn index_is_stale(&mut self) -> Result<bool, Box<dyn Error>> {
+        let size = self.dados.seek(SeekFrom::End(0))?;
+        let header = self.header()?;
+        let entries = self.entries()?;
+
+        if entries.len() as u64 != header.quantidade || entries.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Ok(true);
+        }
+
+        match entries.iter().map(|&(_, position)| position).max() {
+            Some(position) => match self.read_at(position) {
+                Ok((_, length)) => Ok(position + length != size),
+                Err(_) => Ok(true)
+            },
+            None => Ok(size != HEADER_LENGTH)
+        }
+    }
+}
+
+impl ProductRepository for ArquivoProdutos {
+    fn last_id(&mut self) -> Result<u64, Box<dyn Error>> {
+        ArquivoProdutos::last_id(self)
+    }
+
+    fn get(&mut self, id: u64) -> Result<Produto, Box<dyn Error>> {
+        Ok(self.search(id)?.0)
+    }
+
+    fn list(&mut self) -> Result<Vec<Produto>, Box<dyn Error>> {
+        ArquivoProdutos::list(self)
+    }
+
+    fn barcode_id(&mut self, barcode: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        ArquivoProdutos::barcode_id(self, barcode)
+    }
+
+    fn insert(&mut self, product: Produto) -> Result<u64, Box<dyn Error>> {
+        ArquivoProdutos::insert(self, product)
+    }
+
+    fn update(&mut self, product: &Produto) -> Result<(), Box<dyn Error>> {
+        ArquivoProdutos::update(self, product)
+    }
+
+    fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        ArquivoProdutos::remove(self, id)
+    }
+
+    fn movements(&mut self, id: u64) -> Result<Vec<Movimento>, Box<dyn Error>> {
+        self.movimentos.by_product(id)
+    }
+
+    fn balances(&mut self) -> Result<BTreeMap<u64, i64>, Box<dyn Error>> {
+        self.movimentos.balances()
+    }
+
+    fn append_movements(&mut self, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        self.movimentos.append(movements)
+    }
+
+    fn categories(&mut self) -> Result<Vec<Categoria>, Box<dyn Error>> {
+        self.categorias.list()
+    }
+
+    fn insert_category(&mut self, category: Categoria) -> Result<u64, Box<dyn Error>> {
+        self.categorias.insert(category)
+    }
+
+    fn update_category(&mut self, category: &Categoria) -> Result<(), Box<dyn Error>> {
+        self.categorias.update(category)
+    }
+
+    fn remove_category(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        self.categorias.remove(id)
+    }
+
+    fn promotions(&mut self) -> Result<Vec<Promocao>, Box<dyn Error>> {
+        self.promocoes.list()
+    }
+
+    fn insert_promotion(&mut self, promotion: Promocao) -> Result<u64, Box<dyn Error>> {
+        self.promocoes.insert(promotion)
+    }
+
+    fn update_promotion(&mut self, promotion: &Promocao) -> Result<(), Box<dyn Error>> {
+        self.promocoes.update(promotion)
+    }
+
+    fn remove_promotion(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        self.promocoes.remove(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Dinheiro, test_utils::{get_test_products, read_all, remove_test_products, test_path}, categories};
+
+    fn product(nome: &str) -> Produto {
+        Produto::new(nome.to_string(), 0, 10, Dinheiro::from_centavos(500), 2, chrono::NaiveDate::default(), categories::GERAL)
+    }
+
+    #[test]
+    fn test_insert_and_search() {
+        let path = &test_path("test_products_insert.bin");
+        let mut products = get_test_products(path);
+
+        let long_name = "Feijão carioca tipo 1 pacote econômico com 5 quilos da marca da casa";
+
+        assert_eq!(products.insert(product("Arroz")).unwrap(), 1);
+        assert_eq!(products.insert(product(long_name)).unwrap(), 2);
+        assert_eq!(products.insert(product("Café")).unwrap(), 3);
+
+        let (found, position) = products.search(2).expect("Erro na busca pelo produto.");
+
+        assert_eq!(found.nome, long_name);
+        assert_eq!(position, HEADER_LENGTH + serialize_record(&product("Arroz")).unwrap().len() as u64);
+        assert_eq!(products.header().unwrap().quantidade, 3);
+        assert!(products.search(4).is_err());
+        assert!(!products.index_is_stale().unwrap());
+
+        remove_test_products(path);
+    }
+
+    #[test]
+    fn test_update_relocates_record() {
+        let path = &test_path("test_products_update.bin");
+        let mut products = get_test_products(path);
+
+        for nome in ["A", "B", "C"] {
+            products.insert(product(nome)).unwrap();
+        }
+
+        let (mut first, _) = products.search(1).unwrap();
+        first.nome = "Um nome bem mais comprido que o anterior".to_string();
+
+        products.update(&first).unwrap();
+
+        let names: Vec<String> = products.list().unwrap().into_iter().map(|product| product.nome).collect();
+
+        assert_eq!(names, vec!["Um nome bem mais comprido que o anterior", "B", "C"]);
+        assert_eq!(products.search(3).unwrap().0.nome, "C");
+        assert_eq!(products.last_id().unwrap(), 3);
+        assert!(!products.index_is_stale().unwrap());
+
+        remove_test_products(path);
+    }
+
+    #[test]
+    fn test_barcode_index() {
+        let path = &test_path("test_products_barcode.bin");
+        let mut products = get_test_products(path);
+
+        let mut arroz = product("Arroz");
+        arroz.codigo_barras = Some("7891234567895".to_string());
+
+        products.insert(arroz).unwrap();
+        products.insert(product("Café")).unwrap();
+
+        assert_eq!(products.barcode_id("7891234567895").unwrap(), Some(1));
+        assert_eq!(products.barcode_id("4006381333931").unwrap(), None);
+
+        let (mut cafe, _) = products.search(2).unwrap();
+        cafe.codigo_barras = Some("4006381333931".to_string());
+        products.update(&cafe).unwrap();
+
+        let (mut arroz, _) = products.search(1).unwrap();
+        arroz.codigo_barras = None;
+        products.update(&arroz).unwrap();
+
+        assert_eq!(products.barcode_id("4006381333931").unwrap(), Some(2));
+        assert_eq!(products.barcode_id("7891234567895").unwrap(), None);
+
+        products.remove(2).unwrap();
+        assert_eq!(products.barcode_id("4006381333931").unwrap(), None);
+
+        remove_test_products(path);
+    }
+
+    #[test]
+    fn test_remove_and_rebuild_index() {
+        let path = &test_path("test_products_remove.bin");
+        let mut products = get_test_products(path);
+
+        for nome in ["A", "B", "C"] {
+            products.insert(product(nome)).unwrap();
+        }
+
+        products.remove(2).unwrap();
+
+        assert!(products.search(2).is_err());
+        assert_eq!(products.search(3).unwrap().0.nome, "C");
+        assert_eq!(products.header().unwrap().quantidade, 2);
+
+        let index = read_all(&mut products.indice);
+
+        products.indice.set_len(0).unwrap();
+
+        assert!(products.index_is_stale().unwrap());
+
+        products.rebuild_index().unwrap();
+
+        assert_eq!(index, read_all(&mut products.indice));
+        assert_eq!(products.insert(product("D")).unwrap(), 4);
+
+        remove_test_products(path);
+    }
+}
@@ -0,0 +1,386 @@
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use super::{categories, core, errors::CustomErrors, format::{self, Cabecalho, HEADER_LENGTH, PROMOTIONS_MAGIC}, money::Dinheiro, Categoria, Desconto, ItemVenda, Produto, Promocao, PromocaoAplicada, Regra, Venda};
+
+// Produtos de uma venda a registrar, com os descontos dados pelo vendedor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cesta {
+    pub itens: Vec<(u64, u64)>,
+    // Desconto em cada produto, aplicado sobre o que sobra depois da promoção.
+    pub descontos: Vec<(u64, Desconto)>,
+    // Desconto sobre o total, aplicado depois dos descontos dos produtos.
+    pub desconto: Option<Desconto>
+}
+
+impl Cesta {
+    pub fn new(itens: Vec<(u64, u64)>) -> Self {
+        Cesta { itens, ..Cesta::default() }
+    }
+}
+
+pub fn validate_promotion(promocao: &Promocao) -> Result<(), CustomErrors> {
+    match promocao.regra {
+        Regra::LevePague { leve, pague, .. } if pague == 0 || pague >= leve => return Err(CustomErrors::InvalidPromotion),
+        Regra::Categoria { percentual, .. } if !(1..=100).contains(&percentual) => return Err(CustomErrors::InvalidPromotion),
+        _ => ()
+    }
+
+    match (promocao.inicio, promocao.fim) {
+        (Some(inicio), Some(fim)) if inicio > fim => Err(CustomErrors::InvalidPeriod),
+        _ => Ok(())
+    }
+}
+
+pub fn discount_value(desconto: Desconto, valor: Dinheiro) -> Result<Dinheiro, CustomErrors> {
+    match desconto {
+        Desconto::Percentual(percentual) if (1..=100).contains(&percentual) => valor.proportion(percentual, 100),
+        Desconto::Fixo(fixo) if fixo > Dinheiro::ZERO && fixo <= valor => Ok(fixo),
+        _ => Err(CustomErrors::InvalidDiscount)
+    }
+}
+
+// Desconto que a promoção dá na linha da venda, se ela vale para o produto.
+fn promotion_discount(promocao: &Promocao, item: &ItemVenda, categoria: u64, categorias: &[Categoria]) -> Result<Option<Dinheiro>, CustomErrors> {
+    match promocao.regra {
+        Regra::LevePague { produto, leve, pague } if produto == item.id && item.quantidade >= leve => {
+            Ok(Some(item.valor_unitario.checked_mul(item.quantidade / leve * (leve - pague))?))
+        },
+        Regra::Categoria { categoria: alvo, percentual } if categories::with_descendants(categorias, alvo).contains(&categoria) => {
+            Ok(Some(item.valor_total.proportion(percentual, 100)?))
+        },
+        _ => Ok(None)
+    }
+}
+
+// As promoções não se somam: o produto recebe a de maior desconto entre as vigentes na data da venda.
+fn apply_promotion(sale_date: chrono::NaiveDate, item: &mut ItemVenda, product: Option<&Produto>, categorias: &[Categoria], promocoes: &[Promocao]) -> Result<Option<PromocaoAplicada>, CustomErrors> {
+    item.desconto = Dinheiro::ZERO;
+
+    let Some(product) = product else {
+        return Ok(None);
+    };
+
+    let mut best: Option<PromocaoAplicada> = None;
+
+    for promocao in promocoes.iter().filter(|promocao| promocao.vigente(sale_date)) {
+        match promotion_discount(promocao, item, product.categoria, categorias)? {
+            Some(valor) if valor > best.as_ref().map_or(Dinheiro::ZERO, |aplicada| aplicada.valor) => {
+                best = Some(PromocaoAplicada { promocao: promocao.id, nome: promocao.nome.clone(), produto: item.id, valor });
+            },
+            _ => ()
+        }
+    }
+
+    if let Some(aplicada) = &best {
+        item.desconto = aplicada.valor;
+    }
+
+    Ok(best)
+}
+
+pub(crate) fn lines_total(sale: &Venda) -> Result<Dinheiro, CustomErrors> {
+    sale.produtos.iter().try_fold(Dinheiro::ZERO, |total, item| total.checked_add(item.valor_liquido()?))
+}
+
+// Calcula os descontos de uma venda nova: primeiro as promoções, depois o desconto do vendedor
+// em cada produto e por último o desconto sobre o total.
+pub fn apply_discounts(sale: &mut Venda, products: &[Produto], categorias: &[Categoria], promocoes: &[Promocao], cesta: &Cesta) -> Result<(), CustomErrors> {
+    if cesta.descontos.iter().any(|(id, _)| !sale.produtos.iter().any(|item| item.id == *id)) {
+        return Err(CustomErrors::InvalidDiscount);
+    }
+
+    sale.promocoes.clear();
+
+    for item in sale.produtos.iter_mut() {
+        let product = products.iter().find(|product| product.id == item.id);
+
+        if let Some(aplicada) = apply_promotion(sale.data, item, product, categorias, promocoes)? {
+            sale.promocoes.push(aplicada);
+        }
+
+        if let Some(&(_, desconto)) = cesta.descontos.iter().find(|(id, _)| *id == item.id) {
+            item.desconto = item.desconto.checked_add(discount_value(desconto, item.valor_liquido()?)?)?;
+        }
+    }
+
+    let total = lines_total(sale)?;
+
+    sale.desconto = match cesta.desconto {
+        Some(desconto) => discount_value(desconto, total)?,
+        None => Dinheiro::ZERO
+    };
+
+    sale.valor = total.checked_sub(sale.desconto)?;
+
+    Ok(())
+}
+
+// Depois de uma alteração nos itens, as linhas alteradas perdem o desconto do vendedor e as
+// promoções são calculadas de novo. O desconto sobre o total continua o mesmo.
+pub fn reprice(sale: &mut Venda, changed: &[u64], products: &[Produto], categorias: &[Categoria], promocoes: &[Promocao]) -> Result<(), CustomErrors> {
+    sale.promocoes.retain(|aplicada| !changed.contains(&aplicada.produto));
+
+    for item in sale.produtos.iter_mut().filter(|item| changed.contains(&item.id)) {
+        let product = products.iter().find(|product| product.id == item.id);
+
+        if let Some(aplicada) = apply_promotion(sale.data, item, product, categorias, promocoes)? {
+            sale.promocoes.push(aplicada);
+        }
+    }
+
+    let total = lines_total(sale)?;
+
+    if sale.desconto > total {
+        return Err(CustomErrors::InvalidDiscount);
+    }
+
+    sale.valor = total.checked_sub(sale.desconto)?;
+
+    Ok(())
+}
+
+// Valor devolvido por `amount` unidades da linha: a parte do valor líquido da linha,
+// menos a parte proporcional do desconto sobre o total.
+pub fn refund_value(sale: &Venda, item: &ItemVenda, amount: u64) -> Result<Dinheiro, CustomErrors> {
+    let value = item.valor_liquido()?.proportion(amount, item.quantidade)?;
+
+    if sale.desconto == Dinheiro::ZERO {
+        return Ok(value);
+    }
+
+    let total = lines_total(sale)?;
+    let share = value.proportion(sale.desconto.centavos() as u64, total.centavos() as u64)?;
+
+    value.checked_sub(share)
+}
+
+pub fn serialize_record(promotion: &Promocao) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(promotion)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+// Cada promoção com a posição e o tamanho do seu registro.
+type Registro = (Promocao, u64, u64);
+
+#[derive(Debug)]
+pub struct ArquivoPromocoes {
+    pub dados: File
+}
+
+impl ArquivoPromocoes {
+    pub fn new(dados: File) -> Self {
+        ArquivoPromocoes { dados }
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, PROMOTIONS_MAGIC)
+    }
+
+    fn records(&mut self) -> Result<Vec<Registro>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut promotions = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let size: u64 = bincode::deserialize(&buf[offset..])?;
+            let end = offset + 8 + size as usize;
+
+            let record = buf.get(offset + 8..end).ok_or(CustomErrors::FormatMismatch)?;
+            promotions.push((bincode::deserialize(record)?, HEADER_LENGTH + offset as u64, end as u64 - offset as u64));
+
+            offset = end;
+        }
+
+        Ok(promotions)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Promocao>, Box<dyn Error>> {
+        let mut promotions: Vec<Promocao> = self.records()?.into_iter().map(|(promotion, _, _)| promotion).collect();
+        promotions.sort_by_key(|promotion| promotion.id);
+
+        Ok(promotions)
+    }
+
+    pub fn insert(&mut self, mut promotion: Promocao) -> Result<u64, Box<dyn Error>> {
+        let mut header = self.header()?;
+        promotion.id = header.ultimo_id + 1;
+
+        self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(&promotion)?)?;
+
+        header.quantidade += 1;
+        header.ultimo_id = promotion.id;
+        format::write_header(&mut self.dados, &header)?;
+        self.dados.sync_all()?;
+
+        Ok(promotion.id)
+    }
+
+    fn record(&mut self, id: u64) -> Result<Registro, Box<dyn Error>> {
+        let record = self.records()?.into_iter()
+            .find(|(promotion, _, _)| promotion.id == id)
+            .ok_or(CustomErrors::PromotionNotFound)?;
+
+        Ok(record)
+    }
+
+    pub fn update(&mut self, promotion: &Promocao) -> Result<(), Box<dyn Error>> {
+        let (_, position, length) = self.record(promotion.id)?;
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(promotion)?)?;
+        self.dados.sync_all()?;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        let (_, position, length) = self.record(id)?;
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        let mut header = self.header()?;
+        header.quantidade -= 1;
+        format::write_header(&mut self.dados, &header)?;
+        self.dados.sync_all()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{test_utils::{get_test_promotions, test_path}, MetodoPagamento};
+
+    fn day(day: u32) -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn product(id: u64, centavos: i64, categoria: u64) -> Produto {
+        Produto::new(format!("Produto{id}"), id, 10, Dinheiro::from_centavos(centavos), 1, day(1), categoria)
+    }
+
+    fn sale(items: &[(&Produto, u64)]) -> Venda {
+        let mut sale = Venda::new("Ana".to_string(), 0, Dinheiro::ZERO, day(20), MetodoPagamento::Pix);
+
+        for (product, amount) in items {
+            sale.produtos.push(ItemVenda::new(product.id, product.nome.clone(), *amount, product.valor).unwrap());
+        }
+
+        sale
+    }
+
+    #[test]
+    fn test_discount_value() {
+        let valor = Dinheiro::from_centavos(1999);
+
+        assert_eq!(discount_value(Desconto::Percentual(10), valor).unwrap(), Dinheiro::from_centavos(200));
+        assert_eq!(discount_value(Desconto::Percentual(100), valor).unwrap(), valor);
+        assert_eq!(discount_value(Desconto::Fixo(Dinheiro::from_centavos(500)), valor).unwrap(), Dinheiro::from_centavos(500));
+
+        for desconto in [Desconto::Percentual(0), Desconto::Percentual(101), Desconto::Fixo(Dinheiro::ZERO), Desconto::Fixo(Dinheiro::from_centavos(2000))] {
+            assert!(matches!(discount_value(desconto, valor), Err(CustomErrors::InvalidDiscount)), "{desconto}");
+        }
+    }
+
+    #[test]
+    fn test_apply_discounts() {
+        let mut categorias = categories::built_in();
+        categorias.push(Categoria::new(5, "Bebidas".to_string(), Some(categories::ALIMENTO)));
+
+        let arroz = product(1, 1000, categories::ALIMENTO);
+        let suco = product(2, 500, 5);
+        let camisa = product(3, 4000, categories::ROUPA);
+        let products = [arroz.clone(), suco.clone(), camisa.clone()];
+
+        let promocoes = [
+            Promocao::new(1, "Leve 3 pague 2".to_string(), Regra::LevePague { produto: 1, leve: 3, pague: 2 }, None, None),
+            Promocao::new(2, "Semana dos alimentos".to_string(), Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 }, Some(day(15)), Some(day(21))),
+            Promocao::new(3, "Roupas encerrada".to_string(), Regra::Categoria { categoria: categories::ROUPA, percentual: 50 }, None, Some(day(19)))
+        ];
+
+        let mut venda = sale(&[(&arroz, 7), (&suco, 2), (&camisa, 1)]);
+
+        let mut cesta = Cesta::new(vec![(1, 7), (2, 2), (3, 1)]);
+        cesta.descontos.push((3, Desconto::Fixo(Dinheiro::from_centavos(1000))));
+        cesta.desconto = Some(Desconto::Percentual(5));
+
+        apply_discounts(&mut venda, &products, &categorias, &promocoes, &cesta).unwrap();
+
+        // Leve 3 pague 2 duas vezes no arroz vence os 10%; o suco está numa subcategoria de Alimento.
+        let descontos: Vec<i64> = venda.produtos.iter().map(|item| item.desconto.centavos()).collect();
+        assert_eq!(descontos, vec![2000, 100, 1000]);
+        assert_eq!(venda.promocoes.iter().map(|aplicada| (aplicada.promocao, aplicada.produto)).collect::<Vec<_>>(), vec![(1, 1), (2, 2)]);
+
+        // 5000 + 900 + 3000 = 8900, menos 5% sobre o total.
+        assert_eq!(venda.desconto, Dinheiro::from_centavos(445));
+        assert_eq!(venda.valor, Dinheiro::from_centavos(8455));
+        assert_eq!(venda.subtotal().unwrap(), Dinheiro::from_centavos(12000));
+        assert_eq!(venda.descontos().unwrap(), Dinheiro::from_centavos(3545));
+
+        // Devolver um suco de 450 líquido devolve também a parte do desconto sobre o total.
+        assert_eq!(refund_value(&venda, &venda.produtos[1], 1).unwrap(), Dinheiro::from_centavos(427));
+
+        let mut cesta = Cesta::new(vec![(1, 1)]);
+        cesta.descontos.push((2, Desconto::Percentual(10)));
+        assert!(matches!(apply_discounts(&mut sale(&[(&arroz, 1)]), &products, &categorias, &promocoes, &cesta), Err(CustomErrors::InvalidDiscount)));
+
+        // Reduzir o arroz a 2 unidades tira o leve 3 pague 2 e deixa a promoção da categoria.
+        venda.produtos[0] = ItemVenda::new(1, arroz.nome.clone(), 2, arroz.valor).unwrap();
+        reprice(&mut venda, &[1], &products, &categorias, &promocoes).unwrap();
+
+        assert_eq!(venda.produtos[0].desconto, Dinheiro::from_centavos(200));
+        assert_eq!(venda.promocoes.iter().map(|aplicada| (aplicada.promocao, aplicada.produto)).collect::<Vec<_>>(), vec![(2, 2), (2, 1)]);
+        assert_eq!(venda.valor, Dinheiro::from_centavos(5255));
+    }
+
+    #[test]
+    fn test_validate_promotion() {
+        let regra = Regra::LevePague { produto: 1, leve: 3, pague: 2 };
+        assert!(validate_promotion(&Promocao::new(0, "Leve 3".to_string(), regra, Some(day(1)), Some(day(31)))).is_ok());
+
+        assert!(matches!(validate_promotion(&Promocao::new(0, "Leve 3".to_string(), regra, Some(day(31)), Some(day(1)))), Err(CustomErrors::InvalidPeriod)));
+
+        for regra in [Regra::LevePague { produto: 1, leve: 2, pague: 2 }, Regra::LevePague { produto: 1, leve: 2, pague: 0 }, Regra::Categoria { categoria: 1, percentual: 0 }, Regra::Categoria { categoria: 1, percentual: 120 }] {
+            assert!(matches!(validate_promotion(&Promocao::new(0, "Inválida".to_string(), regra, None, None)), Err(CustomErrors::InvalidPromotion)));
+        }
+    }
+
+    #[test]
+    fn test_promotion_file() {
+        let path = &test_path("test_promotions.bin");
+        let mut promotions = get_test_promotions(path);
+
+        assert!(promotions.list().unwrap().is_empty());
+
+        let regra = Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 };
+        assert_eq!(promotions.insert(Promocao::new(0, "Alimentos".to_string(), regra, Some(day(1)), None)).unwrap(), 1);
+        assert_eq!(promotions.insert(Promocao::new(0, "Leve 3".to_string(), Regra::LevePague { produto: 1, leve: 3, pague: 2 }, None, None)).unwrap(), 2);
+
+        promotions.update(&Promocao::new(1, "Alimentos".to_string(), Regra::Categoria { categoria: categories::GERAL, percentual: 10 }, Some(day(1)), None)).unwrap();
+        promotions.remove(2).unwrap();
+
+        let list = promotions.list().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].regra, Regra::Categoria { categoria: categories::GERAL, percentual: 10 });
+        assert_eq!(list[0].inicio, Some(day(1)));
+
+        assert_eq!(promotions.insert(Promocao::new(0, "Outra".to_string(), regra, None, None)).unwrap(), 3);
+        assert!(matches!(promotions.remove(2).unwrap_err().downcast_ref::<CustomErrors>(), Some(CustomErrors::PromotionNotFound)));
+
+        fs::remove_file(path).expect("Erro ao tentar excluir o arquivo.");
+    }
+}
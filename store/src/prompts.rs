@@ -0,0 +1,721 @@
+use std::{error::Error, io::{self, BufRead}};
+
+use store::{categories, errors, money::Dinheiro, reports, search::FiltroProdutos, validation::{parse_adjustment, parse_discount, parse_movement_type, parse_payment_method, validate_category, validate_int, validate_line_discount, validate_money, validate_name, validate_product, validate_receipt, validate_sale, validate_scan, validate_taxes, ItemLido, ItemRecebido}, Categoria, Desconto, MetodoPagamento, Produto, Promocao, Regra, TipoMovimento, Tributacao};
+
+use crate::screens;
+
+pub fn confirm<R: BufRead>(reader: &mut R) -> Result<bool, errors::CustomErrors> {
+    loop {
+        let answer = validate_string(reader)?;
+
+        match answer.to_lowercase().as_str() {
+            "s" | "sim" => return Ok(true),
+            "n" | "nao" | "não" => return Ok(false),
+            _ => eprintln!("\nResponda com 's' ou 'n'.\n")
+        }
+    }
+}
+
+pub fn get_option() -> u64 {
+    loop {
+        screens::menu_screen();
+
+        let mut buf = String::new();
+        match io::stdin().read_line(&mut buf) {
+            // Fim da entrada encerra o programa como 'sair'
+            Ok(0) => return 0,
+            Ok(_) => (),
+            Err(error) => {
+                eprintln!("\nOcorreu um erro ao tentar ler a opção selecionada: {error}\nCertifique-se de ter inserido corretamente.\n");
+                continue;
+            }
+        }
+
+        if buf.trim().to_lowercase() == "sair" {
+            return 0;
+        }
+
+        let option: u64 = match buf.trim().parse() {
+            Ok(value) => value,
+            Err(error) => {
+                eprintln!("\nOcorreu um erro ao tentar ler a opção selecionada: {error}\nCertifique-se de ter inserido corretamente.\n");
+                continue;
+            }
+        };
+
+        return option;
+    }
+}
+
+pub fn validate_string<R: BufRead>(reader: &mut R) -> Result<String, errors::CustomErrors> {
+    loop {
+        let mut buf = String::new();
+        match reader.read_line(&mut buf) {
+            // Fim da entrada cancela a operação, como 'sair'
+            Ok(0) => return Err(errors::CustomErrors::OperationCanceled),
+            Ok(_) => (),
+            Err(error) => {
+                eprintln!("\nUm erro ocorreu na leitura: {error}\n");
+                continue;
+            }
+        }
+
+        if buf.trim().to_lowercase() == "sair" {
+            return Err(errors::CustomErrors::OperationCanceled);
+        }
+
+        return Ok(buf.trim().to_string());
+    }
+}
+
+pub fn validate_search<R: BufRead>(search: &str, reader: &mut R) -> Result<u64, errors::CustomErrors> {
+
+    match search {
+        "id" => println!("\nDigite o ID do produto (ou sair para cancelar a operação):"),
+        _ => println!("\nDigite o código da venda (ou sair para cancelar a operação):")
+    }
+
+    loop {
+        let buf = validate_string(reader)?;
+
+        match validate_int(&buf) {
+            Ok(id) => return Ok(id),
+            Err(error) => eprintln!("\nUm erro ocorreu ao tentar converter o ID: {error}\nCertifique-se de que um valor válido foi inserido.\n")
+        };
+    }
+}
+
+pub fn get_product_info<R: BufRead>(reader: &mut R, categorias: &[Categoria]) -> Result<Produto, Box<dyn Error>> {
+    loop {
+        screens::add_product_screen();
+        println!("  Categorias cadastradas: {}\n", category_options(categorias));
+
+        let mut buf = String::new();
+
+        if reader.read_line(&mut buf)? == 0 || buf.trim().to_lowercase() == "sair" {
+            return Err(Box::new(errors::CustomErrors::OperationCanceled));
+        }
+
+        let fields: Vec<&str> = buf.split(' ').map(|field| field.trim()).collect();
+
+        if !(6..=7).contains(&fields.len()) {
+            eprintln!("\nNúmero incorreto de argumentos.\n");
+            continue;
+        }
+
+        match validate_product(fields, categorias) {
+            Ok(product) => return Ok(product),
+            Err(error) => eprintln!("\nUm erro ocorreu durante a conversão de argumentos: {error}\nVerifique se todos os campos foram inseridos corretamente.\n")
+        };
+    }
+}
+
+fn category_options(categorias: &[Categoria]) -> String {
+    categorias.iter()
+        .map(|categoria| format!("{} ({})", categoria.nome(), categoria.id()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn get_category<R: BufRead>(message: &str, reader: &mut R, categorias: &[Categoria]) -> Result<u64, errors::CustomErrors> {
+    println!("\n{message}\n\nOpções: {}\n", category_options(categorias));
+
+    loop {
+        match validate_category(&validate_string(reader)?, categorias) {
+            Ok(id) => return Ok(id),
+            Err(error) => eprintln!("\n{error} Escolha uma das categorias cadastradas.\n")
+        }
+    }
+}
+
+// Nome da nova categoria e, opcionalmente, a categoria acima dela.
+pub fn get_category_info<R: BufRead>(reader: &mut R, categorias: &[Categoria]) -> Result<(String, Option<u64>), errors::CustomErrors> {
+    println!("\nDigite o nome da nova categoria (ou 'sair' para cancelar):");
+
+    let nome = loop {
+        match validate_name(&validate_string(reader)?) {
+            Ok(nome) => break nome,
+            Err(_) => eprintln!("\nO nome da categoria não pode ser vazio.\n")
+        }
+    };
+
+    println!("\nDigite o nome ou o id da categoria acima desta (deixe em branco para uma categoria principal):\n\nOpções: {}\n", category_options(categorias));
+    let pai = validate_optional(reader, |text| validate_category(text, categorias), "Categoria inválida. Escolha uma das categorias cadastradas.")?;
+
+    Ok((nome, pai))
+}
+
+pub fn get_tax_info<R: BufRead>(reader: &mut R) -> Result<Tributacao, errors::CustomErrors> {
+    screens::product_taxes_screen();
+
+    loop {
+        let buf = validate_string(reader)?;
+        let fields: Vec<&str> = buf.split_whitespace().collect();
+
+        match validate_taxes(&fields) {
+            Ok(tributacao) => return Ok(tributacao),
+            Err(error) => eprintln!("\n{error}\nVerifique se todos os campos foram inseridos corretamente.\n")
+        }
+    }
+}
+
+pub fn get_sale_info<R: BufRead>(reader: &mut R) -> Result<(chrono::NaiveDate, MetodoPagamento), Box<dyn Error>> {
+    println!("\nDigite a data da venda seguindo o formato dd/mm/YYYY (ou digite 'sair' para cancelar):");
+
+    let date = validate_date(reader)?;
+    let payment_method = validate_payment_method(reader)?;
+
+    Ok((date, payment_method))
+}
+
+pub fn get_items<R: BufRead>(reader: &mut R) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    read_items(reader, validate_sale)
+}
+
+pub fn get_sale_items<R: BufRead>(reader: &mut R) -> Result<Vec<ItemLido>, Box<dyn Error>> {
+    read_items(reader, validate_scan)
+}
+
+pub fn get_line_discounts<R: BufRead>(reader: &mut R) -> Result<Vec<(u64, Desconto)>, Box<dyn Error>> {
+    println!("\nDigite os descontos por produto no formato 'ID DESCONTO' (ex.: '3 10%' ou '3 2,50'), ou 'concluir' se não houver:\n");
+    read_items(reader, validate_line_discount)
+}
+
+pub fn get_sale_discount<R: BufRead>(reader: &mut R) -> Result<Option<Desconto>, errors::CustomErrors> {
+    println!("\nDigite o desconto sobre o total da venda (ex.: 10% ou 5,00; deixe em branco para não aplicar):");
+    validate_optional(reader, parse_discount, "Desconto inválido. Use uma porcentagem (ex.: 10%) ou um valor (ex.: 5,00).")
+}
+
+// Nome, regra e vigência da nova promoção; a regra é conferida ao cadastrar.
+pub fn get_promotion_info<R: BufRead>(reader: &mut R, categorias: &[Categoria]) -> Result<Promocao, errors::CustomErrors> {
+    println!("\nDigite o nome da promoção (ou 'sair' para cancelar):");
+
+    let nome = loop {
+        match validate_name(&validate_string(reader)?) {
+            Ok(nome) => break nome,
+            Err(_) => eprintln!("\nO nome da promoção não pode ser vazio.\n")
+        }
+    };
+
+    println!("\nEscolha o tipo da promoção:\n\nOpções: produto (leve N, pague M), categoria (porcentagem de desconto)\n");
+
+    let regra = loop {
+        match validate_string(reader)?.to_lowercase().as_str() {
+            "produto" => {
+                let produto = validate_search("id", reader)?;
+                let leve = validate_number("Digite quantas unidades o cliente leva:", reader)?;
+                let pague = validate_number("Digite quantas unidades o cliente paga:", reader)?;
+
+                break Regra::LevePague { produto, leve, pague };
+            },
+            "categoria" => {
+                let categoria = get_category("Digite o nome ou o id da categoria em promoção (ou 'sair' para cancelar):", reader, categorias)?;
+                let percentual = validate_number("Digite a porcentagem de desconto (ex.: 15):", reader)?;
+
+                break Regra::Categoria { categoria, percentual };
+            },
+            _ => eprintln!("\nTipo inválido. Escolha entre produto e categoria.\n")
+        }
+    };
+
+    println!("\nDigite a data de início seguindo o formato dd/mm/YYYY (deixe em branco para começar já):");
+    let inicio = validate_optional_date(reader)?;
+
+    println!("\nDigite a data de término seguindo o formato dd/mm/YYYY (deixe em branco para não terminar):");
+    let fim = validate_optional_date(reader)?;
+
+    Ok(Promocao::new(0, nome, regra, inicio, fim))
+}
+
+fn validate_number<R: BufRead>(message: &str, reader: &mut R) -> Result<u64, errors::CustomErrors> {
+    println!("\n{message}");
+
+    loop {
+        match validate_int(&validate_string(reader)?) {
+            Ok(number) => return Ok(number),
+            Err(_) => eprintln!("\nQuantidade inválida. Digite um número inteiro.\n")
+        }
+    }
+}
+
+pub fn get_receipt_items<R: BufRead>(reader: &mut R) -> Result<Vec<ItemRecebido>, Box<dyn Error>> {
+    read_items(reader, validate_receipt)
+}
+
+pub fn get_supplier<R: BufRead>(reader: &mut R) -> Result<String, errors::CustomErrors> {
+    println!("\nInsira o nome do fornecedor (ou 'sair' para cancelar):");
+
+    loop {
+        match validate_name(&validate_string(reader)?) {
+            Ok(name) => return Ok(name),
+            Err(_) => eprintln!("\nO nome do fornecedor não pode ser vazio.\n")
+        }
+    }
+}
+
+fn read_items<R: BufRead, T>(reader: &mut R, parse: fn(&str) -> Result<T, Box<dyn Error>>) -> Result<Vec<T>, Box<dyn Error>> {
+    let mut items = Vec::new();
+
+    loop {
+        let mut buf = String::new();
+
+        if reader.read_line(&mut buf)? == 0 {
+            return Err(Box::new(errors::CustomErrors::OperationCanceled));
+        }
+
+        match buf.trim().to_lowercase().as_str() {
+            "sair" => return Err(Box::new(errors::CustomErrors::OperationCanceled)),
+            "concluir" => return Ok(items),
+            _ => ()
+        }
+
+        items.push(parse(buf.trim())?);
+        println!("\nPróximo produto (ou 'concluir' para finalizar, 'sair' para cancelar):\n")
+    }
+}
+
+pub fn get_reason<R: BufRead>(reader: &mut R) -> Result<String, errors::CustomErrors> {
+    println!("\nInforme o motivo (ou 'sair' para cancelar):");
+
+    loop {
+        let reason = validate_string(reader)?;
+
+        match reason.is_empty() {
+            true => eprintln!("\nO motivo não pode ser vazio.\n"),
+            false => return Ok(reason)
+        }
+    }
+}
+
+pub fn get_adjustment_info<R: BufRead>(reader: &mut R) -> Result<(TipoMovimento, i64, String), errors::CustomErrors> {
+    println!("\nInsira o tipo do movimento:\n\nOpções: ajuste, perda (ou digite 'sair' para cancelar)\n");
+
+    let tipo = loop {
+        match parse_movement_type(&validate_string(reader)?) {
+            Ok(tipo) => break tipo,
+            Err(_) => eprintln!("\nTipo de movimento inválido.\n")
+        }
+    };
+
+    match tipo {
+        TipoMovimento::Perda => println!("\nDigite a quantidade de unidades perdidas ou avariadas:"),
+        _ => println!("\nDigite a quantidade do ajuste (use sinal negativo para retirar unidades do estoque):")
+    }
+
+    let quantity = loop {
+        match parse_adjustment(tipo, &validate_string(reader)?) {
+            Ok(quantity) => break quantity,
+            Err(error) => eprintln!("\n{error}\n")
+        }
+    };
+
+    let reason = get_reason(reader)?;
+
+    Ok((tipo, quantity, reason))
+}
+
+pub fn validate_payment_method<R: BufRead>(reader: &mut R) -> Result<MetodoPagamento, Box<dyn Error>> {
+    
+    println!("\nInsira a forma de pagamento:\n\nOpções: credito, debito, pix, dinheiro\n\n* Atenção: Não utilizar acento! *\n");
+
+    let mut buf = String::new();
+
+    reader.read_line(&mut buf)?;
+
+    Ok(parse_payment_method(&buf)?)
+}
+
+pub fn get_session_info<R: BufRead>(reader: &mut R) -> Result<(String, Dinheiro), errors::CustomErrors> {
+    println!("\nInsira o nome do operador do caixa (ou 'sair' para cancelar):");
+
+    let operator = loop {
+        match validate_name(&validate_string(reader)?) {
+            Ok(name) => break name,
+            Err(_) => eprintln!("\nO nome do operador não pode ser vazio.\n")
+        }
+    };
+
+    let float = validate_amount("Digite o valor do fundo de troco (ou 'sair' para cancelar):", reader)?;
+
+    Ok((operator, float))
+}
+
+pub fn validate_amount<R: BufRead>(message: &str, reader: &mut R) -> Result<Dinheiro, errors::CustomErrors> {
+    println!("\n{message}");
+
+    loop {
+        let buf = validate_string(reader)?;
+
+        match validate_money(&buf) {
+            Ok(value) if value.centavos() >= 0 => return Ok(value),
+            _ => eprintln!("\nValor inválido. Use vírgula ou ponto como separador decimal (ex.: 100,00).\n")
+        }
+    }
+}
+
+pub fn get_report_info<R: BufRead>(reader: &mut R) -> Result<(reports::Agrupamento, Option<chrono::NaiveDate>, Option<chrono::NaiveDate>), errors::CustomErrors> {
+    println!("\nAgrupar as vendas por:\n\nOpções: dia, semana, mes, vendedor, pagamento\n\n* Atenção: Não utilizar acento! * (ou digite 'sair' para cancelar)\n");
+
+    let agrupamento = loop {
+        match reports::parse_grouping(&validate_string(reader)?) {
+            Ok(agrupamento) => break agrupamento,
+            Err(_) => eprintln!("\nAgrupamento inválido. Escolha entre dia, semana, mes, vendedor ou pagamento.\n")
+        }
+    };
+
+    println!("\nDigite a data inicial seguindo o formato dd/mm/YYYY (deixe em branco para não limitar):");
+    let from = validate_optional_date(reader)?;
+
+    println!("\nDigite a data final seguindo o formato dd/mm/YYYY (deixe em branco para não limitar):");
+    let to = validate_optional_date(reader)?;
+
+    Ok((agrupamento, from, to))
+}
+
+pub fn get_product_filter<R: BufRead>(reader: &mut R, categorias: &[Categoria]) -> Result<FiltroProdutos, errors::CustomErrors> {
+    loop {
+        println!("\nDigite o nome ou parte do nome do produto (deixe em branco para não filtrar, ou digite 'sair' para cancelar):");
+        let nome = Some(validate_string(reader)?).filter(|nome| !nome.is_empty());
+
+        println!("\nDigite o nome ou o id da categoria do produto; as subcategorias também são incluídas (deixe em branco para não filtrar):\n\nOpções: {}\n", category_options(categorias));
+        let categoria = validate_optional(reader, |text| validate_category(text, categorias), "Categoria inválida. Escolha uma das categorias cadastradas.")?;
+
+        println!("\nDigite o preço mínimo (deixe em branco para não limitar):");
+        let valor_minimo = validate_optional(reader, validate_price, "Valor inválido. Use vírgula ou ponto como separador decimal (ex.: 100,00).")?;
+
+        println!("\nDigite o preço máximo (deixe em branco para não limitar):");
+        let valor_maximo = validate_optional(reader, validate_price, "Valor inválido. Use vírgula ou ponto como separador decimal (ex.: 100,00).")?;
+
+        println!("\nDigite o estoque mínimo (deixe em branco para não limitar):");
+        let estoque_minimo = validate_optional(reader, validate_int, "Quantidade inválida. Digite um número inteiro.")?;
+
+        println!("\nDigite o estoque máximo (deixe em branco para não limitar):");
+        let estoque_maximo = validate_optional(reader, validate_int, "Quantidade inválida. Digite um número inteiro.")?;
+
+        println!("\nMostrar apenas produtos com estoque na quantidade de restoque ou abaixo dela? (s/n)");
+        let abaixo_restoque = confirm(reader)?;
+
+        let categorias = categoria.map(|id| categories::with_descendants(categorias, id)).unwrap_or_default();
+        let filtro = FiltroProdutos { nome, categorias, valor_minimo, valor_maximo, estoque_minimo, estoque_maximo, abaixo_restoque };
+
+        match filtro.validate() {
+            Ok(()) => return Ok(filtro),
+            Err(error) => eprintln!("\n{error} Digite os filtros novamente.\n")
+        }
+    }
+}
+
+fn validate_price(string: &str) -> Result<Dinheiro, errors::CustomErrors> {
+    match validate_money(string)? {
+        value if value.centavos() >= 0 => Ok(value),
+        _ => Err(errors::CustomErrors::InvalidValue)
+    }
+}
+
+// Lê um valor opcional: a linha em branco significa que o campo não foi informado.
+fn validate_optional<R: BufRead, T, E, F: Fn(&str) -> Result<T, E>>(reader: &mut R, parse: F, message: &str) -> Result<Option<T>, errors::CustomErrors> {
+    loop {
+        let buf = validate_string(reader)?;
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        match parse(&buf) {
+            Ok(value) => return Ok(Some(value)),
+            Err(_) => eprintln!("\n{message}\n")
+        }
+    }
+}
+
+pub fn validate_optional_date<R: BufRead>(reader: &mut R) -> Result<Option<chrono::NaiveDate>, errors::CustomErrors> {
+    loop {
+        let buf = validate_string(reader)?;
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        match chrono::NaiveDate::parse_from_str(&buf, "%d/%m/%Y") {
+            Ok(date) => return Ok(Some(date)),
+            Err(error) => eprintln!("\nOcorreu um erro ao tentar ler a data informada: {error}\nCertifique-se de que a data está inserida no formato correto.\n")
+        }
+    }
+}
+
+pub fn validate_date<R: BufRead>(reader: &mut R) -> Result<chrono::NaiveDate, errors::CustomErrors> {
+    loop {
+        let buf = validate_string(reader)?;
+
+        match chrono::NaiveDate::parse_from_str(&buf, "%d/%m/%Y") {
+            Ok(date) => return Ok(date),
+            Err(error) => eprintln!("\nOcorreu um erro ao tentar ler a data informada: {error}\nCertifique-se de que a data está inserida no formato correto.\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_confirm() {
+        let mut cursor = Cursor::new(b"talvez\nS");
+
+        assert!(confirm(&mut cursor).unwrap());
+
+        let mut cursor = Cursor::new(b"nao");
+
+        assert!(!confirm(&mut cursor).unwrap());
+
+        let mut cursor = Cursor::new(b"talvez\n");
+
+        assert!(matches!(confirm(&mut cursor), Err(errors::CustomErrors::OperationCanceled)));
+    }
+
+    #[test]
+    fn test_validate_string() {
+        let input = b"Carlos";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_string(&mut cursor);
+
+        assert!(result.is_ok());
+
+        let input = b"sair";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_string(&mut cursor);
+
+        assert!(result.is_err());
+
+        let mut cursor = Cursor::new(b"");
+
+        assert!(matches!(validate_string(&mut cursor), Err(errors::CustomErrors::OperationCanceled)));
+    }
+
+    #[test]
+    fn test_validate_search() {
+        let input = b"3";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_search("id", &mut cursor);
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_get_product_info() {
+        let input = b"Camisa 10 50 5 10/8/2023 Geral";
+        let mut cursor = Cursor::new(input);
+
+        let result = get_product_info(&mut cursor, &categories::built_in());
+
+        assert!(result.is_ok());
+
+        let produto = result.unwrap();
+
+        assert_eq!(produto.nome(), "Camisa");
+        assert_eq!(produto.quantidade_estoque(), 10);
+        assert_eq!(produto.quantidade_restoque(), 5);
+        assert!(produto.codigo_barras().is_none());
+    }
+
+    #[test]
+    fn test_get_tax_info() {
+        let mut cursor = Cursor::new(b"10063021 5102 18\n10063021 6102 12 1,65 7,6\n");
+        assert_eq!(get_tax_info(&mut cursor).unwrap().cfop(), "6102");
+
+        let mut cursor = Cursor::new(b"sair\n");
+        assert!(matches!(get_tax_info(&mut cursor), Err(errors::CustomErrors::OperationCanceled)));
+    }
+
+    #[test]
+    fn test_get_discounts() {
+        let mut cursor = Cursor::new(b"3 10%\n1 1,00\nconcluir\n\n");
+
+        assert_eq!(get_line_discounts(&mut cursor).unwrap(), vec![(3, Desconto::Percentual(10)), (1, Desconto::Fixo(Dinheiro::from_centavos(100)))]);
+        assert_eq!(get_sale_discount(&mut cursor).unwrap(), None);
+
+        let mut cursor = Cursor::new(b"abc\n5%\n");
+        assert_eq!(get_sale_discount(&mut cursor).unwrap(), Some(Desconto::Percentual(5)));
+    }
+
+    #[test]
+    fn test_get_sale_info() {
+        let input = b"1/1/1970\ncredito";
+        let mut cursor = Cursor::new(input);
+
+        let result = get_sale_info(&mut cursor);
+
+        assert!(result.is_ok());
+
+        let sale_info = result.unwrap();
+
+        assert_eq!(sale_info.0, chrono::NaiveDate::default());
+    }
+
+    #[test]
+    fn test_get_session_info() {
+        let input = b"\nAna\n-5\n150,00\n";
+        let mut cursor = Cursor::new(input);
+
+        let (operator, float) = get_session_info(&mut cursor).unwrap();
+
+        assert_eq!(operator, "Ana");
+        assert_eq!(float, Dinheiro::from_centavos(15000));
+    }
+
+    #[test]
+    fn test_get_report_info() {
+        let input = b"ano\nsemana\n\n31/12/2024\n";
+        let mut cursor = Cursor::new(input);
+
+        let (agrupamento, from, to) = get_report_info(&mut cursor).unwrap();
+
+        assert_eq!(agrupamento, reports::Agrupamento::Semana);
+        assert!(from.is_none());
+        assert_eq!(to, chrono::NaiveDate::from_ymd_opt(2024, 12, 31));
+    }
+
+    #[test]
+    fn test_get_category_info() {
+        let mut cursor = Cursor::new("  \nBebidas\nbrinquedo\nalimento\n");
+
+        assert_eq!(get_category_info(&mut cursor, &categories::built_in()).unwrap(), ("Bebidas".to_string(), Some(categories::ALIMENTO)));
+
+        let mut cursor = Cursor::new("Limpeza\n\n");
+        assert_eq!(get_category_info(&mut cursor, &categories::built_in()).unwrap(), ("Limpeza".to_string(), None));
+
+        let mut cursor = Cursor::new("bebidas\n4\n");
+        assert_eq!(get_category("Categoria:", &mut cursor, &categories::built_in()).unwrap(), categories::GERAL);
+    }
+
+    #[test]
+    fn test_get_product_filter() {
+        let mut categorias = categories::built_in();
+        categorias.push(Categoria::new(5, "Bebidas".to_string(), Some(categories::ALIMENTO)));
+
+        let mut cursor = Cursor::new("feijão\nbrinquedo\nalimento\n\n10,50\n\n\nn\n");
+
+        let filtro = get_product_filter(&mut cursor, &categorias).unwrap();
+
+        assert_eq!(filtro.nome.as_deref(), Some("feijão"));
+        assert_eq!(filtro.categorias, vec![categories::ALIMENTO, 5]);
+        assert!(filtro.valor_minimo.is_none());
+        assert_eq!(filtro.valor_maximo, Some(Dinheiro::from_centavos(1050)));
+        assert!(filtro.estoque_minimo.is_none() && filtro.estoque_maximo.is_none());
+        assert!(!filtro.abaixo_restoque);
+
+        // Com o mínimo acima do máximo os filtros são pedidos de novo.
+        let mut cursor = Cursor::new("\n\n\n\n10\n2\nn\n\n\n\n\n2\n10\ns\n");
+
+        let filtro = get_product_filter(&mut cursor, &categorias).unwrap();
+
+        assert_eq!((filtro.estoque_minimo, filtro.estoque_maximo), (Some(2), Some(10)));
+        assert!(filtro.nome.is_none() && filtro.categorias.is_empty() && filtro.abaixo_restoque);
+    }
+
+    #[test]
+    fn test_get_receipt_items() {
+        let mut cursor = Cursor::new("Atacadão\n2 10 4,50\n3 1 12\nconcluir\n");
+
+        assert_eq!(get_supplier(&mut cursor).unwrap(), "Atacadão");
+        assert_eq!(get_receipt_items(&mut cursor).unwrap(), vec![(2, 10, Dinheiro::from_centavos(450)), (3, 1, Dinheiro::from_centavos(1200))]);
+    }
+
+    #[test]
+    fn test_get_items() {
+        let mut cursor = Cursor::new(b"2 3\n4\nconcluir\n");
+        assert_eq!(get_items(&mut cursor).unwrap(), vec![(2, 3), (4, 1)]);
+
+        let mut cursor = Cursor::new(b"2 3\nsair\n");
+        assert!(get_items(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn test_get_sale_items() {
+        let mut cursor = Cursor::new(b"7891234567895\n2 3\n4006381333931 2\n7891234567895\nconcluir\n");
+
+        assert_eq!(get_sale_items(&mut cursor).unwrap(), vec![
+            ItemLido::CodigoBarras("7891234567895".to_string(), 1),
+            ItemLido::Id(2, 3),
+            ItemLido::CodigoBarras("4006381333931".to_string(), 2),
+            ItemLido::CodigoBarras("7891234567895".to_string(), 1)
+        ]);
+
+        // Uma leitura com o dígito verificador errado não é confundida com um id.
+        let mut cursor = Cursor::new(b"7891234567890\nconcluir\n");
+        assert!(get_sale_items(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn test_get_promotion_info() {
+        let mut cursor = Cursor::new("\nLeve 3\nbrinde\nproduto\n2\n3\n2\n01/02/2024\n\n");
+        let promotion = get_promotion_info(&mut cursor, &categories::built_in()).unwrap();
+
+        assert_eq!(promotion.nome(), "Leve 3");
+        assert_eq!(promotion.regra(), Regra::LevePague { produto: 2, leve: 3, pague: 2 });
+        assert_eq!(promotion.inicio(), chrono::NaiveDate::from_ymd_opt(2024, 2, 1));
+        assert_eq!(promotion.fim(), None);
+
+        let mut cursor = Cursor::new("Roupas\ncategoria\nroupa\n20\n\n\n");
+        let promotion = get_promotion_info(&mut cursor, &categories::built_in()).unwrap();
+
+        assert_eq!(promotion.regra(), Regra::Categoria { categoria: categories::ROUPA, percentual: 20 });
+    }
+
+    #[test]
+    fn test_get_reason() {
+        let mut cursor = Cursor::new(b"\nProduto com defeito\n");
+        assert_eq!(get_reason(&mut cursor).unwrap(), "Produto com defeito");
+    }
+
+    #[test]
+    fn test_get_adjustment_info() {
+        let mut cursor = Cursor::new(b"troca
+perda
+-2
+0
+3
+Caixa amassada
+");
+        assert_eq!(get_adjustment_info(&mut cursor).unwrap(), (TipoMovimento::Perda, -3, "Caixa amassada".to_string()));
+
+        assert_eq!(parse_adjustment(TipoMovimento::Ajuste, "-4").unwrap(), -4);
+        assert!(parse_adjustment(TipoMovimento::Ajuste, "dois").is_err());
+        assert!(parse_movement_type("restoque").is_err());
+    }
+
+    #[test]
+    fn test_validate_payment_method() {
+        let input = b"credito";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_payment_method(&mut cursor);
+
+        assert!(result.is_ok());
+
+        let input = b"invalido";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_payment_method(&mut cursor);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_date() {
+        let input = b"1/1/1970";
+        let mut cursor = Cursor::new(input);
+
+        let result = validate_date(&mut cursor);
+
+        assert!(result.is_ok());
+
+        assert_eq!(result.unwrap(), chrono::NaiveDate::default());
+    }
+}
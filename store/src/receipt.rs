@@ -0,0 +1,293 @@
+use std::{error::Error, fs, path::{Path, PathBuf}};
+
+use super::{config, errors::CustomErrors, money::Dinheiro, search, taxes, Situacao, Venda};
+
+pub const STORE_FILE: &str = "loja.conf";
+
+// Colunas de uma bobina de 80 mm na fonte padrão das impressoras térmicas.
+const WIDTH: usize = 48;
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+
+// Cabeçalho do cupom e impressora da loja, lidos do arquivo loja.conf no diretório de dados.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Loja {
+    pub nome: String,
+    pub cnpj: String,
+    pub endereco: String,
+    // Arquivo ou dispositivo da impressora térmica, que recebe o cupom em ESC/POS.
+    pub impressora: Option<PathBuf>
+}
+
+pub fn read_store(dir: &Path) -> Result<Loja, Box<dyn Error>> {
+    let mut loja = Loja::default();
+
+    for (key, value) in config::read_settings(&dir.join(STORE_FILE))? {
+        match key.as_str() {
+            "nome" => loja.nome = value,
+            "cnpj" => loja.cnpj = value,
+            "endereco" => loja.endereco = value,
+            "impressora" => loja.impressora = Some(PathBuf::from(value)),
+            _ => ()
+        }
+    }
+
+    Ok(loja)
+}
+
+enum Linha {
+    // Centralizada e, na impressora, em negrito.
+    Titulo(String),
+    Centro(String),
+    Texto(String),
+    Separador
+}
+
+// Texto à esquerda e valor à direita, cortando o texto quando os dois não cabem na linha.
+fn columns(left: &str, right: &str) -> Linha {
+    let space = WIDTH.saturating_sub(right.chars().count() + 1);
+    let left: String = left.chars().take(space).collect();
+
+    Linha::Texto(format!("{left:<space$} {right}"))
+}
+
+fn negative(value: Dinheiro) -> String {
+    format!("-{value}")
+}
+
+fn lines(sale: &Venda, loja: &Loja) -> Result<Vec<Linha>, CustomErrors> {
+    let mut lines = Vec::new();
+
+    if !loja.nome.is_empty() {
+        lines.push(Linha::Titulo(loja.nome.clone()));
+    }
+
+    if !loja.cnpj.is_empty() {
+        lines.push(Linha::Centro(format!("CNPJ: {}", loja.cnpj)));
+    }
+
+    if !loja.endereco.is_empty() {
+        lines.push(Linha::Centro(loja.endereco.clone()));
+    }
+
+    lines.push(Linha::Separador);
+    lines.push(Linha::Titulo("CUPOM NÃO FISCAL".to_string()));
+    lines.push(columns(&format!("Venda {}", sale.codigo), &sale.data.format("%d/%m/%Y").to_string()));
+
+    match sale.caixa {
+        Some(caixa) => lines.push(columns(&format!("Vendedor: {}", sale.vendedor), &format!("Caixa: {caixa}"))),
+        None => lines.push(Linha::Texto(format!("Vendedor: {}", sale.vendedor)))
+    }
+
+    lines.push(Linha::Separador);
+
+    let taxes = taxes::sale_taxes(sale)?;
+
+    for (number, item) in sale.produtos.iter().enumerate() {
+        lines.push(Linha::Texto(format!("{:03} {} (ID: {})", number + 1, item.nome, item.id)));
+        lines.push(columns(&format!("    {} x {}", item.quantidade, item.valor_unitario), &item.valor_total.to_string()));
+
+        if item.desconto > Dinheiro::ZERO {
+            let promocao = sale.promocoes.iter().find(|aplicada| aplicada.produto == item.id);
+            let label = promocao.map_or("Desconto".to_string(), |aplicada| format!("Desconto - {}", aplicada.nome));
+
+            lines.push(columns(&format!("    {label}"), &negative(item.desconto)));
+        }
+
+        let (Some(tributacao), Some(tributos)) = (&item.tributacao, taxes.iter().find(|tributos| tributos.produto == item.id)) else {
+            continue;
+        };
+
+        lines.push(Linha::Texto(format!("    NCM {}  CFOP {}", tributacao.ncm, tributacao.cfop)));
+        lines.push(columns(&format!("    ICMS {}", tributacao.icms), &tributos.icms.to_string()));
+        lines.push(columns(&format!("    PIS {}", tributacao.pis), &tributos.pis.to_string()));
+        lines.push(columns(&format!("    COFINS {}", tributacao.cofins), &tributos.cofins.to_string()));
+    }
+
+    lines.push(Linha::Separador);
+
+    if sale.descontos()? > Dinheiro::ZERO {
+        lines.push(columns("Subtotal", &sale.subtotal()?.to_string()));
+        lines.push(columns("Descontos", &negative(sale.descontos()?)));
+    }
+
+    lines.push(columns("TOTAL", &sale.valor.to_string()));
+    lines.push(columns(&format!("Pagamento: {}", sale.metodo_pagamento), &sale.valor.to_string()));
+
+    if !sale.devolucoes.is_empty() {
+        lines.push(columns("Devolvido", &negative(sale.valor.checked_sub(sale.valor_liquido()?)?)));
+        lines.push(columns("Valor líquido", &sale.valor_liquido()?.to_string()));
+    }
+
+    if sale.situacao == Situacao::Cancelada {
+        lines.push(Linha::Titulo("VENDA CANCELADA".to_string()));
+    }
+
+    // Valor aproximado dos tributos da venda, como pede a Lei 12.741/2012.
+    if !taxes.is_empty() {
+        lines.push(Linha::Separador);
+        lines.push(columns("Tributos aproximados", &taxes::total_taxes(&taxes)?.to_string()));
+
+        let sum = |tributo: fn(&taxes::TributosItem) -> Dinheiro| taxes.iter().try_fold(Dinheiro::ZERO, |total, item| total.checked_add(tributo(item)));
+
+        lines.push(columns("  ICMS", &sum(|item| item.icms)?.to_string()));
+        lines.push(columns("  PIS", &sum(|item| item.pis)?.to_string()));
+        lines.push(columns("  COFINS", &sum(|item| item.cofins)?.to_string()));
+    }
+
+    lines.push(Linha::Separador);
+    lines.push(Linha::Centro("Documento sem valor fiscal".to_string()));
+
+    Ok(lines)
+}
+
+fn center(text: &str) -> String {
+    let padding = WIDTH.saturating_sub(text.chars().count()) / 2;
+
+    format!("{}{text}", " ".repeat(padding))
+}
+
+pub fn render_text(sale: &Venda, loja: &Loja) -> Result<String, CustomErrors> {
+    let lines: Vec<String> = lines(sale, loja)?.into_iter()
+        .map(|linha| match linha {
+            Linha::Titulo(text) | Linha::Centro(text) => center(&text),
+            Linha::Texto(text) => text,
+            Linha::Separador => "-".repeat(WIDTH)
+        })
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+// As impressoras usam a página de código padrão, então os acentos são retirados do texto.
+fn ascii(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|letter| {
+            let plain = search::normalize(&letter.to_string()).chars().next().unwrap_or(letter);
+            let plain = if letter.is_uppercase() { plain.to_ascii_uppercase() } else { plain };
+
+            if plain.is_ascii() { plain as u8 } else { b'?' }
+        })
+        .collect()
+}
+
+// Inicializa a impressora, imprime o cupom, avança o papel e faz o corte parcial.
+pub fn render_escpos(sale: &Venda, loja: &Loja) -> Result<Vec<u8>, CustomErrors> {
+    let mut bytes = vec![ESC, b'@'];
+
+    for linha in lines(sale, loja)? {
+        match linha {
+            Linha::Titulo(text) => {
+                bytes.extend([ESC, b'a', 1, ESC, b'E', 1]);
+                bytes.extend(ascii(&text));
+                bytes.extend([b'\n', ESC, b'E', 0, ESC, b'a', 0]);
+            },
+            Linha::Centro(text) => {
+                bytes.extend([ESC, b'a', 1]);
+                bytes.extend(ascii(&text));
+                bytes.extend([b'\n', ESC, b'a', 0]);
+            },
+            Linha::Texto(text) => {
+                bytes.extend(ascii(&text));
+                bytes.push(b'\n');
+            },
+            Linha::Separador => {
+                bytes.extend([b'-'; WIDTH]);
+                bytes.push(b'\n');
+            }
+        }
+    }
+
+    bytes.extend([ESC, b'd', 4, GS, b'V', 1]);
+
+    Ok(bytes)
+}
+
+pub fn send(sale: &Venda, loja: &Loja, printer: &Path) -> Result<(), Box<dyn Error>> {
+    fs::write(printer, render_escpos(sale, loja)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Aliquota, test_utils::test_path, ItemVenda, MetodoPagamento, Tributacao};
+
+    fn loja() -> Loja {
+        Loja {
+            nome: "Mercadinho São João".to_string(),
+            cnpj: "12.345.678/0001-90".to_string(),
+            endereco: "Rua das Flores, 10".to_string(),
+            impressora: None
+        }
+    }
+
+    fn sale() -> Venda {
+        let mut sale = Venda::new("Ana".to_string(), 12, Dinheiro::from_centavos(5900), chrono::NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(), MetodoPagamento::Pix);
+        sale.caixa = Some(3);
+
+        let mut arroz = ItemVenda::new(1, "Arroz".to_string(), 3, Dinheiro::from_centavos(2000)).unwrap();
+        arroz.desconto = Dinheiro::from_centavos(600);
+        arroz.tributacao = Some(Tributacao::new("10063021".to_string(), "5102".to_string(), Aliquota::from_centesimos(1800), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760)));
+
+        sale.produtos = vec![arroz, ItemVenda::new(2, "Sacola".to_string(), 1, Dinheiro::from_centavos(500)).unwrap()];
+
+        sale
+    }
+
+    #[test]
+    fn test_read_store() {
+        let dir = test_path("test_read_store");
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(read_store(&dir).unwrap(), Loja::default());
+
+        fs::write(dir.join(STORE_FILE), "# Filial centro\nnome = Mercadinho São João\ncnpj = 12.345.678/0001-90\nimpressora = /dev/usb/lp0\n").unwrap();
+
+        let loja = read_store(&dir).unwrap();
+        assert_eq!((loja.nome.as_str(), loja.cnpj.as_str(), loja.endereco.as_str()), ("Mercadinho São João", "12.345.678/0001-90", ""));
+        assert_eq!(loja.impressora, Some(PathBuf::from("/dev/usb/lp0")));
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = render_text(&sale(), &loja()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines.iter().all(|line| line.chars().count() <= WIDTH));
+        assert_eq!(lines[0].trim(), "Mercadinho São João");
+        assert_eq!(lines[1].trim(), "CNPJ: 12.345.678/0001-90");
+        assert!(lines.contains(&"                CUPOM NÃO FISCAL"));
+        assert!(text.contains(&format!("Venda 12{}18/10/2026", " ".repeat(30))));
+        assert!(text.contains("001 Arroz (ID: 1)\n    3 x R$ 20,00"));
+        assert!(text.contains("    NCM 10063021  CFOP 5102\n"));
+
+        // O ICMS de 18% incide sobre o valor líquido da linha: R$ 54,00.
+        assert_eq!(lines.iter().find(|line| line.starts_with("    ICMS")).unwrap(), &format!("    ICMS 18,00%{}R$ 9,72", " ".repeat(26)));
+        assert!(text.contains("Subtotal"));
+        assert!(lines.iter().any(|line| line.starts_with("Pagamento: PIX") && line.ends_with("R$ 59,00")));
+        assert!(lines.iter().any(|line| line.starts_with("Tributos aproximados") && line.ends_with("R$ 14,71")));
+
+        // A sacola não tem dados fiscais e fica sem tributos.
+        assert!(text.contains("002 Sacola (ID: 2)"));
+        assert_eq!(text.matches("NCM").count(), 1);
+        assert_eq!(lines.last().unwrap().trim(), "Documento sem valor fiscal");
+    }
+
+    #[test]
+    fn test_render_escpos() {
+        let bytes = render_escpos(&sale(), &loja()).unwrap();
+
+        assert!(bytes.starts_with(&[ESC, b'@', ESC, b'a', 1, ESC, b'E', 1]));
+        assert!(bytes.ends_with(&[ESC, b'd', 4, GS, b'V', 1]));
+        assert!(bytes.is_ascii());
+
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.contains("Mercadinho Sao Joao"));
+        assert!(text.contains("CUPOM NAO FISCAL"));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use super::{errors::CustomErrors, money::Dinheiro, repository::SaleRepository, Situacao, Venda};
+
+const DATE_FORMAT: &str = "%d/%m/%Y";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Agrupamento {
+    Dia,
+    Semana,
+    Mes,
+    Vendedor,
+    MetodoPagamento
+}
+
+impl fmt::Display for Agrupamento {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Agrupamento::Dia => write!(f, "Dia"),
+            Agrupamento::Semana => write!(f, "Semana"),
+            Agrupamento::Mes => write!(f, "Mês"),
+            Agrupamento::Vendedor => write!(f, "Vendedor"),
+            Agrupamento::MetodoPagamento => write!(f, "Método de pagamento")
+        }
+    }
+}
+
+pub fn parse_grouping(string: &str) -> Result<Agrupamento, CustomErrors> {
+    match string.trim().to_lowercase().as_str() {
+        "dia" => Ok(Agrupamento::Dia),
+        "semana" => Ok(Agrupamento::Semana),
+        "mes" => Ok(Agrupamento::Mes),
+        "vendedor" => Ok(Agrupamento::Vendedor),
+        "pagamento" => Ok(Agrupamento::MetodoPagamento),
+        _ => Err(CustomErrors::InvalidCommand)
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LinhaRelatorio {
+    pub chave: String,
+    pub vendas: u64,
+    pub descontos: Dinheiro,
+    pub receita: Dinheiro
+}
+
+// Quantas vezes cada promoção foi aplicada no período e quanto ela descontou.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ResumoPromocao {
+    pub promocao: u64,
+    pub nome: String,
+    pub usos: u64,
+    pub desconto: Dinheiro
+}
+
+#[derive(Serialize, Debug)]
+pub struct Relatorio {
+    pub agrupamento: Agrupamento,
+    #[serde(with = "crate::date::optional")]
+    pub inicio: Option<NaiveDate>,
+    #[serde(with = "crate::date::optional")]
+    pub fim: Option<NaiveDate>,
+    pub linhas: Vec<LinhaRelatorio>,
+    pub vendas: u64,
+    pub descontos: Dinheiro,
+    pub receita: Dinheiro,
+    pub promocoes: Vec<ResumoPromocao>
+}
+
+// A primeira parte ordena as linhas (períodos em ordem cronológica), a segunda é exibida.
+fn group_key(sale: &Venda, agrupamento: Agrupamento) -> (String, String) {
+    match agrupamento {
+        Agrupamento::Dia => (sale.data.format("%Y-%m-%d").to_string(), sale.data.format(DATE_FORMAT).to_string()),
+        Agrupamento::Semana => {
+            let monday = sale.data - chrono::Duration::days(sale.data.weekday().num_days_from_monday() as i64);
+            let sunday = monday + chrono::Duration::days(6);
+
+            (monday.format("%Y-%m-%d").to_string(), format!("{} a {}", monday.format(DATE_FORMAT), sunday.format(DATE_FORMAT)))
+        },
+        Agrupamento::Mes => (sale.data.format("%Y-%m").to_string(), sale.data.format("%m/%Y").to_string()),
+        Agrupamento::Vendedor => (sale.vendedor.clone(), sale.vendedor.clone()),
+        Agrupamento::MetodoPagamento => (sale.metodo_pagamento.to_string(), sale.metodo_pagamento.to_string())
+    }
+}
+
+pub fn build_report(sales: &[Venda], agrupamento: Agrupamento, inicio: Option<NaiveDate>, fim: Option<NaiveDate>) -> Result<Relatorio, CustomErrors> {
+    let mut groups: BTreeMap<String, LinhaRelatorio> = BTreeMap::new();
+    let mut promocoes: BTreeMap<u64, ResumoPromocao> = BTreeMap::new();
+    let mut vendas = 0;
+    let mut descontos = Dinheiro::ZERO;
+    let mut receita = Dinheiro::ZERO;
+
+    // Vendas canceladas ficam fora; devoluções parciais descontam da receita.
+    for sale in sales.iter().filter(|sale| sale.situacao != Situacao::Cancelada) {
+        let (order, chave) = group_key(sale, agrupamento);
+        let line = groups.entry(order).or_insert(LinhaRelatorio { chave, vendas: 0, descontos: Dinheiro::ZERO, receita: Dinheiro::ZERO });
+        let valor = sale.valor_liquido()?;
+        let desconto = sale.descontos()?;
+
+        line.vendas += 1;
+        line.descontos = line.descontos.checked_add(desconto)?;
+        line.receita = line.receita.checked_add(valor)?;
+
+        vendas += 1;
+        descontos = descontos.checked_add(desconto)?;
+        receita = receita.checked_add(valor)?;
+
+        for aplicada in sale.promocoes.iter() {
+            let resumo = promocoes.entry(aplicada.promocao).or_insert(ResumoPromocao { promocao: aplicada.promocao, nome: aplicada.nome.clone(), usos: 0, desconto: Dinheiro::ZERO });
+
+            resumo.usos += 1;
+            resumo.desconto = resumo.desconto.checked_add(aplicada.valor)?;
+        }
+    }
+
+    Ok(Relatorio {
+        agrupamento,
+        inicio,
+        fim,
+        linhas: groups.into_values().collect(),
+        vendas,
+        descontos,
+        receita,
+        promocoes: promocoes.into_values().collect()
+    })
+}
+
+pub fn sales_report<S: SaleRepository>(sales: &mut S, agrupamento: Agrupamento, inicio: Option<NaiveDate>, fim: Option<NaiveDate>) -> Result<Relatorio, Box<dyn Error>> {
+    if let (Some(inicio), Some(fim)) = (inicio, fim) {
+        if inicio > fim {
+            return Err(Box::new(CustomErrors::InvalidPeriod));
+        }
+    }
+
+    Ok(build_report(&sales.by_range(inicio, fim)?, agrupamento, inicio, fim)?)
+}
+
+impl fmt::Display for Relatorio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let period = match (self.inicio, self.fim) {
+            (Some(inicio), Some(fim)) => format!("de {} a {}", inicio.format(DATE_FORMAT), fim.format(DATE_FORMAT)),
+            (Some(inicio), None) => format!("a partir de {}", inicio.format(DATE_FORMAT)),
+            (None, Some(fim)) => format!("até {}", fim.format(DATE_FORMAT)),
+            (None, None) => "todo o período".to_string()
+        };
+
+        writeln!(f, "Relatório de vendas por {} ({period})\n", self.agrupamento.to_string().to_lowercase())?;
+
+        let header = self.agrupamento.to_string();
+        let width = self.linhas.iter()
+            .map(|line| line.chave.chars().count())
+            .chain([header.chars().count(), "Total".len()])
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "{header:<width$}  {:>8}  {:>18}  {:>18}", "Vendas", "Descontos", "Receita")?;
+
+        for line in self.linhas.iter() {
+            writeln!(f, "{:<width$}  {:>8}  {:>18}  {:>18}", line.chave, line.vendas, line.descontos.to_string(), line.receita.to_string())?;
+        }
+
+        writeln!(f, "{}", "-".repeat(width + 50))?;
+        write!(f, "{:<width$}  {:>8}  {:>18}  {:>18}", "Total", self.vendas, self.descontos.to_string(), self.receita.to_string())?;
+
+        if self.promocoes.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, "\n\nPromoções aplicadas:")?;
+
+        for resumo in self.promocoes.iter() {
+            write!(f, "\n{} (ID: {}) - Usos: {} - Desconto: {}", resumo.nome, resumo.promocao, resumo.usos, resumo.desconto)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, SeekFrom, Write};
+
+    use super::*;
+    use crate::{format, sales::serialize_record, test_utils::{get_test_sales, remove_test_sales, test_path}, MetodoPagamento};
+
+    fn sale(codigo: u64, vendedor: &str, data: NaiveDate, centavos: i64, metodo_pagamento: MetodoPagamento) -> Venda {
+        Venda::new(vendedor.to_string(), codigo, Dinheiro::from_centavos(centavos), data, metodo_pagamento)
+    }
+
+    fn day(day: u32, month: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn sample() -> Vec<Venda> {
+        vec![
+            sale(1, "Ana", day(4, 3), 1000, MetodoPagamento::Pix),
+            sale(2, "Bia", day(10, 3), 2550, MetodoPagamento::Credito),
+            sale(3, "Ana", day(11, 3), 500, MetodoPagamento::Pix),
+            sale(4, "Ana", day(2, 4), 4000, MetodoPagamento::Dinheiro)
+        ]
+    }
+
+    fn keys(report: &Relatorio) -> Vec<(&str, u64, i64)> {
+        report.linhas.iter().map(|line| (line.chave.as_str(), line.vendas, line.receita.centavos())).collect()
+    }
+
+    #[test]
+    fn test_parse_grouping() {
+        assert_eq!(parse_grouping("Mes").unwrap(), Agrupamento::Mes);
+        assert_eq!(parse_grouping("pagamento").unwrap(), Agrupamento::MetodoPagamento);
+        assert!(parse_grouping("ano").is_err());
+    }
+
+    #[test]
+    fn test_build_report_by_period() {
+        let sales = sample();
+
+        let report = build_report(&sales, Agrupamento::Dia, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("04/03/2024", 1, 1000), ("10/03/2024", 1, 2550), ("11/03/2024", 1, 500), ("02/04/2024", 1, 4000)]);
+
+        let report = build_report(&sales, Agrupamento::Semana, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("04/03/2024 a 10/03/2024", 2, 3550), ("11/03/2024 a 17/03/2024", 1, 500), ("01/04/2024 a 07/04/2024", 1, 4000)]);
+
+        let report = build_report(&sales, Agrupamento::Mes, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("03/2024", 3, 4050), ("04/2024", 1, 4000)]);
+        assert_eq!(report.vendas, 4);
+        assert_eq!(report.receita, Dinheiro::from_centavos(8050));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!((json["agrupamento"].as_str(), json["receita"].as_i64()), (Some("Mes"), Some(8050)));
+        assert_eq!(json["linhas"][1]["chave"].as_str(), Some("04/2024"));
+    }
+
+    #[test]
+    fn test_build_report_by_seller_and_payment() {
+        let sales = sample();
+
+        let report = build_report(&sales, Agrupamento::Vendedor, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("Ana", 3, 5500), ("Bia", 1, 2550)]);
+
+        let mut returned = sample();
+        returned[0].situacao = Situacao::Cancelada;
+        returned[1].situacao = Situacao::DevolucaoParcial;
+        returned[1].devolucoes.push(crate::Devolucao { data: day(12, 3), motivo: "Defeito".to_string(), itens: vec![(1, 1)], valor: Dinheiro::from_centavos(550) });
+
+        let report = build_report(&returned, Agrupamento::Vendedor, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("Ana", 2, 4500), ("Bia", 1, 2000)]);
+
+        let report = build_report(&sales, Agrupamento::MetodoPagamento, None, None).unwrap();
+        assert_eq!(keys(&report), vec![("Cartão de crédito", 1, 2550), ("Dinheiro", 1, 4000), ("PIX", 2, 1500)]);
+
+        let table = report.to_string();
+        assert!(table.starts_with("Relatório de vendas por método de pagamento (todo o período)"));
+        assert_eq!(table.lines().last().unwrap(), format!("{:<19}  {:>8}  {:>18}  {:>18}", "Total", 4, "R$ 0,00", "R$ 80,50"));
+    }
+
+    #[test]
+    fn test_build_report_discounts() {
+        let mut sales = sample();
+
+        sales[0].desconto = Dinheiro::from_centavos(100);
+        sales[1].produtos.push(crate::ItemVenda { desconto: Dinheiro::from_centavos(450), ..crate::ItemVenda::new(1, "Camisa".to_string(), 3, Dinheiro::from_centavos(1000)).unwrap() });
+        sales[1].promocoes.push(crate::PromocaoAplicada { promocao: 2, nome: "Camisas".to_string(), produto: 1, valor: Dinheiro::from_centavos(300) });
+        sales[2].promocoes.push(crate::PromocaoAplicada { promocao: 2, nome: "Camisas".to_string(), produto: 1, valor: Dinheiro::from_centavos(200) });
+
+        let report = build_report(&sales, Agrupamento::Vendedor, None, None).unwrap();
+        let descontos: Vec<i64> = report.linhas.iter().map(|line| line.descontos.centavos()).collect();
+
+        assert_eq!(descontos, vec![100, 450]);
+        assert_eq!(report.descontos, Dinheiro::from_centavos(550));
+        assert_eq!(report.promocoes, vec![ResumoPromocao { promocao: 2, nome: "Camisas".to_string(), usos: 2, desconto: Dinheiro::from_centavos(500) }]);
+        assert_eq!(report.to_string().lines().last().unwrap(), "Camisas (ID: 2) - Usos: 2 - Desconto: R$ 5,00");
+    }
+
+    #[test]
+    fn test_sales_report_range() {
+        let path = &test_path("test_sales_report_range.bin");
+        let mut sales = get_test_sales(path);
+
+        let mut header = sales.header().unwrap();
+
+        for sale in sample() {
+            let position = sales.dados.seek(SeekFrom::End(0)).unwrap();
+            sales.dados.write_all(&serialize_record(&sale).unwrap()).unwrap();
+
+            header.quantidade += 1;
+            header.ultimo_id = sale.codigo;
+            format::write_header(&mut sales.dados, &header).unwrap();
+
+            sales.add(&sale, position).unwrap();
+        }
+
+        let report = sales_report(&mut sales, Agrupamento::Vendedor, Some(day(5, 3)), Some(day(31, 3))).unwrap();
+        assert_eq!(keys(&report), vec![("Ana", 1, 500), ("Bia", 1, 2550)]);
+
+        let report = sales_report(&mut sales, Agrupamento::Mes, Some(day(1, 4)), None).unwrap();
+        assert_eq!(keys(&report), vec![("04/2024", 1, 4000)]);
+
+        let error = sales_report(&mut sales, Agrupamento::Dia, Some(day(1, 4)), Some(day(1, 3))).unwrap_err();
+        assert!(matches!(error.downcast_ref::<CustomErrors>(), Some(CustomErrors::InvalidPeriod)));
+
+        remove_test_sales(path);
+    }
+}
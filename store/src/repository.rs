@@ -0,0 +1,101 @@
+use std::{collections::BTreeMap, error::Error};
+
+use super::{Categoria, Movimento, Produto, Promocao, Venda};
+
+// Armazenamento dos produtos, das categorias, das promoções e do histórico de movimentos de estoque.
+pub trait ProductRepository {
+    fn last_id(&mut self) -> Result<u64, Box<dyn Error>>;
+
+    fn get(&mut self, id: u64) -> Result<Produto, Box<dyn Error>>;
+
+    fn list(&mut self) -> Result<Vec<Produto>, Box<dyn Error>>;
+
+    // Id do produto cadastrado com o código de barras, se houver.
+    fn barcode_id(&mut self, barcode: &str) -> Result<Option<u64>, Box<dyn Error>>;
+
+    // O id do produto é definido pelo repositório.
+    fn insert(&mut self, product: Produto) -> Result<u64, Box<dyn Error>>;
+
+    fn update(&mut self, product: &Produto) -> Result<(), Box<dyn Error>>;
+
+    fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>>;
+
+    fn movements(&mut self, id: u64) -> Result<Vec<Movimento>, Box<dyn Error>>;
+
+    // Estoque de cada produto recalculado somando todos os movimentos.
+    fn balances(&mut self) -> Result<BTreeMap<u64, i64>, Box<dyn Error>>;
+
+    fn append_movements(&mut self, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>>;
+
+    // Categorias em ordem de id.
+    fn categories(&mut self) -> Result<Vec<Categoria>, Box<dyn Error>>;
+
+    // O id da categoria é definido pelo repositório.
+    fn insert_category(&mut self, category: Categoria) -> Result<u64, Box<dyn Error>>;
+
+    fn update_category(&mut self, category: &Categoria) -> Result<(), Box<dyn Error>>;
+
+    fn remove_category(&mut self, id: u64) -> Result<(), Box<dyn Error>>;
+
+    // Promoções em ordem de id, inclusive as que já terminaram ou ainda não começaram.
+    fn promotions(&mut self) -> Result<Vec<Promocao>, Box<dyn Error>>;
+
+    // O id da promoção é definido pelo repositório.
+    fn insert_promotion(&mut self, promotion: Promocao) -> Result<u64, Box<dyn Error>>;
+
+    fn update_promotion(&mut self, promotion: &Promocao) -> Result<(), Box<dyn Error>>;
+
+    fn remove_promotion(&mut self, id: u64) -> Result<(), Box<dyn Error>>;
+}
+
+pub trait SaleRepository {
+    fn last_code(&mut self) -> Result<u64, Box<dyn Error>>;
+
+    fn get(&mut self, code: u64) -> Result<Venda, Box<dyn Error>>;
+
+    fn list(&mut self) -> Result<Vec<Venda>, Box<dyn Error>>;
+
+    fn by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, Box<dyn Error>>;
+
+    // Vendas do período em ordem de data; sem início ou fim o período fica aberto.
+    fn by_range(&mut self, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<Vec<Venda>, Box<dyn Error>>;
+
+    fn by_product(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>>;
+
+    fn by_session(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>>;
+
+    // O código da venda é definido pelo repositório.
+    fn insert(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>>;
+
+    // Grava a venda com o código que ela já tem, que não pode pertencer a outra venda.
+    fn import(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>>;
+
+    fn rewrite(&mut self, sale: &Venda) -> Result<(), Box<dyn Error>>;
+
+    fn remove(&mut self, code: u64) -> Result<(), Box<dyn Error>>;
+}
+
+// Grava as alterações dos produtos junto com os movimentos de estoque: se a gravação falhar, nenhuma das alterações vale.
+pub trait StockTransaction<P: ProductRepository> {
+    // Os movimentos de cada produto são montados depois que o repositório define o id dele.
+    fn insert_products<F: FnMut(&Produto) -> Vec<Movimento>>(&mut self, products: &mut P, new_products: Vec<Produto>, movements: F) -> Result<Vec<u64>, Box<dyn Error>>;
+
+    fn update_products(&mut self, products: &mut P, updated_products: &[Produto], movements: Vec<Movimento>) -> Result<(), Box<dyn Error>>;
+
+    fn remove_product(&mut self, products: &mut P, id: u64, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>>;
+
+    // Alterações do catálogo que mexem em várias categorias, promoções e produtos de uma vez;
+    // `ids` são os produtos que a gravação vai alterar.
+    fn update_catalog<R, F: FnOnce(&mut P) -> Result<R, Box<dyn Error>>>(&mut self, products: &mut P, ids: &[u64], write: F) -> Result<R, Box<dyn Error>>;
+}
+
+// Grava o estoque dos produtos, os movimentos e a venda juntos: se a gravação falhar, nenhuma das alterações vale.
+pub trait SaleTransaction<P: ProductRepository, S: SaleRepository>: StockTransaction<P> {
+    // Os movimentos são montados com o código definido para a venda.
+    fn insert_sale<F: FnOnce(&Venda) -> Vec<Movimento>>(&mut self, products: &mut P, sales: &mut S, updated_products: &[Produto], sale: Venda, movements: F) -> Result<Venda, Box<dyn Error>>;
+
+    fn update_sale(&mut self, products: &mut P, sales: &mut S, updated_products: &[Produto], sale: &Venda, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>>;
+
+    // Vendas importadas são só histórico: gravadas todas juntas, com os próprios códigos e sem alterar o estoque.
+    fn insert_sales(&mut self, products: &mut P, sales: &mut S, new_sales: Vec<Venda>) -> Result<Vec<u64>, Box<dyn Error>>;
+}
@@ -0,0 +1,479 @@
+use std::{collections::BTreeMap, error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use chrono::Datelike;
+use serde::{Serialize, Deserialize};
+
+use super::{core, errors, format::{self, Cabecalho, HEADER_LENGTH, SALES_MAGIC}, repository::SaleRepository, Venda};
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+struct IndiceVendas {
+    tamanho: u64,
+    quantidade: u64,
+    ultimo_codigo: u64,
+    codigos: BTreeMap<u64, u64>,
+    datas: BTreeMap<i32, Vec<u64>>,
+    produtos: BTreeMap<u64, Vec<u64>>,
+    caixas: BTreeMap<u64, Vec<u64>>
+}
+
+impl IndiceVendas {
+    fn add(&mut self, sale: &Venda, position: u64) {
+        self.codigos.insert(sale.codigo, position);
+        insert_code(&mut self.datas, sale.data.num_days_from_ce(), sale.codigo);
+
+        for item in sale.produtos.iter() {
+            insert_code(&mut self.produtos, item.id, sale.codigo);
+        }
+
+        if let Some(caixa) = sale.caixa {
+            insert_code(&mut self.caixas, caixa, sale.codigo);
+        }
+    }
+
+    fn remove(&mut self, sale: &Venda) {
+        self.codigos.remove(&sale.codigo);
+        remove_code(&mut self.datas, sale.data.num_days_from_ce(), sale.codigo);
+
+        for item in sale.produtos.iter() {
+            remove_code(&mut self.produtos, item.id, sale.codigo);
+        }
+
+        if let Some(caixa) = sale.caixa {
+            remove_code(&mut self.caixas, caixa, sale.codigo);
+        }
+    }
+
+    fn shift(&mut self, removed: u64, length: u64) {
+        for position in self.codigos.values_mut().filter(|position| **position > removed) {
+            *position -= length;
+        }
+    }
+}
+
+fn insert_code<K: Ord>(map: &mut BTreeMap<K, Vec<u64>>, key: K, code: u64) {
+    let codes = map.entry(key).or_default();
+
+    if let Err(index) = codes.binary_search(&code) {
+        codes.insert(index, code);
+    }
+}
+
+fn remove_code<K: Ord>(map: &mut BTreeMap<K, Vec<u64>>, key: K, code: u64) {
+    if let Some(codes) = map.get_mut(&key) {
+        codes.retain(|&entry| entry != code);
+
+        if codes.is_empty() {
+            map.remove(&key);
+        }
+    }
+}
+
+pub fn serialize_record(sale: &Venda) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(sale)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+#[derive(Debug)]
+pub struct ArquivoVendas {
+    pub dados: File,
+    pub indice: File,
+    entradas: IndiceVendas
+}
+
+impl ArquivoVendas {
+    pub fn open(dados: File, indice: File) -> Result<Self, Box<dyn Error>> {
+        let mut sales = ArquivoVendas {
+            dados,
+            indice,
+            entradas: IndiceVendas::default()
+        };
+
+        let mut buf = Vec::new();
+
+        sales.indice.seek(SeekFrom::Start(0))?;
+        sales.indice.read_to_end(&mut buf)?;
+
+        match bincode::deserialize(&buf) {
+            Ok(entradas) => {
+                sales.entradas = entradas;
+
+                if sales.index_is_stale()? {
+                    sales.rebuild_index()?;
+                }
+            },
+            Err(_) => sales.rebuild_index()?
+        }
+
+        Ok(sales)
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, SALES_MAGIC)
+    }
+
+    fn index_is_stale(&mut self) -> Result<bool, Box<dyn Error>> {
+        let size = self.dados.seek(SeekFrom::End(0))?;
+        let header = self.header()?;
+
+        Ok(self.entradas.tamanho != size
+            || self.entradas.quantidade != header.quantidade
+            || self.entradas.ultimo_codigo != header.ultimo_id
+            || self.entradas.codigos.len() as u64 != header.quantidade)
+    }
+
+    fn save_index(&mut self) -> Result<(), Box<dyn Error>> {
+        let header = self.header()?;
+
+        self.entradas.tamanho = self.dados.seek(SeekFrom::End(0))?;
+        self.entradas.quantidade = header.quantidade;
+        self.entradas.ultimo_codigo = header.ultimo_id;
+
+        let serialized = bincode::serialize(&self.entradas)?;
+
+        self.indice.seek(SeekFrom::Start(0))?;
+        self.indice.write_all(&serialized)?;
+        self.indice.set_len(serialized.len() as u64)?;
+
+        Ok(())
+    }
+
+    // Um índice vazio é sempre reconstruído ao abrir, então uma escrita interrompida não o deixa inconsistente.
+    pub fn invalidate(&mut self) -> Result<(), Box<dyn Error>> {
+        self.indice.set_len(0)?;
+        self.indice.sync_all()?;
+
+        Ok(())
+    }
+
+    pub fn rebuild_index(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut entradas = IndiceVendas::default();
+        let mut position = HEADER_LENGTH;
+        let size = self.dados.seek(SeekFrom::End(0))?;
+
+        while position < size {
+            let (sale, length) = self.read_at(position)?;
+
+            entradas.add(&sale, position);
+            position += length;
+        }
+
+        self.entradas = entradas;
+        self.save_index()
+    }
+
+    pub fn read_at(&mut self, position: u64) -> Result<(Venda, u64), Box<dyn Error>> {
+        let mut size_buf = vec![0; 8];
+
+        self.dados.seek(SeekFrom::Start(position))?;
+        self.dados.read_exact(&mut size_buf)?;
+
+        let size: u64 = bincode::deserialize(&size_buf)?;
+        let mut buf = vec![0; size as usize];
+        self.dados.read_exact(&mut buf)?;
+
+        Ok((bincode::deserialize(&buf)?, size + 8))
+    }
+
+    pub fn search(&mut self, code: u64) -> Result<(Venda, u64), Box<dyn Error>> {
+        let position = *self.entradas.codigos.get(&code).ok_or(errors::CustomErrors::SaleNotFound)?;
+        let (sale, _) = self.read_at(position)?;
+
+        Ok((sale, position))
+    }
+
+    fn read_codes(&mut self, codes: Vec<u64>) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let mut sales = Vec::with_capacity(codes.len());
+
+        for code in codes {
+            sales.push(self.search(code)?.0);
+        }
+
+        Ok(sales)
+    }
+
+    pub fn by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let codes = self.entradas.datas.get(&date.num_days_from_ce()).cloned().unwrap_or_default();
+
+        self.read_codes(codes)
+    }
+
+    pub fn by_range(&mut self, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let start = from.map_or(i32::MIN, |date| date.num_days_from_ce());
+        let end = to.map_or(i32::MAX, |date| date.num_days_from_ce());
+
+        if start > end {
+            return Ok(Vec::new());
+        }
+
+        let codes = self.entradas.datas.range(start..=end).flat_map(|(_, codes)| codes.iter().copied()).collect();
+
+        self.read_codes(codes)
+    }
+
+    pub fn by_product(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let codes = self.entradas.produtos.get(&id).cloned().unwrap_or_default();
+
+        self.read_codes(codes)
+    }
+
+    pub fn by_session(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let codes = self.entradas.caixas.get(&id).cloned().unwrap_or_default();
+
+        self.read_codes(codes)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let codes = self.entradas.codigos.keys().copied().collect();
+
+        self.read_codes(codes)
+    }
+
+    pub fn add(&mut self, sale: &Venda, position: u64) -> Result<(), Box<dyn Error>> {
+        self.entradas.add(sale, position);
+        self.save_index()
+    }
+
+    pub fn insert(&mut self, mut sale: Venda) -> Result<u64, Box<dyn Error>> {
+        let mut header = self.header()?;
+        sale.codigo = header.ultimo_id + 1;
+
+        self.invalidate()?;
+
+        let position = self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(&sale)?)?;
+
+        header.quantidade += 1;
+        header.ultimo_id = sale.codigo;
+        format::write_header(&mut self.dados, &header)?;
+
+        self.add(&sale, position)?;
+
+        Ok(sale.codigo)
+    }
+
+    pub fn import(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        if self.entradas.codigos.contains_key(&sale.codigo) {
+            return Err(Box::new(errors::CustomErrors::DuplicateSale));
+        }
+
+        let mut header = self.header()?;
+
+        self.invalidate()?;
+
+        let position = self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&serialize_record(&sale)?)?;
+
+        header.quantidade += 1;
+        header.ultimo_id = header.ultimo_id.max(sale.codigo);
+        format::write_header(&mut self.dados, &header)?;
+
+        self.add(&sale, position)?;
+
+        Ok(sale.codigo)
+    }
+
+    pub fn rewrite(&mut self, sale: &Venda) -> Result<(), Box<dyn Error>> {
+        let (old_sale, position) = self.search(sale.codigo)?;
+        let (_, length) = self.read_at(position)?;
+        let record = serialize_record(sale)?;
+
+        self.invalidate()?;
+        self.entradas.remove(&old_sale);
+
+        if record.len() as u64 == length {
+            self.dados.seek(SeekFrom::Start(position))?;
+            self.dados.write_all(&record)?;
+
+            self.entradas.add(sale, position);
+
+            return self.save_index();
+        }
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+        self.entradas.shift(position, length);
+
+        let new_position = self.dados.seek(SeekFrom::End(0))?;
+        self.dados.write_all(&record)?;
+
+        self.entradas.add(sale, new_position);
+        self.save_index()
+    }
+
+    pub fn remove(&mut self, code: u64) -> Result<(), Box<dyn Error>> {
+        let (sale, position) = self.search(code)?;
+        let (_, length) = self.read_at(position)?;
+
+        self.invalidate()?;
+
+        core::remove_bytes(&mut self.dados, position, length)?;
+
+        let mut header = self.header()?;
+        header.quantidade -= 1;
+        format::write_header(&mut self.dados, &header)?;
+
+        self.entradas.remove(&sale);
+        self.entradas.shift(position, length);
+        self.save_index()
+    }
+}
+
+impl SaleRepository for ArquivoVendas {
+    fn last_code(&mut self) -> Result<u64, Box<dyn Error>> {
+        Ok(self.header()?.ultimo_id)
+    }
+
+    fn get(&mut self, code: u64) -> Result<Venda, Box<dyn Error>> {
+        Ok(self.search(code)?.0)
+    }
+
+    fn list(&mut self) -> Result<Vec<Venda>, Box<dyn Error>> {
+        ArquivoVendas::list(self)
+    }
+
+    fn by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, Box<dyn Error>> {
+        ArquivoVendas::by_date(self, date)
+    }
+
+    fn by_range(&mut self, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<Vec<Venda>, Box<dyn Error>> {
+        ArquivoVendas::by_range(self, from, to)
+    }
+
+    fn by_product(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        ArquivoVendas::by_product(self, id)
+    }
+
+    fn by_session(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        ArquivoVendas::by_session(self, id)
+    }
+
+    fn insert(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        ArquivoVendas::insert(self, sale)
+    }
+
+    fn import(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        ArquivoVendas::import(self, sale)
+    }
+
+    fn rewrite(&mut self, sale: &Venda) -> Result<(), Box<dyn Error>> {
+        ArquivoVendas::rewrite(self, sale)
+    }
+
+    fn remove(&mut self, code: u64) -> Result<(), Box<dyn Error>> {
+        ArquivoVendas::remove(self, code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Dinheiro, test_utils::{get_test_file, get_test_sales, remove_test_sales, test_path}, ItemVenda, MetodoPagamento};
+
+    fn append(sales: &mut ArquivoVendas, vendedor: &str, date: chrono::NaiveDate, product_ids: &[u64]) {
+        let mut header = sales.header().unwrap();
+        let mut sale = Venda::new(vendedor.to_string(), header.ultimo_id + 1, Dinheiro::ZERO, date, MetodoPagamento::Pix);
+
+        for &id in product_ids {
+            sale.produtos.push(ItemVenda::new(id, format!("Produto{id}"), 1, Dinheiro::from_centavos(100)).unwrap());
+        }
+
+        let position = sales.dados.seek(SeekFrom::End(0)).unwrap();
+        sales.dados.write_all(&serialize_record(&sale).unwrap()).unwrap();
+
+        header.quantidade += 1;
+        header.ultimo_id = sale.codigo;
+        format::write_header(&mut sales.dados, &header).unwrap();
+
+        sales.add(&sale, position).unwrap();
+    }
+
+    fn codes(sales: Vec<Venda>) -> Vec<u64> {
+        sales.into_iter().map(|sale| sale.codigo).collect()
+    }
+
+    #[test]
+    fn test_index_lookups() {
+        let path = &test_path("test_sales_index_lookups.bin");
+        let mut sales = get_test_sales(path);
+
+        let day1 = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let day2 = chrono::NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+
+        append(&mut sales, "Ana", day1, &[1, 2]);
+        append(&mut sales, "Bia", day2, &[2]);
+        append(&mut sales, "Caio", day1, &[3, 3]);
+
+        assert_eq!(codes(sales.by_date(day1).unwrap()), vec![1, 3]);
+        assert_eq!(codes(sales.by_product(2).unwrap()), vec![1, 2]);
+        assert_eq!(codes(sales.by_product(3).unwrap()), vec![3]);
+        assert_eq!(codes(sales.by_range(Some(day1), Some(day2)).unwrap()), vec![1, 3, 2]);
+        assert_eq!(codes(sales.by_range(Some(day2), None).unwrap()), vec![2]);
+        assert!(sales.by_range(Some(day2), Some(day1)).unwrap().is_empty());
+        assert_eq!(sales.search(2).unwrap().0.vendedor, "Bia");
+        assert!(sales.search(4).is_err());
+
+        remove_test_sales(path);
+    }
+
+    #[test]
+    fn test_index_after_rewrite_and_remove() {
+        let path = &test_path("test_sales_index_rewrite.bin");
+        let mut sales = get_test_sales(path);
+
+        let day1 = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let day2 = chrono::NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+
+        append(&mut sales, "Ana", day1, &[1]);
+        append(&mut sales, "Bia", day1, &[2]);
+        append(&mut sales, "Caio", day1, &[1]);
+
+        let (mut sale, _) = sales.search(1).unwrap();
+        sale.vendedor = "Ana Beatriz de Souza".to_string();
+        sale.data = day2;
+
+        sales.rewrite(&sale).unwrap();
+
+        assert_eq!(codes(sales.by_date(day1).unwrap()), vec![2, 3]);
+        assert_eq!(codes(sales.by_date(day2).unwrap()), vec![1]);
+        assert_eq!(sales.search(3).unwrap().0.vendedor, "Caio");
+
+        sales.remove(2).unwrap();
+
+        assert!(sales.search(2).is_err());
+        assert!(sales.by_product(2).unwrap().is_empty());
+        assert_eq!(codes(sales.list().unwrap()), vec![1, 3]);
+        assert_eq!(sales.search(1).unwrap().0.vendedor, "Ana Beatriz de Souza");
+
+        let entradas = bincode::serialize(&sales.entradas).unwrap();
+
+        sales.rebuild_index().unwrap();
+
+        assert_eq!(entradas, bincode::serialize(&sales.entradas).unwrap());
+
+        remove_test_sales(path);
+    }
+
+    #[test]
+    fn test_stale_index_is_rebuilt() {
+        let path = &test_path("test_sales_index_stale.bin");
+        let mut sales = get_test_sales(path);
+
+        append(&mut sales, "Ana", chrono::NaiveDate::default(), &[1]);
+
+        let ArquivoVendas { dados, indice, .. } = sales;
+        let mut sales = ArquivoVendas::open(dados, indice).unwrap();
+
+        append(&mut sales, "Bia", chrono::NaiveDate::default(), &[1]);
+        sales.invalidate().unwrap();
+
+        let ArquivoVendas { dados, .. } = sales;
+        let mut sales = ArquivoVendas::open(dados, get_test_file(&path.with_extension("idx"))).unwrap();
+
+        assert_eq!(codes(sales.by_product(1).unwrap()), vec![1, 2]);
+
+        remove_test_sales(path);
+    }
+}
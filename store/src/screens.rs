@@ -15,8 +15,32 @@ pub fn menu_screen() {
     10 -  Buscar vendas de um produto
     11 -  Listar vendas
     12 -  Atualizar venda
-    13 -  Remover venda
-    14 -  Atualizar caixa em serviço
+    13 -  Cancelar venda
+    14 -  Abrir caixa
+    15 -  Emitir relatório de vendas
+    16 -  Fechar caixa
+    17 -  Listar caixas
+    18 -  Registrar devolução de produtos
+    19 -  Alterar produtos de uma venda
+    20 -  Registrar ajuste ou perda de estoque
+    21 -  Histórico de movimentos de um produto
+    22 -  Auditar estoque pelo histórico de movimentos
+    23 -  Receber mercadoria de um fornecedor
+    24 -  Gerar pedidos de compra
+    25 -  Listar pedidos de compra
+    26 -  Receber pedido de compra
+    27 -  Buscar produtos por nome, categoria, preço ou estoque
+    28 -  Buscar produto por código de barras
+    29 -  Listar categorias
+    30 -  Cadastrar categoria
+    31 -  Renomear categoria
+    32 -  Juntar duas categorias
+    33 -  Remover categoria
+    34 -  Listar promoções
+    35 -  Cadastrar promoção
+    36 -  Remover promoção
+    37 -  Emitir cupom de uma venda
+    38 -  Atualizar dados fiscais de um produto
 
     Digite 'sair' para encerrar o programa
     \n\n------------------------------------------------------------");
@@ -29,8 +53,21 @@ pub fn add_product_screen() {
 \n* Atenção: insira os campos com um espaço entre eles! *\n
   Exemplo de input: (lapis 3 200.00 5 20/05/2024 alimento)\n
   [Nome, quantidade em estoque, valor, quantidade minima para que seja necessitado um restoque, data do ultimo restoque no seguinte formato: dd/mm/YYYY *categoria*]
-\n* Categorias possíveis: eletronico, roupa, alimento, geral
-  * Atenção: Não utilizar acento em categoria! *\n
+\n* O valor aceita vírgula ou ponto como separador decimal (ex.: 12,50 ou 12.50)
+  * O código de barras EAN-13 é opcional e pode ser informado depois da categoria
+  * A categoria pode ser informada pelo nome ou pelo id; para nomes com espaços, use o id
+
+  Digite 'sair' para cancelar a operação\n");
+}
+
+pub fn product_taxes_screen() {
+    println!("\
+\n\nInsira os dados fiscais do produto no seguinte formato:
+\n[NCM, CFOP, alíquota de ICMS, alíquota de PIS, alíquota de COFINS]
+\n* Atenção: insira os campos com um espaço entre eles! *\n
+  Exemplo de input: (10063021 5102 18 1,65 7,6)\n
+  * O NCM tem 8 dígitos e o CFOP 4, começando por 5, 6 ou 7; os pontos podem ser omitidos
+  * As alíquotas são porcentagens de 0 a 100, com até duas casas decimais
 
   Digite 'sair' para cancelar a operação\n");
 }
@@ -41,7 +78,155 @@ pub fn add_sale_screen() {
 \n[ID do produto, quantidade vendida do produto]
 \n* Atenção: insira os campos com um espaço entre eles! *\n
   Exemplo de input: [2 10] (Para adicionar a venda de 10 produtos do id 2)\n
+  O produto também pode ser lido pelo leitor de código de barras: cada leitura adiciona 1 unidade,
+  ou digite a quantidade depois do código (ex.: 7891234567895 3)\n
 Insira todos os produtos vendidos e digite 'concluir' para finalizar a venda\n
+Depois dos produtos, informe os descontos: as promoções vigentes são aplicadas sozinhas\n
+
+Digite 'sair' para cancelar a operação\n");
+}
+
+pub fn receive_stock_screen() {
+    println!("\
+\n\nInsira os produtos recebidos no seguinte formato:
+\n[ID do produto, quantidade recebida, custo unitário]
+\n* Atenção: insira os campos com um espaço entre eles! *\n
+  Exemplo de input: [2 10 4,50] (Para receber 10 unidades do produto de id 2 a R$ 4,50 cada)\n
+Insira todos os produtos recebidos e digite 'concluir' para finalizar o recebimento\n
+
+Digite 'sair' para cancelar a operação\n");
+}
+
+pub fn edit_sale_items_screen() {
+    println!("\
+\n\nInsira os produtos a alterar no seguinte formato:
+\n[ID do produto, nova quantidade do produto na venda]
+\n* Atenção: insira os campos com um espaço entre eles! *\n
+  Exemplo de input: [2 4] (Para que a venda passe a ter 4 unidades do produto de id 2)
+  Use a quantidade 0 para retirar o produto da venda\n
+Insira todas as alterações e digite 'concluir' para finalizar\n
+
+Digite 'sair' para cancelar a operação\n");
+}
+
+pub fn return_items_screen() {
+    println!("\
+\n\nInsira os produtos devolvidos no seguinte formato:
+\n[ID do produto, quantidade devolvida do produto]
+\n* Atenção: insira os campos com um espaço entre eles! *\n
+  Exemplo de input: [2 1] (Para devolver 1 unidade do produto de id 2)\n
+Insira todos os produtos devolvidos e digite 'concluir' para finalizar a devolução\n
 
 Digite 'sair' para cancelar a operação\n");
-}
\ No newline at end of file
+}
+
+pub fn help_screen() {
+    println!("\
+Uso: store [--data-dir DIRETORIO] [comando]
+
+Sem comando, o menu interativo é aberto.
+
+O diretório de dados é escolhido, nesta ordem, pela opção --data-dir, pela variável de
+ambiente STORE_DATA_DIR ou pela linha 'data_dir = DIRETORIO' do arquivo de configuração
+(STORE_CONFIG ou ~/.config/store/config). Sem nenhum deles, o diretório atual é usado.
+
+Comandos:
+  init
+  product add --name NOME --stock QTD --price VALOR --restock QTD [--date dd/mm/YYYY] --category CATEGORIA [--barcode CODIGO]
+              [--ncm NCM --cfop CFOP --icms ALIQUOTA --pis ALIQUOTA --cofins ALIQUOTA] [--operator NOME]
+  product get ID [--json]
+  product get --barcode CODIGO [--json]
+  product list [--json]
+  product search [--name TEXTO] [--category CATEGORIA] [--min-price VALOR] [--max-price VALOR] [--min-stock QTD] [--max-stock QTD] [--low-stock] [--json]
+  product restock [--days DIAS] [--lead-time DIAS] [--coverage DIAS]
+  product update ID [--name NOME] [--stock QTD] [--price VALOR] [--restock QTD] [--date dd/mm/YYYY] [--category CATEGORIA] [--barcode CODIGO]
+                 [--ncm NCM] [--cfop CFOP] [--icms ALIQUOTA] [--pis ALIQUOTA] [--cofins ALIQUOTA] [--operator NOME]
+  product remove ID [--operator NOME]
+  category list [--json]
+  category add --name NOME [--parent CATEGORIA]
+  category rename CATEGORIA --name NOME
+  category merge CATEGORIA --into CATEGORIA
+  category remove CATEGORIA
+  promotion list [--json]
+  promotion add --name NOME (--product ID --buy QTD --pay QTD | --category CATEGORIA --percent PORCENTAGEM) [--from dd/mm/YYYY] [--to dd/mm/YYYY]
+  promotion remove ID
+  product receive --supplier NOME --item ID:QTD:CUSTO [--item ID:QTD:CUSTO...] [--operator NOME]
+  product adjust ID [--type ajuste|perda] --quantity QTD --reason MOTIVO [--operator NOME]
+  product history ID [--json]
+  product audit [--fix]
+  order create [--days DIAS] [--lead-time DIAS] [--coverage DIAS]
+  order get ID [--json]
+  order list [--json]
+  order receive ID [--supplier NOME] [--cost ID:CUSTO...] [--operator NOME]
+  export products|sales [--format csv]
+  import products ARQUIVO [--format csv] [--dry-run] [--operator NOME]
+  import sales ARQUIVO [--format csv] [--dry-run]
+  migrate sqlite
+  sale register --seller NOME --item ID|CODIGO[:QTD] [--item ID|CODIGO[:QTD]...] [--discount ID:DESCONTO...] [--sale-discount DESCONTO] --payment credito|debito|pix|dinheiro
+                [--receipt] [--printer ARQUIVO]
+  sale get CODIGO [--json]
+  sale receipt CODIGO [--printer ARQUIVO]
+  sale update CODIGO [--date dd/mm/YYYY] [--payment credito|debito|pix|dinheiro] [--item ID:QTD...] [--operator NOME]
+  sale cancel CODIGO --reason MOTIVO [--operator NOME]
+  sale return CODIGO --item ID[:QTD] [--item ID[:QTD]...] --reason MOTIVO [--operator NOME]
+  sales list [--date dd/mm/YYYY | --product ID] [--json]
+  sales report [--by dia|semana|mes|vendedor|pagamento] [--from dd/mm/YYYY] [--to dd/mm/YYYY]
+  session open --operator NOME [--float VALOR]
+  session close --counted VALOR
+  session get ID [--json]
+  session list [--json]
+  help
+
+Os comandos que alteram o estoque registram um movimento em nome de --operator ou, sem a
+opção, do operador do caixa aberto.
+
+O restoque sugere a compra a partir das vendas dos últimos --days dias (padrão 30), do prazo de
+entrega do fornecedor --lead-time (padrão 7) e dos dias de venda que o pedido deve cobrir
+depois de chegar --coverage (padrão 30).
+
+Os comandos marcados com [--json] também aceitam --format text|json|jsonl. Em JSON os campos
+mantêm os nomes gravados nos arquivos, os valores monetários vêm em centavos e as datas no
+formato dd/mm/YYYY; em jsonl cada registro ocupa uma linha.
+
+A busca de produtos combina os filtros informados. O nome é procurado em qualquer parte do nome
+do produto, sem diferenciar maiúsculas, minúsculas ou acentos; --low-stock mostra apenas os produtos
+com estoque na quantidade de restoque ou abaixo dela.
+
+As categorias são indicadas pelo nome ou pelo id e podem ficar abaixo de outra com --parent. A
+busca por uma categoria inclui as suas subcategorias. Juntar categorias move os produtos e as
+subcategorias para a categoria de --into e remove a primeira; só é possível remover uma categoria
+sem produtos, sem subcategorias e sem promoções.
+
+Os descontos são uma porcentagem (10%) ou um valor (2,50). Na venda, as promoções vigentes na data
+são aplicadas primeiro; cada produto recebe só a de maior desconto. Em seguida vêm os descontos de
+--discount em cada produto e, por último, o de --sale-discount sobre o total. Uma promoção leva e
+paga em um produto (--buy 3 --pay 2) ou dá uma porcentagem em uma categoria e nas subcategorias.
+Na devolução, o valor devolvido desconta a parte proporcional dos descontos.
+
+Os códigos de barras seguem o padrão EAN-13, com o dígito verificador conferido, e não podem se
+repetir entre produtos. Na venda, um item com os 13 dígitos de um código é procurado pelo código de
+barras em vez do id.
+
+Os dados fiscais do produto (NCM, CFOP e as alíquotas de ICMS, PIS e COFINS) são copiados para
+cada venda, que guarda os valores do momento. O cupom não fiscal da venda traz os tributos de cada
+produto, calculados sobre o valor com os descontos, e o total aproximado de tributos. Com --receipt
+o cupom é mostrado ao registrar a venda; com --printer ele é enviado em ESC/POS para o arquivo da
+impressora. O nome, o CNPJ, o endereço e a impressora da loja ficam no arquivo loja.conf do
+diretório de dados, em linhas 'nome = ...', 'cnpj = ...', 'endereco = ...' e 'impressora = ...'.
+
+A importação valida todas as linhas antes de gravar e lista os erros de cada linha; se houver
+alguma linha inválida, nada é importado. Com --dry-run, apenas mostra os números que seriam
+atribuídos. As vendas importadas não alteram o estoque dos produtos.
+
+Nas versões compiladas com o recurso sqlite, migrate sqlite copia produtos, movimentos e vendas
+para o banco store.db no diretório de dados. A partir daí, produtos e vendas são lidos e gravados
+no banco; caixas e pedidos continuam nos arquivos.
+
+Códigos de saída:
+  0 sucesso, 1 erro de leitura ou escrita, 2 comando inválido, 3 valor, desconto, promoção ou dados fiscais inválidos,
+  4 produto, venda, caixa, pedido, categoria ou promoção não encontrados,
+  5 estoque insuficiente, caixa já aberto, venda já cancelada, pedido já recebido,
+    código de barras ou categoria repetido, ou categoria ainda em uso,
+  6 arquivo em formato inesperado,
+  7 estoque inexistente ou já criado no diretório de dados");
+}
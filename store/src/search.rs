@@ -0,0 +1,119 @@
+use super::{errors::CustomErrors, money::Dinheiro, Produto};
+
+// Os filtros informados são combinados: o produto precisa atender a todos eles.
+#[derive(Default, Clone, Debug)]
+pub struct FiltroProdutos {
+    pub nome: Option<String>,
+    // Ids aceitos: a categoria escolhida e as suas subcategorias. Vazio aceita qualquer categoria.
+    pub categorias: Vec<u64>,
+    pub valor_minimo: Option<Dinheiro>,
+    pub valor_maximo: Option<Dinheiro>,
+    pub estoque_minimo: Option<u64>,
+    pub estoque_maximo: Option<u64>,
+    pub abaixo_restoque: bool
+}
+
+impl FiltroProdutos {
+    pub fn validate(&self) -> Result<(), CustomErrors> {
+        let inverted_price = matches!((self.valor_minimo, self.valor_maximo), (Some(minimo), Some(maximo)) if minimo > maximo);
+        let inverted_stock = matches!((self.estoque_minimo, self.estoque_maximo), (Some(minimo), Some(maximo)) if minimo > maximo);
+
+        match inverted_price || inverted_stock {
+            true => Err(CustomErrors::InvalidRange),
+            false => Ok(())
+        }
+    }
+
+    pub fn matches(&self, product: &Produto) -> bool {
+        self.nome.as_ref().is_none_or(|nome| normalize(&product.nome).contains(&normalize(nome)))
+            && (self.categorias.is_empty() || self.categorias.contains(&product.categoria))
+            && self.valor_minimo.is_none_or(|valor| product.valor >= valor)
+            && self.valor_maximo.is_none_or(|valor| product.valor <= valor)
+            && self.estoque_minimo.is_none_or(|estoque| product.quantidade_estoque >= estoque)
+            && self.estoque_maximo.is_none_or(|estoque| product.quantidade_estoque <= estoque)
+            && (!self.abaixo_restoque || product.quantidade_estoque <= product.quantidade_restoque)
+    }
+}
+
+// Minúsculas e sem acentos, para que "feijao" encontre "Feijão".
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|letter| match letter {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            _ => letter
+        })
+        .collect()
+}
+
+pub fn filter_products(products: Vec<Produto>, filtro: &FiltroProdutos) -> Vec<Produto> {
+    products.into_iter().filter(|product| filtro.matches(product)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::categories::{ALIMENTO, ELETRONICO, ROUPA};
+
+    fn product(nome: &str, estoque: u64, valor: i64, categoria: u64) -> Produto {
+        Produto::new(nome.to_string(), 0, estoque, Dinheiro::from_centavos(valor), 5, chrono::NaiveDate::default(), categoria)
+    }
+
+    fn names(products: Vec<Produto>) -> Vec<String> {
+        products.into_iter().map(|product| product.nome).collect()
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Feijão"), "feijao");
+        assert_eq!(normalize("AÇÚCAR Cristal"), "acucar cristal");
+        assert_eq!(normalize("pão-de-ló"), "pao-de-lo");
+    }
+
+    #[test]
+    fn test_filter_products() {
+        let products = || vec![
+            product("Feijão Carioca", 3, 850, ALIMENTO),
+            product("Feijão Preto", 20, 920, ALIMENTO),
+            product("Camiseta", 8, 4990, ROUPA),
+            product("Fone de ouvido", 0, 12000, ELETRONICO)
+        ];
+
+        let filtro = FiltroProdutos { nome: Some("FEIJAO".to_string()), ..FiltroProdutos::default() };
+        assert_eq!(names(filter_products(products(), &filtro)), vec!["Feijão Carioca", "Feijão Preto"]);
+
+        let filtro = FiltroProdutos { nome: Some("feijão".to_string()), abaixo_restoque: true, ..FiltroProdutos::default() };
+        assert_eq!(names(filter_products(products(), &filtro)), vec!["Feijão Carioca"]);
+
+        let filtro = FiltroProdutos { valor_minimo: Some(Dinheiro::from_centavos(900)), valor_maximo: Some(Dinheiro::from_centavos(5000)), ..FiltroProdutos::default() };
+        assert_eq!(names(filter_products(products(), &filtro)), vec!["Feijão Preto", "Camiseta"]);
+
+        let filtro = FiltroProdutos { categorias: vec![ALIMENTO], estoque_minimo: Some(5), ..FiltroProdutos::default() };
+        assert_eq!(names(filter_products(products(), &filtro)), vec!["Feijão Preto"]);
+
+        let filtro = FiltroProdutos { estoque_maximo: Some(0), ..FiltroProdutos::default() };
+        assert_eq!(names(filter_products(products(), &filtro)), vec!["Fone de ouvido"]);
+
+        assert_eq!(filter_products(products(), &FiltroProdutos::default()).len(), 4);
+    }
+
+    #[test]
+    fn test_validate_filter() {
+        assert!(FiltroProdutos::default().validate().is_ok());
+
+        let filtro = FiltroProdutos { estoque_minimo: Some(5), estoque_maximo: Some(5), ..FiltroProdutos::default() };
+        assert!(filtro.validate().is_ok());
+
+        let filtro = FiltroProdutos { valor_minimo: Some(Dinheiro::from_centavos(1000)), valor_maximo: Some(Dinheiro::from_centavos(500)), ..FiltroProdutos::default() };
+        assert!(matches!(filtro.validate(), Err(CustomErrors::InvalidRange)));
+
+        let filtro = FiltroProdutos { estoque_minimo: Some(10), estoque_maximo: Some(2), ..FiltroProdutos::default() };
+        assert!(matches!(filtro.validate(), Err(CustomErrors::InvalidRange)));
+    }
+}
@@ -0,0 +1,143 @@
+use std::{error::Error, fs::File, io::{Read, Seek, SeekFrom, Write}};
+
+use super::{core, errors::CustomErrors, journal, format::{self, Cabecalho, HEADER_LENGTH, SESSIONS_MAGIC}, money::Dinheiro, Caixa, Fechamento};
+
+pub fn serialize_record(session: &Caixa) -> Result<Vec<u8>, Box<dyn Error>> {
+    let serialized = bincode::serialize(session)?;
+
+    let mut record = bincode::serialize(&(serialized.len() as u64))?;
+    record.extend(serialized);
+
+    Ok(record)
+}
+
+// Cada caixa com a posição e o tamanho do seu registro.
+type Registro = (Caixa, u64, u64);
+
+#[derive(Debug)]
+pub struct ArquivoCaixas {
+    pub dados: File,
+    pub diario: File
+}
+
+impl ArquivoCaixas {
+    pub fn new(dados: File, diario: File) -> Self {
+        ArquivoCaixas { dados, diario }
+    }
+
+    pub fn header(&mut self) -> Result<Cabecalho, Box<dyn Error>> {
+        format::read_header(&mut self.dados, SESSIONS_MAGIC)
+    }
+
+    fn records(&mut self) -> Result<Vec<Registro>, Box<dyn Error>> {
+        let mut buf = Vec::new();
+
+        self.dados.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        self.dados.read_to_end(&mut buf)?;
+
+        let mut sessions = Vec::new();
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let size: u64 = bincode::deserialize(&buf[offset..])?;
+            let end = offset + 8 + size as usize;
+
+            let record = buf.get(offset + 8..end).ok_or(CustomErrors::FormatMismatch)?;
+            sessions.push((bincode::deserialize(record)?, HEADER_LENGTH + offset as u64, end as u64 - offset as u64));
+
+            offset = end;
+        }
+
+        Ok(sessions)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<Caixa>, Box<dyn Error>> {
+        Ok(self.records()?.into_iter().map(|(session, _, _)| session).collect())
+    }
+
+    pub fn search(&mut self, id: u64) -> Result<Caixa, Box<dyn Error>> {
+        let session = self.list()?.into_iter()
+            .find(|session| session.id == id)
+            .ok_or(CustomErrors::SessionNotFound)?;
+
+        Ok(session)
+    }
+
+    pub fn current(&mut self) -> Result<Option<Caixa>, Box<dyn Error>> {
+        Ok(self.list()?.into_iter().find(|session| session.fechamento.is_none()))
+    }
+
+    pub fn open(&mut self, operador: String, fundo: Dinheiro, data: chrono::NaiveDate) -> Result<Caixa, Box<dyn Error>> {
+        if self.current()?.is_some() {
+            return Err(Box::new(CustomErrors::SessionAlreadyOpen));
+        }
+
+        let mut header = self.header()?;
+        let session = Caixa::new(header.ultimo_id + 1, operador, fundo, data);
+
+        journal::rewrite_file(&mut self.diario, &mut self.dados, |dados| {
+            dados.seek(SeekFrom::End(0))?;
+            dados.write_all(&serialize_record(&session)?)?;
+
+            header.quantidade += 1;
+            header.ultimo_id = session.id;
+            format::write_header(dados, &header)
+        })?;
+
+        Ok(session)
+    }
+
+    pub fn close(&mut self, esperado: Dinheiro, contado: Dinheiro, data: chrono::NaiveDate) -> Result<Caixa, Box<dyn Error>> {
+        let (mut session, position, length) = self.records()?.into_iter()
+            .find(|(session, _, _)| session.fechamento.is_none())
+            .ok_or(CustomErrors::SessionNotFound)?;
+
+        session.fechamento = Some(Fechamento { data, esperado, contado });
+
+        journal::rewrite_file(&mut self.diario, &mut self.dados, |dados| {
+            core::remove_bytes(dados, position, length)?;
+
+            dados.seek(SeekFrom::End(0))?;
+            dados.write_all(&serialize_record(&session)?)?;
+
+            Ok(())
+        })?;
+
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{get_test_sessions, remove_test_sessions, test_path};
+
+    #[test]
+    fn test_open_and_close_sessions() {
+        let path = &test_path("test_sessions.bin");
+        let mut sessions = get_test_sessions(path);
+
+        let today = chrono::NaiveDate::default();
+
+        assert!(sessions.current().unwrap().is_none());
+        assert!(sessions.close(Dinheiro::ZERO, Dinheiro::ZERO, today).is_err());
+
+        let first = sessions.open("Ana".to_string(), Dinheiro::from_centavos(10000), today).unwrap();
+        assert_eq!(first.id, 1);
+
+        let error = sessions.open("Bia".to_string(), Dinheiro::ZERO, today).err().unwrap();
+        assert!(matches!(error.downcast_ref::<CustomErrors>(), Some(CustomErrors::SessionAlreadyOpen)));
+
+        let closed = sessions.close(Dinheiro::from_centavos(12000), Dinheiro::from_centavos(11950), today).unwrap();
+        assert_eq!(closed.fechamento.as_ref().unwrap().contado, Dinheiro::from_centavos(11950));
+
+        let second = sessions.open("Bia".to_string(), Dinheiro::ZERO, today).unwrap();
+        assert_eq!(second.id, 2);
+        assert_eq!(sessions.current().unwrap().unwrap().operador, "Bia");
+        assert!(sessions.search(1).unwrap().fechamento.is_some());
+        assert!(sessions.search(3).is_err());
+        assert_eq!(sessions.header().unwrap().quantidade, 2);
+
+        remove_test_sessions(path);
+    }
+}
@@ -0,0 +1,1105 @@
+use std::{collections::BTreeMap, error::Error, path::Path, rc::Rc};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{categories, errors, money::{Aliquota, Dinheiro}, products::ArquivoProdutos, repository::{ProductRepository, SaleRepository, SaleTransaction, StockTransaction}, sales::ArquivoVendas, Categoria, Devolucao, ItemVenda, Movimento, Produto, Promocao, PromocaoAplicada, Recebimento, Regra, Tributacao, Venda};
+
+pub const DATABASE_FILE: &str = "store.db";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+// Datas no formato ISO para que possam ser comparadas e agrupadas em consultas SQL.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS categorias (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        nome TEXT NOT NULL,
+        pai INTEGER REFERENCES categorias (id)
+    );
+
+    CREATE TABLE IF NOT EXISTS produtos (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        nome TEXT NOT NULL,
+        quantidade_estoque INTEGER NOT NULL,
+        valor INTEGER NOT NULL,
+        quantidade_restoque INTEGER NOT NULL,
+        categoria INTEGER NOT NULL,
+        data_restoque TEXT NOT NULL,
+        fornecedor TEXT,
+        custo_unitario INTEGER,
+        codigo_barras TEXT,
+        ncm TEXT,
+        cfop TEXT,
+        icms INTEGER,
+        pis INTEGER,
+        cofins INTEGER
+    );
+
+    CREATE TABLE IF NOT EXISTS movimentos (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        produto INTEGER NOT NULL,
+        tipo TEXT NOT NULL,
+        quantidade INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        operador TEXT NOT NULL,
+        motivo TEXT NOT NULL,
+        fornecedor TEXT,
+        custo_unitario INTEGER
+    );
+
+    CREATE INDEX IF NOT EXISTS movimentos_produto ON movimentos (produto);
+
+    CREATE TABLE IF NOT EXISTS vendas (
+        codigo INTEGER PRIMARY KEY AUTOINCREMENT,
+        vendedor TEXT NOT NULL,
+        valor INTEGER NOT NULL,
+        metodo_pagamento TEXT NOT NULL,
+        data TEXT NOT NULL,
+        caixa INTEGER,
+        situacao TEXT NOT NULL,
+        desconto INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS vendas_data ON vendas (data);
+    CREATE INDEX IF NOT EXISTS vendas_caixa ON vendas (caixa);
+
+    CREATE TABLE IF NOT EXISTS itens_venda (
+        venda INTEGER NOT NULL REFERENCES vendas (codigo) ON DELETE CASCADE,
+        posicao INTEGER NOT NULL,
+        produto INTEGER NOT NULL,
+        nome TEXT NOT NULL,
+        quantidade INTEGER NOT NULL,
+        valor_unitario INTEGER NOT NULL,
+        valor_total INTEGER NOT NULL,
+        desconto INTEGER NOT NULL DEFAULT 0,
+        ncm TEXT,
+        cfop TEXT,
+        icms INTEGER,
+        pis INTEGER,
+        cofins INTEGER,
+        PRIMARY KEY (venda, posicao)
+    );
+
+    CREATE INDEX IF NOT EXISTS itens_venda_produto ON itens_venda (produto);
+
+    CREATE TABLE IF NOT EXISTS devolucoes (
+        id INTEGER PRIMARY KEY,
+        venda INTEGER NOT NULL REFERENCES vendas (codigo) ON DELETE CASCADE,
+        data TEXT NOT NULL,
+        motivo TEXT NOT NULL,
+        valor INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS itens_devolucao (
+        devolucao INTEGER NOT NULL REFERENCES devolucoes (id) ON DELETE CASCADE,
+        produto INTEGER NOT NULL,
+        quantidade INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS promocoes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        nome TEXT NOT NULL,
+        tipo TEXT NOT NULL,
+        produto INTEGER,
+        leve INTEGER,
+        pague INTEGER,
+        categoria INTEGER,
+        percentual INTEGER,
+        inicio TEXT,
+        fim TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS promocoes_venda (
+        venda INTEGER NOT NULL REFERENCES vendas (codigo) ON DELETE CASCADE,
+        promocao INTEGER NOT NULL,
+        nome TEXT NOT NULL,
+        produto INTEGER NOT NULL,
+        valor INTEGER NOT NULL
+    );
+";
+
+const PRODUCT_COLUMNS: &str = "id, nome, quantidade_estoque, valor, quantidade_restoque, categoria, data_restoque, fornecedor, custo_unitario, codigo_barras, ncm, cfop, icms, pis, cofins";
+const CATEGORY_COLUMNS: &str = "id, nome, pai";
+const MOVEMENT_COLUMNS: &str = "id, produto, tipo, quantidade, data, operador, motivo, fornecedor, custo_unitario";
+const SALE_COLUMNS: &str = "codigo, vendedor, valor, metodo_pagamento, data, caixa, situacao, desconto";
+const PROMOTION_COLUMNS: &str = "id, nome, tipo, produto, leve, pague, categoria, percentual, inicio, fim";
+const ITEM_COLUMNS: &str = "venda, posicao, produto, nome, quantidade, valor_unitario, valor_total, desconto, ncm, cfop, icms, pis, cofins";
+
+// Colunas dos dados fiscais, presentes em produtos e em itens_venda.
+const TAX_COLUMNS: [(&str, &str); 5] = [("ncm", "TEXT"), ("cfop", "TEXT"), ("icms", "INTEGER"), ("pis", "INTEGER"), ("cofins", "INTEGER")];
+
+// Produtos e vendas dividem a mesma conexão para que uma venda e o estoque sejam gravados na mesma transação.
+#[derive(Debug)]
+pub struct ProdutosSqlite {
+    conexao: Rc<Connection>
+}
+
+#[derive(Debug)]
+pub struct VendasSqlite {
+    conexao: Rc<Connection>
+}
+
+#[derive(Debug)]
+pub struct TransacaoSqlite {
+    conexao: Rc<Connection>
+}
+
+pub fn open(path: &Path) -> Result<(ProdutosSqlite, VendasSqlite, TransacaoSqlite), Box<dyn Error>> {
+    let conexao = Connection::open(path)?;
+
+    conexao.pragma_update(None, "foreign_keys", true)?;
+    conexao.execute_batch(SCHEMA)?;
+    upgrade_schema(&conexao)?;
+
+    let conexao = Rc::new(conexao);
+
+    Ok((
+        ProdutosSqlite { conexao: Rc::clone(&conexao) },
+        VendasSqlite { conexao: Rc::clone(&conexao) },
+        TransacaoSqlite { conexao }
+    ))
+}
+
+fn has_column(conexao: &Connection, table: &str, column: &str) -> Result<bool, Box<dyn Error>> {
+    Ok(conexao.query_row("SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2", params![table, column], |row| row.get(0))?)
+}
+
+// Bancos criados antes do código de barras ganham a coluna; o índice único impede códigos repetidos.
+fn upgrade_schema(conexao: &Connection) -> Result<(), Box<dyn Error>> {
+    if !has_column(conexao, "produtos", "codigo_barras")? {
+        conexao.execute("ALTER TABLE produtos ADD COLUMN codigo_barras TEXT", [])?;
+    }
+
+    // As vendas gravadas antes dos descontos ficam sem desconto.
+    for table in ["vendas", "itens_venda"] {
+        if !has_column(conexao, table, "desconto")? {
+            conexao.execute(&format!("ALTER TABLE {table} ADD COLUMN desconto INTEGER NOT NULL DEFAULT 0"), [])?;
+        }
+    }
+
+    // Produtos e linhas de venda gravados antes dos dados fiscais ficam sem eles.
+    for table in ["produtos", "itens_venda"] {
+        for (column, kind) in TAX_COLUMNS {
+            if !has_column(conexao, table, column)? {
+                conexao.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {kind}"), [])?;
+            }
+        }
+    }
+
+    conexao.execute("CREATE UNIQUE INDEX IF NOT EXISTS produtos_codigo_barras ON produtos (codigo_barras)", [])?;
+
+    // Bancos novos e os anteriores às categorias cadastradas começam com as quatro categorias fixas,
+    // e os produtos gravados com o nome da categoria fixa passam a guardar o id dela.
+    if last_sequence(conexao, "categorias")? == 0 {
+        for category in categories::built_in() {
+            conexao.execute(&format!("INSERT INTO categorias ({CATEGORY_COLUMNS}) VALUES (?1, ?2, ?3)"), params![category.id(), category.nome(), category.pai()])?;
+        }
+    }
+
+    conexao.execute("
+        UPDATE produtos SET categoria = CASE categoria
+            WHEN 'Eletronico' THEN ?1 WHEN 'Roupa' THEN ?2 WHEN 'Alimento' THEN ?3 WHEN 'Geral' THEN ?4
+        END
+        WHERE categoria IN ('Eletronico', 'Roupa', 'Alimento', 'Geral')", params![categories::ELETRONICO, categories::ROUPA, categories::ALIMENTO, categories::GERAL])?;
+
+    Ok(())
+}
+
+// Copia produtos, movimentos e vendas dos arquivos para um banco novo, mantendo ids e códigos.
+pub fn migrate(products_file: &mut ArquivoProdutos, sales_file: &mut ArquivoVendas, path: &Path) -> Result<(usize, usize), Box<dyn Error>> {
+    if path.exists() {
+        return Err(Box::new(errors::CustomErrors::StoreAlreadyExists));
+    }
+
+    let products = products_file.list()?;
+    let categories = products_file.categorias.list()?;
+    let last_category = products_file.categorias.header()?.ultimo_id;
+    let promotions = products_file.promocoes.list()?;
+    let last_promotion = products_file.promocoes.header()?.ultimo_id;
+    let movements = products_file.movimentos.list()?;
+    let sales = sales_file.list()?;
+    let last_id = products_file.last_id()?;
+    let last_code = sales_file.header()?.ultimo_id;
+
+    let (database, _, _) = open(path)?;
+
+    let result = atomically(&database.conexao, |conexao| {
+        // As categorias do arquivo substituem as iniciais criadas com o banco.
+        conexao.execute("DELETE FROM categorias", [])?;
+
+        for category in categories.iter() {
+            conexao.execute(&format!("INSERT INTO categorias ({CATEGORY_COLUMNS}) VALUES (?1, ?2, ?3)"), params![category.id, category.nome, category.pai])?;
+        }
+
+        for product in products.iter() {
+            let (ncm, cfop, icms, pis, cofins) = tax_values(product.tributacao.as_ref());
+
+            conexao.execute(&format!("INSERT INTO produtos ({PRODUCT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"), params![
+                product.id,
+                product.nome,
+                product.quantidade_estoque,
+                product.valor.centavos(),
+                product.quantidade_restoque,
+                product.categoria,
+                product.data_restoque.format(DATE_FORMAT).to_string(),
+                product.fornecedor,
+                product.custo_unitario.map(|custo| custo.centavos()),
+                product.codigo_barras,
+                ncm,
+                cfop,
+                icms,
+                pis,
+                cofins
+            ])?;
+        }
+
+        for movement in movements.iter() {
+            insert_movement(conexao, Some(movement.id), movement)?;
+        }
+
+        for promotion in promotions.iter() {
+            write_promotion(conexao, Some(promotion.id), promotion)?;
+        }
+
+        for sale in sales.iter() {
+            write_sale(conexao, Some(sale.codigo), sale)?;
+        }
+
+        // Ids de produtos, categorias e promoções e códigos de vendas excluídos não voltam a ser usados.
+        for (table, last) in [("produtos", last_id), ("vendas", last_code), ("categorias", last_category), ("promocoes", last_promotion)] {
+            conexao.execute("DELETE FROM sqlite_sequence WHERE name = ?1", params![table])?;
+            conexao.execute("INSERT INTO sqlite_sequence (name, seq) VALUES (?1, ?2)", params![table, last])?;
+        }
+
+        Ok((products.len(), sales.len()))
+    });
+
+    if result.is_err() {
+        drop(database);
+        std::fs::remove_file(path)?;
+    }
+
+    result
+}
+
+// Agrupa as gravações numa transação, a menos que já exista uma em andamento na conexão.
+fn atomically<T, F: FnOnce(&Connection) -> Result<T, Box<dyn Error>>>(conexao: &Connection, write: F) -> Result<T, Box<dyn Error>> {
+    if !conexao.is_autocommit() {
+        return write(conexao);
+    }
+
+    let transaction = conexao.unchecked_transaction()?;
+    let result = write(&transaction)?;
+    transaction.commit()?;
+
+    Ok(result)
+}
+
+fn last_sequence(conexao: &Connection, table: &str) -> Result<u64, Box<dyn Error>> {
+    let last = conexao.query_row("SELECT seq FROM sqlite_sequence WHERE name = ?1", params![table], |row| row.get(0)).optional()?;
+
+    Ok(last.unwrap_or(0))
+}
+
+// Enumerações gravadas com o nome da variante, como no JSON.
+fn to_text<T: Serialize>(value: &T) -> Result<String, Box<dyn Error>> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(text) => Ok(text),
+        _ => Err(Box::new(errors::CustomErrors::FormatMismatch))
+    }
+}
+
+fn conversion<E: Error + Send + Sync + 'static>(index: usize) -> impl FnOnce(E) -> rusqlite::Error {
+    move |error| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(error))
+}
+
+fn enum_column<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    serde_json::from_value(serde_json::Value::String(row.get(index)?)).map_err(conversion(index))
+}
+
+fn date_column(row: &Row, index: usize) -> rusqlite::Result<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(&row.get::<_, String>(index)?, DATE_FORMAT).map_err(conversion(index))
+}
+
+fn money_column(row: &Row, index: usize) -> rusqlite::Result<Option<Dinheiro>> {
+    Ok(row.get::<_, Option<i64>>(index)?.map(Dinheiro::from_centavos))
+}
+
+// Bancos anteriores às categorias cadastradas declaram a coluna como texto e guardam o id como "3".
+fn category_column(row: &Row, index: usize) -> rusqlite::Result<u64> {
+    match row.get_ref(index)? {
+        rusqlite::types::ValueRef::Text(_) => row.get::<_, String>(index)?.parse().map_err(conversion(index)),
+        _ => row.get(index)
+    }
+}
+
+fn category_from_row(row: &Row) -> rusqlite::Result<Categoria> {
+    Ok(Categoria {
+        id: row.get(0)?,
+        nome: row.get(1)?,
+        pai: row.get(2)?
+    })
+}
+
+// Sem NCM o produto ou a linha da venda não tem dados fiscais.
+fn taxes_from_row(row: &Row, first: usize) -> rusqlite::Result<Option<Tributacao>> {
+    let Some(ncm) = row.get::<_, Option<String>>(first)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(Tributacao {
+        ncm,
+        cfop: row.get(first + 1)?,
+        icms: Aliquota::from_centesimos(row.get(first + 2)?),
+        pis: Aliquota::from_centesimos(row.get(first + 3)?),
+        cofins: Aliquota::from_centesimos(row.get(first + 4)?)
+    }))
+}
+
+type ValoresFiscais<'a> = (Option<&'a str>, Option<&'a str>, Option<u64>, Option<u64>, Option<u64>);
+
+fn tax_values(tributacao: Option<&Tributacao>) -> ValoresFiscais<'_> {
+    match tributacao {
+        Some(tributacao) => (Some(&tributacao.ncm), Some(&tributacao.cfop), Some(tributacao.icms.centesimos()), Some(tributacao.pis.centesimos()), Some(tributacao.cofins.centesimos())),
+        None => (None, None, None, None, None)
+    }
+}
+
+fn product_from_row(row: &Row) -> rusqlite::Result<Produto> {
+    Ok(Produto {
+        id: row.get(0)?,
+        nome: row.get(1)?,
+        quantidade_estoque: row.get(2)?,
+        valor: Dinheiro::from_centavos(row.get(3)?),
+        quantidade_restoque: row.get(4)?,
+        categoria: category_column(row, 5)?,
+        data_restoque: date_column(row, 6)?,
+        fornecedor: row.get(7)?,
+        custo_unitario: money_column(row, 8)?,
+        codigo_barras: row.get(9)?,
+        tributacao: taxes_from_row(row, 10)?
+    })
+}
+
+fn movement_from_row(row: &Row) -> rusqlite::Result<Movimento> {
+    let fornecedor: Option<String> = row.get(7)?;
+    let custo_unitario = money_column(row, 8)?;
+
+    Ok(Movimento {
+        id: row.get(0)?,
+        produto: row.get(1)?,
+        tipo: enum_column(row, 2)?,
+        quantidade: row.get(3)?,
+        data: chrono::NaiveDateTime::parse_from_str(&row.get::<_, String>(4)?, TIMESTAMP_FORMAT).map_err(conversion(4))?,
+        operador: row.get(5)?,
+        motivo: row.get(6)?,
+        recebimento: fornecedor.zip(custo_unitario).map(|(fornecedor, custo_unitario)| Recebimento { fornecedor, custo_unitario })
+    })
+}
+
+fn insert_movement(conexao: &Connection, id: Option<u64>, movement: &Movimento) -> Result<(), Box<dyn Error>> {
+    conexao.execute(&format!("INSERT INTO movimentos ({MOVEMENT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"), params![
+        id,
+        movement.produto,
+        to_text(&movement.tipo)?,
+        movement.quantidade,
+        movement.data.format(TIMESTAMP_FORMAT).to_string(),
+        movement.operador,
+        movement.motivo,
+        movement.recebimento.as_ref().map(|recebimento| recebimento.fornecedor.clone()),
+        movement.recebimento.as_ref().map(|recebimento| recebimento.custo_unitario.centavos())
+    ])?;
+
+    Ok(())
+}
+
+// A regra ocupa as colunas do seu tipo; as da outra regra ficam nulas.
+fn promotion_from_row(row: &Row) -> rusqlite::Result<Promocao> {
+    let regra = match row.get::<_, String>(2)?.as_str() {
+        "LevePague" => Regra::LevePague { produto: row.get(3)?, leve: row.get(4)?, pague: row.get(5)? },
+        _ => Regra::Categoria { categoria: row.get(6)?, percentual: row.get(7)? }
+    };
+
+    let optional_date = |index: usize| -> rusqlite::Result<Option<chrono::NaiveDate>> {
+        row.get::<_, Option<String>>(index)?
+            .map(|date| chrono::NaiveDate::parse_from_str(&date, DATE_FORMAT).map_err(conversion(index)))
+            .transpose()
+    };
+
+    Ok(Promocao {
+        id: row.get(0)?,
+        nome: row.get(1)?,
+        regra,
+        inicio: optional_date(8)?,
+        fim: optional_date(9)?
+    })
+}
+
+// Sem id a promoção recebe o próximo da sequência.
+fn write_promotion(conexao: &Connection, id: Option<u64>, promotion: &Promocao) -> Result<u64, Box<dyn Error>> {
+    let (tipo, produto, leve, pague, categoria, percentual) = match promotion.regra {
+        Regra::LevePague { produto, leve, pague } => ("LevePague", Some(produto), Some(leve), Some(pague), None, None),
+        Regra::Categoria { categoria, percentual } => ("Categoria", None, None, None, Some(categoria), Some(percentual))
+    };
+
+    conexao.execute(&format!("INSERT INTO promocoes ({PROMOTION_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"), params![
+        id,
+        promotion.nome,
+        tipo,
+        produto,
+        leve,
+        pague,
+        categoria,
+        percentual,
+        promotion.inicio.map(|date| date.format(DATE_FORMAT).to_string()),
+        promotion.fim.map(|date| date.format(DATE_FORMAT).to_string())
+    ])?;
+
+    Ok(conexao.last_insert_rowid() as u64)
+}
+
+// Sem código a venda recebe o próximo da sequência.
+fn write_sale(conexao: &Connection, codigo: Option<u64>, sale: &Venda) -> Result<u64, Box<dyn Error>> {
+    conexao.execute(&format!("INSERT INTO vendas ({SALE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"), params![
+        codigo,
+        sale.vendedor,
+        sale.valor.centavos(),
+        to_text(&sale.metodo_pagamento)?,
+        sale.data.format(DATE_FORMAT).to_string(),
+        sale.caixa,
+        to_text(&sale.situacao)?,
+        sale.desconto.centavos()
+    ])?;
+
+    let codigo = conexao.last_insert_rowid() as u64;
+
+    for (posicao, item) in sale.produtos.iter().enumerate() {
+        let (ncm, cfop, icms, pis, cofins) = tax_values(item.tributacao.as_ref());
+
+        conexao.execute(&format!("INSERT INTO itens_venda ({ITEM_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"), params![
+            codigo,
+            posicao,
+            item.id,
+            item.nome,
+            item.quantidade,
+            item.valor_unitario.centavos(),
+            item.valor_total.centavos(),
+            item.desconto.centavos(),
+            ncm,
+            cfop,
+            icms,
+            pis,
+            cofins
+        ])?;
+    }
+
+    for aplicada in sale.promocoes.iter() {
+        conexao.execute("INSERT INTO promocoes_venda (venda, promocao, nome, produto, valor) VALUES (?1, ?2, ?3, ?4, ?5)", params![
+            codigo,
+            aplicada.promocao,
+            aplicada.nome,
+            aplicada.produto,
+            aplicada.valor.centavos()
+        ])?;
+    }
+
+    for devolucao in sale.devolucoes.iter() {
+        conexao.execute("INSERT INTO devolucoes (venda, data, motivo, valor) VALUES (?1, ?2, ?3, ?4)", params![
+            codigo,
+            devolucao.data.format(DATE_FORMAT).to_string(),
+            devolucao.motivo,
+            devolucao.valor.centavos()
+        ])?;
+
+        let id = conexao.last_insert_rowid();
+
+        for (produto, quantidade) in devolucao.itens.iter() {
+            conexao.execute("INSERT INTO itens_devolucao (devolucao, produto, quantidade) VALUES (?1, ?2, ?3)", params![id, produto, quantidade])?;
+        }
+    }
+
+    Ok(codigo)
+}
+
+fn read_sales(conexao: &Connection, filter: &str, parameters: impl rusqlite::Params) -> Result<Vec<Venda>, Box<dyn Error>> {
+    let mut statement = conexao.prepare(&format!("SELECT {SALE_COLUMNS} FROM vendas {filter}"))?;
+
+    let mut sales = statement.query_map(parameters, |row| Ok(Venda {
+        codigo: row.get(0)?,
+        vendedor: row.get(1)?,
+        valor: Dinheiro::from_centavos(row.get(2)?),
+        metodo_pagamento: enum_column(row, 3)?,
+        data: date_column(row, 4)?,
+        caixa: row.get(5)?,
+        situacao: enum_column(row, 6)?,
+        desconto: Dinheiro::from_centavos(row.get(7)?),
+        produtos: Vec::new(),
+        devolucoes: Vec::new(),
+        promocoes: Vec::new()
+    }))?.collect::<Result<Vec<Venda>, rusqlite::Error>>()?;
+
+    let mut items = conexao.prepare("SELECT produto, nome, quantidade, valor_unitario, valor_total, desconto, ncm, cfop, icms, pis, cofins FROM itens_venda WHERE venda = ?1 ORDER BY posicao")?;
+    let mut applied = conexao.prepare("SELECT promocao, nome, produto, valor FROM promocoes_venda WHERE venda = ?1 ORDER BY rowid")?;
+    let mut returns = conexao.prepare("SELECT id, data, motivo, valor FROM devolucoes WHERE venda = ?1 ORDER BY id")?;
+    let mut returned_items = conexao.prepare("SELECT produto, quantidade FROM itens_devolucao WHERE devolucao = ?1 ORDER BY rowid")?;
+
+    for sale in sales.iter_mut() {
+        sale.produtos = items.query_map(params![sale.codigo], |row| Ok(ItemVenda {
+            id: row.get(0)?,
+            nome: row.get(1)?,
+            quantidade: row.get(2)?,
+            valor_unitario: Dinheiro::from_centavos(row.get(3)?),
+            valor_total: Dinheiro::from_centavos(row.get(4)?),
+            desconto: Dinheiro::from_centavos(row.get(5)?),
+            tributacao: taxes_from_row(row, 6)?
+        }))?.collect::<Result<Vec<ItemVenda>, rusqlite::Error>>()?;
+
+        sale.promocoes = applied.query_map(params![sale.codigo], |row| Ok(PromocaoAplicada {
+            promocao: row.get(0)?,
+            nome: row.get(1)?,
+            produto: row.get(2)?,
+            valor: Dinheiro::from_centavos(row.get(3)?)
+        }))?.collect::<Result<Vec<PromocaoAplicada>, rusqlite::Error>>()?;
+
+        let devolucoes = returns.query_map(params![sale.codigo], |row| Ok((row.get::<_, i64>(0)?, Devolucao {
+            data: date_column(row, 1)?,
+            motivo: row.get(2)?,
+            valor: Dinheiro::from_centavos(row.get(3)?),
+            itens: Vec::new()
+        })))?.collect::<Result<Vec<(i64, Devolucao)>, rusqlite::Error>>()?;
+
+        for (id, mut devolucao) in devolucoes {
+            devolucao.itens = returned_items.query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<Vec<(u64, u64)>, rusqlite::Error>>()?;
+            sale.devolucoes.push(devolucao);
+        }
+    }
+
+    Ok(sales)
+}
+
+impl ProductRepository for ProdutosSqlite {
+    fn last_id(&mut self) -> Result<u64, Box<dyn Error>> {
+        last_sequence(&self.conexao, "produtos")
+    }
+
+    fn get(&mut self, id: u64) -> Result<Produto, Box<dyn Error>> {
+        let product = self.conexao.query_row(&format!("SELECT {PRODUCT_COLUMNS} FROM produtos WHERE id = ?1"), params![id], product_from_row).optional()?;
+
+        Ok(product.ok_or(errors::CustomErrors::ProductNotFound)?)
+    }
+
+    fn list(&mut self) -> Result<Vec<Produto>, Box<dyn Error>> {
+        let mut statement = self.conexao.prepare(&format!("SELECT {PRODUCT_COLUMNS} FROM produtos ORDER BY id"))?;
+        let products = statement.query_map([], product_from_row)?.collect::<Result<Vec<Produto>, rusqlite::Error>>()?;
+
+        Ok(products)
+    }
+
+    fn barcode_id(&mut self, barcode: &str) -> Result<Option<u64>, Box<dyn Error>> {
+        Ok(self.conexao.query_row("SELECT id FROM produtos WHERE codigo_barras = ?1", params![barcode], |row| row.get(0)).optional()?)
+    }
+
+    fn insert(&mut self, product: Produto) -> Result<u64, Box<dyn Error>> {
+        let (ncm, cfop, icms, pis, cofins) = tax_values(product.tributacao.as_ref());
+
+        self.conexao.execute("INSERT INTO produtos (nome, quantidade_estoque, valor, quantidade_restoque, categoria, data_restoque, fornecedor, custo_unitario, codigo_barras, ncm, cfop, icms, pis, cofins) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)", params![
+            product.nome,
+            product.quantidade_estoque,
+            product.valor.centavos(),
+            product.quantidade_restoque,
+            product.categoria,
+            product.data_restoque.format(DATE_FORMAT).to_string(),
+            product.fornecedor,
+            product.custo_unitario.map(|custo| custo.centavos()),
+            product.codigo_barras,
+            ncm,
+            cfop,
+            icms,
+            pis,
+            cofins
+        ])?;
+
+        Ok(self.conexao.last_insert_rowid() as u64)
+    }
+
+    fn update(&mut self, product: &Produto) -> Result<(), Box<dyn Error>> {
+        let (ncm, cfop, icms, pis, cofins) = tax_values(product.tributacao.as_ref());
+
+        let updated = self.conexao.execute("UPDATE produtos SET nome = ?2, quantidade_estoque = ?3, valor = ?4, quantidade_restoque = ?5, categoria = ?6, data_restoque = ?7, fornecedor = ?8, custo_unitario = ?9, codigo_barras = ?10, ncm = ?11, cfop = ?12, icms = ?13, pis = ?14, cofins = ?15 WHERE id = ?1", params![
+            product.id,
+            product.nome,
+            product.quantidade_estoque,
+            product.valor.centavos(),
+            product.quantidade_restoque,
+            product.categoria,
+            product.data_restoque.format(DATE_FORMAT).to_string(),
+            product.fornecedor,
+            product.custo_unitario.map(|custo| custo.centavos()),
+            product.codigo_barras,
+            ncm,
+            cfop,
+            icms,
+            pis,
+            cofins
+        ])?;
+
+        match updated {
+            0 => Err(Box::new(errors::CustomErrors::ProductNotFound)),
+            _ => Ok(())
+        }
+    }
+
+    fn remove(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        match self.conexao.execute("DELETE FROM produtos WHERE id = ?1", params![id])? {
+            0 => Err(Box::new(errors::CustomErrors::ProductNotFound)),
+            _ => Ok(())
+        }
+    }
+
+    fn movements(&mut self, id: u64) -> Result<Vec<Movimento>, Box<dyn Error>> {
+        let mut statement = self.conexao.prepare(&format!("SELECT {MOVEMENT_COLUMNS} FROM movimentos WHERE produto = ?1 ORDER BY id"))?;
+        let movements = statement.query_map(params![id], movement_from_row)?.collect::<Result<Vec<Movimento>, rusqlite::Error>>()?;
+
+        Ok(movements)
+    }
+
+    fn balances(&mut self) -> Result<BTreeMap<u64, i64>, Box<dyn Error>> {
+        let mut statement = self.conexao.prepare("SELECT produto, SUM(quantidade) FROM movimentos GROUP BY produto")?;
+        let balances = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<BTreeMap<u64, i64>, rusqlite::Error>>()?;
+
+        Ok(balances)
+    }
+
+    fn append_movements(&mut self, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |conexao| {
+            for movement in movements.iter() {
+                insert_movement(conexao, None, movement)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn categories(&mut self) -> Result<Vec<Categoria>, Box<dyn Error>> {
+        let mut statement = self.conexao.prepare(&format!("SELECT {CATEGORY_COLUMNS} FROM categorias ORDER BY id"))?;
+        let categories = statement.query_map([], category_from_row)?.collect::<Result<Vec<Categoria>, rusqlite::Error>>()?;
+
+        Ok(categories)
+    }
+
+    fn insert_category(&mut self, category: Categoria) -> Result<u64, Box<dyn Error>> {
+        self.conexao.execute("INSERT INTO categorias (nome, pai) VALUES (?1, ?2)", params![category.nome, category.pai])?;
+
+        Ok(self.conexao.last_insert_rowid() as u64)
+    }
+
+    fn update_category(&mut self, category: &Categoria) -> Result<(), Box<dyn Error>> {
+        match self.conexao.execute("UPDATE categorias SET nome = ?2, pai = ?3 WHERE id = ?1", params![category.id, category.nome, category.pai])? {
+            0 => Err(Box::new(errors::CustomErrors::CategoryNotFound)),
+            _ => Ok(())
+        }
+    }
+
+    fn remove_category(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        match self.conexao.execute("DELETE FROM categorias WHERE id = ?1", params![id])? {
+            0 => Err(Box::new(errors::CustomErrors::CategoryNotFound)),
+            _ => Ok(())
+        }
+    }
+
+    fn promotions(&mut self) -> Result<Vec<Promocao>, Box<dyn Error>> {
+        let mut statement = self.conexao.prepare(&format!("SELECT {PROMOTION_COLUMNS} FROM promocoes ORDER BY id"))?;
+        let promotions = statement.query_map([], promotion_from_row)?.collect::<Result<Vec<Promocao>, rusqlite::Error>>()?;
+
+        Ok(promotions)
+    }
+
+    fn insert_promotion(&mut self, promotion: Promocao) -> Result<u64, Box<dyn Error>> {
+        write_promotion(&self.conexao, None, &promotion)
+    }
+
+    fn update_promotion(&mut self, promotion: &Promocao) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |conexao| {
+            if conexao.execute("DELETE FROM promocoes WHERE id = ?1", params![promotion.id])? == 0 {
+                return Err(Box::new(errors::CustomErrors::PromotionNotFound));
+            }
+
+            write_promotion(conexao, Some(promotion.id), promotion)?;
+
+            Ok(())
+        })
+    }
+
+    fn remove_promotion(&mut self, id: u64) -> Result<(), Box<dyn Error>> {
+        match self.conexao.execute("DELETE FROM promocoes WHERE id = ?1", params![id])? {
+            0 => Err(Box::new(errors::CustomErrors::PromotionNotFound)),
+            _ => Ok(())
+        }
+    }
+}
+
+impl SaleRepository for VendasSqlite {
+    fn last_code(&mut self) -> Result<u64, Box<dyn Error>> {
+        last_sequence(&self.conexao, "vendas")
+    }
+
+    fn get(&mut self, code: u64) -> Result<Venda, Box<dyn Error>> {
+        let sale = read_sales(&self.conexao, "WHERE codigo = ?1", params![code])?.pop();
+
+        Ok(sale.ok_or(errors::CustomErrors::SaleNotFound)?)
+    }
+
+    fn list(&mut self) -> Result<Vec<Venda>, Box<dyn Error>> {
+        read_sales(&self.conexao, "ORDER BY codigo", [])
+    }
+
+    fn by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, Box<dyn Error>> {
+        read_sales(&self.conexao, "WHERE data = ?1 ORDER BY codigo", params![date.format(DATE_FORMAT).to_string()])
+    }
+
+    fn by_range(&mut self, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<Vec<Venda>, Box<dyn Error>> {
+        let from = from.map(|date| date.format(DATE_FORMAT).to_string());
+        let to = to.map(|date| date.format(DATE_FORMAT).to_string());
+
+        read_sales(&self.conexao, "WHERE (?1 IS NULL OR data >= ?1) AND (?2 IS NULL OR data <= ?2) ORDER BY data, codigo", params![from, to])
+    }
+
+    fn by_product(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        read_sales(&self.conexao, "WHERE codigo IN (SELECT venda FROM itens_venda WHERE produto = ?1) ORDER BY codigo", params![id])
+    }
+
+    fn by_session(&mut self, id: u64) -> Result<Vec<Venda>, Box<dyn Error>> {
+        read_sales(&self.conexao, "WHERE caixa = ?1 ORDER BY codigo", params![id])
+    }
+
+    fn insert(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        atomically(&self.conexao, |conexao| write_sale(conexao, None, &sale))
+    }
+
+    fn import(&mut self, sale: Venda) -> Result<u64, Box<dyn Error>> {
+        atomically(&self.conexao, |conexao| {
+            if conexao.query_row("SELECT COUNT(*) FROM vendas WHERE codigo = ?1", params![sale.codigo], |row| row.get::<_, u64>(0))? > 0 {
+                return Err(Box::new(errors::CustomErrors::DuplicateSale));
+            }
+
+            write_sale(conexao, Some(sale.codigo), &sale)
+        })
+    }
+
+    // O registro substituído leva junto os itens e as devoluções gravados antes.
+    fn rewrite(&mut self, sale: &Venda) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |conexao| {
+            if conexao.execute("DELETE FROM vendas WHERE codigo = ?1", params![sale.codigo])? == 0 {
+                return Err(Box::new(errors::CustomErrors::SaleNotFound));
+            }
+
+            write_sale(conexao, Some(sale.codigo), sale)?;
+
+            Ok(())
+        })
+    }
+
+    fn remove(&mut self, code: u64) -> Result<(), Box<dyn Error>> {
+        match self.conexao.execute("DELETE FROM vendas WHERE codigo = ?1", params![code])? {
+            0 => Err(Box::new(errors::CustomErrors::SaleNotFound)),
+            _ => Ok(())
+        }
+    }
+}
+
+// Os movimentos entram na mesma transação SQL que grava os produtos e a venda.
+impl StockTransaction<ProdutosSqlite> for TransacaoSqlite {
+    fn insert_products<F: FnMut(&Produto) -> Vec<Movimento>>(&mut self, products: &mut ProdutosSqlite, new_products: Vec<Produto>, mut movements: F) -> Result<Vec<u64>, Box<dyn Error>> {
+        atomically(&self.conexao, |_| {
+            let mut ids = Vec::with_capacity(new_products.len());
+
+            for mut product in new_products {
+                product.id = products.insert(product.clone())?;
+                products.append_movements(movements(&product))?;
+                ids.push(product.id);
+            }
+
+            Ok(ids)
+        })
+    }
+
+    fn update_products(&mut self, products: &mut ProdutosSqlite, updated_products: &[Produto], movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |_| {
+            for product in updated_products.iter() {
+                products.update(product)?;
+            }
+
+            products.append_movements(movements)
+        })
+    }
+
+    fn remove_product(&mut self, products: &mut ProdutosSqlite, id: u64, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |_| {
+            products.remove(id)?;
+            products.append_movements(movements)
+        })
+    }
+
+    fn update_catalog<R, F: FnOnce(&mut ProdutosSqlite) -> Result<R, Box<dyn Error>>>(&mut self, products: &mut ProdutosSqlite, _: &[u64], write: F) -> Result<R, Box<dyn Error>> {
+        atomically(&self.conexao, |_| write(products))
+    }
+}
+
+impl SaleTransaction<ProdutosSqlite, VendasSqlite> for TransacaoSqlite {
+    fn insert_sale<F: FnOnce(&Venda) -> Vec<Movimento>>(&mut self, products: &mut ProdutosSqlite, sales: &mut VendasSqlite, updated_products: &[Produto], mut sale: Venda, movements: F) -> Result<Venda, Box<dyn Error>> {
+        atomically(&self.conexao, |_| {
+            for product in updated_products.iter() {
+                products.update(product)?;
+            }
+
+            sale.codigo = sales.insert(sale.clone())?;
+            products.append_movements(movements(&sale))?;
+
+            Ok(sale)
+        })
+    }
+
+    fn update_sale(&mut self, products: &mut ProdutosSqlite, sales: &mut VendasSqlite, updated_products: &[Produto], sale: &Venda, movements: Vec<Movimento>) -> Result<(), Box<dyn Error>> {
+        atomically(&self.conexao, |_| {
+            for product in updated_products.iter() {
+                products.update(product)?;
+            }
+
+            sales.rewrite(sale)?;
+            products.append_movements(movements)
+        })
+    }
+
+    fn insert_sales(&mut self, _: &mut ProdutosSqlite, sales: &mut VendasSqlite, new_sales: Vec<Venda>) -> Result<Vec<u64>, Box<dyn Error>> {
+        atomically(&self.conexao, |_| new_sales.into_iter().map(|sale| sales.import(sale)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{core, promotions::Cesta, test_utils::{get_test_products, get_test_sales, remove_test_products, remove_test_sales, test_path}, MetodoPagamento, Situacao, TipoMovimento};
+
+    fn product(nome: &str, estoque: u64) -> Produto {
+        Produto::new(nome.to_string(), 0, estoque, Dinheiro::from_centavos(1250), 2, chrono::NaiveDate::from_ymd_opt(2024, 5, 20).unwrap(), categories::ALIMENTO)
+    }
+
+    #[test]
+    fn test_sqlite_operations() {
+        let path = &test_path("test_sqlite_operations.db");
+        let (mut products, mut sales, mut transaction) = open(path).unwrap();
+
+        core::insert_product(&mut products, &mut transaction, product("Feijão", 10), "Ana").unwrap();
+        core::insert_product(&mut products, &mut transaction, product("Arroz", 5), "Ana").unwrap();
+
+        let sale = core::complete_sale(&mut products, &mut sales, &mut transaction, "Ana".to_string(), Some(1), &Cesta::new(vec![(1, 3), (2, 1)]), MetodoPagamento::Pix).unwrap();
+        assert_eq!(sale.codigo, 1);
+        assert_eq!(sale.valor, Dinheiro::from_centavos(5000));
+
+        let error = core::complete_sale(&mut products, &mut sales, &mut transaction, "Ana".to_string(), None, &Cesta::new(vec![(1, 1), (2, 10)]), MetodoPagamento::Pix).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::LowStock)));
+
+        let sale = core::return_items(&mut products, &mut sales, &mut transaction, 1, &[(1, 1)], "Defeito".to_string(), "Ana").unwrap();
+        assert_eq!(sale.situacao, Situacao::DevolucaoParcial);
+
+        let stored = sales.get(1).unwrap();
+        assert_eq!(stored.produtos.len(), 2);
+        assert_eq!(stored.devolucoes[0].itens, vec![(1, 1)]);
+        assert_eq!(stored.valor_liquido().unwrap(), Dinheiro::from_centavos(3750));
+
+        assert_eq!(core::search_product_id(&mut products, 1).unwrap().quantidade_estoque, 8);
+        assert_eq!(sales.by_product(2).unwrap().len(), 1);
+        assert_eq!(sales.by_session(1).unwrap().len(), 1);
+        assert!(core::stock_divergences(&mut products).unwrap().is_empty());
+
+        core::delete_product(&mut products, &mut transaction, 2, "Ana").unwrap();
+        assert_eq!(core::insert_product(&mut products, &mut transaction, product("Açúcar", 1), "Ana").unwrap(), 3);
+
+        let mut feijao = products.get(1).unwrap();
+        feijao.codigo_barras = Some("7891234567895".to_string());
+        core::replace_product(&mut products, &mut transaction, 1, feijao, "Ana").unwrap();
+
+        assert_eq!(products.barcode_id("7891234567895").unwrap(), Some(1));
+        assert_eq!(products.barcode_id("4006381333931").unwrap(), None);
+
+        let mut duplicate = products.get(3).unwrap();
+        duplicate.codigo_barras = Some("7891234567895".to_string());
+
+        // O índice único também recusa o código repetido gravado direto no repositório.
+        assert!(products.update(&duplicate).is_err());
+
+        let bebidas = core::create_category(&mut products, &mut transaction, "Bebidas", Some(categories::ALIMENTO)).unwrap();
+        assert_eq!(bebidas.id, 5);
+
+        let alimentos = Promocao::new(0, "Alimentos".to_string(), Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 }, None, chrono::NaiveDate::from_ymd_opt(2999, 12, 31));
+        assert_eq!(core::create_promotion(&mut products, &mut transaction, alimentos).unwrap().id, 1);
+
+        let error = core::merge_categories(&mut products, &mut transaction, categories::ALIMENTO, bebidas.id).err().unwrap();
+        assert!(matches!(error.downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::InvalidParent)));
+        assert_eq!(core::merge_categories(&mut products, &mut transaction, categories::ALIMENTO, categories::GERAL).unwrap(), 2);
+        assert_eq!(products.get(1).unwrap().categoria, categories::GERAL);
+        assert_eq!(core::search_category(&mut products, bebidas.id).unwrap().pai, Some(categories::GERAL));
+        assert!(products.remove_category(categories::ALIMENTO).is_err());
+        assert_eq!(products.promotions().unwrap()[0].regra, Regra::Categoria { categoria: categories::GERAL, percentual: 10 });
+
+        let mut cesta = Cesta::new(vec![(1, 2)]);
+        cesta.desconto = Some(crate::Desconto::Percentual(5));
+
+        let sale = core::complete_sale(&mut products, &mut sales, &mut transaction, "Ana".to_string(), None, &cesta, MetodoPagamento::Pix).unwrap();
+        let stored = sales.get(sale.codigo).unwrap();
+
+        assert_eq!(stored.produtos[0].desconto, Dinheiro::from_centavos(250));
+        assert_eq!(stored.desconto, Dinheiro::from_centavos(113));
+        assert_eq!(stored.valor, Dinheiro::from_centavos(2137));
+        assert_eq!(stored.promocoes, sale.promocoes);
+        assert_eq!(stored.promocoes[0].nome, "Alimentos");
+
+        core::delete_promotion(&mut products, &mut transaction, 1).unwrap();
+        assert!(matches!(core::delete_promotion(&mut products, &mut transaction, 1).unwrap_err().downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::PromotionNotFound)));
+        assert_eq!(sales.get(sale.codigo).unwrap().promocoes.len(), 1);
+
+        let tributacao = Tributacao::new("07133319".to_string(), "5102".to_string(), Aliquota::from_centesimos(700), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760));
+        assert_eq!(core::set_product_taxes(&mut products, 1, Some(tributacao.clone())).unwrap().tributacao, Some(tributacao.clone()));
+        assert_eq!(products.get(1).unwrap().tributacao, Some(tributacao.clone()));
+
+        let sale = core::complete_sale(&mut products, &mut sales, &mut transaction, "Ana".to_string(), None, &Cesta::new(vec![(1, 1), (3, 1)]), MetodoPagamento::Pix).unwrap();
+        let stored = sales.get(sale.codigo).unwrap();
+
+        assert_eq!(stored.produtos[0].tributacao, Some(tributacao));
+        assert_eq!(stored.produtos[1].tributacao, None);
+
+        drop((products, sales, transaction));
+        fs::remove_file(path).expect("Erro ao tentar excluir o banco de dados.");
+    }
+
+    #[test]
+    fn test_sqlite_movement_failure() {
+        let path = &test_path("test_sqlite_movement_failure.db");
+        let (mut products, mut sales, mut transaction) = open(path).unwrap();
+
+        core::insert_product(&mut products, &mut transaction, product("Feijão", 10), "Ana").unwrap();
+
+        // Com a gravação dos movimentos recusada, o estoque e a venda também são desfeitos.
+        products.conexao.execute_batch("CREATE TEMP TRIGGER recusa_movimentos BEFORE INSERT ON movimentos BEGIN SELECT RAISE(ABORT, 'movimento recusado'); END").unwrap();
+
+        assert!(core::adjust_stock(&mut products, &mut transaction, 1, TipoMovimento::Perda, -6, "Ana", "Avaria").is_err());
+        assert!(core::complete_sale(&mut products, &mut sales, &mut transaction, "Ana".to_string(), None, &Cesta::new(vec![(1, 3)]), MetodoPagamento::Pix).is_err());
+        assert!(core::insert_product(&mut products, &mut transaction, product("Arroz", 5), "Ana").is_err());
+
+        assert_eq!(products.get(1).unwrap().quantidade_estoque, 10);
+        assert_eq!(products.list().unwrap().len(), 1);
+        assert!(sales.list().unwrap().is_empty());
+        assert_eq!(products.movements(1).unwrap().len(), 1);
+        assert!(core::stock_divergences(&mut products).unwrap().is_empty());
+
+        drop((products, sales, transaction));
+        fs::remove_file(path).expect("Erro ao tentar excluir o banco de dados.");
+    }
+
+    #[test]
+    fn test_sqlite_merge_failure() {
+        let path = &test_path("test_sqlite_merge_failure.db");
+        let (mut products, _, mut transaction) = open(path).unwrap();
+
+        core::insert_product(&mut products, &mut transaction, product("Feijão", 10), "Ana").unwrap();
+        let bebidas = core::create_category(&mut products, &mut transaction, "Bebidas", Some(categories::ALIMENTO)).unwrap();
+        let alimentos = Promocao::new(0, "Alimentos".to_string(), Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 }, None, None);
+        core::create_promotion(&mut products, &mut transaction, alimentos).unwrap();
+
+        // A categoria de origem é removida por último; com a remoção recusada, nada do que foi movido fica gravado.
+        products.conexao.execute_batch("CREATE TEMP TRIGGER recusa_categoria BEFORE DELETE ON categorias BEGIN SELECT RAISE(ABORT, 'categoria recusada'); END").unwrap();
+
+        assert!(core::merge_categories(&mut products, &mut transaction, categories::ALIMENTO, categories::GERAL).is_err());
+        assert_eq!(products.get(1).unwrap().categoria, categories::ALIMENTO);
+        assert_eq!(core::search_category(&mut products, bebidas.id).unwrap().pai, Some(categories::ALIMENTO));
+        assert_eq!(products.promotions().unwrap()[0].regra, Regra::Categoria { categoria: categories::ALIMENTO, percentual: 10 });
+
+        drop((products, transaction));
+        fs::remove_file(path).expect("Erro ao tentar excluir o banco de dados.");
+    }
+
+    #[test]
+    fn test_sqlite_import_failure() {
+        let path = &test_path("test_sqlite_import_failure.db");
+        let (mut products, mut sales, mut transaction) = open(path).unwrap();
+
+        // A segunda linha de cada arquivo é recusada pelo banco, então a primeira também é desfeita.
+        products.conexao.execute_batch("
+            CREATE TEMP TRIGGER recusa_produto BEFORE INSERT ON produtos WHEN NEW.nome = 'Arroz' BEGIN SELECT RAISE(ABORT, 'produto recusado'); END;
+            CREATE TEMP TRIGGER recusa_venda BEFORE INSERT ON vendas WHEN NEW.vendedor = 'Bia' BEGIN SELECT RAISE(ABORT, 'venda recusada'); END;
+        ").unwrap();
+
+        let products_csv = "nome,estoque,valor,restoque,data,categoria\nFeijão,3,1,1,10/08/2023,alimento\nArroz,10,20,2,10/08/2023,alimento\n";
+        assert!(core::import_products(&mut products, &mut transaction, products_csv, false, "Ana").is_err());
+        assert!(products.list().unwrap().is_empty());
+        assert!(products.balances().unwrap().is_empty());
+
+        core::insert_product(&mut products, &mut transaction, product("Feijão", 10), "Ana").unwrap();
+
+        let sales_csv = "codigo,data,vendedor,pagamento,produto,quantidade\nA,10/08/2023,Ana,pix,1,2\nB,11/08/2023,Bia,debito,1,1\n";
+        assert!(core::import_sales(&mut products, &mut sales, &mut transaction, sales_csv, false).is_err());
+        assert!(sales.list().unwrap().is_empty());
+
+        drop((products, sales, transaction));
+        fs::remove_file(path).expect("Erro ao tentar excluir o banco de dados.");
+    }
+
+    #[test]
+    fn test_migrate_files() {
+        let path_products = &test_path("test_sqlite_migrate_1.bin");
+        let path_sales = &test_path("test_sqlite_migrate_2.bin");
+        let path_journal = &test_path("test_sqlite_migrate_3.bin");
+        let path_database = &test_path("test_sqlite_migrate.db");
+
+        let mut products_file = get_test_products(path_products);
+        let mut sales_file = get_test_sales(path_sales);
+        let mut journal_file = crate::test_utils::get_test_file(path_journal);
+
+        core::insert_product(&mut products_file, &mut journal_file, product("Feijão", 10), "Ana").unwrap();
+        core::insert_product(&mut products_file, &mut journal_file, product("Arroz", 5), "Ana").unwrap();
+        core::insert_product(&mut products_file, &mut journal_file, product("Sal", 5), "Ana").unwrap();
+        core::delete_product(&mut products_file, &mut journal_file, 3, "Ana").unwrap();
+        core::create_category(&mut products_file, &mut journal_file, "Bebidas", None).unwrap();
+        core::delete_category(&mut products_file, &mut journal_file, 5).unwrap();
+        core::create_category(&mut products_file, &mut journal_file, "Temperos", Some(categories::ALIMENTO)).unwrap();
+
+        let leve_pague = Promocao::new(0, "Leve 3, pague 2".to_string(), Regra::LevePague { produto: 1, leve: 3, pague: 2 }, chrono::NaiveDate::from_ymd_opt(2024, 1, 1), None);
+        core::create_promotion(&mut products_file, &mut journal_file, leve_pague.clone()).unwrap();
+        core::create_promotion(&mut products_file, &mut journal_file, leve_pague).unwrap();
+        core::delete_promotion(&mut products_file, &mut journal_file, 2).unwrap();
+
+        core::complete_sale(&mut products_file, &mut sales_file, &mut journal_file, "Ana".to_string(), None, &Cesta::new(vec![(1, 2)]), MetodoPagamento::Dinheiro).unwrap();
+        core::void_sale(&mut products_file, &mut sales_file, &mut journal_file, 1, "Desistência".to_string(), "Ana").unwrap();
+
+        assert_eq!(migrate(&mut products_file, &mut sales_file, path_database).unwrap(), (2, 1));
+        assert!(migrate(&mut products_file, &mut sales_file, path_database).is_err());
+
+        let (mut products, mut sales, mut transaction) = open(path_database).unwrap();
+
+        assert_eq!(products.list().unwrap().len(), 2);
+        assert_eq!(products.last_id().unwrap(), 3);
+        assert_eq!(products.movements(1).unwrap().len(), 3);
+        assert!(core::stock_divergences(&mut products).unwrap().is_empty());
+
+        assert_eq!(products.categories().unwrap(), products_file.categorias.list().unwrap());
+        assert_eq!(core::create_category(&mut products, &mut transaction, "Limpeza", None).unwrap().id, 7);
+
+        assert_eq!(products.promotions().unwrap(), products_file.promocoes.list().unwrap());
+        assert_eq!(products.insert_promotion(products_file.promocoes.list().unwrap().remove(0)).unwrap(), 3);
+
+        let sale = sales.get(1).unwrap();
+        assert_eq!(sale.situacao, Situacao::Cancelada);
+        assert_eq!(sale.devolucoes[0].motivo, "Desistência");
+        assert_eq!(sales.insert(sale).unwrap(), 2);
+
+        drop((products, sales, transaction));
+
+        remove_test_products(path_products);
+        remove_test_sales(path_sales);
+        fs::remove_file(path_journal).expect("Erro ao tentar excluir o arquivo de transação.");
+        fs::remove_file(path_database).expect("Erro ao tentar excluir o banco de dados.");
+    }
+}
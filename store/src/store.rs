@@ -0,0 +1,342 @@
+use std::{fs::File, path::Path};
+
+use super::{core, csv, errors::StoreError, money::Dinheiro, orders::{self, ArquivoPedidos}, products::ArquivoProdutos, promotions::Cesta, receipt::{self, Loja}, repository::ProductRepository, reports, sales::ArquivoVendas, search::FiltroProdutos, sessions::ArquivoCaixas, taxes::{self, TributosItem}, validation, Caixa, Categoria, MetodoPagamento, Movimento, Pedido, Produto, Promocao, TipoMovimento, Tributacao, Venda};
+
+// Acesso ao estoque como biblioteca: as operações devolvem os registros em vez de imprimi-los.
+#[derive(Debug)]
+pub struct Store {
+    produtos: ArquivoProdutos,
+    vendas: ArquivoVendas,
+    caixas: ArquivoCaixas,
+    pedidos: ArquivoPedidos,
+    transacao: File,
+    avisos: Vec<String>
+}
+
+impl Store {
+    pub fn from_files((produtos, vendas, caixas, pedidos, transacao): validation::Arquivos) -> Self {
+        Store { produtos, vendas, caixas, pedidos, transacao, avisos: Vec::new() }
+    }
+
+    pub fn open(dir: &Path) -> Result<Self, StoreError> {
+        let (files, avisos) = validation::get_files(dir)?;
+
+        Ok(Store { avisos, ..Store::from_files(files) })
+    }
+
+    pub fn create(dir: &Path) -> Result<Self, StoreError> {
+        validation::create_store(dir)?;
+
+        Store::open(dir)
+    }
+
+    // Migrações de formato e gravações interrompidas desfeitas ao abrir o estoque.
+    pub fn notices(&self) -> &[String] {
+        &self.avisos
+    }
+
+    pub fn products(&mut self) -> Result<Vec<Produto>, StoreError> {
+        Ok(self.produtos.list()?)
+    }
+
+    pub fn product(&mut self, id: u64) -> Result<Produto, StoreError> {
+        Ok(core::search_product_id(&mut self.produtos, id)?)
+    }
+
+    pub fn product_by_barcode(&mut self, barcode: &str) -> Result<Produto, StoreError> {
+        Ok(core::search_product_barcode(&mut self.produtos, barcode)?)
+    }
+
+    pub fn search_products(&mut self, filtro: &FiltroProdutos) -> Result<Vec<Produto>, StoreError> {
+        Ok(core::search_products(&mut self.produtos, filtro)?)
+    }
+
+    pub fn categories(&mut self) -> Result<Vec<Categoria>, StoreError> {
+        Ok(self.produtos.categorias.list()?)
+    }
+
+    pub fn add_category(&mut self, nome: &str, pai: Option<u64>) -> Result<Categoria, StoreError> {
+        Ok(core::create_category(&mut self.produtos, &mut self.transacao, nome, pai)?)
+    }
+
+    pub fn rename_category(&mut self, id: u64, nome: &str) -> Result<Categoria, StoreError> {
+        Ok(core::rename_category(&mut self.produtos, &mut self.transacao, id, nome)?)
+    }
+
+    // Devolve quantos produtos passaram para a categoria de destino.
+    pub fn merge_categories(&mut self, from: u64, into: u64) -> Result<usize, StoreError> {
+        Ok(core::merge_categories(&mut self.produtos, &mut self.transacao, from, into)?)
+    }
+
+    pub fn remove_category(&mut self, id: u64) -> Result<(), StoreError> {
+        Ok(core::delete_category(&mut self.produtos, &mut self.transacao, id)?)
+    }
+
+    pub fn promotions(&mut self) -> Result<Vec<Promocao>, StoreError> {
+        Ok(self.produtos.promotions()?)
+    }
+
+    // As vendas já registradas mantêm os descontos das promoções removidas.
+    pub fn add_promotion(&mut self, promotion: Promocao) -> Result<Promocao, StoreError> {
+        Ok(core::create_promotion(&mut self.produtos, &mut self.transacao, promotion)?)
+    }
+
+    pub fn remove_promotion(&mut self, id: u64) -> Result<(), StoreError> {
+        Ok(core::delete_promotion(&mut self.produtos, &mut self.transacao, id)?)
+    }
+
+    pub fn add_product(&mut self, product: Produto, operator: &str) -> Result<u64, StoreError> {
+        Ok(core::insert_product(&mut self.produtos, &mut self.transacao, product, operator)?)
+    }
+
+    pub fn update_product(&mut self, id: u64, product: Produto, operator: &str) -> Result<Produto, StoreError> {
+        core::replace_product(&mut self.produtos, &mut self.transacao, id, product, operator)?;
+
+        self.product(id)
+    }
+
+    pub fn set_product_taxes(&mut self, id: u64, tributacao: Option<Tributacao>) -> Result<Produto, StoreError> {
+        Ok(core::set_product_taxes(&mut self.produtos, id, tributacao)?)
+    }
+
+    pub fn remove_product(&mut self, id: u64, operator: &str) -> Result<(), StoreError> {
+        Ok(core::delete_product(&mut self.produtos, &mut self.transacao, id, operator)?)
+    }
+
+    pub fn receive_stock(&mut self, supplier: &str, items: &[validation::ItemRecebido], operator: &str) -> Result<Vec<Produto>, StoreError> {
+        Ok(core::receive_stock(&mut self.produtos, &mut self.transacao, supplier, items, operator)?)
+    }
+
+    pub fn adjust_stock(&mut self, id: u64, tipo: TipoMovimento, quantity: i64, operator: &str, reason: &str) -> Result<Produto, StoreError> {
+        Ok(core::adjust_stock(&mut self.produtos, &mut self.transacao, id, tipo, quantity, operator, reason)?)
+    }
+
+    pub fn movements(&mut self, id: u64) -> Result<Vec<Movimento>, StoreError> {
+        Ok(self.produtos.movimentos.by_product(id)?)
+    }
+
+    // Produtos com o estoque gravado e o saldo recalculado pelos movimentos, quando diferem.
+    pub fn stock_divergences(&mut self) -> Result<Vec<(Produto, i64)>, StoreError> {
+        Ok(core::stock_divergences(&mut self.produtos)?)
+    }
+
+    pub fn fix_stock(&mut self) -> Result<Vec<Produto>, StoreError> {
+        let divergences = core::stock_divergences(&mut self.produtos)?;
+
+        Ok(core::fix_divergences(&mut self.produtos, divergences)?)
+    }
+
+    pub fn restock_suggestions(&mut self, parametros: &orders::Parametros) -> Result<Vec<orders::Sugestao>, StoreError> {
+        Ok(core::restock_suggestions(&mut self.produtos, &mut self.vendas, parametros)?)
+    }
+
+    // A venda fica vinculada ao caixa aberto, se houver um, e recebe as promoções vigentes.
+    pub fn register_sale(&mut self, seller: &str, cesta: &Cesta, payment_method: MetodoPagamento) -> Result<Venda, StoreError> {
+        let session = self.caixas.current()?.map(|session| session.id());
+
+        Ok(core::complete_sale(&mut self.produtos, &mut self.vendas, &mut self.transacao, seller.to_string(), session, cesta, payment_method)?)
+    }
+
+    pub fn sale(&mut self, code: u64) -> Result<Venda, StoreError> {
+        Ok(core::search_sale_code(&mut self.vendas, code)?)
+    }
+
+    pub fn sale_taxes(&mut self, code: u64) -> Result<Vec<TributosItem>, StoreError> {
+        Ok(taxes::sale_taxes(&self.sale(code)?)?)
+    }
+
+    // O cupom não fiscal em texto, como aparece na tela.
+    pub fn receipt(&mut self, code: u64, loja: &Loja) -> Result<String, StoreError> {
+        Ok(receipt::render_text(&self.sale(code)?, loja)?)
+    }
+
+    pub fn sales(&mut self) -> Result<Vec<Venda>, StoreError> {
+        Ok(self.vendas.list()?)
+    }
+
+    pub fn sales_by_date(&mut self, date: chrono::NaiveDate) -> Result<Vec<Venda>, StoreError> {
+        Ok(self.vendas.by_date(date)?)
+    }
+
+    pub fn sales_by_product(&mut self, id: u64) -> Result<Vec<Venda>, StoreError> {
+        Ok(self.vendas.by_product(id)?)
+    }
+
+    pub fn edit_sale(&mut self, code: u64, date: chrono::NaiveDate, payment_method: MetodoPagamento) -> Result<Venda, StoreError> {
+        core::edit_sale(&mut self.vendas, code, date, payment_method)?;
+
+        self.sale(code)
+    }
+
+    pub fn change_sale_items(&mut self, code: u64, items: &[(u64, u64)], operator: &str) -> Result<Venda, StoreError> {
+        Ok(core::change_sale_items(&mut self.produtos, &mut self.vendas, &mut self.transacao, code, items, operator)?)
+    }
+
+    pub fn cancel_sale(&mut self, code: u64, reason: &str, operator: &str) -> Result<Venda, StoreError> {
+        Ok(core::void_sale(&mut self.produtos, &mut self.vendas, &mut self.transacao, code, reason.to_string(), operator)?)
+    }
+
+    pub fn return_items(&mut self, code: u64, items: &[(u64, u64)], reason: &str, operator: &str) -> Result<Venda, StoreError> {
+        Ok(core::return_items(&mut self.produtos, &mut self.vendas, &mut self.transacao, code, items, reason.to_string(), operator)?)
+    }
+
+    pub fn sales_report(&mut self, agrupamento: reports::Agrupamento, from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Result<reports::Relatorio, StoreError> {
+        Ok(reports::sales_report(&mut self.vendas, agrupamento, from, to)?)
+    }
+
+    pub fn open_session(&mut self, operator: &str, float: Dinheiro) -> Result<Caixa, StoreError> {
+        Ok(core::start_session(&mut self.caixas, validation::validate_name(operator)?, float)?)
+    }
+
+    pub fn current_session(&mut self) -> Result<Option<Caixa>, StoreError> {
+        Ok(self.caixas.current()?)
+    }
+
+    pub fn close_session(&mut self, counted: Dinheiro) -> Result<Caixa, StoreError> {
+        Ok(core::end_session(&mut self.caixas, &mut self.vendas, counted)?)
+    }
+
+    pub fn session(&mut self, id: u64) -> Result<Caixa, StoreError> {
+        Ok(self.caixas.search(id)?)
+    }
+
+    pub fn sessions(&mut self) -> Result<Vec<Caixa>, StoreError> {
+        Ok(self.caixas.list()?)
+    }
+
+    // Dinheiro esperado na gaveta e as vendas do caixa por método de pagamento.
+    pub fn session_summary(&mut self, session: &Caixa) -> Result<(Dinheiro, reports::Relatorio), StoreError> {
+        Ok(core::session_summary(&mut self.vendas, session)?)
+    }
+
+    pub fn create_purchase_orders(&mut self, parametros: &orders::Parametros) -> Result<Vec<Pedido>, StoreError> {
+        Ok(core::create_purchase_orders(&mut self.produtos, &mut self.vendas, &mut self.pedidos, parametros)?)
+    }
+
+    pub fn order(&mut self, id: u64) -> Result<Pedido, StoreError> {
+        Ok(self.pedidos.search(id)?)
+    }
+
+    pub fn orders(&mut self) -> Result<Vec<Pedido>, StoreError> {
+        Ok(self.pedidos.list()?)
+    }
+
+    pub fn receive_order(&mut self, id: u64, supplier: Option<&str>, costs: &[(u64, Dinheiro)], operator: &str) -> Result<Pedido, StoreError> {
+        Ok(core::complete_order(&mut self.produtos, &mut self.pedidos, &mut self.transacao, id, supplier.map(String::from), costs, operator)?)
+    }
+
+    pub fn export_products(&mut self) -> Result<String, StoreError> {
+        Ok(csv::export_products(&self.produtos.list()?, &self.produtos.categorias.list()?))
+    }
+
+    pub fn export_sales(&mut self) -> Result<String, StoreError> {
+        Ok(csv::export_sales(&self.vendas.list()?))
+    }
+
+    // Devolve os ids atribuídos (ou que seriam atribuídos, com dry_run) aos produtos do arquivo.
+    pub fn import_products(&mut self, text: &str, dry_run: bool, operator: &str) -> Result<Vec<u64>, StoreError> {
+        Ok(core::import_products(&mut self.produtos, &mut self.transacao, text, dry_run, operator)?)
+    }
+
+    pub fn import_sales(&mut self, text: &str, dry_run: bool) -> Result<Vec<u64>, StoreError> {
+        Ok(core::import_sales(&mut self.produtos, &mut self.vendas, &mut self.transacao, text, dry_run)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{errors::CustomErrors, money::Aliquota, test_utils::test_path, categories, Desconto, Regra, Situacao};
+
+    fn product(nome: &str, estoque: u64, centavos: i64) -> Produto {
+        Produto::new(nome.to_string(), 0, estoque, Dinheiro::from_centavos(centavos), 2, chrono::NaiveDate::default(), categories::GERAL)
+    }
+
+    #[test]
+    fn test_store_operations() {
+        let dir = test_path("test_store_operations");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut store = Store::create(&dir).unwrap();
+
+        assert!(store.notices().is_empty());
+        assert_eq!(store.add_product(product("Caneta", 10, 250), "Ana").unwrap(), 1);
+        assert_eq!(store.add_product(product("Caderno", 5, 1500), "Ana").unwrap(), 2);
+
+        store.open_session("Ana", Dinheiro::from_centavos(1000)).unwrap();
+
+        let sale = store.register_sale("Ana", &Cesta::new(vec![(1, 4), (2, 1)]), MetodoPagamento::Dinheiro).unwrap();
+
+        assert_eq!(sale.codigo(), 1);
+        assert_eq!(sale.valor(), Dinheiro::from_centavos(2500));
+        assert_eq!(sale.caixa(), Some(1));
+        assert_eq!(store.product(1).unwrap().quantidade_estoque(), 6);
+
+        let error = store.register_sale("Ana", &Cesta::new(vec![(2, 9)]), MetodoPagamento::Pix).unwrap_err();
+        assert!(matches!(error, StoreError::Operation(CustomErrors::LowStock)));
+
+        let sale = store.return_items(1, &[(1, 2)], "defeito", "Ana").unwrap();
+        assert_eq!(sale.situacao(), Situacao::DevolucaoParcial);
+        assert_eq!(sale.devolucoes()[0].itens(), &[(1, 2)]);
+
+        let session = store.close_session(Dinheiro::from_centavos(3000)).unwrap();
+        assert_eq!(session.fechamento().unwrap().esperado(), Dinheiro::from_centavos(3000));
+
+        let report = store.sales_report(reports::Agrupamento::Vendedor, None, None).unwrap();
+        assert_eq!(report.receita, Dinheiro::from_centavos(2000));
+
+        let product = store.adjust_stock(2, TipoMovimento::Perda, -1, "Bia", "avaria").unwrap();
+        assert_eq!(product.quantidade_estoque(), 3);
+        assert_eq!(store.movements(2).unwrap().last().unwrap().operador(), "Bia");
+        assert!(store.stock_divergences().unwrap().is_empty());
+
+        let error = store.import_products("nome,estoque,valor,restoque,data,categoria\nLapis,x,1,1,10/08/2023,geral\n", false, "Ana").unwrap_err();
+        assert!(matches!(error, StoreError::Import(ref lines) if lines[0].0 == 2));
+
+        assert!(matches!(store.sale(9).unwrap_err(), StoreError::Operation(CustomErrors::SaleNotFound)));
+        assert_eq!(store.products().unwrap().len(), 2);
+
+        let mut caderno = store.product(2).unwrap();
+        caderno.codigo_barras = Some("7891234567895".to_string());
+        store.update_product(2, caderno, "Ana").unwrap();
+        assert_eq!(store.product_by_barcode("7891234567895").unwrap().nome(), "Caderno");
+
+        let filtro = FiltroProdutos { nome: Some("CADERNO".to_string()), ..FiltroProdutos::default() };
+        assert_eq!(store.search_products(&filtro).unwrap()[0].id(), 2);
+
+        let promotion = store.add_promotion(Promocao::new(0, "Leve 3 canetas".to_string(), Regra::LevePague { produto: 1, leve: 3, pague: 2 }, None, None)).unwrap();
+        assert_eq!(promotion.id(), 1);
+
+        let error = store.add_promotion(Promocao::new(0, "Sem desconto".to_string(), Regra::Categoria { categoria: categories::GERAL, percentual: 0 }, None, None)).unwrap_err();
+        assert!(matches!(error, StoreError::Operation(CustomErrors::InvalidPromotion)));
+
+        let mut cesta = Cesta::new(vec![(1, 3)]);
+        cesta.desconto = Some(Desconto::Fixo(Dinheiro::from_centavos(100)));
+
+        let sale = store.register_sale("Bia", &cesta, MetodoPagamento::Pix).unwrap();
+        assert_eq!(sale.valor(), Dinheiro::from_centavos(400));
+        assert_eq!(sale.descontos().unwrap(), Dinheiro::from_centavos(350));
+
+        store.remove_promotion(1).unwrap();
+        assert!(store.promotions().unwrap().is_empty());
+
+        let tributacao = Tributacao::new("48201000".to_string(), "5102".to_string(), Aliquota::from_centesimos(1800), Aliquota::ZERO, Aliquota::ZERO);
+        assert_eq!(store.set_product_taxes(2, Some(tributacao)).unwrap().tributacao().unwrap().ncm(), "48201000");
+
+        let sale = store.register_sale("Bia", &Cesta::new(vec![(2, 1)]), MetodoPagamento::Pix).unwrap();
+        assert_eq!(store.sale_taxes(sale.codigo()).unwrap()[0].icms, Dinheiro::from_centavos(270));
+
+        let loja = Loja { nome: "Papelaria Central".to_string(), ..Loja::default() };
+        assert!(store.receipt(sale.codigo(), &loja).unwrap().contains("Papelaria Central"));
+        assert!(matches!(store.receipt(9, &loja).unwrap_err(), StoreError::Operation(CustomErrors::SaleNotFound)));
+
+        drop(store);
+
+        let mut store = Store::open(&dir).unwrap();
+        assert_eq!(store.sales().unwrap().len(), 3);
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
+    }
+}
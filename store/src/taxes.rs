@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+use super::{errors::CustomErrors, money::Dinheiro, promotions, Tributacao, Venda};
+
+// Tributos de uma linha da venda, calculados sobre a base da linha com as alíquotas gravadas nela.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct TributosItem {
+    pub produto: u64,
+    pub base: Dinheiro,
+    pub icms: Dinheiro,
+    pub pis: Dinheiro,
+    pub cofins: Dinheiro
+}
+
+impl TributosItem {
+    fn new(produto: u64, base: Dinheiro, tributacao: &Tributacao) -> Result<Self, CustomErrors> {
+        Ok(TributosItem {
+            produto,
+            base,
+            icms: tributacao.icms.apply(base)?,
+            pis: tributacao.pis.apply(base)?,
+            cofins: tributacao.cofins.apply(base)?
+        })
+    }
+
+    pub fn total(&self) -> Result<Dinheiro, CustomErrors> {
+        self.icms.checked_add(self.pis)?.checked_add(self.cofins)
+    }
+}
+
+// A base de cada linha é o valor líquido menos a parte proporcional do desconto sobre o total.
+// A última linha fica com a diferença do arredondamento, para que as bases somem o valor da venda.
+fn bases(sale: &Venda) -> Result<Vec<Dinheiro>, CustomErrors> {
+    let total = promotions::lines_total(sale)?;
+    let mut remaining = sale.desconto;
+    let mut bases = Vec::with_capacity(sale.produtos.len());
+
+    for (index, item) in sale.produtos.iter().enumerate() {
+        let value = item.valor_liquido()?;
+
+        let share = match index + 1 == sale.produtos.len() || total == Dinheiro::ZERO {
+            true => remaining,
+            false => value.proportion(sale.desconto.centavos() as u64, total.centavos() as u64)?.min(remaining)
+        };
+
+        remaining = remaining.checked_sub(share)?;
+        bases.push(value.checked_sub(share)?);
+    }
+
+    Ok(bases)
+}
+
+// Linhas sem dados fiscais, como as de vendas anteriores a eles, ficam de fora.
+pub fn sale_taxes(sale: &Venda) -> Result<Vec<TributosItem>, CustomErrors> {
+    let mut taxes = Vec::new();
+
+    for (item, base) in sale.produtos.iter().zip(bases(sale)?) {
+        if let Some(tributacao) = &item.tributacao {
+            taxes.push(TributosItem::new(item.id, base, tributacao)?);
+        }
+    }
+
+    Ok(taxes)
+}
+
+pub fn total_taxes(taxes: &[TributosItem]) -> Result<Dinheiro, CustomErrors> {
+    taxes.iter().try_fold(Dinheiro::ZERO, |total, item| total.checked_add(item.total()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{money::Aliquota, ItemVenda, MetodoPagamento};
+
+    fn tributacao(icms: u64) -> Tributacao {
+        Tributacao::new("10063021".to_string(), "5102".to_string(), Aliquota::from_centesimos(icms), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760))
+    }
+
+    #[test]
+    fn test_sale_taxes() {
+        let mut sale = Venda::new("Ana".to_string(), 1, Dinheiro::ZERO, chrono::NaiveDate::default(), MetodoPagamento::Pix);
+
+        let mut arroz = ItemVenda::new(1, "Arroz".to_string(), 3, Dinheiro::from_centavos(2000)).unwrap();
+        arroz.desconto = Dinheiro::from_centavos(600);
+        arroz.tributacao = Some(tributacao(1800));
+
+        let mut cafe = ItemVenda::new(2, "Café".to_string(), 1, Dinheiro::from_centavos(1600)).unwrap();
+        cafe.tributacao = Some(tributacao(1200));
+
+        sale.produtos = vec![arroz, cafe, ItemVenda::new(3, "Sacola".to_string(), 1, Dinheiro::from_centavos(100)).unwrap()];
+        sale.desconto = Dinheiro::from_centavos(701);
+        sale.valor = Dinheiro::from_centavos(6399);
+
+        // A última linha fica com o que sobra do desconto: 701 - 533 - 158.
+        assert_eq!(bases(&sale).unwrap(), vec![Dinheiro::from_centavos(4867), Dinheiro::from_centavos(1442), Dinheiro::from_centavos(90)]);
+
+        let taxes = sale_taxes(&sale).unwrap();
+
+        assert_eq!(taxes.len(), 2);
+        assert_eq!(taxes[0], TributosItem { produto: 1, base: Dinheiro::from_centavos(4867), icms: Dinheiro::from_centavos(876), pis: Dinheiro::from_centavos(80), cofins: Dinheiro::from_centavos(370) });
+        assert_eq!((taxes[1].produto, taxes[1].icms), (2, Dinheiro::from_centavos(173)));
+        assert_eq!(total_taxes(&taxes).unwrap(), Dinheiro::from_centavos(1633));
+    }
+}
@@ -0,0 +1,163 @@
+use std::{env, fs::{self, File, OpenOptions}, io::{Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}};
+
+use crate::{categories::ArquivoCategorias, core, format::{self, Cabecalho}, ledger::ArquivoMovimentos, memory::{MemoriaProdutos, MemoriaVendas}, money::Dinheiro, orders::ArquivoPedidos, products::ArquivoProdutos, promotions::ArquivoPromocoes, repository::{ProductRepository, SaleRepository}, sales::ArquivoVendas, sessions::ArquivoCaixas, ItemVenda, Produto, Venda};
+
+pub fn test_path(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join("store-tests");
+    fs::create_dir_all(&dir).expect("Não foi possível criar o diretório de testes.");
+
+    dir.join(name)
+}
+
+pub fn get_test_file(path: &Path) -> File {
+    let _ = fs::remove_file(path);
+
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .expect("Não foi possível criar o arquivo.")
+}
+
+pub fn get_test_products(path: &Path) -> ArquivoProdutos {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::PRODUCTS_MAGIC, format::PRODUCTS_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoProdutos::new(file, get_test_file(&path.with_extension("idx")), get_test_movements(&path.with_extension("mov")), get_test_categories(&path.with_extension("cat")), get_test_promotions(&path.with_extension("prm")))
+}
+
+pub fn get_test_categories(path: &Path) -> ArquivoCategorias {
+    let mut categories = ArquivoCategorias::new(get_test_file(path));
+    categories.initialize().expect("Erro no arquivo de categorias.");
+
+    categories
+}
+
+pub fn get_test_promotions(path: &Path) -> ArquivoPromocoes {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::PROMOTIONS_MAGIC, format::PROMOTIONS_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoPromocoes::new(file)
+}
+
+pub fn get_test_movements(path: &Path) -> ArquivoMovimentos {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::MOVEMENTS_MAGIC, format::MOVEMENTS_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoMovimentos::new(file)
+}
+
+pub fn get_test_sales(path: &Path) -> ArquivoVendas {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::SALES_MAGIC, format::SALES_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoVendas::open(file, get_test_file(&path.with_extension("idx"))).expect("Erro no arquivo de vendas.")
+}
+
+pub fn get_test_sessions(path: &Path) -> ArquivoCaixas {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::SESSIONS_MAGIC, format::SESSIONS_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoCaixas::new(file, get_test_file(&path.with_extension("trn")))
+}
+
+pub fn get_test_orders(path: &Path) -> ArquivoPedidos {
+    let mut file = get_test_file(path);
+    format::write_header(&mut file, &Cabecalho::new(format::ORDERS_MAGIC, format::ORDERS_VERSION)).expect("Erro no arquivo.");
+
+    ArquivoPedidos::new(file, get_test_file(&path.with_extension("trn")))
+}
+
+pub fn set_test_products<P: ProductRepository>(products: &mut P) {
+    let product1 = Produto::new("Teste1".to_string(), 1, 10, Dinheiro::from_centavos(5000), 5, chrono::NaiveDate::default(), crate::categories::GERAL);
+    let product2 = Produto::new("Teste2".to_string(), 2, 15, Dinheiro::from_centavos(4000), 25, chrono::NaiveDate::default(), crate::categories::ALIMENTO);
+    let product3 = Produto::new("Teste3".to_string(), 3, 20, Dinheiro::from_centavos(6000), 10, chrono::NaiveDate::default(), crate::categories::ELETRONICO);
+
+    for product in [product1, product2, product3] {
+        products.insert(product).unwrap();
+    }
+}
+
+pub fn get_memory_products() -> MemoriaProdutos {
+    let mut products = MemoriaProdutos::new();
+
+    set_test_products(&mut products);
+    core::record_opening_balances(&mut products).unwrap();
+
+    products
+}
+
+fn test_sales() -> Vec<Venda> {
+    let sale1 = Venda::new("Venda1".to_string(), 1, Dinheiro::from_centavos(5000), chrono::NaiveDate::default(), crate::MetodoPagamento::Credito);
+    let sale2 = Venda::new("Venda2".to_string(), 2, Dinheiro::from_centavos(7000), chrono::NaiveDate::default(), crate::MetodoPagamento::Dinheiro);
+    let mut sale3 = Venda::new("Venda3".to_string(), 3, Dinheiro::from_centavos(9000), chrono::NaiveDate::default(), crate::MetodoPagamento::Pix);
+
+    sale3.produtos.push(ItemVenda::new(1, "Teste1".to_string(), 2, Dinheiro::from_centavos(4500)).unwrap());
+
+    vec![sale1, sale2, sale3]
+}
+
+pub fn set_test_sales(sales: &mut ArquivoVendas) {
+    let file = &mut sales.dados;
+
+    file.seek(SeekFrom::End(0)).unwrap();
+
+    for sale in test_sales() {
+        let buf = bincode::serialize(&sale).unwrap();
+
+        file.write_all(&bincode::serialize(&(buf.len() as u64)).unwrap()).unwrap();
+        file.write_all(&buf).unwrap();
+    }
+
+    let mut header = Cabecalho::new(format::SALES_MAGIC, format::SALES_VERSION);
+    header.quantidade = 3;
+    header.ultimo_id = 3;
+
+    format::write_header(file, &header).unwrap();
+
+    sales.rebuild_index().unwrap();
+}
+
+pub fn get_memory_sales() -> MemoriaVendas {
+    let mut sales = MemoriaVendas::new();
+
+    for sale in test_sales() {
+        sales.import(sale).unwrap();
+    }
+
+    sales
+}
+
+pub fn remove_test_sales(path: &Path) {
+    fs::remove_file(path).expect("Erro ao tentar excluir o arquivo de vendas.");
+    fs::remove_file(path.with_extension("idx")).expect("Erro ao tentar excluir o índice de vendas.");
+}
+
+pub fn remove_test_sessions(path: &Path) {
+    fs::remove_file(path).expect("Erro ao tentar excluir o arquivo de caixas.");
+    fs::remove_file(path.with_extension("trn")).expect("Erro ao tentar excluir o diário de caixas.");
+}
+
+pub fn remove_test_orders(path: &Path) {
+    fs::remove_file(path).expect("Erro ao tentar excluir o arquivo de pedidos.");
+    fs::remove_file(path.with_extension("trn")).expect("Erro ao tentar excluir o diário de pedidos.");
+}
+
+pub fn remove_test_products(path: &Path) {
+    fs::remove_file(path).expect("Erro ao tentar excluir o arquivo de produtos.");
+    fs::remove_file(path.with_extension("idx")).expect("Erro ao tentar excluir o índice de produtos.");
+    fs::remove_file(path.with_extension("mov")).expect("Erro ao tentar excluir os movimentos de estoque.");
+    fs::remove_file(path.with_extension("cat")).expect("Erro ao tentar excluir as categorias.");
+    fs::remove_file(path.with_extension("prm")).expect("Erro ao tentar excluir as promoções.");
+}
+
+pub fn read_all(file: &mut File) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    file.seek(SeekFrom::Start(0)).expect("Erro no arquivo.");
+    file.read_to_end(&mut buf).expect("Erro no arquivo.");
+
+    buf
+}
@@ -0,0 +1,46 @@
+use chrono::NaiveDateTime;
+use serde::{self, Deserialize, Serializer, Deserializer};
+
+const FORMAT: &str = "%d/%m/%Y %H:%M:%S";
+
+pub fn serialize<S>(timestamp: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer
+{
+    let s = format!("{}", timestamp.format(FORMAT));
+    serializer.serialize_str(&s)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>
+{
+    let s = String::deserialize(deserializer)?;
+    let dt = NaiveDateTime::parse_from_str(&s, FORMAT)
+        .map_err(serde::de::Error::custom)?;
+
+    Ok(dt)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Serialize, Deserialize};
+    use chrono::NaiveDateTime;
+
+    #[derive(Serialize, Deserialize)]
+    struct Registro {
+        #[serde(with = "super")]
+        timestamp: NaiveDateTime
+    }
+
+    #[test]
+    fn test_serialize_deserialize_timestamp() {
+        let timestamp = chrono::NaiveDate::from_ymd_opt(2024, 3, 15).unwrap().and_hms_opt(14, 30, 5).unwrap();
+        let record = Registro { timestamp };
+
+        let serialized = bincode::serialize(&record).unwrap();
+        let deserialized: Registro = bincode::deserialize(&serialized).unwrap();
+
+        assert_eq!(record.timestamp, deserialized.timestamp);
+    }
+}
@@ -1,242 +1,478 @@
-use super::{Produto, Categoria, MetodoPagamento, errors};
-use std::{error::Error, fs::{File, OpenOptions}, io::{self, BufRead}, process};
-
-pub fn get_files() -> (File, File) {
-    (
+use super::{Produto, Categoria, Desconto, MetodoPagamento, TipoMovimento, Tributacao, categories::{self, ArquivoCategorias}, core, errors, format::{self, Cabecalho}, journal, ledger::ArquivoMovimentos, migration, money::{Aliquota, Dinheiro}, orders::ArquivoPedidos, products::ArquivoProdutos, promotions::ArquivoPromocoes, sales::ArquivoVendas, sessions::ArquivoCaixas};
+use std::{error::Error, fs::{self, File, OpenOptions}, io, path::Path};
+
+pub const PRODUCTS_FILE: &str = "produtos.bin";
+pub const PRODUCTS_INDEX_FILE: &str = "produtos.idx";
+pub const SALES_FILE: &str = "vendas.bin";
+pub const SALES_INDEX_FILE: &str = "vendas.idx";
+pub const SESSIONS_FILE: &str = "caixas.bin";
+pub const MOVEMENTS_FILE: &str = "movimentos.bin";
+pub const ORDERS_FILE: &str = "pedidos.bin";
+pub const CATEGORIES_FILE: &str = "categorias.bin";
+pub const PROMOTIONS_FILE: &str = "promocoes.bin";
+pub const JOURNAL_FILE: &str = "transacao.bin";
+pub const SESSIONS_JOURNAL_FILE: &str = "transacao_caixas.bin";
+pub const ORDERS_JOURNAL_FILE: &str = "transacao_pedidos.bin";
+
+const INTERRUPTED_NOTICE: &str = "Uma gravação interrompida foi encontrada e desfeita para manter os arquivos consistentes.";
+
+fn open_file(path: &Path, create: bool) -> io::Result<File> {
     OpenOptions::new()
         .read(true)
         .write(true)
-        .create(true)
-        .open("produtos.bin")
-        .unwrap_or_else(|error| {
-            eprintln!("\nOcorreu um erro tentando abrir o arquivo: {error}\n");
-            process::exit(1);
-        }),
-    OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open("vendas.bin")
-        .unwrap_or_else(|error| {
-            eprintln!("\nOcorreu um erro tentando abrir o arquivo: {error}\n");
-            process::exit(1);
-        })
-    )
+        .create(create)
+        .truncate(false)
+        .open(path)
 }
 
-pub fn get_option() -> u64 {
-    loop {
-        super::screens::menu_screen();
+pub fn create_store(dir: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
 
-        let mut buf = String::new();
-        if let Err(error) = io::stdin().read_line(&mut buf) {
-            eprintln!("\nOcorreu um erro ao tentar ler a opção selecionada: {error}\nCertifique-se de ter inserido corretamente.\n");
-            continue;
-        };
+    if dir.join(PRODUCTS_FILE).exists() || dir.join(SALES_FILE).exists() {
+        return Err(Box::new(errors::CustomErrors::StoreAlreadyExists));
+    }
 
-        if buf.trim().to_lowercase() == "sair" {
-            return 0;
+    let headers = [
+        (PRODUCTS_FILE, Some(Cabecalho::new(format::PRODUCTS_MAGIC, format::PRODUCTS_VERSION))),
+        (PRODUCTS_INDEX_FILE, None),
+        (SALES_FILE, Some(Cabecalho::new(format::SALES_MAGIC, format::SALES_VERSION))),
+        (SALES_INDEX_FILE, None),
+        (SESSIONS_FILE, Some(Cabecalho::new(format::SESSIONS_MAGIC, format::SESSIONS_VERSION))),
+        (MOVEMENTS_FILE, Some(Cabecalho::new(format::MOVEMENTS_MAGIC, format::MOVEMENTS_VERSION))),
+        (ORDERS_FILE, Some(Cabecalho::new(format::ORDERS_MAGIC, format::ORDERS_VERSION))),
+        (CATEGORIES_FILE, None),
+        (PROMOTIONS_FILE, Some(Cabecalho::new(format::PROMOTIONS_MAGIC, format::PROMOTIONS_VERSION)))
+    ];
+
+    for (path, header) in headers {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dir.join(path))?;
+
+        if let Some(header) = header {
+            format::write_header(&mut file, &header)?;
         }
+    }
+
+    ArquivoCategorias::new(open_file(&dir.join(CATEGORIES_FILE), false)?).initialize()
+}
+
+pub type Arquivos = (ArquivoProdutos, ArquivoVendas, ArquivoCaixas, ArquivoPedidos, File);
+
+pub type ArquivosCompartilhados = (ArquivoCaixas, ArquivoPedidos);
 
-        let option: u64 = match buf.trim().parse() {
-            Ok(value) => value,
-            Err(error) => {
-                eprintln!("\nOcorreu um erro ao tentar ler a opção selecionada: {error}\nCertifique-se de ter inserido corretamente.\n");
-                continue;
-            }
-        };
+pub fn get_shared_files(dir: &Path) -> Result<(ArquivosCompartilhados, Vec<String>), Box<dyn Error>> {
+    let mut notices = Vec::new();
+    let files = load_shared_files(dir, &mut notices)?;
 
-        return option;
+    Ok((files, notices))
+}
+
+// Caixas e pedidos ficam nos arquivos mesmo depois da migração de produtos e vendas para o SQLite.
+fn load_shared_files(dir: &Path, notices: &mut Vec<String>) -> Result<ArquivosCompartilhados, Box<dyn Error>> {
+    let mut sessions = ArquivoCaixas::new(open_file(&dir.join(SESSIONS_FILE), true)?, open_file(&dir.join(SESSIONS_JOURNAL_FILE), true)?);
+    let mut orders = ArquivoPedidos::new(open_file(&dir.join(ORDERS_FILE), true)?, open_file(&dir.join(ORDERS_JOURNAL_FILE), true)?);
+
+    let restored = [
+        journal::restore_file(&mut sessions.diario, &mut sessions.dados)?,
+        journal::restore_file(&mut orders.diario, &mut orders.dados)?
+    ];
+
+    if restored.contains(&true) {
+        notices.push(INTERRUPTED_NOTICE.to_string());
+    }
+
+    // Estoques criados antes dos caixas não possuem o arquivo.
+    if sessions.dados.metadata()?.len() == 0 {
+        format::write_header(&mut sessions.dados, &Cabecalho::new(format::SESSIONS_MAGIC, format::SESSIONS_VERSION))?;
+    }
+
+    if orders.dados.metadata()?.len() == 0 {
+        format::write_header(&mut orders.dados, &Cabecalho::new(format::ORDERS_MAGIC, format::ORDERS_VERSION))?;
     }
+
+    let upgrades = [
+        (&mut sessions.dados, SESSIONS_FILE, &migration::SESSIONS),
+        (&mut orders.dados, ORDERS_FILE, &migration::ORDERS)
+    ];
+
+    upgrade_files(dir, upgrades, notices)?;
+
+    Ok((sessions, orders))
 }
 
-pub fn validate_string<R: BufRead>(reader: &mut R) -> Result<String, errors::CustomErrors> {
-    loop {
-        let mut buf = String::new();
-        if let Err(error) = reader.read_line(&mut buf) {
-            eprintln!("\nUm erro ocorreu na leitura: {error}\n");
-            continue;
+fn upgrade_files<const N: usize>(dir: &Path, upgrades: [(&mut File, &str, &migration::Formato); N], notices: &mut Vec<String>) -> Result<(), Box<dyn Error>> {
+    for (file, name, formato) in upgrades {
+        if let Some(backup) = migration::upgrade(file, &dir.join(name), formato)? {
+            notices.push(format!("O arquivo {name} foi atualizado para o formato atual. A versão anterior foi salva em {}.", backup.display()));
         }
+    }
 
-        if buf.trim().to_lowercase() == "sair" {
-            return Err(errors::CustomErrors::OperationCanceled);
-        }
+    Ok(())
+}
+
+// Abre os arquivos do estoque e devolve, junto com eles, os avisos sobre migrações e vendas desfeitas.
+pub fn get_files(dir: &Path) -> Result<(Arquivos, Vec<String>), Box<dyn Error>> {
+    let mut notices = Vec::new();
 
-        return Ok(buf.trim().to_string());
+    if !dir.join(PRODUCTS_FILE).exists() || !dir.join(SALES_FILE).exists() {
+        return Err(Box::new(errors::CustomErrors::StoreNotFound));
     }
-}
 
-pub fn validate_search<R: BufRead>(search: &str, reader: &mut R) -> Result<u64, errors::CustomErrors> {
+    let movements = ArquivoMovimentos::new(open_file(&dir.join(MOVEMENTS_FILE), true)?);
+    let mut categories = ArquivoCategorias::new(open_file(&dir.join(CATEGORIES_FILE), true)?);
 
-    match search {
-        "id" => println!("\nDigite o ID do produto (ou sair para cancelar a operação):"),
-        _ => println!("\nDigite o código da venda (ou sair para cancelar a operação):")
+    // Estoques anteriores às categorias cadastradas começam com as quatro categorias fixas.
+    if categories.dados.metadata()?.len() == 0 {
+        categories.initialize()?;
     }
 
-    loop {
-        let buf = validate_string(reader)?;
+    let mut promotions = ArquivoPromocoes::new(open_file(&dir.join(PROMOTIONS_FILE), true)?);
+
+    if promotions.dados.metadata()?.len() == 0 {
+        format::write_header(&mut promotions.dados, &Cabecalho::new(format::PROMOTIONS_MAGIC, format::PROMOTIONS_VERSION))?;
+    }
+
+    let mut products = ArquivoProdutos::new(open_file(&dir.join(PRODUCTS_FILE), false)?, open_file(&dir.join(PRODUCTS_INDEX_FILE), true)?, movements, categories, promotions);
+    let mut sales_file = open_file(&dir.join(SALES_FILE), false)?;
+    let (sessions, orders) = load_shared_files(dir, &mut notices)?;
+    let mut journal_file = open_file(&dir.join(JOURNAL_FILE), true)?;
+
+    let new_ledger = products.movimentos.dados.metadata()?.len() == 0;
+
+    if new_ledger {
+        format::write_header(&mut products.movimentos.dados, &Cabecalho::new(format::MOVEMENTS_MAGIC, format::MOVEMENTS_VERSION))?;
+    }
+
+    // Uma gravação desfeita pode ter deixado o índice dos produtos apontando para posições antigas.
+    let rolled_back = journal::rollback(&mut journal_file, &mut products, &mut sales_file)?;
+
+    if rolled_back {
+        notices.push(INTERRUPTED_NOTICE.to_string());
+    }
+
+    let upgrades = [
+        (&mut products.dados, PRODUCTS_FILE, &migration::PRODUCTS),
+        (&mut sales_file, SALES_FILE, &migration::SALES),
+        (&mut products.movimentos.dados, MOVEMENTS_FILE, &migration::MOVEMENTS),
+        (&mut products.categorias.dados, CATEGORIES_FILE, &migration::CATEGORIES),
+        (&mut products.promocoes.dados, PROMOTIONS_FILE, &migration::PROMOTIONS)
+    ];
+
+    upgrade_files(dir, upgrades, &mut notices)?;
+
+    if rolled_back || products.index_is_stale()? {
+        products.rebuild_index()?;
+    }
 
-        match validate_int(&buf) {
-            Ok(id) => return Ok(id),
-            Err(error) => eprintln!("\nUm erro ocorreu ao tentar converter o ID: {error}\nCertifique-se de que um valor válido foi inserido.\n")
-        };
+    // Estoques anteriores ao histórico começam com o saldo atual de cada produto.
+    if new_ledger {
+        core::record_opening_balances(&mut products)?;
     }
+
+    let sales = ArquivoVendas::open(sales_file, open_file(&dir.join(SALES_INDEX_FILE), true)?)?;
+
+    Ok(((products, sales, sessions, orders, journal_file), notices))
 }
 
-fn validate_int(string: &str) -> Result<u64, std::num::ParseIntError> {
+pub fn validate_int(string: &str) -> Result<u64, std::num::ParseIntError> {
     let number = string.parse::<u64>()?;
-    return Ok(number);
+    Ok(number)
 }
 
-fn validate_float(string: &str) -> Result<f64, std::num::ParseFloatError> {
-    let number = string.parse::<f64>()?;
-    return Ok(number);
+pub fn validate_money(string: &str) -> Result<Dinheiro, errors::CustomErrors> {
+    let value = string.parse::<Dinheiro>()?;
+    Ok(value)
 }
 
-pub fn get_product_info<R: BufRead>(reader: &mut R) -> Result<Produto, Box<dyn Error>> {
-    loop {
-        super::screens::add_product_screen();
+pub fn validate_name(string: &str) -> Result<String, errors::CustomErrors> {
+    match string.trim().is_empty() {
+        true => Err(errors::CustomErrors::EmptyName),
+        false => Ok(string.trim().to_string())
+    }
+}
 
-        let mut buf = String::new();
-        reader.read_line(&mut buf)?;
+// A categoria pode ser informada pelo id ou pelo nome, sem diferenciar maiúsculas, minúsculas ou acentos.
+pub fn validate_category(string: &str, categorias: &[Categoria]) -> Result<u64, errors::CustomErrors> {
+    categories::find(categorias, string)
+        .map(|categoria| categoria.id())
+        .ok_or(errors::CustomErrors::NoCategory)
+}
 
-        if buf.trim().to_lowercase() == "sair" {
-            return Err(Box::new(errors::CustomErrors::OperationCanceled));
-        }
+pub fn validate_product(input: Vec<&str>, categorias: &[Categoria]) -> Result<Produto, Box<dyn Error>> {
+    let nome = validate_name(input[0])?;
+    let quantidade_estoque = validate_int(input[1])?;
+    let valor = validate_money(input[2])?;
+    let quantidade_restoque = validate_int(input[3])?;
+    let data_restoque = chrono::NaiveDate::parse_from_str(input[4], "%d/%m/%Y")?;
 
-        let fields: Vec<&str> = buf.split(' ').map(|field| field.trim()).collect();
+    let categoria = validate_category(input[5], categorias)?;
 
-        if fields.len() != 6 {
-            eprintln!("\nNúmero incorreto de argumentos.\n");
-            continue;
-        }
+    let mut product = Produto::new(nome, 0, quantidade_estoque, valor, quantidade_restoque, data_restoque, categoria);
+    product.codigo_barras = input.get(6).map(|barcode| validate_barcode(barcode)).transpose()?;
 
-        match validate_product(fields) {
-            Ok(product) => return Ok(product),
-            Err(error) => eprintln!("\nUm erro ocorreu durante a conversão de argumentos: {error}\nVerifique se todos os campos foram inseridos corretamente.\n")
-        };
+    Ok(product)
+}
+
+fn is_barcode(string: &str) -> bool {
+    string.len() == 13 && string.bytes().all(|digit| digit.is_ascii_digit())
+}
+
+// EAN-13: o último dígito confere os doze anteriores, com pesos 1 e 3 alternados.
+pub fn validate_barcode(string: &str) -> Result<String, errors::CustomErrors> {
+    let barcode = string.trim();
+
+    if !is_barcode(barcode) {
+        return Err(errors::CustomErrors::InvalidBarcode);
+    }
+
+    let digits: Vec<u32> = barcode.bytes().map(|digit| (digit - b'0') as u32).collect();
+    let sum: u32 = digits[..12].iter().enumerate()
+        .map(|(position, digit)| if position % 2 == 0 { *digit } else { digit * 3 })
+        .sum();
+
+    match (10 - sum % 10) % 10 == digits[12] {
+        true => Ok(barcode.to_string()),
+        false => Err(errors::CustomErrors::InvalidBarcode)
     }
 }
 
-fn validate_product(input: Vec<&str>) -> Result<Produto, Box<dyn Error>> {
-    if input[0].len() > 40 {
-        return Err(Box::new(errors::CustomErrors::NameTooLong));
+// Os pontos da forma impressa (1006.30.21 ou 5.102) são ignorados.
+fn tax_code(string: &str, length: usize) -> Result<String, errors::CustomErrors> {
+    let code = string.trim().replace('.', "");
+
+    match code.len() == length && code.bytes().all(|digit| digit.is_ascii_digit()) {
+        true => Ok(code),
+        false => Err(errors::CustomErrors::InvalidTax)
     }
+}
 
-    let quantidade_estoque = validate_int(input[1])?;
-    let valor = validate_float(input[2])?;
-    let quantidade_restoque = validate_int(input[3])?;
-    let data_restoque = chrono::NaiveDate::parse_from_str(input[4], "%d/%m/%Y")?;
+pub fn validate_ncm(string: &str) -> Result<String, errors::CustomErrors> {
+    tax_code(string, 8)
+}
 
-    let categoria = match input[5].to_lowercase().as_str() {
-        "eletronico" => Categoria::Eletronico,
-        "roupa" => Categoria::Roupa,
-        "alimento" => Categoria::Alimento,
-        "geral" => Categoria::Geral,
-        _ => {
-            return Err(Box::new(errors::CustomErrors::NoCategory));
-        }
-    };
+// Só os CFOPs de saída (5 no estado, 6 fora dele e 7 no exterior) servem para a venda.
+pub fn validate_cfop(string: &str) -> Result<String, errors::CustomErrors> {
+    let cfop = tax_code(string, 4)?;
 
-    return Ok(Produto::new(input[0].to_string(), 0, quantidade_estoque, valor, quantidade_restoque, data_restoque, categoria));
+    match cfop.starts_with(['5', '6', '7']) {
+        true => Ok(cfop),
+        false => Err(errors::CustomErrors::InvalidTax)
+    }
 }
 
-pub fn get_sale_info<R: BufRead>(reader: &mut R) -> Result<(chrono::NaiveDate, MetodoPagamento), Box<dyn Error>> {
-    println!("\nDigite a data da venda seguindo o formato dd/mm/YYYY (ou digite 'sair' para cancelar):");
+pub fn parse_rate(string: &str) -> Result<Aliquota, errors::CustomErrors> {
+    string.parse::<Aliquota>()
+}
 
-    let date = validate_date(reader)?;
-    let payment_method = validate_payment_method(reader)?;
+// Dados fiscais montados aos poucos, como na edição pela linha de comando, precisam de NCM e CFOP.
+pub fn check_taxes(tributacao: &Tributacao) -> Result<(), errors::CustomErrors> {
+    validate_ncm(&tributacao.ncm)?;
+    validate_cfop(&tributacao.cfop)?;
 
-    return Ok((date, payment_method));
+    Ok(())
+}
+
+// NCM, CFOP e as alíquotas de ICMS, PIS e COFINS.
+pub fn validate_taxes(input: &[&str]) -> Result<Tributacao, errors::CustomErrors> {
+    let [ncm, cfop, icms, pis, cofins] = input else {
+        return Err(errors::CustomErrors::InvalidTax);
+    };
+
+    Ok(Tributacao::new(validate_ncm(ncm)?, validate_cfop(cfop)?, parse_rate(icms)?, parse_rate(pis)?, parse_rate(cofins)?))
 }
 
 pub fn validate_sale(string: &str) -> Result<(u64, u64), Box<dyn Error>> {
     let info: Vec<&str> = string.split_whitespace().collect();
-    let amount: u64;
-
-    match info.len() {
-        1 => amount = 1,
-        2 => amount = validate_int(info[1])?,
+    let amount = match info.len() {
+        1 => 1,
+        2 => validate_int(info[1])?,
         _ => return Err(Box::new(errors::CustomErrors::TooManyArguments))
-    }
+    };
 
     let id = validate_int(info[0])?;
 
     Ok((id, amount))
 }
 
-pub fn validate_payment_method<R: BufRead>(reader: &mut R) -> Result<MetodoPagamento, Box<dyn Error>> {
-    
-    println!("\nInsira a forma de pagamento:\n\nOpções: credito, debito, pix, dinheiro\n\n* Atenção: Não utilizar acento! *\n");
+// Produto da venda digitado pelo id ou lido pelo leitor de código de barras, com a quantidade.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemLido {
+    Id(u64, u64),
+    CodigoBarras(String, u64)
+}
+
+// O leitor digita os 13 dígitos do código seguidos de Enter; a quantidade pode vir depois, como no id.
+pub fn validate_scan(string: &str) -> Result<ItemLido, Box<dyn Error>> {
+    let info: Vec<&str> = string.split_whitespace().collect();
 
-    let mut buf = String::new();
+    match info.first() {
+        Some(barcode) if is_barcode(barcode) => {
+            let amount = match info.len() {
+                1 => 1,
+                2 => validate_int(info[1])?,
+                _ => return Err(Box::new(errors::CustomErrors::TooManyArguments))
+            };
 
-    reader.read_line(&mut buf)?;
-    
-    let payment_method = match buf.trim().to_lowercase().as_str() {
-        "credito" => MetodoPagamento::Credito,
-        "debito" => MetodoPagamento::Debito,
-        "pix" => MetodoPagamento::Pix,
-        "dinheiro" => MetodoPagamento::Dinheiro,
+            Ok(ItemLido::CodigoBarras(validate_barcode(barcode)?, amount))
+        },
         _ => {
-            return Err(Box::new(errors::CustomErrors::NoCategory));
+            let (id, amount) = validate_sale(string)?;
+            Ok(ItemLido::Id(id, amount))
         }
-    };
+    }
+}
 
-    return Ok(payment_method);
+// Porcentagem com o sinal de % (ex.: 10%) ou valor em reais (ex.: 2,50).
+pub fn parse_discount(string: &str) -> Result<Desconto, errors::CustomErrors> {
+    let string = string.trim();
+
+    match string.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<u64>()
+            .map(Desconto::Percentual)
+            .map_err(|_| errors::CustomErrors::InvalidDiscount),
+        None => match validate_money(string).map_err(|_| errors::CustomErrors::InvalidDiscount)? {
+            value if value.centavos() > 0 => Ok(Desconto::Fixo(value)),
+            _ => Err(errors::CustomErrors::InvalidDiscount)
+        }
+    }
 }
 
-pub fn validate_date<R: BufRead>(reader: &mut R) -> Result<chrono::NaiveDate, errors::CustomErrors> {
-    loop {
-        let buf = validate_string(reader)?;
+pub fn validate_line_discount(string: &str) -> Result<(u64, Desconto), Box<dyn Error>> {
+    let info: Vec<&str> = string.split_whitespace().collect();
 
-        match chrono::NaiveDate::parse_from_str(&buf, "%d/%m/%Y") {
-            Ok(date) => return Ok(date),
-            Err(error) => eprintln!("\nOcorreu um erro ao tentar ler a data informada: {error}\nCertifique-se de que a data está inserida no formato correto.\n")
-        }
+    if info.len() != 2 {
+        return Err(Box::new(errors::CustomErrors::TooManyArguments));
+    }
+
+    Ok((validate_int(info[0])?, parse_discount(info[1])?))
+}
+
+// Produto, quantidade recebida e custo unitário.
+pub type ItemRecebido = (u64, u64, Dinheiro);
+
+pub fn validate_receipt(string: &str) -> Result<ItemRecebido, Box<dyn Error>> {
+    let info: Vec<&str> = string.split_whitespace().collect();
+
+    if info.len() != 3 {
+        return Err(Box::new(errors::CustomErrors::TooManyArguments));
+    }
+
+    let cost = validate_money(info[2])?;
+
+    if cost.centavos() < 0 {
+        return Err(Box::new(errors::CustomErrors::InvalidValue));
+    }
+
+    Ok((validate_int(info[0])?, validate_int(info[1])?, cost))
+}
+
+pub fn parse_movement_type(string: &str) -> Result<TipoMovimento, errors::CustomErrors> {
+    match string.trim().to_lowercase().as_str() {
+        "ajuste" => Ok(TipoMovimento::Ajuste),
+        "perda" => Ok(TipoMovimento::Perda),
+        _ => Err(errors::CustomErrors::InvalidCommand)
+    }
+}
+
+// Perdas são informadas em unidades e sempre retiram do estoque.
+pub fn parse_adjustment(tipo: TipoMovimento, string: &str) -> Result<i64, errors::CustomErrors> {
+    let quantity: i64 = string.trim().parse().map_err(|_| errors::CustomErrors::InvalidAdjustment)?;
+
+    match (tipo, quantity) {
+        (_, 0) => Err(errors::CustomErrors::InvalidAdjustment),
+        (TipoMovimento::Perda, quantity) if quantity > 0 => Ok(-quantity),
+        (TipoMovimento::Perda, _) => Err(errors::CustomErrors::InvalidAdjustment),
+        (_, quantity) => Ok(quantity)
+    }
+}
+
+pub fn parse_payment_method(string: &str) -> Result<MetodoPagamento, errors::CustomErrors> {
+    match string.trim().to_lowercase().as_str() {
+        "credito" => Ok(MetodoPagamento::Credito),
+        "debito" => Ok(MetodoPagamento::Debito),
+        "pix" => Ok(MetodoPagamento::Pix),
+        "dinheiro" => Ok(MetodoPagamento::Dinheiro),
+        _ => Err(errors::CustomErrors::NoCategory)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, io::Cursor};
-
     use super::*;
+    use crate::test_utils::test_path;
 
     #[test]
     fn test_get_files() {
-        get_files();
+        let dir = test_path("test_get_files");
+        let _ = fs::remove_dir_all(&dir);
+
+        let result = get_files(&dir);
 
-        assert!(fs::exists("produtos.bin").expect("Erro ao tentar localizar o arquivo."));
-        assert!(fs::exists("vendas.bin").expect("Erro ao tentar localizar o arquivo."));
+        assert!(matches!(result.unwrap_err().downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::StoreNotFound)));
+        assert!(!fs::exists(&dir).expect("Erro ao tentar localizar o diretório."));
+
+        assert!(create_store(&dir).is_ok());
+        assert!(get_files(&dir).is_ok());
+
+        assert!(fs::exists(dir.join(PRODUCTS_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(PRODUCTS_INDEX_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(SALES_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(SALES_INDEX_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(JOURNAL_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(MOVEMENTS_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(fs::exists(dir.join(ORDERS_FILE)).expect("Erro ao tentar localizar o arquivo."));
+
+        let result = create_store(&dir);
+
+        assert!(matches!(result.unwrap_err().downcast_ref::<errors::CustomErrors>(), Some(errors::CustomErrors::StoreAlreadyExists)));
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
     }
 
     #[test]
-    fn test_validate_string() {
-        let input = b"Carlos";
-        let mut cursor = Cursor::new(input);
+    fn test_get_shared_files() {
+        let dir = test_path("test_get_shared_files");
+        let _ = fs::remove_dir_all(&dir);
 
-        let result = validate_string(&mut cursor);
+        fs::create_dir_all(&dir).unwrap();
 
-        assert!(result.is_ok());
-
-        let input = b"sair";
-        let mut cursor = Cursor::new(input);
+        // Um estoque migrado para o SQLite não precisa dos arquivos de produtos e vendas.
+        let ((mut sessions, mut orders), _) = get_shared_files(&dir).unwrap();
 
-        let result = validate_string(&mut cursor);
+        assert!(sessions.current().unwrap().is_none());
+        assert!(orders.list().unwrap().is_empty());
+        assert!(!fs::exists(dir.join(PRODUCTS_FILE)).expect("Erro ao tentar localizar o arquivo."));
+        assert!(!fs::exists(dir.join(JOURNAL_FILE)).expect("Erro ao tentar localizar o arquivo."));
 
-        assert!(result.is_err());
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
     }
 
     #[test]
-    fn test_validate_search() {
-        let input = b"3";
-        let mut cursor = Cursor::new(input);
+    fn test_get_files_creates_ledger() {
+        let dir = test_path("test_get_files_ledger");
+        let _ = fs::remove_dir_all(&dir);
 
-        let result = validate_search("id", &mut cursor);
+        create_store(&dir).unwrap();
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 3);
+        let ((mut products, _, _, _, _), _) = get_files(&dir).unwrap();
+        products.insert(validate_product(vec!["Arroz", "12", "20", "2", "10/08/2023", "alimento"], &categories::built_in()).unwrap()).unwrap();
+        drop(products);
+
+        fs::remove_file(dir.join(MOVEMENTS_FILE)).unwrap();
+
+        let ((mut products, _, _, _, _), _) = get_files(&dir).unwrap();
+        let movements = products.movimentos.by_product(1).unwrap();
+
+        assert_eq!(movements.len(), 1);
+        assert_eq!(movements[0].tipo, TipoMovimento::Inicial);
+        assert_eq!(movements[0].quantidade, 12);
+
+        drop(products);
+        assert_eq!(get_files(&dir).unwrap().0.0.movimentos.header().unwrap().quantidade, 1);
+
+        fs::remove_dir_all(&dir).expect("Erro ao tentar excluir o diretório.");
     }
 
     #[test]
@@ -250,36 +486,54 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_float() {
-        let int = "5";
+    fn test_validate_money() {
+        let result = validate_money("5");
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Dinheiro::from_centavos(500));
 
-        let result = validate_float(int);
+        let result = validate_money("12,50");
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 5.0);
+        assert_eq!(result.unwrap(), Dinheiro::from_centavos(1250));
+
+        assert!(validate_money("NaN").is_err());
+        assert!(validate_money("inf").is_err());
+        assert!(validate_money("-3.00").is_err());
     }
 
     #[test]
-    fn test_get_product_info() {
-        let input = b"Camisa 10 50 5 10/8/2023 Geral";
-        let mut cursor = Cursor::new(input);
+    fn test_validate_barcode() {
+        assert_eq!(validate_barcode("7891234567895").unwrap(), "7891234567895");
+        assert_eq!(validate_barcode("4006381333931").unwrap(), "4006381333931");
+        assert_eq!(validate_barcode("0000000000000").unwrap(), "0000000000000");
+
+        assert!(matches!(validate_barcode("7891234567896"), Err(errors::CustomErrors::InvalidBarcode)));
+        assert!(validate_barcode("789123456789").is_err());
+        assert!(validate_barcode("78912345678950").is_err());
+        assert!(validate_barcode("789123456789X").is_err());
+    }
 
-        let result = get_product_info(&mut cursor);
+    #[test]
+    fn test_validate_taxes() {
+        let tributacao = validate_taxes(&["1006.30.21", "5.102", "18", "1,65", "7,6%"]).unwrap();
 
-        assert!(result.is_ok());
+        assert_eq!((tributacao.ncm(), tributacao.cfop()), ("10063021", "5102"));
+        assert_eq!((tributacao.icms(), tributacao.pis(), tributacao.cofins()), (Aliquota::from_centesimos(1800), Aliquota::from_centesimos(165), Aliquota::from_centesimos(760)));
 
-        let produto = result.unwrap();
+        assert!(matches!(validate_taxes(&["1006302", "5102", "18", "0", "0"]), Err(errors::CustomErrors::InvalidTax)));
+        assert!(matches!(validate_taxes(&["10063021", "1102", "18", "0", "0"]), Err(errors::CustomErrors::InvalidTax)));
+        assert!(matches!(validate_taxes(&["10063021", "5102", "120", "0", "0"]), Err(errors::CustomErrors::InvalidTax)));
+        assert!(validate_taxes(&["10063021", "5102", "18"]).is_err());
 
-        assert_eq!(produto.nome, "Camisa");
-        assert_eq!(produto.quantidade_estoque, 10);
-        assert_eq!(produto.quantidade_restoque, 5);
+        assert!(check_taxes(&Tributacao::new(String::new(), "5102".to_string(), Aliquota::ZERO, Aliquota::ZERO, Aliquota::ZERO)).is_err());
     }
 
     #[test]
     fn test_validate_product() {
         let input = vec!["Camisa", "10", "35", "5", "15/11/2024", "eletronico"];
 
-        let result = validate_product(input);
+        let result = validate_product(input, &categories::built_in());
 
         assert!(result.is_ok());
 
@@ -288,57 +542,56 @@ mod tests {
         assert_eq!(produto.nome, "Camisa");
         assert_eq!(produto.quantidade_estoque, 10);
         assert_eq!(produto.quantidade_restoque, 5);
-    }
-
-    #[test]
-    fn test_get_sale_info() {
-        let input = b"1/1/1970\ncredito";
-        let mut cursor = Cursor::new(input);
-
-        let result = get_sale_info(&mut cursor);
-
-        assert!(result.is_ok());
+        assert_eq!(produto.categoria, categories::ELETRONICO);
 
-        let sale_info = result.unwrap();
+        let produto = validate_product(vec!["Camisa", "10", "35", "5", "15/11/2024", "roupa", "7891234567895"], &categories::built_in()).unwrap();
+        assert_eq!(produto.codigo_barras.as_deref(), Some("7891234567895"));
 
-        assert_eq!(sale_info.0, chrono::NaiveDate::default());
+        assert!(validate_product(vec!["Camisa", "10", "35", "5", "15/11/2024", "roupa", "7891234567890"], &categories::built_in()).is_err());
     }
 
     #[test]
-    fn test_validate_sale() {
-        let result = validate_sale("2 3");
-
-        assert!(result.is_ok());
-
-        assert_eq!((2, 3), result.unwrap());
+    fn test_validate_category() {
+        let mut categorias = categories::built_in();
+        categorias.push(Categoria::new(5, "Limpeza".to_string(), None));
+
+        assert_eq!(validate_category("Eletrônico", &categorias).unwrap(), categories::ELETRONICO);
+        assert_eq!(validate_category("limpeza", &categorias).unwrap(), 5);
+        assert_eq!(validate_category("2", &categorias).unwrap(), categories::ROUPA);
+        assert!(matches!(validate_category("brinquedo", &categorias), Err(errors::CustomErrors::NoCategory)));
+        assert!(validate_category("9", &categorias).is_err());
     }
 
     #[test]
-    fn test_validate_payment_method() {
-        let input = b"credito";
-        let mut cursor = Cursor::new(input);
-
-        let result = validate_payment_method(&mut cursor);
-
-        assert!(result.is_ok());
+    fn test_validate_name() {
+        let name = "Feijão carioca tipo 1 pacote econômico de 5 quilos";
 
-        let input = b"invalido";
-        let mut cursor = Cursor::new(input);
-
-        let result = validate_payment_method(&mut cursor);
+        assert_eq!(validate_name(name).unwrap(), name);
+        assert!(matches!(validate_name("  "), Err(errors::CustomErrors::EmptyName)));
+    }
 
-        assert!(result.is_err());
+    #[test]
+    fn test_parse_discount() {
+        assert_eq!(parse_discount("10%").unwrap(), Desconto::Percentual(10));
+        assert_eq!(parse_discount(" 15 %").unwrap(), Desconto::Percentual(15));
+        assert_eq!(parse_discount("2,50").unwrap(), Desconto::Fixo(Dinheiro::from_centavos(250)));
+        assert!(matches!(parse_discount("-1"), Err(errors::CustomErrors::InvalidDiscount)));
+        assert!(matches!(parse_discount("dez%"), Err(errors::CustomErrors::InvalidDiscount)));
     }
 
     #[test]
-    fn test_validate_date() {
-        let input = b"1/1/1970";
-        let mut cursor = Cursor::new(input);
+    fn test_validate_receipt() {
+        assert_eq!(validate_receipt("2 10 4,50").unwrap(), (2, 10, Dinheiro::from_centavos(450)));
+        assert!(validate_receipt("2 10").is_err());
+        assert!(validate_receipt("2 10 -1").is_err());
+    }
 
-        let result = validate_date(&mut cursor);
+    #[test]
+    fn test_validate_sale() {
+        let result = validate_sale("2 3");
 
         assert!(result.is_ok());
 
-        assert_eq!(result.unwrap(), chrono::NaiveDate::default());
+        assert_eq!((2, 3), result.unwrap());
     }
-}
\ No newline at end of file
+}